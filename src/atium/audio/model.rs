@@ -0,0 +1,125 @@
+/// A waveform image rendering request
+pub struct WaveformRequest {
+    /// The audio or video file to render
+    pub input_file: String,
+    /// Where the image will be saved. If None, it is saved next to the input
+    pub output_file: Option<String>,
+    /// Image width in pixels
+    pub width: u32,
+    /// Image height in pixels
+    pub height: u32,
+    /// One color per channel, e.g. `white` or `#00FF00`. If empty, `ffmpeg` defaults are used
+    pub colors: Vec<String>
+}
+
+/// Color palettes supported by the `showspectrumpic` filter
+#[derive(Clone)]
+pub enum SpectrogramColor {
+    Channel, Intensity, Rainbow, Moreland, Nebulae, Fire, Fiery, Fruit, Cool, Magma, Green, Viridis, Plasma, Cividis, Terrain
+}
+
+pub fn parse_spectrogram_color(input: Option<String>) -> Option<SpectrogramColor> {
+    input.map(|color| match color.to_lowercase().as_str() {
+        "channel" => SpectrogramColor::Channel,
+        "intensity" => SpectrogramColor::Intensity,
+        "rainbow" => SpectrogramColor::Rainbow,
+        "moreland" => SpectrogramColor::Moreland,
+        "nebulae" => SpectrogramColor::Nebulae,
+        "fire" => SpectrogramColor::Fire,
+        "fiery" => SpectrogramColor::Fiery,
+        "fruit" => SpectrogramColor::Fruit,
+        "cool" => SpectrogramColor::Cool,
+        "magma" => SpectrogramColor::Magma,
+        "green" => SpectrogramColor::Green,
+        "viridis" => SpectrogramColor::Viridis,
+        "plasma" => SpectrogramColor::Plasma,
+        "cividis" => SpectrogramColor::Cividis,
+        "terrain" => SpectrogramColor::Terrain,
+        _ => SpectrogramColor::Intensity
+    })
+}
+
+fn get_spectrogram_color_name(color: SpectrogramColor) -> &'static str {
+    match color {
+        SpectrogramColor::Channel => "channel",
+        SpectrogramColor::Intensity => "intensity",
+        SpectrogramColor::Rainbow => "rainbow",
+        SpectrogramColor::Moreland => "moreland",
+        SpectrogramColor::Nebulae => "nebulae",
+        SpectrogramColor::Fire => "fire",
+        SpectrogramColor::Fiery => "fiery",
+        SpectrogramColor::Fruit => "fruit",
+        SpectrogramColor::Cool => "cool",
+        SpectrogramColor::Magma => "magma",
+        SpectrogramColor::Green => "green",
+        SpectrogramColor::Viridis => "viridis",
+        SpectrogramColor::Plasma => "plasma",
+        SpectrogramColor::Cividis => "cividis",
+        SpectrogramColor::Terrain => "terrain"
+    }
+}
+
+/// A spectrogram image rendering request
+pub struct SpectrogramRequest {
+    /// The audio or video file to render
+    pub input_file: String,
+    /// Where the image will be saved. If None, it is saved next to the input
+    pub output_file: Option<String>,
+    /// Spectrum width in pixels, the legend is drawn around it
+    pub width: u32,
+    /// Spectrum height in pixels, the legend is drawn around it
+    pub height: u32,
+    /// The color palette. If None, the default will be set to [`SpectrogramColor::Intensity`]
+    pub color: Option<SpectrogramColor>,
+    /// Draws time and frequency axes around the spectrum
+    pub legend: bool
+}
+
+/// Builds the `showspectrumpic` filter for the given size, palette and legend
+pub fn build_spectrogram_filter(width: u32, height: u32, color: SpectrogramColor, legend: bool) -> String {
+    format!(
+        "showspectrumpic=s={}x{}:color={}:legend={}",
+        width, height, get_spectrogram_color_name(color), if legend { 1 } else { 0 }
+    )
+}
+
+/// The response of an audio image rendering containing the output filepath
+pub struct AudioImageResponse {
+    pub output: String
+}
+
+/// Builds the `showwavespic` filter for the given size and channel colors
+pub fn build_waveform_filter(width: u32, height: u32, colors: &[String]) -> String {
+    let mut filter = format!("showwavespic=s={}x{}", width, height);
+    if !colors.is_empty() {
+        filter.push_str(&format!(":colors={}", colors.join("|")));
+    }
+
+    filter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_spectrogram_filter() {
+        assert_eq!(
+            build_spectrogram_filter(1024, 512, SpectrogramColor::Magma, true),
+            "showspectrumpic=s=1024x512:color=magma:legend=1"
+        );
+        assert_eq!(
+            build_spectrogram_filter(800, 200, parse_spectrogram_color(Some(String::from("unknown"))).unwrap(), false),
+            "showspectrumpic=s=800x200:color=intensity:legend=0"
+        );
+    }
+
+    #[test]
+    fn test_build_waveform_filter() {
+        assert_eq!(build_waveform_filter(1280, 240, &[]), "showwavespic=s=1280x240");
+        assert_eq!(
+            build_waveform_filter(640, 120, &[String::from("white"), String::from("#00FF00")]),
+            "showwavespic=s=640x120:colors=white|#00FF00"
+        );
+    }
+}
\ No newline at end of file
@@ -0,0 +1,81 @@
+use log::{debug, info};
+use crate::atium::audio::model::{AudioImageResponse, build_spectrogram_filter, build_waveform_filter, SpectrogramColor, SpectrogramRequest, WaveformRequest};
+use crate::atium::common::analysis_helper::{build_output_from_input_path, compute_output_file};
+use crate::atium::common::analysis_service::MediaInfoExtractorService;
+use crate::atium::common::command_manager::CommandManager;
+use crate::atium::common::error::AtiumError;
+use crate::atium::common::model::CollisionPolicy;
+use crate::converter::filter_helper::validate_color;
+
+/// FFMPEG Engine Service for rendering images out of audio streams
+pub struct FFMPEGAudioImageService {
+    command_manager: CommandManager
+}
+
+impl FFMPEGAudioImageService {
+    fn validate_size(&self, width: u32, height: u32) -> Result<(), AtiumError> {
+        if width == 0 || height == 0 {
+            return Err(AtiumError::InvalidArgument(format!("Invalid image size [{}x{}]", width, height)))
+        }
+
+        Ok(())
+    }
+    fn validate_audio_stream(&self, input_file: &str) -> Result<(), AtiumError> {
+        let analysis_output = MediaInfoExtractorService::new()?.get_analysis_output(input_file)?;
+        if analysis_output.get_tracks_by_type("Audio").is_empty() {
+            return Err(AtiumError::InvalidArgument(format!("Input file [{}] has no audio stream", input_file)))
+        }
+
+        Ok(())
+    }
+    fn render(&self, input_file: &str, output_file: Option<String>, filter: String) -> Result<AudioImageResponse, AtiumError> {
+        self.validate_audio_stream(input_file)?;
+
+        let output_file = output_file
+            .unwrap_or_else(|| build_output_from_input_path(input_file, "png"));
+        let output_file = compute_output_file(&output_file, "png", CollisionPolicy::Rename)?;
+
+        debug!("Rendering [{}] with filter [{}]", input_file, filter);
+
+        let args = vec!["-i", input_file, "-filter_complex", filter.as_str(), "-frames:v", "1", output_file.as_str()];
+        let result = self.command_manager.execute_with_args(args)?;
+        if !result.status.success() {
+            self.command_manager.print_command_output(result.stderr)?;
+            return Err(AtiumError::ConversionError("Execution of command returned ERROR".to_string()))
+        }
+
+        info!("Image rendered at path [{}]", output_file);
+        Ok(AudioImageResponse { output: output_file })
+    }
+    /// Renders a waveform image of the input audio and returns an [`AudioImageResponse`]
+    pub fn extract_waveform(&self, request: WaveformRequest) -> Result<AudioImageResponse, AtiumError> {
+        self.validate_size(request.width, request.height)?;
+        for color in &request.colors {
+            validate_color(color)?;
+        }
+
+        let filter = build_waveform_filter(request.width, request.height, &request.colors);
+
+        self.render(&request.input_file, request.output_file, filter)
+    }
+    /// Renders a spectrogram image of the input audio and returns an [`AudioImageResponse`]
+    pub fn extract_spectrogram(&self, request: SpectrogramRequest) -> Result<AudioImageResponse, AtiumError> {
+        self.validate_size(request.width, request.height)?;
+
+        let filter = build_spectrogram_filter(
+            request.width,
+            request.height,
+            request.color.unwrap_or(SpectrogramColor::Intensity),
+            request.legend
+        );
+
+        self.render(&request.input_file, request.output_file, filter)
+    }
+    /// Constructs a new instance of [`FFMPEGAudioImageService`] by loading and checking `ffmpeg` availability
+    pub fn new() -> Result<Self, AtiumError> {
+        let command_manager =
+            CommandManager::new("ffmpeg".to_string(), vec!["-version"])?;
+
+        Ok(Self { command_manager })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,136 @@
+use serde::Serialize;
+
+use crate::atium::common::analysis_helper::AnalysisOutput;
+
+/// What to do with a stream when delivering it in an mp4 for the web
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum StreamAction {
+    /// The stream can be copied as is with `-c copy`
+    Copy,
+    /// The stream must be encoded with the `target` `ffmpeg` encoder
+    Transcode { target: String, reason: String }
+}
+
+/// Advice for a single stream
+#[derive(Clone, Serialize)]
+pub struct StreamAdvice {
+    /// Stream kind as reported by mediainfo, `Video`, `Audio` or `Text`
+    pub kind: String,
+    /// Index of the stream among the ones of the same kind, e.g. `1` for `0:a:1`
+    pub kind_index: usize,
+    /// Stream format as reported by mediainfo, e.g. `AVC` or `AC-3`
+    pub format: String,
+    pub action: StreamAction
+}
+
+/// Per stream advice on whether a media can be delivered on the web without transcoding
+#[derive(Clone, Serialize)]
+pub struct TranscodeAdvice {
+    pub streams: Vec<StreamAdvice>
+}
+
+impl TranscodeAdvice {
+    /// Returns `true` when at least one stream must be transcoded
+    pub fn needs_transcode(&self) -> bool {
+        self.streams.iter().any(|stream| stream.action != StreamAction::Copy)
+    }
+}
+
+fn transcode(target: &str, reason: &str) -> StreamAction {
+    StreamAction::Transcode { target: target.to_string(), reason: reason.to_string() }
+}
+
+fn advise_video(format: &str, bit_depth: Option<u32>) -> StreamAction {
+    match format {
+        "AVC" if bit_depth.unwrap_or(8) > 8 => transcode("libx264", "high bit depth H.264 is not decoded by most browsers"),
+        "AVC" => StreamAction::Copy,
+        "HEVC" => transcode("libx264", "HEVC playback is not supported by all browsers"),
+        _ => transcode("libx264", "the format is not widely supported in mp4 for the web")
+    }
+}
+
+fn advise_audio(format: &str, profile: Option<&str>) -> StreamAction {
+    match (format, profile) {
+        ("AAC", _) => StreamAction::Copy,
+        ("MPEG Audio", Some("Layer 3")) => StreamAction::Copy,
+        ("AC-3", _) | ("E-AC-3", _) => transcode("aac", "Dolby audio is not decoded by most browsers"),
+        _ => transcode("aac", "the format is not widely supported in mp4 for the web")
+    }
+}
+
+fn advise_text(format: &str) -> StreamAction {
+    match format {
+        "Timed Text" => StreamAction::Copy,
+        _ => transcode("mov_text", "mp4 only stores timed text subtitles")
+    }
+}
+
+impl AnalysisOutput {
+    /// Inspects each video, audio and subtitle stream and tells whether it can be copied
+    /// or needs transcoding for web delivery in mp4 (H.264 video, AAC/MP3 audio, timed text)
+    pub fn transcode_advice(&self) -> TranscodeAdvice {
+        let mut streams = Vec::new();
+        for kind in ["Video", "Audio", "Text"] {
+            for (kind_index, track) in self.get_tracks_by_type(kind).into_iter().enumerate() {
+                let format = self.get_track_field(track, "Format").unwrap_or_default();
+                let action = match kind {
+                    "Video" => advise_video(&format, self.get_track_field(track, "BitDepth")
+                        .and_then(|depth| depth.parse().ok())),
+                    "Audio" => advise_audio(&format, self.get_track_field(track, "Format_Profile").as_deref()),
+                    _ => advise_text(&format)
+                };
+                streams.push(StreamAdvice {
+                    kind: kind.to_string(),
+                    kind_index,
+                    format,
+                    action
+                });
+            }
+        }
+
+        TranscodeAdvice { streams }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use crate::atium::common::analysis_helper::MediaInfoJsonLoader;
+    use super::*;
+
+    #[test]
+    fn test_web_ready_media() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/test/info.json");
+        let output = MediaInfoJsonLoader{}.load_json_from_file(&d.to_str().unwrap().to_string()).unwrap();
+
+        let advice = output.transcode_advice();
+
+        assert_eq!(advice.streams.len(), 2);
+        assert!(!advice.needs_transcode());
+    }
+
+    #[test]
+    fn test_transcode_advice() {
+        let json = r#"{"media": {"track": [
+            {"@type": "General", "Format": "Matroska"},
+            {"@type": "Video", "Format": "HEVC", "BitDepth": "10"},
+            {"@type": "Audio", "Format": "AAC"},
+            {"@type": "Audio", "Format": "AC-3"},
+            {"@type": "Text", "Format": "UTF-8"}
+        ]}}"#;
+        let output = MediaInfoJsonLoader{}.load_json_from_string(json).unwrap();
+
+        let advice = output.transcode_advice();
+
+        assert!(advice.needs_transcode());
+        let actions: Vec<(&str, usize, &StreamAction)> = advice.streams.iter()
+            .map(|s| (s.kind.as_str(), s.kind_index, &s.action))
+            .collect();
+        assert!(matches!(actions[0], ("Video", 0, StreamAction::Transcode { .. })));
+        assert_eq!(actions[1], ("Audio", 0, &StreamAction::Copy));
+        assert!(matches!(actions[2], ("Audio", 1, StreamAction::Transcode { target, .. }) if target == "aac"));
+        assert!(matches!(actions[3], ("Text", 0, StreamAction::Transcode { target, .. }) if target == "mov_text"));
+    }
+}
\ No newline at end of file
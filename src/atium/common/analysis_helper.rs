@@ -1,12 +1,12 @@
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
-use log::{debug, error};
-use rand::Rng;
+use log::{debug, error, warn};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 use crate::atium::common::error::AtiumError;
+use crate::atium::common::model::CollisionPolicy;
 
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -29,8 +29,21 @@ impl AnalysisOutput {
             _ => String::from(""),
         }
     }
+    /// Returns the tracks of the given `@type`, e.g. `Video`, `Audio` or `Text`, in the
+    /// order they appear in the media info output
+    pub fn get_tracks_by_type(&self, track_type: &str) -> Vec<&HashMap<String, Value>> {
+        self.media.track.iter()
+            .filter(|track| track.get("@type")
+                .map(|t| self.parse_string_value(t) == track_type)
+                .unwrap_or(false))
+            .collect()
+    }
+    /// Returns the value of a field of the given track, if present
+    pub fn get_track_field(&self, track: &HashMap<String, Value>, field_name: &str) -> Option<String> {
+        track.get(field_name).map(|v| self.parse_string_value(v))
+    }
     /// Tries to extract a value from the media info output
-    pub fn extract_field_from_track(&self, track_number: usize, field_name: &String) -> Result<String, AtiumError> {
+    pub fn extract_field_from_track(&self, track_number: usize, field_name: &str) -> Result<String, AtiumError> {
         match self.media.track.get(track_number) {
             Some(track) => {
                 track.get(field_name)
@@ -40,6 +53,13 @@ impl AnalysisOutput {
             None => Err(AtiumError::IOError("Could not extract track".to_string()))
         }
     }
+    /// Returns the duration of the media in seconds, 0 when it is unknown
+    pub fn duration_secs(&self) -> f64 {
+        self.extract_field_from_track(0, "Duration")
+            .ok()
+            .and_then(|duration| duration.parse::<f64>().ok())
+            .unwrap_or(0.0)
+    }
 }
 
 pub struct MediaInfoJsonLoader{}
@@ -54,6 +74,7 @@ impl MediaInfoJsonLoader {
         Ok(analysis_output)
     }
     /// Loads JSON structure into [`AnalysisOutput`] struct starting from an input file
+    #[allow(dead_code)]
     pub fn load_json_from_file(&self, input_file: &String) -> Result<AnalysisOutput, AtiumError> {
         match fs::read_to_string(input_file) {
             Ok(data) => {
@@ -67,8 +88,8 @@ impl MediaInfoJsonLoader {
         }
     }
     /// Loads JSON structure into [`AnalysisOutput`] struct starting from an input [`String`]
-    pub fn load_json_from_string(&self, input: &String) -> Result<AnalysisOutput, AtiumError> {
-        self.deserialize(input.as_str())
+    pub fn load_json_from_string(&self, input: &str) -> Result<AnalysisOutput, AtiumError> {
+        self.deserialize(input)
     }
 }
 
@@ -94,18 +115,179 @@ fn get_extension_from_path(path: &Path, default_extension: &str) -> Result<Strin
         .to_string())
 }
 
-pub fn compute_output_file(output: &String, default_extension: &str) -> Result<String, AtiumError> {
+fn rename_output_file(path: &Path, default_extension: &str) -> Result<String, AtiumError> {
+    let name = get_file_name_from_path(path)?;
+    let extension = get_extension_from_path(path, default_extension)?;
+
+    let mut counter = 1;
+    loop {
+        let candidate = path.with_file_name(format!("{}_{}.{}", name, counter, extension));
+        if !candidate.exists() {
+            return candidate.to_str()
+                .map(|s| s.to_string())
+                .ok_or(AtiumError::IOError("Could not build output path".to_string()))
+        }
+        counter += 1;
+    }
+}
+
+/// Builds a default output path by appending the given extension to the input path
+pub fn build_output_from_input_path(input_file: &str, extension: &str) -> String {
+    format!("{}.{}", input_file, extension)
+}
+
+/// Tokens available in output templates
+pub const OUTPUT_TEMPLATE_TOKENS: [&str; 3] = ["name", "ext", "input_ext"];
+
+/// Checks that `template` only uses known tokens and has balanced braces
+pub fn validate_output_template(template: &str) -> Result<(), AtiumError> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}')
+            .ok_or(AtiumError::InvalidArgument(format!("Unclosed token in output template [{}]", template)))?;
+        let token = &rest[start + 1..start + end];
+        if !OUTPUT_TEMPLATE_TOKENS.contains(&token) {
+            let valid_tokens: Vec<String> = OUTPUT_TEMPLATE_TOKENS.iter().map(|t| format!("{{{}}}", t)).collect();
+            return Err(AtiumError::InvalidArgument(
+                format!("Unknown token {{{}}} in output template, valid tokens are: {}", token, valid_tokens.join(", "))))
+        }
+        rest = &rest[start + end + 1..];
+    }
+    if rest.contains('}') {
+        return Err(AtiumError::InvalidArgument(format!("Unopened token in output template [{}]", template)))
+    }
+
+    Ok(())
+}
+
+/// Renders an output path from `template` for the given input:
+/// * `{name}`      -> input file name without extension
+/// * `{ext}`       -> the output `extension`
+/// * `{input_ext}` -> input file extension
+///
+/// Relative results are placed in the input's directory
+pub fn render_output_template(template: &str, input_file: &str, extension: &str) -> Result<String, AtiumError> {
+    validate_output_template(template)?;
+
+    let input_path = Path::new(input_file);
+    let rendered = template
+        .replace("{name}", &get_file_name_from_path(input_path)?)
+        .replace("{ext}", extension)
+        .replace("{input_ext}", input_path.extension().and_then(|e| e.to_str()).unwrap_or_default());
+
+    let rendered_path = Path::new(&rendered);
+    if rendered_path.is_absolute() {
+        return Ok(rendered)
+    }
+
+    input_path.with_file_name(rendered_path)
+        .to_str()
+        .map(|s| s.to_string())
+        .ok_or(AtiumError::IOError("Could not build output path".to_string()))
+}
+
+/// Turns a preset file into `convert` options. Each line holds an option and its value,
+/// e.g. `--codec h265` or `--title My show`, the value being the rest of the line so it can
+/// have spaces. Blank lines and `#` comments are skipped
+pub fn parse_preset_options(content: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    for line in content.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        match line.split_once(char::is_whitespace) {
+            Some((option, value)) => {
+                args.push(option.to_string());
+                args.push(value.trim().to_string());
+            }
+            None => args.push(line.to_string())
+        }
+    }
+
+    args
+}
+
+/// Turns a preset file into `convert` arguments like [`parse_preset_options`]. Presets
+/// usually leave out the input and the output, so `input.mp4` and `output.mp4` placeholders
+/// are added when missing
+pub fn parse_preset_args(content: &str) -> Vec<String> {
+    let mut args = parse_preset_options(content);
+    let has_option = |args: &[String], names: &[&str]| args.iter().any(|arg| names.contains(&arg.as_str()));
+    if !has_option(&args, &["-i", "--input"]) {
+        args.extend([String::from("--input"), String::from("input.mp4")]);
+    }
+    if !has_option(&args, &["-o", "--output", "--output-template"]) {
+        args.extend([String::from("--output"), String::from("output.mp4")]);
+    }
+
+    args
+}
+
+/// Returns `true` when `path` is an existing named pipe (FIFO). Always `false` outside Unix
+#[cfg(unix)]
+pub fn is_fifo(path: &str) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+
+    fs::metadata(path)
+        .map(|metadata| metadata.file_type().is_fifo())
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+pub fn is_fifo(_path: &str) -> bool {
+    false
+}
+
+/// Computes the output path applying the given [`CollisionPolicy`] when the requested
+/// output already exists. An existing FIFO is not a collision, it is written as it is
+/// for the process reading it
+pub fn compute_output_file(output: &str, default_extension: &str, policy: CollisionPolicy) -> Result<String, AtiumError> {
     let path = Path::new(output);
-    if path.exists() {
-        let mut rng = rand::thread_rng();
-        let random_n = rng.gen_range(0..10000).to_string();
-        let name = get_file_name_from_path(path)?;
-        let extension = get_extension_from_path(path, default_extension)?;
+    if !path.exists() {
+        return Ok(output.to_string())
+    }
+    if is_fifo(output) {
+        debug!("Output [{}] is a FIFO, writing to it directly", output);
+        return Ok(output.to_string())
+    }
+
+    match policy {
+        CollisionPolicy::Error =>
+            Err(AtiumError::IOError(format!("Output file [{}] already exists", output))),
+        CollisionPolicy::Overwrite => {
+            debug!("Output file [{}] will be overwritten", output);
+            Ok(output.to_string())
+        }
+        CollisionPolicy::Rename => rename_output_file(path, default_extension)
+    }
+}
 
-        return Ok(format!("{}-{}.{}", name, random_n, extension))
+/// Tells whether an existing output is complete and can be skipped: `probe` must analyze
+/// it and find a video track. An output that cannot be probed, e.g. one truncated by an
+/// interrupted conversion, is converted again
+pub fn is_complete_output<F: FnOnce(&str) -> Result<AnalysisOutput, AtiumError>>(output_file: &str, probe: F) -> bool {
+    // probing a FIFO would consume the data meant for its reader
+    if !Path::new(output_file).exists() || is_fifo(output_file) {
+        return false
     }
 
-    Ok(output.clone())
+    match probe(output_file) {
+        Ok(analysis_output) => !analysis_output.get_tracks_by_type("Video").is_empty(),
+        Err(err) => {
+            warn!("Existing output [{}] cannot be probed, converting again: {}", output_file, err);
+            false
+        }
+    }
+}
+
+/// Sets the modification time of `target` to the one of `source`
+pub fn copy_modified_time(source: &str, target: &str) -> Result<(), AtiumError> {
+    let modified = fs::metadata(source)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|err| AtiumError::IOError(format!("Could not read modification time of [{}]: {}", source, err)))?;
+
+    fs::File::options()
+        .write(true)
+        .open(target)
+        .and_then(|file| file.set_modified(modified))
+        .map_err(|err| AtiumError::IOError(format!("Could not set modification time of [{}]: {}", target, err)))
 }
 
 #[cfg(test)]
@@ -135,33 +317,144 @@ mod tests {
         let result = output.extract_field_from_track(1, &String::from("Width"));
 
         assert!(result.is_ok());
-        assert_eq!(result.ok().unwrap(), String::from("1920"))
+        assert_eq!(result.ok().unwrap(), String::from("1920"));
+        assert_eq!(output.duration_secs(), 20.0);
     }
 
     #[test]
     fn test_make_output_path() {
-        let result = compute_output_file(&String::from("/Users/user.name/path/to/video.mp4"), "mp4");
+        let result = compute_output_file("/Users/user.name/path/to/video.mp4", "mp4", CollisionPolicy::Rename);
         println!("{}", result.ok().unwrap());
 
         // [WARNING] needs creating a tmp file
-        fs::write("/tmp/example.mp4", b"");
-        let result = compute_output_file(&String::from("/tmp/example.mp4"), "mp4");
+        fs::write("/tmp/example.mp4", b"").unwrap();
+        let result = compute_output_file("/tmp/example.mp4", "mp4", CollisionPolicy::Rename);
         println!("{}", result.ok().unwrap());
     }
 
+    #[test]
+    fn test_collision_policies() {
+        let dir = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("video.mp4");
+        let output = output.to_str().unwrap();
+
+        let free = compute_output_file(output, "mp4", CollisionPolicy::Error);
+        assert_eq!(free.ok().unwrap(), output);
+
+        fs::write(output, b"").unwrap();
+        assert!(compute_output_file(output, "mp4", CollisionPolicy::Error).is_err());
+        assert_eq!(compute_output_file(output, "mp4", CollisionPolicy::Overwrite).ok().unwrap(), output);
+
+        let first = compute_output_file(output, "mp4", CollisionPolicy::Rename).ok().unwrap();
+        assert_eq!(first, dir.join("video_1.mp4").to_str().unwrap());
+
+        fs::write(&first, b"").unwrap();
+        let second = compute_output_file(output, "mp4", CollisionPolicy::Rename).ok().unwrap();
+        assert_eq!(second, dir.join("video_2.mp4").to_str().unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_is_complete_output() {
+        let dir = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("video.mp4");
+        let output = output.to_str().unwrap();
+        // stands in for mediainfo: only files holding its JSON output are readable media
+        let probe = |file: &str| MediaInfoJsonLoader{}.load_json_from_file(&file.to_string());
+
+        assert!(!is_complete_output(output, probe));
+
+        let mut info = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        info.push("resources/test/info.json");
+        fs::copy(&info, output).unwrap();
+        assert!(is_complete_output(output, probe));
+
+        fs::write(output, b"").unwrap();
+        assert!(!is_complete_output(output, probe));
+
+        fs::write(output, b"\x00\x00\x00\x18ftypmp42").unwrap();
+        assert!(!is_complete_output(output, probe));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fifo_output() {
+        let dir = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let fifo = dir.join("output.mkv");
+        let fifo_path = std::ffi::CString::new(fifo.to_str().unwrap()).unwrap();
+        // SAFETY: mkfifo only reads the provided path
+        assert_eq!(unsafe { libc::mkfifo(fifo_path.as_ptr(), 0o600) }, 0);
+        let file = dir.join("output.mp4");
+        fs::write(&file, b"").unwrap();
+
+        assert!(is_fifo(fifo.to_str().unwrap()));
+        assert!(!is_fifo(file.to_str().unwrap()));
+        assert_eq!(compute_output_file(fifo.to_str().unwrap(), "mkv", CollisionPolicy::Error).ok().unwrap(), fifo.to_str().unwrap());
+        assert!(compute_output_file(file.to_str().unwrap(), "mp4", CollisionPolicy::Error).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_copy_modified_time() {
+        let dir = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("source.mov");
+        let target = dir.join("target.mp4");
+        fs::write(&source, b"").unwrap();
+        fs::write(&target, b"").unwrap();
+        let modified = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000);
+        fs::File::options().write(true).open(&source).unwrap().set_modified(modified).unwrap();
+
+        copy_modified_time(source.to_str().unwrap(), target.to_str().unwrap()).ok().unwrap();
+
+        assert_eq!(fs::metadata(&target).unwrap().modified().unwrap(), modified);
+        assert!(copy_modified_time(dir.join("missing.mov").to_str().unwrap(), target.to_str().unwrap()).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_output_template() {
+        let result = render_output_template("{name}_converted.{ext}", "/tmp/videos/holiday.mov", "mp4");
+        assert_eq!(result.ok().unwrap(), "/tmp/videos/holiday_converted.mp4");
+
+        let result = render_output_template("/out/{name}.{input_ext}.{ext}", "holiday.mov", "mp4");
+        assert_eq!(result.ok().unwrap(), "/out/holiday.mov.mp4");
+
+        assert!(validate_output_template("{name}_{resolution}.{ext}").is_err());
+        assert!(validate_output_template("{name.mp4").is_err());
+        assert!(validate_output_template("name}.mp4").is_err());
+    }
+
+    #[test]
+    fn test_parse_preset_args() {
+        let preset = "# web delivery\n--codec h265\n\n  --title   My show  \n--hardware-encode\n";
+
+        assert_eq!(parse_preset_args(preset), vec![
+            "--codec", "h265", "--title", "My show", "--hardware-encode",
+            "--input", "input.mp4", "--output", "output.mp4"
+        ]);
+        assert_eq!(parse_preset_args("-o out.webm"), vec!["-o", "out.webm", "--input", "input.mp4"]);
+    }
+
     #[test]
     fn test_get_name_from_path() {
-        let result = get_file_name_from_path(&Path::new("/tmp/example.mp4"));
+        let result = get_file_name_from_path(Path::new("/tmp/example.mp4"));
 
         assert_eq!(result.ok().unwrap(), String::from("example"));
     }
 
     #[test]
     fn test_get_extension_from_path() {
-        let result = get_extension_from_path(&Path::new("/tmp/example.mp4"), "mp4");
+        let result = get_extension_from_path(Path::new("/tmp/example.mp4"), "mp4");
         assert_eq!(result.ok().unwrap(), String::from("mp4"));
 
-        let point_in_path = get_extension_from_path(&Path::new("/Users/user.name/dir/example.mp4"), "mp4");
+        let point_in_path = get_extension_from_path(Path::new("/Users/user.name/dir/example.mp4"), "mp4");
         assert_eq!(point_in_path.ok().unwrap(), String::from("mp4"));
     }
 }
\ No newline at end of file
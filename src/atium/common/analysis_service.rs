@@ -1,9 +1,12 @@
 use std::fs;
-use log::{debug, error};
+use std::path::Path;
+use log::{debug, error, warn};
 use uuid::Uuid;
+use crate::atium::common::analysis_helper::{AnalysisOutput, MediaInfoJsonLoader};
 use crate::atium::common::command_manager::CommandManager;
 use crate::atium::common::error::AtiumError;
-use crate::atium::common::model::{InfoExtractorResponse, InfoExtractorResponseOutput, InfoFormat, InfoOutputType};
+use crate::atium::common::track_helper::TrackList;
+use crate::atium::common::model::{detect_protocol, InfoExtractorResponse, InfoExtractorResponseOutput, InfoFormat, InfoOutputType, InputProtocol};
 use crate::InfoExtractorRequest;
 
 /// MediaInfo Engine Service for info extraction
@@ -16,7 +19,8 @@ impl MediaInfoExtractorService {
         let ext = match format {
             InfoFormat::Json => ".json",
             InfoFormat::Html => ".html",
-            InfoFormat::Xml => ".xml"
+            InfoFormat::Xml => ".xml",
+            InfoFormat::Summary => ".txt"
         };
         let mut id = out_filepath;
         if id.is_empty() {
@@ -36,8 +40,33 @@ impl MediaInfoExtractorService {
             }
         }
     }
+    fn prettify_output(&self, mut execution_result: std::process::Output, format: &InfoFormat) -> Result<std::process::Output, AtiumError> {
+        if let InfoFormat::Json = format {
+            let value: serde_json::Value = serde_json::from_slice(&execution_result.stdout)
+                .map_err(|err| AtiumError::IOError(format!("Could not parse json: {}", err)))?;
+            execution_result.stdout = serde_json::to_string_pretty(&value)
+                .map_err(|err| AtiumError::IOError(format!("Could not serialize json: {}", err)))?
+                .into_bytes();
+        } else {
+            warn!("Pretty printing is only available for the json format, ignoring it");
+        }
+
+        Ok(execution_result)
+    }
+    fn summarize_output(&self, mut execution_result: std::process::Output) -> Result<std::process::Output, AtiumError> {
+        let content = self.command_manager.get_command_output_as_string(execution_result.stdout)?;
+        let summary = MediaInfoJsonLoader{}.load_json_from_string(&content)?.summary();
+        execution_result.stdout = format!("{}\n", summary).into_bytes();
+
+        Ok(execution_result)
+    }
     fn write_result(&self, execution_result: std::process::Output, request: InfoExtractorRequest, format: InfoFormat) -> Result<InfoExtractorResponse, AtiumError> {
-        return match request.output_type.unwrap_or(InfoOutputType::Stdout) {
+        let execution_result = if request.pretty {
+            self.prettify_output(execution_result, &format)?
+        } else {
+            execution_result
+        };
+        match request.output_type.unwrap_or(InfoOutputType::Stdout) {
             InfoOutputType::Stdout => self.command_manager.print_command_output(execution_result.stdout)
                 .map(|_| InfoExtractorResponse {
                     output: InfoExtractorResponseOutput {
@@ -70,6 +99,28 @@ impl MediaInfoExtractorService {
 
         Ok(Self { command_manager })
     }
+    /// Runs a JSON analysis of the input and loads it into an [`AnalysisOutput`]
+    pub fn get_analysis_output(&self, input: &str) -> Result<AnalysisOutput, AtiumError> {
+        let request = InfoExtractorRequest {
+            input: input.to_string(),
+            format: Some(InfoFormat::Json),
+            full: None,
+            output_file: None,
+            output_type: Some(InfoOutputType::Plain),
+            pretty: false
+        };
+
+        let response = self.get_info(request)?
+            .output
+            .content
+            .unwrap_or_default();
+
+        MediaInfoJsonLoader{}.load_json_from_string(&response)
+    }
+    /// Lists the audio and subtitle streams of the input, see [`AnalysisOutput::list_tracks`]
+    pub fn list_tracks(&self, input: &str) -> Result<TrackList, AtiumError> {
+        Ok(self.get_analysis_output(input)?.list_tracks())
+    }
     pub fn get_info(&self, request: InfoExtractorRequest) -> Result<InfoExtractorResponse, AtiumError> {
 
         let binding = request.clone();
@@ -77,10 +128,24 @@ impl MediaInfoExtractorService {
         let format = binding.format.unwrap_or(InfoFormat::Json);
         let full = binding.full.unwrap_or(true);
 
+        match detect_protocol(&binding.input) {
+            InputProtocol::Local => {
+                if !Path::new(&binding.input).exists() {
+                    return Err(AtiumError::IOError(format!("Input file [{}] not found", binding.input)))
+                }
+            }
+            InputProtocol::Remote(scheme) => {
+                if !["http", "https"].contains(&scheme.as_str()) {
+                    return Err(AtiumError::InvalidArgument(format!("Unsupported protocol [{}], only http(s) URLs can be analyzed", scheme)))
+                }
+                debug!("Analyzing remote input [{}]", binding.input);
+            }
+        }
+
         let mut args: Vec<&str> = Vec::new();
 
         match format {
-            InfoFormat::Json => {
+            InfoFormat::Json | InfoFormat::Summary => {
                 args.push("--output=JSON");
             }
             InfoFormat::Html => {
@@ -97,7 +162,7 @@ impl MediaInfoExtractorService {
 
         args.push(binding.input.as_str());
 
-        return match self.command_manager.execute_with_args(args) {
+        match self.command_manager.execute_with_args(args) {
             Ok(execution_result) => {
                 if !execution_result.status.success() {
                     // WARN: MEDIAINFO WRITES ERRORS TO STDOUT
@@ -105,6 +170,11 @@ impl MediaInfoExtractorService {
                     return Err(AtiumError::CommandError("Command execution returned ERROR status".to_string()))
                 }
 
+                let execution_result = match format {
+                    InfoFormat::Summary => self.summarize_output(execution_result)?,
+                    _ => execution_result
+                };
+
                 self.write_result(execution_result, request, format)
             }
             Err(_) => Err(AtiumError::CommandError("Could not execute command".to_string()))
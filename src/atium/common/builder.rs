@@ -0,0 +1,108 @@
+use crate::atium::common::error::AtiumError;
+use crate::atium::common::model::{InfoExtractorRequest, InfoFormat, InfoOutputType};
+
+/// Fluent builder for an [`InfoExtractorRequest`].
+/// Options left unset get their defaults when calling `build`: [`InfoFormat::Json`],
+/// full output and [`InfoOutputType::Stdout`].
+///
+/// ```
+/// let request = InfoExtractorRequestBuilder::new("/path/to/input.mp4")
+///     .format(InfoFormat::Xml)
+///     .output_file("/path/to/info.xml")
+///     .build()?;
+/// ```
+pub struct InfoExtractorRequestBuilder {
+    input: String,
+    format: Option<InfoFormat>,
+    full: Option<bool>,
+    output_file: Option<String>,
+    output_type: Option<InfoOutputType>,
+    pretty: bool
+}
+
+impl InfoExtractorRequestBuilder {
+    /// Constructs a new builder analysing `input`
+    pub fn new(input: &str) -> Self {
+        InfoExtractorRequestBuilder {
+            input: input.to_string(),
+            format: None,
+            full: None,
+            output_file: None,
+            output_type: None,
+            pretty: false
+        }
+    }
+    pub fn format(mut self, format: InfoFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+    pub fn full(mut self, full: bool) -> Self {
+        self.full = Some(full);
+        self
+    }
+    /// Writes the output to `output_file`, the output type defaults to [`InfoOutputType::File`]
+    pub fn output_file(mut self, output_file: &str) -> Self {
+        self.output_file = Some(output_file.to_string());
+        self.output_type = self.output_type.or(Some(InfoOutputType::File));
+        self
+    }
+    pub fn output_type(mut self, output_type: InfoOutputType) -> Self {
+        self.output_type = Some(output_type);
+        self
+    }
+    /// Pretty-prints the JSON output
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+    /// Applies the defaults and returns the [`InfoExtractorRequest`]
+    pub fn build(self) -> Result<InfoExtractorRequest, AtiumError> {
+        let output_type = self.output_type.unwrap_or(InfoOutputType::Stdout);
+        if let (InfoOutputType::File, None) = (&output_type, &self.output_file) {
+            return Err(AtiumError::InvalidArgument("An output file is required when writing info to a file".to_string()))
+        }
+
+        Ok(InfoExtractorRequest {
+            input: self.input,
+            format: Some(self.format.unwrap_or(InfoFormat::Json)),
+            full: Some(self.full.unwrap_or(true)),
+            output_file: self.output_file,
+            output_type: Some(output_type),
+            pretty: self.pretty
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_defaults() {
+        let request = InfoExtractorRequestBuilder::new("/tmp/input.mp4").build().ok().unwrap();
+
+        assert_eq!(request.input, "/tmp/input.mp4");
+        assert!(matches!(request.format, Some(InfoFormat::Json)));
+        assert_eq!(request.full, Some(true));
+        assert!(request.output_file.is_none());
+        assert!(matches!(request.output_type, Some(InfoOutputType::Stdout)));
+        assert!(!request.pretty);
+    }
+
+    #[test]
+    fn test_build_output_file() {
+        let request = InfoExtractorRequestBuilder::new("/tmp/input.mp4")
+            .output_file("/tmp/info.json")
+            .full(false)
+            .build()
+            .ok()
+            .unwrap();
+
+        assert!(matches!(request.output_type, Some(InfoOutputType::File)));
+        assert_eq!(request.full, Some(false));
+        assert!(InfoExtractorRequestBuilder::new("/tmp/input.mp4")
+            .output_type(InfoOutputType::File)
+            .build()
+            .is_err());
+    }
+}
\ No newline at end of file
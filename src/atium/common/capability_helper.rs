@@ -0,0 +1,277 @@
+use std::sync::Mutex;
+use log::debug;
+use serde::Serialize;
+use crate::atium::common::command_manager::CommandManager;
+use crate::atium::common::error::AtiumError;
+
+static ENCODERS: Mutex<Option<Vec<String>>> = Mutex::new(None);
+static DECODERS: Mutex<Option<Vec<String>>> = Mutex::new(None);
+static INPUT_PROTOCOLS: Mutex<Option<Vec<String>>> = Mutex::new(None);
+static HWACCELS: Mutex<Option<Vec<String>>> = Mutex::new(None);
+static MUXERS: Mutex<Option<Vec<String>>> = Mutex::new(None);
+
+/// Parses the codec names listed by `ffmpeg -encoders` or `ffmpeg -decoders`.
+/// The list starts after the ` ------` separator and each line has the form
+/// ` V....D name    description`
+pub fn parse_codec_list(output: &str) -> Vec<String> {
+    output.lines()
+        .skip_while(|line| !line.trim().starts_with("---"))
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Parses the input protocols listed by `ffmpeg -protocols`, which are the names
+/// between the `Input:` and the `Output:` headers
+pub fn parse_input_protocol_list(output: &str) -> Vec<String> {
+    output.lines()
+        .skip_while(|line| line.trim() != "Input:")
+        .skip(1)
+        .take_while(|line| line.trim() != "Output:")
+        .map(|line| line.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Parses the methods listed by `ffmpeg -hwaccels`, one per line after the
+/// `Hardware acceleration methods:` header
+pub fn parse_hwaccel_list(output: &str) -> Vec<String> {
+    output.lines()
+        .skip_while(|line| !line.trim().starts_with("Hardware acceleration methods"))
+        .skip(1)
+        .map(|line| line.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Parses the muxer names listed by `ffmpeg -muxers`. The list starts after the ` --`
+/// separator and each line has the form `  E name    description`
+pub fn parse_muxer_list(output: &str) -> Vec<String> {
+    output.lines()
+        .skip_while(|line| line.trim() != "--")
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// The build of the local `ffmpeg`, as reported by `ffmpeg -version`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BuildConfig {
+    /// The version string, e.g. `6.1.1-3ubuntu5` or `N-113000-g1234abcd` for git builds
+    pub version: String,
+    /// The `--enable-*` options of the build configuration without their prefix, e.g. `libx264`
+    pub enabled: Vec<String>,
+    /// Name and version of each library, e.g. `("libavcodec", "60.31.102")`
+    pub libraries: Vec<(String, String)>
+}
+
+/// Parses the output of `ffmpeg -version`: the version is the third word of the first line,
+/// the features come from the `configuration:` line and each `libav*` line reads as
+/// `libavcodec     60. 31.102 / 60. 31.102`, the compile time version before the slash
+pub fn parse_build_config(output: &str) -> BuildConfig {
+    let version = output.lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(2))
+        .unwrap_or_default()
+        .to_string();
+    let enabled = output.lines()
+        .find_map(|line| line.trim().strip_prefix("configuration:"))
+        .map(|configuration| configuration.split_whitespace()
+            .filter_map(|option| option.strip_prefix("--enable-"))
+            .map(|feature| feature.to_string())
+            .collect())
+        .unwrap_or_default();
+    let libraries = output.lines()
+        .filter(|line| line.starts_with("lib"))
+        .filter_map(|line| {
+            let (name, versions) = line.split_once(char::is_whitespace)?;
+            let version: String = versions.split('/').next()?.split_whitespace().collect();
+            Some((name.to_string(), version))
+        })
+        .collect();
+
+    BuildConfig { version, enabled, libraries }
+}
+
+/// Probes the version and the build configuration of the local `ffmpeg`, e.g. to include
+/// them in a bug report
+pub fn ffmpeg_build_config() -> Result<BuildConfig, AtiumError> {
+    let command_manager = CommandManager::new("ffmpeg".to_string(), vec!["-version"])?;
+    let output = command_manager.execute_with_args(vec!["-version"])?;
+    if !output.status.success() {
+        return Err(AtiumError::CommandError("Command execution returned ERROR status".to_string()))
+    }
+
+    Ok(parse_build_config(&command_manager.get_command_output_as_string(output.stdout)?))
+}
+
+fn probe_list(cache: &Mutex<Option<Vec<String>>>, flag: &str, parse: fn(&str) -> Vec<String>) -> Result<Vec<String>, AtiumError> {
+    let mut cached = cache.lock()
+        .map_err(|_| AtiumError::IOError("Capability cache is not accessible".to_string()))?;
+
+    if let Some(entries) = cached.as_ref() {
+        return Ok(entries.clone())
+    }
+
+    debug!("Probing ffmpeg {}", flag);
+    let command_manager = CommandManager::new("ffmpeg".to_string(), vec!["-version"])?;
+    let output = command_manager.execute_with_args(vec!["-hide_banner", flag])?;
+    if !output.status.success() {
+        return Err(AtiumError::CommandError("Command execution returned ERROR status".to_string()))
+    }
+
+    let entries = parse(&command_manager.get_command_output_as_string(output.stdout)?);
+    *cached = Some(entries.clone());
+
+    Ok(entries)
+}
+
+/// Returns `true` when the local `ffmpeg` build provides the given encoder, e.g. `libx265`.
+/// The encoders list is probed once and then cached
+pub fn is_encoder_available(encoder: &str) -> Result<bool, AtiumError> {
+    Ok(probe_list(&ENCODERS, "-encoders", parse_codec_list)?.iter().any(|e| e == encoder))
+}
+
+/// Returns `true` when the local `ffmpeg` build provides the given decoder, e.g. `h264`.
+/// The decoders list is probed once and then cached
+pub fn is_decoder_available(decoder: &str) -> Result<bool, AtiumError> {
+    Ok(probe_list(&DECODERS, "-decoders", parse_codec_list)?.iter().any(|d| d == decoder))
+}
+
+/// Fails with [`AtiumError::DependencyMissing`] when the given encoder is not available
+pub fn require_encoder(encoder: &str) -> Result<(), AtiumError> {
+    if !is_encoder_available(encoder)? {
+        return Err(AtiumError::DependencyMissing(format!("encoder [{}] is not available in ffmpeg", encoder)))
+    }
+
+    Ok(())
+}
+
+/// Fails with [`AtiumError::DependencyMissing`] when the given decoder is not available
+pub fn require_decoder(decoder: &str) -> Result<(), AtiumError> {
+    if !is_decoder_available(decoder)? {
+        return Err(AtiumError::DependencyMissing(format!("decoder [{}] is not available in ffmpeg", decoder)))
+    }
+
+    Ok(())
+}
+
+/// Fails with [`AtiumError::InvalidArgument`] when `ffmpeg` cannot read the given protocol.
+/// The input protocols list is probed once and then cached
+pub fn require_input_protocol(protocol: &str) -> Result<(), AtiumError> {
+    if !probe_list(&INPUT_PROTOCOLS, "-protocols", parse_input_protocol_list)?.iter().any(|p| p == protocol) {
+        return Err(AtiumError::InvalidArgument(format!("protocol [{}] is not supported by ffmpeg", protocol)))
+    }
+
+    Ok(())
+}
+
+/// Fails with [`AtiumError::DependencyMissing`] when `ffmpeg` does not list the given
+/// hardware accelerator in `ffmpeg -hwaccels`
+pub fn require_hwaccel(hwaccel: &str) -> Result<(), AtiumError> {
+    if !probe_list(&HWACCELS, "-hwaccels", parse_hwaccel_list)?.iter().any(|h| h == hwaccel) {
+        return Err(AtiumError::DependencyMissing(format!("hardware accelerator [{}] is not available in ffmpeg", hwaccel)))
+    }
+
+    Ok(())
+}
+
+/// Fails with [`AtiumError::DependencyMissing`] when `ffmpeg` cannot write the given
+/// format, e.g. `dash`. The muxers list is probed once and then cached
+pub fn require_muxer(muxer: &str) -> Result<(), AtiumError> {
+    if !probe_list(&MUXERS, "-muxers", parse_muxer_list)?.iter().any(|m| m == muxer) {
+        return Err(AtiumError::DependencyMissing(format!("muxer [{}] is not available in ffmpeg", muxer)))
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_build_config() {
+        let output = "ffmpeg version 6.1.1-3ubuntu5 Copyright (c) 2000-2023 the FFmpeg developers
+built with gcc 13 (Ubuntu 13.2.0-23ubuntu3)
+configuration: --prefix=/usr --extra-version=3ubuntu5 --enable-gpl --disable-stripping --enable-libx264 --enable-vaapi
+libavutil      58. 29.100 / 58. 29.100
+libavcodec     60. 31.102 / 60. 31.102
+";
+
+        assert_eq!(parse_build_config(output), BuildConfig {
+            version: String::from("6.1.1-3ubuntu5"),
+            enabled: vec![String::from("gpl"), String::from("libx264"), String::from("vaapi")],
+            libraries: vec![
+                (String::from("libavutil"), String::from("58.29.100")),
+                (String::from("libavcodec"), String::from("60.31.102"))
+            ]
+        });
+        assert!(parse_build_config("").enabled.is_empty());
+    }
+
+    #[test]
+    fn test_parse_muxer_list() {
+        let output = "File formats:
+ D. = Demuxing supported
+ .E = Muxing supported
+ --
+  E 3g2             3GP2 (3GPP2 file format)
+  E dash            DASH Muxer
+ DE matroska        Matroska
+";
+
+        assert_eq!(parse_muxer_list(output), vec!["3g2", "dash", "matroska"]);
+    }
+
+    #[test]
+    fn test_parse_codec_list() {
+        let output = "Encoders:
+ V..... = Video
+ A..... = Audio
+ S..... = Subtitle
+ .F.... = Frame-level multithreading
+ ..S... = Slice-level multithreading
+ ...X.. = Codec is experimental
+ ....B. = Supports draw_horiz_band
+ .....D = Supports direct rendering method 1
+ ------
+ V....D a64multi             Multicolor charset for Commodore 64 (codec a64_multi)
+ V..... libx264              libx264 H.264 / AVC / MPEG-4 AVC / MPEG-4 part 10 (codec h264)
+ A....D aac                  AAC (Advanced Audio Coding)
+";
+
+        let codecs = parse_codec_list(output);
+
+        assert_eq!(codecs, vec!["a64multi", "libx264", "aac"]);
+    }
+
+    #[test]
+    fn test_parse_hwaccel_list() {
+        let output = "Hardware acceleration methods:
+vdpau
+cuda
+vaapi
+
+";
+
+        assert_eq!(parse_hwaccel_list(output), vec!["vdpau", "cuda", "vaapi"]);
+    }
+
+    #[test]
+    fn test_parse_input_protocol_list() {
+        let output = "Supported file protocols:
+Input:
+  async
+  file
+  https
+Output:
+  file
+  icecast
+";
+
+        assert_eq!(parse_input_protocol_list(output), vec!["async", "file", "https"]);
+    }
+}
\ No newline at end of file
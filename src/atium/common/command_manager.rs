@@ -1,17 +1,135 @@
 use std::ffi::OsStr;
-use std::process::{Command, Output};
-use log::debug;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::Duration;
+use log::{debug, error, Level, log};
+use crate::atium::common::env_helper::binary_path;
 use crate::atium::common::error::AtiumError;
+use crate::atium::common::model::ResourceUsage;
+
+/// The result of a command execution along with the resources used by the child process
+pub struct CommandOutcome {
+    pub output: Output,
+    pub resource_usage: ResourceUsage
+}
+
+#[cfg(unix)]
+fn children_usage() -> Option<libc::rusage> {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+    // SAFETY: getrusage only writes into the provided struct
+    let result = unsafe { libc::getrusage(libc::RUSAGE_CHILDREN, usage.as_mut_ptr()) };
+    if result != 0 {
+        return None
+    }
+
+    // SAFETY: getrusage returned successfully so the struct is initialized
+    Some(unsafe { usage.assume_init() })
+}
+
+#[cfg(unix)]
+fn timeval_to_duration(time: libc::timeval) -> Duration {
+    Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+}
+
+/// Computes the resources used between two `RUSAGE_CHILDREN` snapshots.
+/// CPU time is a proper delta, while the peak memory is the highest one among all the
+/// waited-for children, since the kernel does not expose a per-child value this way
+#[cfg(unix)]
+fn compute_resource_usage(before: Option<libc::rusage>, after: Option<libc::rusage>) -> ResourceUsage {
+    match (before, after) {
+        (Some(before), Some(after)) => {
+            // macOS reports ru_maxrss in bytes, Linux in kilobytes
+            let max_rss_kb = if cfg!(target_os = "macos") {
+                after.ru_maxrss as u64 / 1024
+            } else {
+                after.ru_maxrss as u64
+            };
+            let user_cpu_time = timeval_to_duration(after.ru_utime)
+                .saturating_sub(timeval_to_duration(before.ru_utime));
+
+            ResourceUsage {
+                max_rss_kb: Some(max_rss_kb),
+                user_cpu_time: Some(user_cpu_time)
+            }
+        }
+        _ => ResourceUsage { max_rss_kb: None, user_cpu_time: None }
+    }
+}
+
+/// Returns the log level used when teeing a stderr line: errors and warnings keep
+/// their severity, while everything else, including `ffmpeg` stats, is debug output
+fn classify_stderr_line(line: &str) -> Level {
+    let lowercase = line.to_lowercase();
+    if lowercase.contains("error") || lowercase.contains("invalid") {
+        Level::Error
+    } else if lowercase.contains("warning") || lowercase.contains("deprecated") {
+        Level::Warn
+    } else {
+        Level::Debug
+    }
+}
+
+/// Reads `stderr` to the end, logging each line as soon as it is complete when `tee` is set.
+/// Both `\n` and `\r` end a line since `ffmpeg` rewrites its stats line in place
+fn drain_stderr(mut stderr: impl Read, tee: bool) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    let mut line = Vec::new();
+    let mut chunk = [0u8; 4096];
+    while let Ok(read) = stderr.read(&mut chunk) {
+        if read == 0 {
+            break
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if !tee {
+            continue
+        }
+        for byte in &chunk[..read] {
+            if *byte == b'\n' || *byte == b'\r' {
+                log_stderr_line(&line);
+                line.clear();
+            } else {
+                line.push(*byte);
+            }
+        }
+    }
+    if tee {
+        log_stderr_line(&line);
+    }
+
+    buffer
+}
+
+fn log_stderr_line(line: &[u8]) {
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim();
+    if !line.is_empty() {
+        log!(classify_stderr_line(line), "{}", line);
+    }
+}
+
+/// Quotes `arg` for a POSIX shell. Arguments made only of safe characters are left as
+/// they are, the others are wrapped in single quotes
+pub fn shell_quote(arg: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_-./:=+,@%".contains(c);
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        return arg.to_string()
+    }
+
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
 
 /// A simple struct that holds the logic needed for managing external commands
 pub struct CommandManager {
-    command: String
+    command: String,
+    tee_stderr: bool
 }
 
 impl CommandManager {
     /// Creates a new [`CommandManager`] by trying if the provided command is available on the
-    /// executing environment.
+    /// executing environment. The binary is taken from `ATIUM_<COMMAND>` when it is set
     pub fn new(command: String, command_args: Vec<&str>) -> Result<CommandManager, AtiumError> {
+        let command = binary_path(&command);
         debug!("Loading a new command {}", command.clone());
 
         let command_output = Command::new(command.clone())
@@ -24,12 +142,18 @@ impl CommandManager {
                     return Err(AtiumError::CommandError("Command execution returned ERROR status".to_string()))
                 }
 
-                Ok(CommandManager { command })
+                Ok(CommandManager { command, tee_stderr: false })
             }
             Err(_) =>
                 Err(AtiumError::CommandError("error when executing command".to_string()))
         }
     }
+    /// Logs the stderr of the commands run with [`CommandManager::execute_with_stdout_lines`]
+    /// line by line while they run, instead of only collecting it for the end
+    pub fn with_stderr_tee(mut self, tee_stderr: bool) -> Self {
+        self.tee_stderr = tee_stderr;
+        self
+    }
     /// Prints Command Output to stdout
     pub fn print_command_output(&self, output: Vec<u8>) -> Result<(), AtiumError> {
         match String::from_utf8(output) {
@@ -40,21 +164,99 @@ impl CommandManager {
             Err(_) => Err(AtiumError::IOError("error when writing to stdout".to_string())),
         }
     }
+    /// Logs Command Output as errors, which go to stderr, for the commands whose stdout is
+    /// the data itself, e.g. audio written to `-`
+    pub fn log_command_output(&self, output: Vec<u8>) {
+        String::from_utf8_lossy(&output).lines().for_each(|line| error!("{}", line));
+    }
     /// Returns a [`String`] containing the content of the command execution output
     pub fn get_command_output_as_string(&self, output: Vec<u8>) -> Result<String, AtiumError> {
         String::from_utf8(output)
             .map_err(|_| AtiumError::IOError("error when writing to stdout".to_string()))
     }
-    /// Executes the previously built command by suppplying a collection of arguments
-    pub fn execute_with_args(&self, args: Vec<&str>) -> Result<Output, AtiumError> {
-        let mut cmd = Command::new(self.command.clone());
-        let cmd_with_args = cmd.args(args);
+    /// Executes the previously built command like [`CommandManager::execute_with_stdout_lines`]
+    /// and reports the resources used by the child process.
+    /// On non-Unix platforms the [`ResourceUsage`] fields are always None
+    pub fn execute_with_usage(&self, args: Vec<&str>, on_stdout_line: &mut dyn FnMut(&str)) -> Result<CommandOutcome, AtiumError> {
+        #[cfg(unix)]
+        {
+            let before = children_usage();
+            let output = self.execute_with_stdout_lines(args, on_stdout_line)?;
+            let resource_usage = compute_resource_usage(before, children_usage());
 
+            Ok(CommandOutcome { output, resource_usage })
+        }
+        #[cfg(not(unix))]
+        {
+            let output = self.execute_with_stdout_lines(args, on_stdout_line)?;
 
+            Ok(CommandOutcome {
+                output,
+                resource_usage: ResourceUsage { max_rss_kb: None, user_cpu_time: None }
+            })
+        }
+    }
+    fn log_args(&self, cmd: &Command) {
         debug!("Commands list begin:");
-        let all_args: Vec<&OsStr> = cmd_with_args.get_args().collect();
+        let all_args: Vec<&OsStr> = cmd.get_args().collect();
         all_args.iter().for_each(|a| debug!(" {}\n", a.to_str().unwrap_or("")));
         debug!("Commands list end");
+    }
+    /// Executes the previously built command while handing each line written to stdout to
+    /// `on_stdout_line` as soon as it is available. Stdout and stderr are still collected
+    /// into the returned [`Output`], and stderr is logged in real time when the tee is enabled
+    pub fn execute_with_stdout_lines(&self, args: Vec<&str>, on_stdout_line: &mut dyn FnMut(&str)) -> Result<Output, AtiumError> {
+        let mut cmd = Command::new(self.command.clone());
+        cmd.args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        self.log_args(&cmd);
+
+        let mut child = cmd.spawn()
+            .map_err(|_| AtiumError::CommandError("error when executing command".to_string()))?;
+
+        // stderr is drained on its own thread so a chatty child can't block on a full pipe
+        let stderr = child.stderr.take();
+        let tee_stderr = self.tee_stderr;
+        let stderr_reader = thread::spawn(move || {
+            stderr.map(|stderr| drain_stderr(stderr, tee_stderr)).unwrap_or_default()
+        });
+
+        let mut stdout_buffer = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                on_stdout_line(&line);
+                stdout_buffer.extend_from_slice(line.as_bytes());
+                stdout_buffer.push(b'\n');
+            }
+        }
+
+        let status = child.wait()
+            .map_err(|_| AtiumError::CommandError("error when waiting for command".to_string()))?;
+        let stderr_buffer = stderr_reader.join().unwrap_or_default();
+
+        Ok(Output { status, stdout: stdout_buffer, stderr: stderr_buffer })
+    }
+    /// Executes the previously built command with its stdout written straight to ours, e.g.
+    /// to pipe raw media to another tool. Only stderr is collected into the returned [`Output`]
+    pub fn execute_with_inherited_stdout(&self, args: Vec<&str>) -> Result<Output, AtiumError> {
+        let mut cmd = Command::new(self.command.clone());
+        cmd.args(args)
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::piped());
+
+        self.log_args(&cmd);
+
+        cmd.output()
+            .map_err(|_| AtiumError::CommandError("error when executing command".to_string()))
+    }
+    /// Executes the previously built command by suppplying a collection of arguments
+    pub fn execute_with_args(&self, args: Vec<&str>) -> Result<Output, AtiumError> {
+        let mut cmd = Command::new(self.command.clone());
+        let cmd_with_args = cmd.args(args);
+
+        self.log_args(cmd_with_args);
 
         match cmd_with_args.output() {
             Ok(result) => Ok(result),
@@ -63,3 +265,33 @@ impl CommandManager {
     }
 }
 
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_stderr_line() {
+        assert_eq!(classify_stderr_line("[swscaler @ 0x1] deprecated pixel format used, make sure you did set range correctly"), Level::Warn);
+        assert_eq!(classify_stderr_line("Error while decoding stream #0:1: Invalid data found when processing input"), Level::Error);
+        assert_eq!(classify_stderr_line("frame=  120 fps= 60 q=28.0 size=     256kB time=00:00:04.00"), Level::Debug);
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("-c:v"), "-c:v");
+        assert_eq!(shell_quote("/tmp/my video.mp4"), "'/tmp/my video.mp4'");
+        assert_eq!(shell_quote("scale=1280:720,fps=25"), "scale=1280:720,fps=25");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn test_drain_stderr() {
+        let stderr = b"Input #0\nframe=1\rframe=2\rwarning: last".to_vec();
+
+        assert_eq!(drain_stderr(&stderr[..], true), stderr);
+        assert_eq!(drain_stderr(&stderr[..], false), stderr);
+    }
+}
\ No newline at end of file
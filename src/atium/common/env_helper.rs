@@ -0,0 +1,62 @@
+use std::env;
+use std::path::PathBuf;
+use crate::atium::common::error::AtiumError;
+
+/// Prefix of the environment variables overriding the built-in defaults, e.g. `ATIUM_CODEC`
+const ENV_PREFIX: &str = "ATIUM_";
+
+/// Returns the value of the `ATIUM_<name>` environment variable, None when it is unset or empty
+pub fn env_default(name: &str) -> Option<String> {
+    env::var(format!("{}{}", ENV_PREFIX, name)).ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Merges a setting of a request: the `explicit` value wins, then the `env_value` of the
+/// `ATIUM_<name>` variable read through `parse`, then the `built_in` default. A variable that
+/// can't be parsed is an error, unless an explicit value makes it irrelevant
+pub fn resolve_setting<T>(
+    explicit: Option<T>,
+    name: &str,
+    env_value: Option<String>,
+    parse: impl FnOnce(&str) -> Result<T, AtiumError>,
+    built_in: T
+) -> Result<T, AtiumError> {
+    if let Some(explicit) = explicit {
+        return Ok(explicit)
+    }
+
+    match env_value {
+        Some(env_value) => parse(&env_value)
+            .map_err(|err| AtiumError::InvalidArgument(format!("Invalid {}{} [{}]: {}", ENV_PREFIX, name, env_value, err))),
+        None => Ok(built_in)
+    }
+}
+
+/// Directory receiving the temporary files, `ATIUM_TMP_DIR` or the one of the system
+pub fn temp_dir() -> PathBuf {
+    env_default("TMP_DIR").map(PathBuf::from).unwrap_or_else(env::temp_dir)
+}
+
+/// Path of the `command` binary: `ATIUM_<COMMAND>`, e.g. `ATIUM_FFMPEG=/opt/ffmpeg/bin/ffmpeg`,
+/// or the command itself, looked up in the `PATH`
+pub fn binary_path(command: &str) -> String {
+    env_default(&command.to_uppercase()).unwrap_or_else(|| command.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_setting() {
+        let parse = |value: &str| value.parse::<u8>()
+            .map_err(|_| AtiumError::InvalidArgument(String::from("not a number")));
+
+        assert_eq!(resolve_setting(Some(20), "CRF", Some(String::from("28")), parse, 23).ok().unwrap(), 20);
+        assert_eq!(resolve_setting(None, "CRF", Some(String::from("28")), parse, 23).ok().unwrap(), 28);
+        assert_eq!(resolve_setting(None, "CRF", None, parse, 23).ok().unwrap(), 23);
+        assert_eq!(resolve_setting(Some(20), "CRF", Some(String::from("high")), parse, 23).ok().unwrap(), 20);
+        assert!(resolve_setting(None, "CRF", Some(String::from("high")), parse, 23).is_err());
+    }
+}
\ No newline at end of file
@@ -6,7 +6,10 @@ use std::fmt::{Formatter};
 pub enum AtiumError {
     ConversionError(String),
     IOError(String),
-    CommandError(String)
+    CommandError(String),
+    InvalidArgument(String),
+    DependencyMissing(String),
+    HardwareError(String)
 }
 
 impl fmt::Display for AtiumError {
@@ -14,7 +17,10 @@ impl fmt::Display for AtiumError {
         match *self {
             AtiumError::ConversionError(ref msg) => write!(f, "Conversion Error: {}", msg),
             AtiumError::IOError(ref msg) => write!(f, "I/O Error: {}", msg),
-            AtiumError::CommandError(ref msg) => write!(f, "Command Error: {}", msg)
+            AtiumError::CommandError(ref msg) => write!(f, "Command Error: {}", msg),
+            AtiumError::InvalidArgument(ref msg) => write!(f, "Invalid Argument: {}", msg),
+            AtiumError::DependencyMissing(ref msg) => write!(f, "Dependency Missing: {}", msg),
+            AtiumError::HardwareError(ref msg) => write!(f, "Hardware Error: {}", msg)
         }
     }
 }
\ No newline at end of file
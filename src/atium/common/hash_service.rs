@@ -0,0 +1,109 @@
+use log::debug;
+use crate::atium::common::command_manager::CommandManager;
+use crate::atium::common::error::AtiumError;
+
+/// The hash of the decoded content of a stream, as written by the `streamhash` muxer
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamHash {
+    /// Index of the stream in the output of the muxer
+    pub index: u32,
+    /// Media type of the stream, `v` for video and `a` for audio
+    pub media_type: String,
+    /// The hexadecimal digest, without the algorithm prefix
+    pub hash: String
+}
+
+/// Parses the `index,type,ALGORITHM=hash` lines written by `-f streamhash`. Lines in any
+/// other shape are skipped
+pub fn parse_stream_hashes(output: &str) -> Vec<StreamHash> {
+    output.lines()
+        .filter_map(|line| {
+            let mut fields = line.trim().splitn(3, ',');
+            let index = fields.next()?.parse::<u32>().ok()?;
+            let media_type = fields.next()?.to_string();
+            let (_, hash) = fields.next()?.split_once('=')?;
+
+            Some(StreamHash { index, media_type, hash: hash.to_lowercase() })
+        })
+        .collect()
+}
+
+/// Tells whether two sets of stream hashes describe the same content. Containers may
+/// store the streams in a different order, so each stream only has to match one of the
+/// other set with the same media type
+pub fn compare_stream_hashes(first: &[StreamHash], second: &[StreamHash]) -> bool {
+    let sorted = |hashes: &[StreamHash]| {
+        let mut hashes: Vec<(String, String)> = hashes.iter()
+            .map(|hash| (hash.media_type.clone(), hash.hash.clone()))
+            .collect();
+        hashes.sort();
+        hashes
+    };
+
+    !first.is_empty() && sorted(first) == sorted(second)
+}
+
+/// FFMPEG Engine Service for hashing the decoded streams of a media
+pub struct FFMPEGHashService {
+    command_manager: CommandManager
+}
+
+impl FFMPEGHashService {
+    /// Returns the SHA-256 of the decoded frames of each video and audio stream of
+    /// `input_file`. Since the decoded content is hashed, the container and the muxing
+    /// don't change the result, while any re-encode does
+    pub fn stream_hashes(&self, input_file: &str) -> Result<Vec<StreamHash>, AtiumError> {
+        let result = self.command_manager.execute_with_args(vec![
+            "-v", "error",
+            "-i", input_file,
+            "-map", "0:v?",
+            "-map", "0:a?",
+            "-f", "streamhash",
+            "-hash", "sha256",
+            "-"
+        ])?;
+        if !result.status.success() {
+            self.command_manager.print_command_output(result.stderr)?;
+            return Err(AtiumError::CommandError("Execution of command returned ERROR".to_string()))
+        }
+
+        let hashes = parse_stream_hashes(&self.command_manager.get_command_output_as_string(result.stdout)?);
+        debug!("Hashed [{}] streams of [{}]", hashes.len(), input_file);
+
+        Ok(hashes)
+    }
+    /// Tells whether the video and audio streams of `first` and `second` decode to the
+    /// same content, even when they are in different containers
+    pub fn streams_identical(&self, first: &str, second: &str) -> Result<bool, AtiumError> {
+        let first_hashes = self.stream_hashes(first)?;
+        let second_hashes = self.stream_hashes(second)?;
+
+        Ok(compare_stream_hashes(&first_hashes, &second_hashes))
+    }
+    /// Constructs a new instance of [`FFMPEGHashService`] by loading and checking `ffmpeg` availability
+    pub fn new() -> Result<Self, AtiumError> {
+        let command_manager =
+            CommandManager::new("ffmpeg".to_string(), vec!["-version"])?;
+
+        Ok(Self { command_manager })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_stream_hashes() {
+        let mp4 = parse_stream_hashes("0,v,SHA256=9F2B6E0A\n1,a,SHA256=41C07D3E\n");
+        let mkv = parse_stream_hashes("0,a,SHA256=41c07d3e\n1,v,SHA256=9f2b6e0a\nInput #0, matroska\n");
+        let reencoded = parse_stream_hashes("0,v,SHA256=0D55A1B2\n1,a,SHA256=41C07D3E\n");
+
+        assert_eq!(mp4[0], StreamHash { index: 0, media_type: String::from("v"), hash: String::from("9f2b6e0a") });
+        assert_eq!(mkv.len(), 2);
+        assert!(compare_stream_hashes(&mp4, &mkv));
+        assert!(!compare_stream_hashes(&mp4, &reencoded));
+        assert!(!compare_stream_hashes(&mp4, &mp4[..1]));
+        assert!(!compare_stream_hashes(&[], &[]));
+    }
+}
\ No newline at end of file
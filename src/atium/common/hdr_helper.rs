@@ -0,0 +1,143 @@
+use serde::Serialize;
+
+use crate::atium::common::analysis_helper::AnalysisOutput;
+
+/// Light levels of the mastering display, in cd/m2
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MasteringLuminance {
+    pub min: f64,
+    pub max: f64
+}
+
+/// Color and HDR metadata of the first video stream, as reported by mediainfo
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HdrInfo {
+    /// e.g. `BT.2020`
+    pub color_primaries: Option<String>,
+    /// e.g. `PQ` or `HLG`
+    pub transfer_characteristics: Option<String>,
+    /// e.g. `BT.2020 non-constant`
+    pub matrix_coefficients: Option<String>,
+    /// HDR format as reported by mediainfo, e.g. `SMPTE ST 2086` or `Dolby Vision`
+    pub hdr_format: Option<String>,
+    /// e.g. `Display P3`
+    pub mastering_display_primaries: Option<String>,
+    pub mastering_display_luminance: Option<MasteringLuminance>,
+    /// Maximum content light level in cd/m2
+    pub max_cll: Option<u32>,
+    /// Maximum frame-average light level in cd/m2
+    pub max_fall: Option<u32>
+}
+
+impl HdrInfo {
+    /// Returns `true` when the stream uses an HDR transfer function, PQ or HLG, or carries
+    /// an HDR format such as Dolby Vision
+    pub fn is_hdr(&self) -> bool {
+        let hdr_transfer = self.transfer_characteristics.as_deref()
+            .map(|transfer| matches!(transfer, "PQ" | "HLG" | "SMPTE ST 2084" | "ARIB STD-B67"))
+            .unwrap_or(false);
+
+        hdr_transfer || self.hdr_format.is_some()
+    }
+}
+
+/// Parses a light level like `1000 cd/m2`
+fn parse_light_level(value: &str) -> Option<f64> {
+    value.trim()
+        .trim_end_matches("cd/m2")
+        .trim()
+        .parse::<f64>()
+        .ok()
+}
+
+/// Parses a mastering display luminance like `min: 0.0050 cd/m2, max: 1000 cd/m2`
+fn parse_mastering_luminance(value: &str) -> Option<MasteringLuminance> {
+    let mut min = None;
+    let mut max = None;
+    for part in value.split(',') {
+        match part.trim().split_once(':') {
+            Some(("min", level)) => min = parse_light_level(level),
+            Some(("max", level)) => max = parse_light_level(level),
+            _ => {}
+        }
+    }
+
+    Some(MasteringLuminance { min: min?, max: max? })
+}
+
+impl AnalysisOutput {
+    /// Returns the [`HdrInfo`] of the first video stream, None when there is no video
+    pub fn hdr_info(&self) -> Option<HdrInfo> {
+        let track = *self.get_tracks_by_type("Video").first()?;
+        let field = |name: &str| self.get_track_field(track, name);
+
+        Some(HdrInfo {
+            color_primaries: field("colour_primaries"),
+            transfer_characteristics: field("transfer_characteristics"),
+            matrix_coefficients: field("matrix_coefficients"),
+            hdr_format: field("HDR_Format"),
+            mastering_display_primaries: field("MasteringDisplay_ColorPrimaries"),
+            mastering_display_luminance: field("MasteringDisplay_Luminance")
+                .and_then(|luminance| parse_mastering_luminance(&luminance)),
+            max_cll: field("MaxCLL")
+                .and_then(|level| parse_light_level(&level))
+                .map(|level| level as u32),
+            max_fall: field("MaxFALL")
+                .and_then(|level| parse_light_level(&level))
+                .map(|level| level as u32)
+        })
+    }
+    /// Returns `true` when the first video stream is HDR, see [`HdrInfo::is_hdr`]
+    pub fn is_hdr(&self) -> bool {
+        self.hdr_info()
+            .map(|hdr_info| hdr_info.is_hdr())
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use crate::atium::common::analysis_helper::MediaInfoJsonLoader;
+    use super::*;
+
+    #[test]
+    fn test_hdr10_metadata() {
+        let json = r#"{"media": {"track": [
+            {"@type": "General", "Format": "Matroska"},
+            {"@type": "Video", "Format": "HEVC", "BitDepth": "10",
+             "HDR_Format": "SMPTE ST 2086", "HDR_Format_Compatibility": "HDR10",
+             "colour_primaries": "BT.2020", "transfer_characteristics": "PQ",
+             "matrix_coefficients": "BT.2020 non-constant",
+             "MasteringDisplay_ColorPrimaries": "Display P3",
+             "MasteringDisplay_Luminance": "min: 0.0050 cd/m2, max: 1000 cd/m2",
+             "MaxCLL": "1000 cd/m2", "MaxFALL": "400 cd/m2"}
+        ]}}"#;
+        let output = MediaInfoJsonLoader{}.load_json_from_string(json).unwrap();
+
+        let hdr_info = output.hdr_info().unwrap();
+
+        assert!(output.is_hdr());
+        assert_eq!(hdr_info.transfer_characteristics.as_deref(), Some("PQ"));
+        assert_eq!(hdr_info.mastering_display_luminance, Some(MasteringLuminance { min: 0.005, max: 1000.0 }));
+        assert_eq!(hdr_info.max_cll, Some(1000));
+        assert_eq!(hdr_info.max_fall, Some(400));
+    }
+
+    #[test]
+    fn test_hlg_and_sdr_media() {
+        let json = r#"{"media": {"track": [
+            {"@type": "General"},
+            {"@type": "Video", "Format": "HEVC", "colour_primaries": "BT.2020", "transfer_characteristics": "HLG"}
+        ]}}"#;
+        assert!(MediaInfoJsonLoader{}.load_json_from_string(json).unwrap().is_hdr());
+
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/test/info.json");
+        let output = MediaInfoJsonLoader{}.load_json_from_file(&d.to_str().unwrap().to_string()).unwrap();
+        assert!(!output.is_hdr());
+
+        let audio_only = r#"{"media": {"track": [{"@type": "General"}, {"@type": "Audio"}]}}"#;
+        assert!(MediaInfoJsonLoader{}.load_json_from_string(audio_only).unwrap().hdr_info().is_none());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,62 @@
+use log::debug;
+use crate::atium::common::command_manager::CommandManager;
+use crate::atium::common::error::AtiumError;
+
+/// Parses the `csv=p=0` output of `ffprobe -show_entries frame=pts_time,pkt_pts_time` into
+/// timestamps in seconds. Newer `ffprobe` versions only know `pts_time` and older ones only
+/// `pkt_pts_time`, so the first number of each line is taken. Lines without one, e.g. `N/A`, are skipped
+pub fn parse_keyframe_times(output: &str) -> Vec<f64> {
+    output.lines()
+        .filter_map(|line| line.split(',')
+            .find_map(|field| field.trim().parse::<f64>().ok().filter(|time| time.is_finite())))
+        .collect()
+}
+
+/// FFProbe Engine Service for inspecting the frames of a media
+pub struct FFProbeKeyframeService {
+    command_manager: CommandManager
+}
+
+impl FFProbeKeyframeService {
+    /// Returns the timestamps, in seconds, of the keyframes of the first video stream of
+    /// `input_file`. Only keyframes are decoded, so it is quick even on long files
+    pub fn keyframes(&self, input_file: &str) -> Result<Vec<f64>, AtiumError> {
+        let result = self.command_manager.execute_with_args(vec![
+            "-v", "error",
+            "-select_streams", "v:0",
+            "-skip_frame", "nokey",
+            "-show_frames",
+            "-show_entries", "frame=pts_time,pkt_pts_time",
+            "-of", "csv=p=0",
+            input_file
+        ])?;
+        if !result.status.success() {
+            self.command_manager.print_command_output(result.stderr)?;
+            return Err(AtiumError::CommandError("Execution of command returned ERROR".to_string()))
+        }
+
+        let keyframes = parse_keyframe_times(&self.command_manager.get_command_output_as_string(result.stdout)?);
+        debug!("Found [{}] keyframes in [{}]", keyframes.len(), input_file);
+
+        Ok(keyframes)
+    }
+    /// Constructs a new instance of [`FFProbeKeyframeService`] by loading and checking `ffprobe` availability
+    pub fn new() -> Result<Self, AtiumError> {
+        let command_manager =
+            CommandManager::new("ffprobe".to_string(), vec!["-version"])?;
+
+        Ok(Self { command_manager })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keyframe_times() {
+        assert_eq!(parse_keyframe_times("0.000000\n2.002000\n\n4.004000,\nN/A\n"), vec![0.0, 2.002, 4.004]);
+        assert_eq!(parse_keyframe_times("N/A,6.006000\n"), vec![6.006]);
+        assert!(parse_keyframe_times("").is_empty());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,66 @@
+use std::env;
+use std::io::Write;
+use chrono::{SecondsFormat, Utc};
+use log::Level;
+use serde_json::json;
+use crate::atium::common::error::AtiumError;
+
+/// How log records are written to stderr
+#[derive(Clone, Debug, PartialEq)]
+pub enum LogFormat {
+    /// The default `env_logger` human readable lines
+    Text,
+    /// A JSON object per line, suitable for log aggregators
+    Json
+}
+
+/// Returns a value of [`LogFormat`] based on input: `text` or `json`
+pub fn parse_log_format(input: &str) -> Result<LogFormat, AtiumError> {
+    match input.to_lowercase().as_str() {
+        "text" => Ok(LogFormat::Text),
+        "json" => Ok(LogFormat::Json),
+        _ => Err(AtiumError::InvalidArgument(format!("Unknown log format [{}], valid formats are: text, json", input)))
+    }
+}
+
+/// Formats a log record as a single JSON line
+fn format_json_line(timestamp: &str, level: Level, target: &str, message: &str) -> String {
+    json!({
+        "timestamp": timestamp,
+        "level": level.as_str(),
+        "target": target,
+        "message": message
+    }).to_string()
+}
+
+/// Initializes the logger with the given [`LogFormat`]. The level filter is still read
+/// from `RUST_LOG`, which defaults to `error`
+pub fn init_logger(format: LogFormat) {
+    let rust_log = "RUST_LOG";
+    if env::var(rust_log).is_err() {
+        env::set_var(rust_log, "error");
+    }
+
+    let mut builder = env_logger::Builder::from_default_env();
+    if let LogFormat::Json = format {
+        builder.format(|buf, record| {
+            let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+            writeln!(buf, "{}", format_json_line(&timestamp, record.level(), record.target(), &record.args().to_string()))
+        });
+    }
+    builder.init()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_json_line() {
+        let line = format_json_line("2023-01-10T10:00:00.000Z", Level::Warn, "atium::converter", "Output \"a.mp4\" exists");
+
+        assert_eq!(line, r#"{"level":"WARN","message":"Output \"a.mp4\" exists","target":"atium::converter","timestamp":"2023-01-10T10:00:00.000Z"}"#);
+        assert_eq!(parse_log_format("JSON").ok().unwrap(), LogFormat::Json);
+        assert!(parse_log_format("xml").is_err());
+    }
+}
\ No newline at end of file
@@ -1,6 +1,20 @@
 pub mod command_manager;
 pub mod error;
 pub mod model;
+pub mod builder;
 pub mod thumbnail_service;
 pub mod analysis_helper;
 pub mod analysis_service;
+pub mod capability_helper;
+pub mod advice_helper;
+pub mod sprite_helper;
+pub mod logging_helper;
+pub mod track_helper;
+pub mod hdr_helper;
+pub mod summary_helper;
+pub mod keyframe_service;
+pub mod hash_service;
+pub mod env_helper;
+pub mod time_helper;
+#[cfg(feature = "watch")]
+pub mod watch_service;
\ No newline at end of file
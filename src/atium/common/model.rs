@@ -1,14 +1,43 @@
+use std::time::Duration;
+use serde::Serialize;
+
+/// How the frames used as thumbnails are selected
+#[derive(Clone, Serialize)]
+pub enum ThumbnailSelection {
+    /// A single frame at the requested timestamp
+    Timestamp,
+    /// A frame for each detected scene change, `threshold` goes from 0 to 1 and lower
+    /// values detect more cuts
+    Scenes { threshold: f32 },
+    /// The brightest of the frames at 10, 25, 50 and 75% of the input, by their average
+    /// luminance, so black fades and intros are not picked
+    AvoidBlack,
+    /// The frame at this percentage of the duration, from 0 to 100, so thumbnails of
+    /// inputs of any length are taken at the same point
+    Percentage(f32)
+}
+
 /// A Thumbnail extraction request
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct ThumbnailRequest {
-    /// A timestamp with format `hh:mm:ss`
+    /// A timestamp like `hh:mm:ss` or `ss.mmm`, see [`crate::atium::common::time_helper::parse_timestamp`]
     pub timestamp: Option<String>,
     /// The filepath from where the thumbnail will be extracted
     pub input_file: Option<String>,
     /// A filepath where the thumbnail will be saved
     pub output_file: Option<String>,
     /// Input file duration. If None, it will be computed afterwards
-    pub input_duration: Option<String>
+    pub input_duration: Option<String>,
+    /// How frames are selected. If None, the default will be set to [`ThumbnailSelection::Timestamp`]
+    pub selection: Option<ThumbnailSelection>,
+    /// Crops the largest centered square of the frame, as avatar-style thumbnails need
+    pub crop_to_center: bool,
+    /// Width of the thumbnail in pixels, the height keeps the aspect ratio. If None,
+    /// the frame size is kept
+    pub width: Option<u32>,
+    /// Image quality from 0 to 100, translated to the encoder scale of the output format.
+    /// If None, `ffmpeg` defaults are used
+    pub quality: Option<u8>
 }
 
 impl ThumbnailRequest {
@@ -29,21 +58,133 @@ impl ThumbnailRequest {
                 input_file: input_file.clone(),
                 output_file: output_file.clone(),
                 // Will be computed later
-                input_duration: None
+                input_duration: None,
+                selection: None,
+                crop_to_center: false,
+                width: None,
+                quality: None
             }
         )
     }
 }
 
+/// A request extracting every frame of a video as numbered `frame_%06d.png` images
+pub struct FrameExtractionRequest {
+    pub input_file: String,
+    /// Directory where frames are written, it is created when missing
+    pub output_dir: String,
+    /// Subsamples the frames with the `fps` filter, e.g. `1` for a frame per second.
+    /// If None, every frame is extracted
+    pub fps: Option<String>,
+    /// Number of the first image, e.g. to carry on the numbering of a previous run in the
+    /// same directory. If None, numbering starts at 1
+    pub start_number: Option<u64>
+}
+
+pub struct FrameExtractionResponse {
+    pub output_dir: String,
+    /// Number of frame images found in `output_dir` after the extraction
+    pub frame_count: usize
+}
+
+pub struct SpriteRequest {
+    pub input_file: String,
+    /// Sprite sheet image, the WebVTT track is written next to it with the `vtt` extension
+    pub output_file: String,
+    /// Seconds of video covered by each tile
+    pub interval_secs: f64,
+    /// Tiles in each row of the sprite
+    pub columns: u32,
+    /// Width of each tile in pixels, the height keeps the source aspect ratio
+    pub tile_width: u32
+}
+
+pub struct SpriteResponse {
+    pub output_file: String,
+    pub vtt_file: String,
+    pub tile_count: u32
+}
+
+/// A single row strip of frames spread evenly over the input, as timeline UIs expect.
+/// Unlike the [`SpriteRequest`] grid it has a fixed number of frames and no track
+pub struct FilmstripRequest {
+    pub input_file: String,
+    pub output_file: String,
+    /// Frames placed side by side
+    pub count: u32,
+    /// Height of each frame in pixels, the width keeps the source aspect ratio
+    pub height: u32
+}
+
+pub struct FilmstripResponse {
+    pub output_file: String,
+    /// Timestamp of each frame of the strip, from left to right
+    pub timestamps_secs: Vec<f64>
+}
+
+/// Resources used by an external command, useful for profiling encode settings
+#[derive(Clone)]
+pub struct ResourceUsage {
+    /// Peak resident memory of the child process in kilobytes, None when unavailable
+    pub max_rss_kb: Option<u64>,
+    /// User CPU time spent by the child process, None when unavailable
+    pub user_cpu_time: Option<Duration>
+}
+
 pub struct ThumbnailResponse {
-    pub output: String
+    pub output: String,
+    /// Approximate timestamp of the extracted frame in seconds, when known
+    pub timestamp_secs: Option<f64>
+}
+
+/// Strategy applied when the requested output path already exists
+#[derive(Clone, Serialize)]
+pub enum CollisionPolicy {
+    /// Fails with an error
+    Error,
+    /// Replaces the existing file
+    Overwrite,
+    /// Appends `_1`, `_2`, etc. to the file name until a free path is found
+    Rename
+}
+
+pub fn parse_collision_policy(input: Option<String>) -> Option<CollisionPolicy> {
+    input.map(|policy| match policy.to_lowercase().as_str() {
+        "error" => CollisionPolicy::Error,
+        "overwrite" => CollisionPolicy::Overwrite,
+        "rename" => CollisionPolicy::Rename,
+        _ => CollisionPolicy::Rename
+    })
+}
+
+
+/// Where an input is read from
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputProtocol {
+    /// A path on the local file system
+    Local,
+    /// A remote input, holding the lowercase URL scheme, e.g. `https`
+    Remote(String)
 }
 
+/// Distinguishes URLs from local paths. Inputs like `https://host/video.mp4` are
+/// [`InputProtocol::Remote`], anything without a valid `scheme://` prefix is [`InputProtocol::Local`]
+pub fn detect_protocol(input: &str) -> InputProtocol {
+    match input.split_once("://") {
+        Some((scheme, _)) if !scheme.is_empty()
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.') =>
+            InputProtocol::Remote(scheme.to_lowercase()),
+        _ => InputProtocol::Local
+    }
+}
 
 /// Supported values for infos output format
 #[derive(Clone)]
 pub enum InfoFormat {
-    Json, Html, Xml
+    Json, Html, Xml,
+    /// A one-line summary such as `1920x1080 h264 24fps 5m32s 8.2Mbps`, built from the json output
+    Summary
 }
 
 pub fn parse_info_format(input: Option<String>) -> Option<InfoFormat> {
@@ -51,6 +192,7 @@ pub fn parse_info_format(input: Option<String>) -> Option<InfoFormat> {
         "json" => InfoFormat::Json,
         "html" => InfoFormat::Html,
         "xml" => InfoFormat::Xml,
+        "summary" => InfoFormat::Summary,
         _ => InfoFormat::Json
     })
 }
@@ -92,7 +234,9 @@ pub struct InfoExtractorRequest {
     pub output_file: Option<String>,
     /// If Some of [`InfoOutputType`] it contains one of Stdout, File, Plain options
     /// Default to Stdout
-    pub output_type: Option<InfoOutputType>
+    pub output_type: Option<InfoOutputType>,
+    /// Pretty-prints the JSON output, ignored for the other formats
+    pub pretty: bool
 }
 
 /// It represents the response output of the extracted info
@@ -107,4 +251,19 @@ pub struct InfoExtractorResponseOutput {
 pub struct InfoExtractorResponse {
     /// The [`InfoExtractorResponseOutput`] containing pointers to the actual output
     pub output: InfoExtractorResponseOutput
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_protocol() {
+        assert_eq!(detect_protocol("https://example.com/video.mp4"), InputProtocol::Remote(String::from("https")));
+        assert_eq!(detect_protocol("HTTP://example.com/video.mp4"), InputProtocol::Remote(String::from("http")));
+        assert_eq!(detect_protocol("/tmp/video.mp4"), InputProtocol::Local);
+        assert_eq!(detect_protocol("video://.mp4"), InputProtocol::Remote(String::from("video")));
+        assert_eq!(detect_protocol("/tmp/a://b.mp4"), InputProtocol::Local);
+        assert_eq!(detect_protocol("://video.mp4"), InputProtocol::Local);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,165 @@
+use std::path::Path;
+use crate::atium::common::error::AtiumError;
+
+/// Placement of the tiles of a sprite sheet, all cells share the same size
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteLayout {
+    pub columns: u32,
+    pub rows: u32,
+    pub tile_width: u32,
+    pub tile_height: u32
+}
+
+impl SpriteLayout {
+    /// Computes the layout of a sprite holding a tile every `interval_secs` of a video lasting
+    /// `duration_secs`. The tile height keeps the source aspect ratio and is rounded to an even
+    /// number, like `ffmpeg` scaling does for most pixel formats
+    pub fn compute(source_size: (u32, u32), duration_secs: f64, interval_secs: f64, columns: u32, tile_width: u32) -> Result<SpriteLayout, AtiumError> {
+        let (width, height) = source_size;
+        if width == 0 || height == 0 {
+            return Err(AtiumError::InvalidArgument(format!("Invalid source resolution [{}x{}]", width, height)))
+        }
+        if columns == 0 || tile_width == 0 {
+            return Err(AtiumError::InvalidArgument("Sprite columns and tile width must be greater than 0".to_string()))
+        }
+        let tile_count = compute_tile_count(duration_secs, interval_secs)?;
+
+        let tile_height = ((tile_width as f64 * height as f64 / width as f64 / 2.0).round() as u32 * 2).max(2);
+
+        Ok(SpriteLayout {
+            columns,
+            rows: tile_count.div_ceil(columns),
+            tile_width,
+            tile_height
+        })
+    }
+    /// Computes the single row layout of a filmstrip of `count` frames, each `tile_height` pixels
+    /// tall. The tile width keeps the source aspect ratio and is rounded to an even number
+    pub fn filmstrip(source_size: (u32, u32), count: u32, tile_height: u32) -> Result<SpriteLayout, AtiumError> {
+        let (width, height) = source_size;
+        if width == 0 || height == 0 {
+            return Err(AtiumError::InvalidArgument(format!("Invalid source resolution [{}x{}]", width, height)))
+        }
+        if count == 0 || tile_height == 0 {
+            return Err(AtiumError::InvalidArgument("Filmstrip frame count and height must be greater than 0".to_string()))
+        }
+        let tile_width = ((tile_height as f64 * width as f64 / height as f64 / 2.0).round() as u32 * 2).max(2);
+        if count as u64 * tile_width as u64 > MAX_IMAGE_SIDE as u64 {
+            return Err(AtiumError::InvalidArgument(format!(
+                "Filmstrip of [{}] frames [{}] pixels wide is wider than [{}] pixels", count, tile_width, MAX_IMAGE_SIDE)))
+        }
+
+        Ok(SpriteLayout { columns: count, rows: 1, tile_width, tile_height })
+    }
+    /// Returns the `x,y` pixel position of the `index`th tile, filling rows first
+    pub fn cell_position(&self, index: u32) -> (u32, u32) {
+        ((index % self.columns) * self.tile_width, (index / self.columns) * self.tile_height)
+    }
+}
+
+/// Widest image the jpeg encoder can write, in pixels
+const MAX_IMAGE_SIDE: u32 = 65535;
+
+/// Returns the seconds between the frames of a filmstrip spreading `count` frames over
+/// `duration_secs`, along with the timestamp of each frame, the first one being at 0
+pub fn compute_filmstrip_timestamps(duration_secs: f64, count: u32) -> Result<(f64, Vec<f64>), AtiumError> {
+    if duration_secs.is_nan() || duration_secs <= 0.0 {
+        return Err(AtiumError::InvalidArgument("Cannot build a filmstrip for an input without duration".to_string()))
+    }
+    if count == 0 {
+        return Err(AtiumError::InvalidArgument("Filmstrip frame count must be greater than 0".to_string()))
+    }
+    let interval_secs = duration_secs / count as f64;
+
+    Ok((interval_secs, (0..count).map(|index| index as f64 * interval_secs).collect()))
+}
+
+/// Returns the number of tiles needed to cover `duration_secs` with a tile every `interval_secs`
+pub fn compute_tile_count(duration_secs: f64, interval_secs: f64) -> Result<u32, AtiumError> {
+    if interval_secs.is_nan() || interval_secs <= 0.0 {
+        return Err(AtiumError::InvalidArgument(format!("Sprite interval [{}] must be greater than 0", interval_secs)))
+    }
+    if duration_secs.is_nan() || duration_secs <= 0.0 {
+        return Err(AtiumError::InvalidArgument("Cannot build a sprite for an input without duration".to_string()))
+    }
+
+    Ok((duration_secs / interval_secs).ceil() as u32)
+}
+
+/// Formats seconds as a WebVTT timestamp, `hh:mm:ss.mmm`
+fn format_vtt_timestamp(secs: f64) -> String {
+    let millis = (secs * 1000.0).round() as u64;
+
+    format!("{:02}:{:02}:{:02}.{:03}", millis / 3_600_000, (millis / 60_000) % 60, (millis / 1000) % 60, millis % 1000)
+}
+
+/// Builds a WebVTT thumbnail track for `sprite_file`, with a cue for each tile of the sprite
+/// pointing at its region through a `#xywh=x,y,w,h` fragment. Cues are referenced by file name
+/// only, so the track is expected to be served next to the sprite
+pub fn build_vtt_track(sprite_file: &str, layout: &SpriteLayout, duration_secs: f64, interval_secs: f64) -> Result<String, AtiumError> {
+    let sprite_name = Path::new(sprite_file).file_name()
+        .and_then(|name| name.to_str())
+        .ok_or(AtiumError::IOError("Could not parse sprite filename".to_string()))?;
+    let tile_count = compute_tile_count(duration_secs, interval_secs)?;
+
+    let mut track = String::from("WEBVTT\n");
+    for index in 0..tile_count {
+        let start = index as f64 * interval_secs;
+        let end = (start + interval_secs).min(duration_secs);
+        let (x, y) = layout.cell_position(index);
+        track.push_str(&format!("\n{} --> {}\n{}#xywh={},{},{},{}\n",
+            format_vtt_timestamp(start),
+            format_vtt_timestamp(end),
+            sprite_name,
+            x, y, layout.tile_width, layout.tile_height));
+    }
+
+    Ok(track)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_sprite_layout() {
+        let layout = SpriteLayout::compute((1920, 1080), 125.0, 10.0, 5, 160).ok().unwrap();
+
+        assert_eq!(layout, SpriteLayout { columns: 5, rows: 3, tile_width: 160, tile_height: 90 });
+        assert_eq!(layout.cell_position(7), (320, 90));
+        assert!(SpriteLayout::compute((1920, 1080), 125.0, 0.0, 5, 160).is_err());
+        assert!(SpriteLayout::compute((1920, 1080), 125.0, 10.0, 0, 160).is_err());
+    }
+
+    #[test]
+    fn test_filmstrip() {
+        let layout = SpriteLayout::filmstrip((1920, 1080), 8, 90).ok().unwrap();
+
+        assert_eq!(layout, SpriteLayout { columns: 8, rows: 1, tile_width: 160, tile_height: 90 });
+        assert_eq!(layout.cell_position(7), (1120, 0));
+        assert!(SpriteLayout::filmstrip((1920, 1080), 0, 90).is_err());
+        assert!(SpriteLayout::filmstrip((1920, 1080), 500, 90).is_err());
+
+        assert_eq!(compute_filmstrip_timestamps(20.0, 4).ok().unwrap(), (5.0, vec![0.0, 5.0, 10.0, 15.0]));
+        assert!(compute_filmstrip_timestamps(0.0, 4).is_err());
+    }
+
+    #[test]
+    fn test_build_vtt_track() {
+        let layout = SpriteLayout { columns: 2, rows: 2, tile_width: 160, tile_height: 90 };
+        let track = build_vtt_track("/tmp/out/sprite.jpg", &layout, 25.5, 10.0).ok().unwrap();
+
+        assert_eq!(track, "WEBVTT
+
+00:00:00.000 --> 00:00:10.000
+sprite.jpg#xywh=0,0,160,90
+
+00:00:10.000 --> 00:00:20.000
+sprite.jpg#xywh=160,0,160,90
+
+00:00:20.000 --> 00:00:25.500
+sprite.jpg#xywh=0,90,160,90
+");
+        assert_eq!(format_vtt_timestamp(3725.25), "01:02:05.250");
+    }
+}
\ No newline at end of file
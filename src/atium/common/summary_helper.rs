@@ -0,0 +1,109 @@
+use crate::atium::common::analysis_helper::AnalysisOutput;
+
+/// Formats a duration in seconds the compact way, e.g. `5m32s` or `1h02m05s`
+pub fn format_compact_duration(duration_secs: f64) -> String {
+    let total_secs = duration_secs.max(0.0).round() as u64;
+    let (hours, minutes, secs) = (total_secs / 3600, total_secs % 3600 / 60, total_secs % 60);
+
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", secs),
+        (0, _) => format!("{}m{:02}s", minutes, secs),
+        _ => format!("{}h{:02}m{:02}s", hours, minutes, secs)
+    }
+}
+
+/// Formats a bitrate in bits per second the compact way, e.g. `8.2Mbps` or `128kbps`
+pub fn format_compact_bitrate(bitrate_bps: u64) -> String {
+    if bitrate_bps >= 1_000_000 {
+        format!("{:.1}Mbps", bitrate_bps as f64 / 1_000_000.0)
+    } else {
+        format!("{}kbps", (bitrate_bps as f64 / 1000.0).round())
+    }
+}
+
+/// Formats a frame rate dropping the trailing zeros, e.g. `24.000` becomes `24fps`
+pub fn format_compact_frame_rate(frame_rate: f64) -> String {
+    let formatted = format!("{:.3}", frame_rate);
+
+    format!("{}fps", formatted.trim_end_matches('0').trim_end_matches('.'))
+}
+
+/// Maps a mediainfo format name to the name `ffmpeg` users know, e.g. `AVC` to `h264`
+fn compact_codec_name(format: &str) -> String {
+    match format {
+        "AVC" => String::from("h264"),
+        "HEVC" => String::from("hevc"),
+        "MPEG-4 Visual" => String::from("mpeg4"),
+        _ => format.to_lowercase().replace(' ', "")
+    }
+}
+
+impl AnalysisOutput {
+    /// Returns a one-line summary of the media, e.g. `1920x1080 h264 24fps 5m32s 8.2Mbps`.
+    /// Fields missing from the analysis are left out, and audio-only media start with
+    /// the audio codec
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        let general = self.get_tracks_by_type("General").into_iter().next();
+        let video = self.get_tracks_by_type("Video").into_iter().next();
+        let audio = self.get_tracks_by_type("Audio").into_iter().next();
+
+        if let Some(video) = video {
+            if let (Some(width), Some(height)) = (self.get_track_field(video, "Width"), self.get_track_field(video, "Height")) {
+                parts.push(format!("{}x{}", width, height));
+            }
+            if let Some(format) = self.get_track_field(video, "Format") {
+                parts.push(compact_codec_name(&format));
+            }
+            if let Some(frame_rate) = self.get_track_field(video, "FrameRate").and_then(|rate| rate.parse::<f64>().ok()) {
+                parts.push(format_compact_frame_rate(frame_rate));
+            }
+        } else if let Some(format) = audio.and_then(|audio| self.get_track_field(audio, "Format")) {
+            parts.push(compact_codec_name(&format));
+        }
+
+        if let Some(general) = general {
+            if let Some(duration) = self.get_track_field(general, "Duration").and_then(|duration| duration.parse::<f64>().ok()) {
+                parts.push(format_compact_duration(duration));
+            }
+            if let Some(bitrate) = self.get_track_field(general, "OverallBitRate").and_then(|bitrate| bitrate.parse::<f64>().ok()) {
+                parts.push(format_compact_bitrate(bitrate as u64));
+            }
+        }
+
+        parts.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::atium::common::analysis_helper::MediaInfoJsonLoader;
+    use super::*;
+
+    #[test]
+    fn test_format_compact_values() {
+        assert_eq!(format_compact_duration(332.4), "5m32s");
+        assert_eq!(format_compact_duration(45.0), "45s");
+        assert_eq!(format_compact_duration(3725.0), "1h02m05s");
+        assert_eq!(format_compact_bitrate(8_200_000), "8.2Mbps");
+        assert_eq!(format_compact_bitrate(128_000), "128kbps");
+        assert_eq!(format_compact_frame_rate(24.0), "24fps");
+        assert_eq!(format_compact_frame_rate(23.976), "23.976fps");
+    }
+
+    #[test]
+    fn test_summary() {
+        let video = MediaInfoJsonLoader{}.load_json_from_string(r#"{"media": {"track": [
+            {"@type": "General", "Duration": "332.000", "OverallBitRate": "8200000"},
+            {"@type": "Video", "Format": "AVC", "Width": "1920", "Height": "1080", "FrameRate": "24.000"},
+            {"@type": "Audio", "Format": "AAC"}
+        ]}}"#).ok().unwrap();
+        let audio = MediaInfoJsonLoader{}.load_json_from_string(r#"{"media": {"track": [
+            {"@type": "General", "Duration": "185.2", "OverallBitRate": "320000"},
+            {"@type": "Audio", "Format": "MPEG Audio"}
+        ]}}"#).ok().unwrap();
+
+        assert_eq!(video.summary(), "1920x1080 h264 24fps 5m32s 8.2Mbps");
+        assert_eq!(audio.summary(), "mpegaudio 3m05s 320kbps");
+    }
+}
\ No newline at end of file
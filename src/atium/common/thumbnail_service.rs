@@ -1,33 +1,152 @@
-use chrono::{NaiveTime};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
 use log::{debug, error, info, warn};
-use crate::atium::common::analysis_helper::{compute_output_file, MediaInfoJsonLoader};
+use crate::atium::common::analysis_helper::{build_output_from_input_path, compute_output_file, MediaInfoJsonLoader};
 use crate::atium::common::command_manager::CommandManager;
 use crate::atium::common::error::AtiumError;
-use crate::atium::common::model::{InfoFormat, InfoOutputType, ThumbnailRequest, ThumbnailResponse};
+use crate::atium::common::model::{CollisionPolicy, FilmstripRequest, FilmstripResponse, FrameExtractionRequest, FrameExtractionResponse, InfoFormat, InfoOutputType, SpriteRequest, SpriteResponse, ThumbnailRequest, ThumbnailResponse, ThumbnailSelection};
+use crate::atium::common::sprite_helper::{build_vtt_track, compute_filmstrip_timestamps, compute_tile_count, SpriteLayout};
+use crate::atium::common::time_helper::{format_timestamp, parse_timestamp};
 use crate::{InfoExtractorRequest, MediaInfoExtractorService};
+use crate::converter::model::validate_frame_rate;
 
+/// Positions of the frames sampled by [`ThumbnailSelection::AvoidBlack`], as fractions of the duration
+const AVOID_BLACK_POSITIONS: [f64; 4] = [0.10, 0.25, 0.50, 0.75];
+
+/// How far before the end a [`ThumbnailSelection::Percentage`] of 100 seeks, since there
+/// is no frame left to extract right at the end
+const LAST_FRAME_MARGIN_SECS: f64 = 0.1;
+
+/// Checks that a [`ThumbnailSelection::Percentage`] is between 0 and 100
+fn validate_percentage(percentage: f32) -> Result<(), AtiumError> {
+    if (0.0..=100.0).contains(&percentage) {
+        Ok(())
+    } else {
+        Err(AtiumError::InvalidArgument(format!("Thumbnail percentage [{}] must be between 0 and 100", percentage)))
+    }
+}
+
+/// Returns the timestamp, in seconds, found at `percentage` of `duration_secs`
+fn compute_percentage_timestamp(duration_secs: f64, percentage: f32) -> f64 {
+    let last_frame_secs = (duration_secs - LAST_FRAME_MARGIN_SECS).max(0.0);
+
+    (duration_secs * percentage as f64 / 100.0).min(last_frame_secs)
+}
+
+/// Extracts the `pts_time` of each frame reported by the `showinfo` filter, in order
+fn parse_showinfo_timestamps(output: &str) -> Vec<f64> {
+    output.lines()
+        .filter(|line| line.contains("Parsed_showinfo"))
+        .filter_map(|line| line.split_whitespace()
+            .find_map(|token| token.strip_prefix("pts_time:"))
+            .and_then(|value| value.parse::<f64>().ok()))
+        .collect()
+}
+
+/// Extracts the average luminance printed by `signalstats,metadata=print` for the first
+/// frame, None when the output has no `YAVG` entry
+fn parse_signalstats_yavg(output: &str) -> Option<f64> {
+    output.lines()
+        .find_map(|line| line.split_whitespace()
+            .find_map(|token| token.strip_prefix("lavfi.signalstats.YAVG="))
+            .and_then(|value| value.parse::<f64>().ok()))
+}
+
+/// Returns the timestamp of the brightest `(timestamp, luminance)` candidate, the earliest
+/// one on ties
+fn pick_brightest(candidates: &[(f64, f64)]) -> Option<f64> {
+    candidates.iter()
+        .fold(None, |brightest: Option<(f64, f64)>, &(timestamp, luminance)| match brightest {
+            Some((_, brightest_luminance)) if brightest_luminance >= luminance => brightest,
+            _ => Some((timestamp, luminance))
+        })
+        .map(|(timestamp, _)| timestamp)
+}
+
+/// Builds the filters resizing a thumbnail: the centered square crop comes first so the
+/// scaling applies to the cropped region. `min(iw,ih)` picks the short side, so portrait
+/// and landscape sources are both handled without probing them. Returns None when the
+/// frame is kept as it is
+fn build_thumbnail_filter(crop_to_center: bool, width: Option<u32>) -> Option<String> {
+    let mut filters = Vec::new();
+    if crop_to_center {
+        filters.push(String::from("crop=min(iw\\,ih):min(iw\\,ih)"));
+    }
+    if let Some(width) = width {
+        filters.push(format!("scale={}:-2", width));
+    }
+
+    if filters.is_empty() {
+        None
+    } else {
+        Some(filters.join(","))
+    }
+}
+
+/// Translates a 0-100 `quality` into the encoder options of the image format of
+/// `output_file`: `-q:v` from 31 down to 2 for JPEG, and `-compression_level` from 9 down
+/// to 0 for PNG. PNG is lossless, so there a higher quality only trades size for speed
+fn build_quality_args(output_file: &str, quality: u8) -> Result<Vec<String>, AtiumError> {
+    if quality > 100 {
+        return Err(AtiumError::InvalidArgument(format!("Thumbnail quality [{}] must be between 0 and 100", quality)))
+    }
+
+    let extension = Path::new(output_file).extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase())
+        .unwrap_or_default();
+    let quality = quality as u32;
+    match extension.as_str() {
+        "jpg" | "jpeg" => Ok(vec![
+            String::from("-q:v"),
+            (31 - (quality * 29 + 50) / 100).to_string()
+        ]),
+        "png" => Ok(vec![
+            String::from("-compression_level"),
+            (((100 - quality) * 9 + 50) / 100).to_string()
+        ]),
+        _ => Err(AtiumError::InvalidArgument(format!("Thumbnail quality is only supported for jpeg and png outputs, not [{}]", output_file)))
+    }
+}
+
+/// Counts the `frame_<digits>.png` files written by a frame extraction in `output_dir`
+fn count_frame_files(output_dir: &Path) -> Result<usize, AtiumError> {
+    let entries = fs::read_dir(output_dir)
+        .map_err(|err| AtiumError::IOError(format!("Could not read output directory: {}", err)))?;
+
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_str()
+            .and_then(|name| name.strip_prefix("frame_"))
+            .and_then(|name| name.strip_suffix(".png"))
+            .map(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or(false))
+        .count())
+}
 
 pub struct FFMPEGThumbnailService {
     command_manager: CommandManager
 }
 
 impl FFMPEGThumbnailService{
-    fn build_args(&self, input_file: String, output_file: String, timestamp: String) -> Vec<String> {
-        vec![
+    fn build_args(&self, input_file: String, output_file: String, timestamp: String, filter: Option<String>, quality_args: Vec<String>) -> Vec<String> {
+        let mut args = vec![
             String::from("-i"),
             input_file,
             String::from("-ss"),
             timestamp,
             String::from("-vframes"),
-            String::from("1"),
-            output_file,
-        ]
-    }
-    fn build_output_from_input_path(&self, input_file: String) -> String {
-        let mut out = input_file;
-        out.push_str(".jpeg");
+            String::from("1")
+        ];
+        if let Some(filter) = filter {
+            args.push(String::from("-vf"));
+            args.push(filter);
+        }
+        args.extend(quality_args);
+        args.push(output_file);
 
-        out
+        args
     }
     fn get_source_duration(&self, input_file: String) -> Result<String, AtiumError> {
         let service = MediaInfoExtractorService::new()?;
@@ -36,7 +155,8 @@ impl FFMPEGThumbnailService{
             format: Some(InfoFormat::Json),
             full: None,
             output_file: None,
-            output_type: Some(InfoOutputType::Plain)
+            output_type: Some(InfoOutputType::Plain),
+            pretty: false
         };
 
         let response = service.get_info(request)?
@@ -49,10 +169,10 @@ impl FFMPEGThumbnailService{
             Ok(output) => {
                 debug!("Analysis done!");
 
-                output.extract_field_from_track(0, &"Duration_String3".to_string())
+                output.extract_field_from_track(0, "Duration_String3")
                     .map(|duration_field| {
                         let split = duration_field.split('.').collect::<Vec<_>>();
-                        let duration_in_secs = split.get(0)
+                        let duration_in_secs = split.first()
                             .map(|s| s.to_string())
                             .unwrap_or("00:00:01".to_string());
 
@@ -67,28 +187,178 @@ impl FFMPEGThumbnailService{
             }
         }
     }
-    fn compute_timestamp(&self, input_file: String, thumbnail_request: ThumbnailRequest) -> Result<String, AtiumError> {
+    fn compute_timestamp(&self, input_file: String, thumbnail_request: ThumbnailRequest) -> Result<Duration, AtiumError> {
         debug!("Computing timestamp for thumbnail extraction");
-        let binding_req_ts = thumbnail_request.timestamp.unwrap_or_else(|| String::from("00:00:01"));
-        let source_duration = self.get_source_duration(input_file)?;
-        let req_time = NaiveTime::parse_from_str(binding_req_ts.as_str(), "%H:%M:%S")
-            .map_err(|err| {
-                warn!("Error parsing requested timestamp: {}", err);
-                AtiumError::IOError("An error occurred when parsing requested timestamp".to_string())
-            })?;
-        let source_duration_time = NaiveTime::parse_from_str(source_duration.as_str(), "%H:%M:%S")
-            .map_err(|err| {
-                warn!("Error parsing duration timestamp: {}", err);
-                AtiumError::IOError("An error occurred when parsing duration timestamp".to_string())
-            })?;
+        let req_time = match &thumbnail_request.timestamp {
+            Some(timestamp) => parse_timestamp(timestamp)?,
+            None => Duration::from_secs(1)
+        };
+        let source_duration_secs = self.get_source_duration_secs(input_file, thumbnail_request.input_duration)?;
 
-        if req_time.lt(&source_duration_time) {
-            Ok(binding_req_ts)
+        if req_time.as_secs_f64() < source_duration_secs {
+            Ok(req_time)
         } else {
             debug!("Requested timestamp is greater than the input duration");
             debug!("Thumbnail extraction timestamp will be set to 00:00:00.000");
 
-            Ok("00:00:00.000".to_string())
+            Ok(Duration::ZERO)
+        }
+    }
+    fn build_pattern_output(&self, output_file: &str) -> Result<(String, String), AtiumError> {
+        let path = Path::new(output_file);
+        let stem = path.file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or(AtiumError::IOError("Could not parse filename".to_string()))?;
+        let extension = path.extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("jpeg");
+
+        let prefix = path.with_file_name(stem);
+        let prefix = prefix.to_str()
+            .ok_or(AtiumError::IOError("Could not build output path".to_string()))?;
+
+        Ok((prefix.to_string(), extension.to_string()))
+    }
+    fn extract_scene_thumbnails(&self, thumbnail_request: ThumbnailRequest, threshold: f32) -> Result<Vec<ThumbnailResponse>, AtiumError> {
+        if !(threshold > 0.0 && threshold < 1.0) {
+            return Err(AtiumError::InvalidArgument(format!("Scene threshold [{}] must be between 0 and 1", threshold)))
+        }
+
+        let input_file = thumbnail_request.input_file.expect("INPUT_FILE Cannot be empty");
+        let output_file = thumbnail_request.output_file
+            .unwrap_or_else(|| build_output_from_input_path(&input_file, "jpeg"));
+        let output_file = compute_output_file(&output_file, "jpeg", CollisionPolicy::Rename)?;
+        let (prefix, extension) = self.build_pattern_output(&output_file)?;
+        let mut filters = vec![format!("select='gt(scene,{})'", threshold)];
+        filters.extend(build_thumbnail_filter(thumbnail_request.crop_to_center, thumbnail_request.width));
+        filters.push(String::from("showinfo"));
+
+        let mut args = vec![
+            String::from("-i"),
+            input_file,
+            String::from("-vf"),
+            filters.join(","),
+            String::from("-vsync"),
+            String::from("vfr")
+        ];
+        if let Some(quality) = thumbnail_request.quality {
+            args.extend(build_quality_args(&output_file, quality)?);
+        }
+        args.push(format!("{}_%03d.{}", prefix, extension));
+
+        let result = self.command_manager.execute_with_args(args.iter().map(AsRef::as_ref).collect())?;
+        if !result.status.success() {
+            self.command_manager.print_command_output(result.stderr)?;
+            return Err(AtiumError::ConversionError("Execution of command returned ERROR".to_string()))
+        }
+
+        let timestamps = parse_showinfo_timestamps(&self.command_manager.get_command_output_as_string(result.stderr)?);
+        debug!("Detected [{}] scene changes", timestamps.len());
+
+        Ok(timestamps.into_iter()
+            .enumerate()
+            .map(|(index, timestamp)| ThumbnailResponse {
+                output: format!("{}_{:03}.{}", prefix, index + 1, extension),
+                timestamp_secs: Some(timestamp)
+            })
+            .filter(|thumbnail| Path::new(&thumbnail.output).exists())
+            .collect())
+    }
+    fn get_source_duration_secs(&self, input_file: String, input_duration: Option<String>) -> Result<f64, AtiumError> {
+        if let Some(duration) = input_duration.and_then(|duration| duration.parse::<f64>().ok()) {
+            return Ok(duration)
+        }
+
+        let duration = self.get_source_duration(input_file)?;
+        parse_timestamp(&duration)
+            .map(|duration| duration.as_secs_f64())
+            .map_err(|err| {
+                warn!("Error parsing duration timestamp: {}", err);
+                AtiumError::IOError("An error occurred when parsing duration timestamp".to_string())
+            })
+    }
+    /// Measures the average luminance of the frame at `timestamp` with `signalstats`
+    fn measure_luminance(&self, input_file: &str, timestamp: f64) -> Result<Option<f64>, AtiumError> {
+        let timestamp = format!("{:.3}", timestamp);
+        let args = vec![
+            "-ss", timestamp.as_str(),
+            "-i", input_file,
+            "-vf", "signalstats,metadata=print",
+            "-frames:v", "1",
+            "-f", "null", "-"
+        ];
+
+        let result = self.command_manager.execute_with_args(args)?;
+        if !result.status.success() {
+            self.command_manager.print_command_output(result.stderr)?;
+            return Err(AtiumError::ConversionError("Execution of command returned ERROR".to_string()))
+        }
+
+        Ok(parse_signalstats_yavg(&self.command_manager.get_command_output_as_string(result.stderr)?))
+    }
+    fn extract_brightest_thumbnail(&self, thumbnail_request: ThumbnailRequest) -> Result<ThumbnailResponse, AtiumError> {
+        let input_file = thumbnail_request.clone().input_file.expect("INPUT_FILE Cannot be empty");
+        let duration = self.get_source_duration_secs(input_file.clone(), thumbnail_request.input_duration.clone())?;
+
+        let mut candidates = Vec::new();
+        for position in AVOID_BLACK_POSITIONS {
+            let timestamp = duration * position;
+            match self.measure_luminance(&input_file, timestamp)? {
+                Some(luminance) => {
+                    debug!("Frame at [{:.3}s] has an average luminance of [{:.1}]", timestamp, luminance);
+                    candidates.push((timestamp, luminance));
+                }
+                None => warn!("Could not measure the luminance of the frame at [{:.3}s]", timestamp)
+            }
+        }
+        let timestamp = pick_brightest(&candidates)
+            .ok_or(AtiumError::ConversionError("Could not measure the luminance of any candidate frame".to_string()))?;
+
+        let response = self.extract_frame_at(thumbnail_request, input_file, timestamp)?;
+        info!("Brightest thumbnail extracted at path [{}]", response.output);
+
+        Ok(response)
+    }
+    fn extract_percentage_thumbnail(&self, thumbnail_request: ThumbnailRequest, percentage: f32) -> Result<ThumbnailResponse, AtiumError> {
+        let input_file = thumbnail_request.clone().input_file.expect("INPUT_FILE Cannot be empty");
+        validate_percentage(percentage)?;
+        let duration = self.get_source_duration_secs(input_file.clone(), thumbnail_request.input_duration.clone())?;
+        let timestamp = compute_percentage_timestamp(duration, percentage);
+        debug!("[{}%] of [{:.3}s] is at [{:.3}s]", percentage, duration, timestamp);
+
+        let response = self.extract_frame_at(thumbnail_request, input_file, timestamp)?;
+        info!("Thumbnail at [{}%] extracted at path [{}]", percentage, response.output);
+
+        Ok(response)
+    }
+    /// Extracts the frame at `timestamp`, in seconds, with the output settings of the request
+    fn extract_frame_at(&self, thumbnail_request: ThumbnailRequest, input_file: String, timestamp: f64) -> Result<ThumbnailResponse, AtiumError> {
+        let output_file = thumbnail_request.clone().output_file
+            .unwrap_or_else(|| build_output_from_input_path(&input_file, "jpeg"));
+        let output_file = compute_output_file(&output_file, "jpeg", CollisionPolicy::Rename)?;
+        let filter = build_thumbnail_filter(thumbnail_request.crop_to_center, thumbnail_request.width);
+        let quality_args = match thumbnail_request.quality {
+            Some(quality) => build_quality_args(&output_file, quality)?,
+            None => Vec::new()
+        };
+
+        let args = self.build_args(input_file, output_file.clone(), format!("{:.3}", timestamp), filter, quality_args);
+        let result = self.command_manager.execute_with_args(args.iter().map(AsRef::as_ref).collect())?;
+        if !result.status.success() {
+            self.command_manager.print_command_output(result.stderr)?;
+            return Err(AtiumError::ConversionError("Execution of command returned ERROR".to_string()))
+        }
+
+        Ok(ThumbnailResponse { output: output_file, timestamp_secs: Some(timestamp) })
+    }
+    /// Extracts the thumbnails requested by the [`ThumbnailSelection`] of the request
+    /// and returns a [`ThumbnailResponse`] for each of them
+    pub fn extract_thumbnails(&self, thumbnail_request: ThumbnailRequest) -> Result<Vec<ThumbnailResponse>, AtiumError> {
+        match thumbnail_request.selection.clone().unwrap_or(ThumbnailSelection::Timestamp) {
+            ThumbnailSelection::Timestamp => Ok(vec![self.extract_thumbnail(thumbnail_request)?]),
+            ThumbnailSelection::Scenes { threshold } => self.extract_scene_thumbnails(thumbnail_request, threshold),
+            ThumbnailSelection::AvoidBlack => Ok(vec![self.extract_brightest_thumbnail(thumbnail_request)?]),
+            ThumbnailSelection::Percentage(percentage) => Ok(vec![self.extract_percentage_thumbnail(thumbnail_request, percentage)?])
         }
     }
     /// Extracts a thumbnail and returns a [`ThumbnailResponse`]
@@ -96,13 +366,19 @@ impl FFMPEGThumbnailService{
         let input_file = thumbnail_request.clone().input_file.expect("INPUT_FILE Cannot be empty");
 
         let mut output_file = thumbnail_request.clone().output_file
-            .unwrap_or_else(|| self.build_output_from_input_path(input_file.clone()));
+            .unwrap_or_else(|| build_output_from_input_path(&input_file, "jpeg"));
 
-        output_file = compute_output_file(&output_file, "jpeg")?;
+        output_file = compute_output_file(&output_file, "jpeg", CollisionPolicy::Rename)?;
 
+        let filter = build_thumbnail_filter(thumbnail_request.crop_to_center, thumbnail_request.width);
+        let quality_args = match thumbnail_request.quality {
+            Some(quality) => build_quality_args(&output_file, quality)?,
+            None => Vec::new()
+        };
         let timestamp = self.compute_timestamp(input_file.clone(), thumbnail_request)?;
+        let timestamp_secs = Some(timestamp.as_secs_f64());
 
-        let args = self.build_args(input_file, output_file.clone(), timestamp);
+        let args = self.build_args(input_file, output_file.clone(), format_timestamp(timestamp), filter, quality_args);
 
         match self.command_manager.execute_with_args(args.iter().map(AsRef::as_ref).collect()) {
             Ok(result) => {
@@ -111,11 +387,148 @@ impl FFMPEGThumbnailService{
                     return Err(AtiumError::ConversionError("Execution of command returned ERROR".to_string()))
                 }
                 info!("Thumbnail extracted at path [{}]", output_file);
-                Ok(ThumbnailResponse{ output: output_file })
+                Ok(ThumbnailResponse{ output: output_file, timestamp_secs })
             }
             Err(err) => Err(err)
         }
     }
+    /// Extracts all the frames of a video, or a subsample of them, as numbered images
+    /// and returns a [`FrameExtractionResponse`] with the number of frames written
+    pub fn extract_frames(&self, request: FrameExtractionRequest) -> Result<FrameExtractionResponse, AtiumError> {
+        if !Path::new(&request.input_file).exists() {
+            return Err(AtiumError::IOError(format!("Input file [{}] not found", request.input_file)))
+        }
+        if let Some(fps) = &request.fps {
+            validate_frame_rate(fps)?;
+        }
+
+        let output_dir = Path::new(&request.output_dir);
+        fs::create_dir_all(output_dir)
+            .map_err(|err| AtiumError::IOError(format!("Could not create output directory: {}", err)))?;
+        let pattern = output_dir.join("frame_%06d.png");
+
+        let mut args = vec![
+            String::from("-i"),
+            request.input_file
+        ];
+        match &request.fps {
+            Some(fps) => {
+                args.push(String::from("-vf"));
+                args.push(format!("fps={}", fps));
+            }
+            None => {
+                // Keeps the source frames as they are, without duplicates or drops
+                args.push(String::from("-vsync"));
+                args.push(String::from("passthrough"));
+            }
+        }
+        if let Some(start_number) = request.start_number {
+            args.push(String::from("-start_number"));
+            args.push(start_number.to_string());
+        }
+        args.push(pattern.to_str()
+            .ok_or(AtiumError::IOError("Could not build output path".to_string()))?
+            .to_string());
+
+        let result = self.command_manager.execute_with_args(args.iter().map(AsRef::as_ref).collect())?;
+        if !result.status.success() {
+            self.command_manager.print_command_output(result.stderr)?;
+            return Err(AtiumError::ConversionError("Execution of command returned ERROR".to_string()))
+        }
+
+        let frame_count = count_frame_files(output_dir)?;
+        info!("Extracted [{}] frames in [{}]", frame_count, request.output_dir);
+
+        Ok(FrameExtractionResponse {
+            output_dir: request.output_dir,
+            frame_count
+        })
+    }
+    /// Returns the `(width, height)` of the first video track of `input_file` and its duration
+    /// in seconds, zeroes when they are unknown
+    fn probe_size_and_duration(&self, input_file: &str) -> Result<((u32, u32), f64), AtiumError> {
+        let analysis_output = MediaInfoExtractorService::new()?.get_analysis_output(input_file)?;
+        let parse_dimension = |field: &str| analysis_output.extract_field_from_track(1, field)
+            .ok()
+            .and_then(|value| value.parse::<u32>().ok())
+            .unwrap_or(0);
+        let source_size = (parse_dimension("Width"), parse_dimension("Height"));
+
+        Ok((source_size, analysis_output.duration_secs()))
+    }
+    /// Builds a sprite sheet with a tile every `interval_secs` of the input, along with a
+    /// WebVTT thumbnail track mapping each interval to its tile, as web players expect
+    pub fn generate_sprite(&self, request: SpriteRequest) -> Result<SpriteResponse, AtiumError> {
+        if !Path::new(&request.input_file).exists() {
+            return Err(AtiumError::IOError(format!("Input file [{}] not found", request.input_file)))
+        }
+
+        let (source_size, duration) = self.probe_size_and_duration(&request.input_file)?;
+        let layout = SpriteLayout::compute(source_size, duration, request.interval_secs, request.columns, request.tile_width)?;
+        let tile_count = compute_tile_count(duration, request.interval_secs)?;
+        debug!("Sprite layout of [{}] tiles is [{}x{}]", tile_count, layout.columns, layout.rows);
+
+        let output_file = compute_output_file(&request.output_file, "jpeg", CollisionPolicy::Rename)?;
+        let vtt_file = Path::new(&output_file).with_extension("vtt");
+        let vtt_file = vtt_file.to_str()
+            .ok_or(AtiumError::IOError("Could not build output path".to_string()))?
+            .to_string();
+
+        let args = [
+            String::from("-i"),
+            request.input_file,
+            String::from("-vf"),
+            format!("fps=1/{},scale={}:{},tile={}x{}",
+                request.interval_secs, layout.tile_width, layout.tile_height, layout.columns, layout.rows),
+            String::from("-frames:v"),
+            String::from("1"),
+            output_file.clone()
+        ];
+
+        let result = self.command_manager.execute_with_args(args.iter().map(AsRef::as_ref).collect())?;
+        if !result.status.success() {
+            self.command_manager.print_command_output(result.stderr)?;
+            return Err(AtiumError::ConversionError("Execution of command returned ERROR".to_string()))
+        }
+
+        fs::write(&vtt_file, build_vtt_track(&output_file, &layout, duration, request.interval_secs)?)
+            .map_err(|err| AtiumError::IOError(format!("Could not write thumbnail track: {}", err)))?;
+        info!("Sprite available at [{}] with its track at [{}]", output_file, vtt_file);
+
+        Ok(SpriteResponse { output_file, vtt_file, tile_count })
+    }
+    /// Builds a filmstrip: `count` frames evenly spread over the input, side by side in one image
+    pub fn generate_filmstrip(&self, request: FilmstripRequest) -> Result<FilmstripResponse, AtiumError> {
+        if !Path::new(&request.input_file).exists() {
+            return Err(AtiumError::IOError(format!("Input file [{}] not found", request.input_file)))
+        }
+
+        let (source_size, duration) = self.probe_size_and_duration(&request.input_file)?;
+        let layout = SpriteLayout::filmstrip(source_size, request.count, request.height)?;
+        let (interval_secs, timestamps_secs) = compute_filmstrip_timestamps(duration, request.count)?;
+        debug!("Filmstrip of [{}] frames taken every [{:.3}s]", request.count, interval_secs);
+
+        let output_file = compute_output_file(&request.output_file, "jpeg", CollisionPolicy::Rename)?;
+        let args = [
+            String::from("-i"),
+            request.input_file,
+            String::from("-vf"),
+            format!("fps=1/{},scale={}:{},tile={}x1",
+                interval_secs, layout.tile_width, layout.tile_height, layout.columns),
+            String::from("-frames:v"),
+            String::from("1"),
+            output_file.clone()
+        ];
+
+        let result = self.command_manager.execute_with_args(args.iter().map(AsRef::as_ref).collect())?;
+        if !result.status.success() {
+            self.command_manager.print_command_output(result.stderr)?;
+            return Err(AtiumError::ConversionError("Execution of command returned ERROR".to_string()))
+        }
+        info!("Filmstrip available at [{}]", output_file);
+
+        Ok(FilmstripResponse { output_file, timestamps_secs })
+    }
     /// Constructs a new instance of [`FFMPEGThumbnailService`] by loading and checking `ffmpeg` availability
     pub fn new() -> Result<Self, AtiumError> {
         let command_manager =
@@ -123,4 +536,77 @@ impl FFMPEGThumbnailService{
 
         Ok(Self { command_manager })
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_showinfo_timestamps() {
+        let stderr = "Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'input.mp4':
+[Parsed_showinfo_1 @ 0x7f8b0c004a40] config in time_base: 1/12800, frame_rate: 25/1
+[Parsed_showinfo_1 @ 0x7f8b0c004a40] n:   0 pts:  46592 pts_time:3.64    duration:    512 duration_time:0.04    fmt:yuv420p
+[Parsed_showinfo_1 @ 0x7f8b0c004a40] n:   1 pts: 155648 pts_time:12.16   duration:    512 duration_time:0.04    fmt:yuv420p
+frame=    2 fps=0.0 q=2.0 Lsize=N/A time=00:00:12.20 bitrate=N/A speed=30.1x";
+
+        assert_eq!(parse_showinfo_timestamps(stderr), vec![3.64, 12.16]);
+    }
+
+    #[test]
+    fn test_pick_brightest_frame() {
+        let stderr = "[Parsed_metadata_1 @ 0x55d0c8a0] frame:0    pts:0       pts_time:0
+[Parsed_metadata_1 @ 0x55d0c8a0] lavfi.signalstats.YMIN=16
+[Parsed_metadata_1 @ 0x55d0c8a0] lavfi.signalstats.YAVG=17.0342
+[Parsed_metadata_1 @ 0x55d0c8a0] lavfi.signalstats.YMAX=35";
+
+        assert_eq!(parse_signalstats_yavg(stderr), Some(17.0342));
+        assert_eq!(parse_signalstats_yavg("frame=    1 fps=0.0 q=-0.0 Lsize=N/A"), None);
+        assert_eq!(pick_brightest(&[(6.0, 16.2), (15.0, 94.5), (30.0, 94.5), (45.0, 60.1)]), Some(15.0));
+        assert_eq!(pick_brightest(&[]), None);
+    }
+
+    #[test]
+    fn test_compute_percentage_timestamp() {
+        assert_eq!(compute_percentage_timestamp(200.0, 30.0), 60.0);
+        assert_eq!(compute_percentage_timestamp(200.0, 0.0), 0.0);
+        assert_eq!(compute_percentage_timestamp(200.0, 100.0), 199.9);
+        assert_eq!(compute_percentage_timestamp(0.05, 50.0), 0.0);
+        assert!(validate_percentage(100.0).is_ok());
+        assert!(validate_percentage(100.5).is_err());
+        assert!(validate_percentage(-1.0).is_err());
+        assert!(validate_percentage(f32::NAN).is_err());
+    }
+
+    #[test]
+    fn test_build_thumbnail_filter() {
+        assert_eq!(build_thumbnail_filter(true, Some(256)).unwrap(), "crop=min(iw\\,ih):min(iw\\,ih),scale=256:-2");
+        assert_eq!(build_thumbnail_filter(true, None).unwrap(), "crop=min(iw\\,ih):min(iw\\,ih)");
+        assert_eq!(build_thumbnail_filter(false, Some(320)).unwrap(), "scale=320:-2");
+        assert!(build_thumbnail_filter(false, None).is_none());
+    }
+
+    #[test]
+    fn test_build_quality_args() {
+        assert_eq!(build_quality_args("/tmp/thumb.jpg", 100).ok().unwrap(), vec!["-q:v", "2"]);
+        assert_eq!(build_quality_args("/tmp/thumb.JPEG", 0).ok().unwrap(), vec!["-q:v", "31"]);
+        assert_eq!(build_quality_args("/tmp/thumb.jpeg", 75).ok().unwrap(), vec!["-q:v", "9"]);
+        assert_eq!(build_quality_args("/tmp/thumb.png", 100).ok().unwrap(), vec!["-compression_level", "0"]);
+        assert_eq!(build_quality_args("/tmp/thumb.png", 0).ok().unwrap(), vec!["-compression_level", "9"]);
+        assert!(build_quality_args("/tmp/thumb.webp", 80).is_err());
+        assert!(build_quality_args("/tmp/thumb.jpg", 101).is_err());
+    }
+
+    #[test]
+    fn test_count_frame_files() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["frame_000001.png", "frame_000002.png", "frame_.png", "frame_000003.jpeg", "cover.png"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+
+        assert_eq!(count_frame_files(&dir).ok().unwrap(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
\ No newline at end of file
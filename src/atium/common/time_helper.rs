@@ -0,0 +1,89 @@
+use std::time::Duration;
+use crate::atium::common::error::AtiumError;
+
+/// Parses a user supplied timestamp: `SS`, `SS.mmm`, `MM:SS`, `HH:MM:SS` or `HH:MM:SS.mmm`.
+/// The first field can be as large as needed, e.g. `90` or `90:00`, while the ones after
+/// it must stay below 60. Signs, exponents and empty fields are rejected, as they would
+/// otherwise reach `ffmpeg` and be read in ways nobody meant
+pub fn parse_timestamp(input: &str) -> Result<Duration, AtiumError> {
+    let invalid = || AtiumError::InvalidArgument(format!(
+        "Invalid timestamp [{}], expected SS, SS.mmm, MM:SS, HH:MM:SS or HH:MM:SS.mmm", input));
+    let is_number = |field: &str| !field.is_empty() && field.chars().all(|c| c.is_ascii_digit());
+
+    let fields: Vec<&str> = input.trim().split(':').collect();
+    if fields.len() > 3 {
+        return Err(invalid())
+    }
+    let (seconds, leading) = fields.split_last().ok_or_else(invalid)?;
+    let (whole_seconds, fraction) = match seconds.split_once('.') {
+        Some((whole_seconds, fraction)) => (whole_seconds, Some(fraction)),
+        None => (*seconds, None)
+    };
+    if !is_number(whole_seconds) || !leading.iter().all(|field| is_number(field)) {
+        return Err(invalid())
+    }
+    let nanos = match fraction {
+        Some(fraction) if is_number(fraction) && fraction.len() <= 9 =>
+            format!("{:0<9}", fraction).parse::<u32>().map_err(|_| invalid())?,
+        Some(_) => return Err(invalid()),
+        None => 0
+    };
+
+    let mut total_secs = 0u64;
+    for (position, field) in fields.iter().enumerate() {
+        let field = if position == fields.len() - 1 { whole_seconds } else { field };
+        let value = field.parse::<u64>().map_err(|_| invalid())?;
+        if position > 0 && value >= 60 {
+            return Err(invalid())
+        }
+        total_secs = total_secs.checked_mul(60)
+            .and_then(|total_secs| total_secs.checked_add(value))
+            .ok_or_else(invalid)?;
+    }
+
+    Ok(Duration::new(total_secs, nanos))
+}
+
+/// Formats `duration` as the `HH:MM:SS.mmm` handed to `ffmpeg`, truncated to the millisecond
+pub fn format_timestamp(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    format!("{:02}:{:02}:{:02}.{:03}", secs / 3600, (secs % 3600) / 60, secs % 60, duration.subsec_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("90").ok().unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_timestamp("2.5").ok().unwrap(), Duration::from_millis(2500));
+        assert_eq!(parse_timestamp("01:05").ok().unwrap(), Duration::from_secs(65));
+        assert_eq!(parse_timestamp("90:00").ok().unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_timestamp("01:02:03").ok().unwrap(), Duration::from_secs(3723));
+        assert_eq!(parse_timestamp("01:02:03.045").ok().unwrap(), Duration::from_millis(3723045));
+        assert_eq!(parse_timestamp(" 00:00:10 ").ok().unwrap(), Duration::from_secs(10));
+        assert_eq!(parse_timestamp("0.000001").ok().unwrap(), Duration::from_micros(1));
+    }
+
+    #[test]
+    fn test_parse_timestamp_rejects_malformed_input() {
+        let malformed = [
+            "", " ", "ten", "-2", "+2", "1e3", "2.", ".5", "1.2.3", "1:2:3:4", ":30", "01::30",
+            "01:60", "01:60:00", "00:00:60.5", "1:-30", "0.1234567891", "00:00:1,5",
+            "18446744073709551615:00"
+        ];
+        for timestamp in malformed {
+            assert!(matches!(parse_timestamp(timestamp), Err(AtiumError::InvalidArgument(_))), "[{}] was accepted", timestamp);
+        }
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(Duration::ZERO), "00:00:00.000");
+        assert_eq!(format_timestamp(Duration::from_millis(3723045)), "01:02:03.045");
+        assert_eq!(format_timestamp(parse_timestamp("2.5").ok().unwrap()), "00:00:02.500");
+        assert_eq!(format_timestamp(Duration::from_secs(100 * 3600)), "100:00:00.000");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,168 @@
+use std::path::Path;
+use serde::Serialize;
+
+use crate::atium::common::analysis_helper::AnalysisOutput;
+
+/// An audio or subtitle stream of a media, as needed by a track selection UI
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TrackInfo {
+    /// Index of the stream among the ones of the same kind, e.g. `1` for `0:a:1`
+    pub index: usize,
+    /// Stream format as reported by mediainfo, e.g. `AAC` or `UTF-8`
+    pub codec: String,
+    /// Language code as reported by mediainfo, e.g. `en`
+    pub language: Option<String>,
+    pub title: Option<String>,
+    /// Number of audio channels, always None for subtitles
+    pub channels: Option<u32>,
+    /// `true` when the stream carries the default disposition
+    pub default: bool
+}
+
+impl TrackInfo {
+    /// Returns a human readable label, e.g. `English - Commentary (AAC, 2 channels, default)`
+    pub fn label(&self) -> String {
+        let mut label = self.language.clone().unwrap_or_else(|| format!("Track {}", self.index + 1));
+        if let Some(title) = &self.title {
+            label.push_str(&format!(" - {}", title));
+        }
+
+        let mut details = vec![self.codec.clone()];
+        if let Some(channels) = self.channels {
+            details.push(match channels {
+                1 => String::from("mono"),
+                2 => String::from("stereo"),
+                _ => format!("{} channels", channels)
+            });
+        }
+        if self.default {
+            details.push(String::from("default"));
+        }
+
+        format!("{} ({})", label, details.join(", "))
+    }
+    /// Returns the file name of the track when exported next to the other streams of `input_file`,
+    /// e.g. `movie_a1_english.mka` for the second audio stream of `movie.mp4`, or `movie_a1.mka`
+    /// when the language is unknown
+    pub fn export_file_name(&self, input_file: &str, kind: &str, extension: &str) -> String {
+        let stem = Path::new(input_file)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("track");
+        let language = self.language.as_deref()
+            .map(|language| language.to_lowercase().chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>())
+            .filter(|language| !language.is_empty());
+
+        match language {
+            Some(language) => format!("{}_{}{}_{}.{}", stem, kind, self.index, language, extension),
+            None => format!("{}_{}{}.{}", stem, kind, self.index, extension)
+        }
+    }
+}
+
+/// The audio and subtitle streams of a media
+#[derive(Clone, Debug, Serialize)]
+pub struct TrackList {
+    pub audio: Vec<TrackInfo>,
+    pub subtitles: Vec<TrackInfo>
+}
+
+impl AnalysisOutput {
+    fn collect_tracks(&self, kind: &str) -> Vec<TrackInfo> {
+        self.get_tracks_by_type(kind).into_iter()
+            .enumerate()
+            .map(|(index, track)| TrackInfo {
+                index,
+                codec: self.get_track_field(track, "Format").unwrap_or_default(),
+                language: ["Language_String", "Language"].iter()
+                    .find_map(|field| self.get_track_field(track, field)),
+                title: self.get_track_field(track, "Title"),
+                channels: self.get_track_field(track, "Channels")
+                    .and_then(|channels| channels.parse().ok()),
+                default: self.get_track_field(track, "Default")
+                    .map(|default| default == "Yes")
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
+    /// Lists the audio and subtitle streams, in the order `ffmpeg` maps them
+    pub fn list_tracks(&self) -> TrackList {
+        TrackList {
+            audio: self.collect_tracks("Audio"),
+            subtitles: self.collect_tracks("Text")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use uuid::Uuid;
+    use crate::atium::common::analysis_helper::{compute_output_file, MediaInfoJsonLoader};
+    use crate::atium::common::model::CollisionPolicy;
+    use super::*;
+
+    #[test]
+    fn test_list_tracks() {
+        let json = r#"{"media": {"track": [
+            {"@type": "General", "Format": "Matroska"},
+            {"@type": "Video", "Format": "AVC"},
+            {"@type": "Audio", "Format": "AAC", "Language": "en", "Language_String": "English", "Channels": "2", "Default": "Yes"},
+            {"@type": "Audio", "Format": "AC-3", "Language": "it", "Title": "Commento", "Channels": "6", "Default": "No"},
+            {"@type": "Text", "Format": "UTF-8", "Language": "it", "Default": "No"},
+            {"@type": "Text", "Format": "PGS"}
+        ]}}"#;
+        let output = MediaInfoJsonLoader{}.load_json_from_string(json).unwrap();
+
+        let tracks = output.list_tracks();
+
+        assert_eq!(tracks.audio.len(), 2);
+        assert_eq!(tracks.audio[0].label(), "English (AAC, stereo, default)");
+        assert_eq!(tracks.audio[1], TrackInfo {
+            index: 1,
+            codec: String::from("AC-3"),
+            language: Some(String::from("it")),
+            title: Some(String::from("Commento")),
+            channels: Some(6),
+            default: false
+        });
+        assert_eq!(tracks.audio[1].label(), "it - Commento (AC-3, 6 channels)");
+        assert_eq!(tracks.subtitles.len(), 2);
+        assert_eq!(tracks.subtitles[1].label(), "Track 2 (PGS)");
+        assert_eq!(tracks.audio[0].export_file_name("/media/movie.mkv", "a", "mka"), "movie_a0_english.mka");
+        assert_eq!(tracks.subtitles[1].export_file_name("/media/movie.mkv", "s", "sup"), "movie_s1.sup");
+    }
+
+    #[test]
+    fn test_export_file_name() {
+        let track = |index: usize, language: Option<&str>| TrackInfo {
+            index,
+            codec: String::from("AAC"),
+            language: language.map(String::from),
+            title: None,
+            channels: Some(2),
+            default: false
+        };
+
+        assert_eq!(track(0, None).export_file_name("movie.mp4", "a", "mka"), "movie_a0.mka");
+        assert_eq!(track(2, Some("Português (Brasil)")).export_file_name("movie.mp4", "a", "mka"), "movie_a2_portugusbrasil.mka");
+        // a language with no ASCII letter is dropped instead of leaving a dangling separator
+        assert_eq!(track(1, Some("日本語")).export_file_name("movie.mp4", "a", "mka"), "movie_a1.mka");
+        assert_eq!(track(0, Some("English")).export_file_name("", "a", "mka"), "track_a0_english.mka");
+
+        // two dubs in the same language stay apart thanks to their stream index
+        let first = track(0, Some("Italian")).export_file_name("/media/movie.mkv", "a", "mka");
+        let second = track(1, Some("Italian")).export_file_name("/media/movie.mkv", "a", "mka");
+        assert_eq!(first, "movie_a0_italian.mka");
+        assert_eq!(second, "movie_a1_italian.mka");
+
+        // a track file left by a previous export is renamed like any other output
+        let dir = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let existing = dir.join(&first);
+        fs::write(&existing, b"").unwrap();
+        assert_eq!(compute_output_file(existing.to_str().unwrap(), "mka", CollisionPolicy::Rename).ok().unwrap(),
+                   dir.join("movie_a0_italian_1.mka").to_str().unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
\ No newline at end of file
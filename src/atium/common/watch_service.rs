@@ -0,0 +1,169 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use log::{debug, error, info, warn};
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use crate::atium::common::analysis_helper::build_output_from_input_path;
+use crate::atium::common::analysis_service::MediaInfoExtractorService;
+use crate::atium::common::error::AtiumError;
+use crate::atium::common::model::{InfoExtractorRequest, InfoFormat, InfoOutputType};
+
+/// Extensions of the files analyzed by the watcher, reports and other files are skipped
+const WATCHED_EXTENSIONS: [&str; 12] = ["mp4", "m4v", "mov", "mkv", "webm", "avi", "mxf", "ts", "flv", "wmv", "mpg", "mpeg"];
+
+/// How often the size of a new file is checked while it is being written
+const SIZE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Tells whether `path` is a video the watcher should analyze. Hidden files are skipped
+/// since uploaders like `rsync` write to a hidden name before renaming it
+fn is_watched_file(path: &Path) -> bool {
+    let hidden = path.file_name()
+        .and_then(|name| name.to_str())
+        .is_none_or(|name| name.starts_with('.'));
+    let watched = path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| WATCHED_EXTENSIONS.contains(&extension.to_lowercase().as_str()));
+
+    !hidden && watched
+}
+
+/// Tracks the sizes of a file being written, it is deemed complete once its size stays
+/// the same for `required_samples` consecutive checks
+struct SizeStabilizer {
+    last_size: Option<u64>,
+    stable_samples: u32,
+    required_samples: u32
+}
+
+impl SizeStabilizer {
+    fn new(required_samples: u32) -> Self {
+        Self { last_size: None, stable_samples: 0, required_samples: required_samples.max(1) }
+    }
+    /// Records a new size and returns true when the file is stable
+    fn observe(&mut self, size: u64) -> bool {
+        if self.last_size == Some(size) {
+            self.stable_samples += 1;
+        } else {
+            self.last_size = Some(size);
+            self.stable_samples = 0;
+        }
+
+        self.stable_samples >= self.required_samples
+    }
+}
+
+/// Directory watcher writing a `MediaInfo` report next to each new video file
+pub struct WatchService {
+    info_extractor_service: MediaInfoExtractorService
+}
+
+impl WatchService {
+    /// Waits until the size of `path` stops changing for `settle`, so a file that is still
+    /// being copied is not probed halfway
+    fn wait_for_stable_size(&self, path: &Path, settle: Duration) -> Result<u64, AtiumError> {
+        let required_samples = (settle.as_millis() / SIZE_POLL_INTERVAL.as_millis()) as u32;
+        let mut stabilizer = SizeStabilizer::new(required_samples);
+        loop {
+            let size = fs::metadata(path)
+                .map_err(|err| AtiumError::IOError(format!("Could not read [{}]: {}", path.display(), err)))?
+                .len();
+            if stabilizer.observe(size) {
+                return Ok(size)
+            }
+            thread::sleep(SIZE_POLL_INTERVAL);
+        }
+    }
+    fn write_report(&self, path: &Path, format: &InfoFormat) -> Result<String, AtiumError> {
+        let input = path.to_str()
+            .ok_or(AtiumError::IOError("Could not parse filename".to_string()))?
+            .to_string();
+        let request = InfoExtractorRequest {
+            output_file: Some(build_output_from_input_path(&input, "info")),
+            input,
+            format: Some(format.clone()),
+            full: None,
+            output_type: Some(InfoOutputType::File),
+            pretty: matches!(format, InfoFormat::Json)
+        };
+
+        self.info_extractor_service.get_info(request)?
+            .output
+            .file
+            .ok_or(AtiumError::IOError("No report was written".to_string()))
+    }
+    /// Watches `dir` until interrupted and writes a `<file>.info.<format>` report next to each
+    /// video file created or moved into it, once its size has been stable for `settle`
+    pub fn watch(&self, dir: &str, format: InfoFormat, settle: Duration) -> Result<(), AtiumError> {
+        if !Path::new(dir).is_dir() {
+            return Err(AtiumError::IOError(format!("Directory [{}] not found", dir)))
+        }
+
+        let (sender, receiver) = mpsc::channel::<notify::Result<Event>>();
+        let mut watcher = notify::recommended_watcher(sender)
+            .map_err(|err| AtiumError::IOError(format!("Could not create the watcher: {}", err)))?;
+        watcher.watch(Path::new(dir), RecursiveMode::NonRecursive)
+            .map_err(|err| AtiumError::IOError(format!("Could not watch [{}]: {}", dir, err)))?;
+        info!("Watching [{}] for new video files", dir);
+
+        let mut analyzed: HashSet<PathBuf> = HashSet::new();
+        for event in receiver {
+            let event = match event {
+                Ok(event) => event,
+                Err(err) => {
+                    warn!("Watcher error: {}", err);
+                    continue
+                }
+            };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To))) {
+                continue
+            }
+
+            for path in event.paths.into_iter().filter(|path| is_watched_file(path)) {
+                if !analyzed.insert(path.clone()) {
+                    continue
+                }
+                debug!("New file [{}], waiting for it to be complete", path.display());
+                let report = self.wait_for_stable_size(&path, settle)
+                    .and_then(|_| self.write_report(&path, &format));
+                match report {
+                    Ok(report) => info!("Report of [{}] written to [{}]", path.display(), report),
+                    Err(err) => error!("Could not analyze [{}]: {}", path.display(), err)
+                }
+            }
+        }
+
+        Ok(())
+    }
+    /// Constructs a new instance of [`WatchService`] by loading and checking `mediainfo` availability
+    pub fn new() -> Result<Self, AtiumError> {
+        Ok(Self { info_extractor_service: MediaInfoExtractorService::new()? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_watched_file() {
+        assert!(is_watched_file(Path::new("/ingest/clip.MP4")));
+        assert!(is_watched_file(Path::new("/ingest/show.mkv")));
+        assert!(!is_watched_file(Path::new("/ingest/clip.mp4.info.json")));
+        assert!(!is_watched_file(Path::new("/ingest/.clip.mp4")));
+        assert!(!is_watched_file(Path::new("/ingest/notes")));
+    }
+
+    #[test]
+    fn test_size_stabilizer() {
+        let mut stabilizer = SizeStabilizer::new(2);
+
+        assert!(!stabilizer.observe(1024));
+        assert!(!stabilizer.observe(4096));
+        assert!(!stabilizer.observe(4096));
+        assert!(stabilizer.observe(4096));
+    }
+}
\ No newline at end of file
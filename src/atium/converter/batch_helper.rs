@@ -0,0 +1,127 @@
+use serde::{Serialize, Serializer};
+use crate::atium::common::error::AtiumError;
+
+/// An input of a batch job which was processed successfully
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BatchItem {
+    pub input_file: String,
+    pub output_file: String
+}
+
+/// What processing an input of a batch job did, along with its output path
+pub enum BatchOutcome {
+    Converted(String),
+    /// The output was already there, e.g. from an interrupted run resumed with `--skip-existing`
+    AlreadyDone(String)
+}
+
+/// Outcome of a batch job, each input ending up in exactly one of the lists
+#[derive(Debug, Serialize)]
+pub struct BatchReport {
+    pub succeeded: Vec<BatchItem>,
+    /// Inputs whose output already existed and was kept as it is
+    pub already_done: Vec<BatchItem>,
+    /// Inputs which failed along with their error, serialized as `{input_file, error}` objects
+    #[serde(serialize_with = "serialize_failures")]
+    pub failed: Vec<(String, AtiumError)>,
+    /// Inputs never processed since the job stopped at the first error
+    pub skipped: Vec<String>
+}
+
+impl BatchReport {
+    /// Returns `true` when every input was processed successfully
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty() && self.skipped.is_empty()
+    }
+}
+
+fn serialize_failures<S: Serializer>(failed: &[(String, AtiumError)], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(failed.iter()
+        .map(|(input_file, err)| serde_json::json!({ "input_file": input_file, "error": err.to_string() })))
+}
+
+/// Runs `process` on each input in order, gathering each result into a [`BatchReport`].
+/// Unless `continue_on_error` is set, the job stops at the first failure and the remaining
+/// inputs are reported as skipped
+pub fn run_batch(inputs: &[String], continue_on_error: bool, mut process: impl FnMut(&str) -> Result<BatchOutcome, AtiumError>) -> BatchReport {
+    let mut report = BatchReport { succeeded: Vec::new(), already_done: Vec::new(), failed: Vec::new(), skipped: Vec::new() };
+    for (position, input_file) in inputs.iter().enumerate() {
+        match process(input_file) {
+            Ok(BatchOutcome::Converted(output_file)) =>
+                report.succeeded.push(BatchItem { input_file: input_file.clone(), output_file }),
+            Ok(BatchOutcome::AlreadyDone(output_file)) =>
+                report.already_done.push(BatchItem { input_file: input_file.clone(), output_file }),
+            Err(err) => {
+                report.failed.push((input_file.clone(), err));
+                if !continue_on_error {
+                    report.skipped.extend_from_slice(&inputs[position + 1..]);
+                    break
+                }
+            }
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(input_file: &str) -> Result<BatchOutcome, AtiumError> {
+        if input_file.starts_with("broken") {
+            Err(AtiumError::ConversionError(format!("cannot decode [{}]", input_file)))
+        } else if input_file.starts_with("done") {
+            Ok(BatchOutcome::AlreadyDone(input_file.replace(".mov", ".mp4")))
+        } else {
+            Ok(BatchOutcome::Converted(input_file.replace(".mov", ".mp4")))
+        }
+    }
+
+    #[test]
+    fn test_run_batch() {
+        let inputs = vec![String::from("a.mov"), String::from("broken.mov"), String::from("c.mov")];
+
+        let report = run_batch(&inputs, true, convert);
+        assert_eq!(report.succeeded, vec![
+            BatchItem { input_file: String::from("a.mov"), output_file: String::from("a.mp4") },
+            BatchItem { input_file: String::from("c.mov"), output_file: String::from("c.mp4") }
+        ]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "broken.mov");
+        assert!(report.skipped.is_empty());
+        assert!(!report.is_success());
+
+        let report = run_batch(&inputs, false, convert);
+        assert_eq!(report.succeeded.len(), 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.skipped, vec![String::from("c.mov")]);
+
+        assert!(run_batch(&inputs[..1], false, convert).is_success());
+    }
+
+    #[test]
+    fn test_run_batch_already_done() {
+        let inputs = vec![String::from("done.mov"), String::from("b.mov")];
+
+        let report = run_batch(&inputs, false, convert);
+        assert_eq!(report.already_done, vec![
+            BatchItem { input_file: String::from("done.mov"), output_file: String::from("done.mp4") }
+        ]);
+        assert_eq!(report.succeeded.len(), 1);
+        assert!(report.skipped.is_empty());
+        assert!(report.is_success());
+    }
+
+    #[test]
+    fn test_serialize_batch_report() {
+        let inputs = vec![String::from("a.mov"), String::from("broken.mov")];
+        let report = serde_json::to_value(run_batch(&inputs, true, convert)).unwrap();
+
+        assert_eq!(report["succeeded"][0]["output_file"], "a.mp4");
+        assert_eq!(report["failed"][0]["input_file"], "broken.mov");
+        assert_eq!(report["failed"][0]["error"], "Conversion Error: cannot decode [broken.mov]");
+        assert_eq!(report["already_done"], serde_json::json!([]));
+        assert_eq!(report["skipped"], serde_json::json!([]));
+    }
+}
\ No newline at end of file
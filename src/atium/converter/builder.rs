@@ -0,0 +1,483 @@
+use std::collections::HashMap;
+use crate::atium::common::error::AtiumError;
+use crate::atium::common::env_helper::{env_default, resolve_setting};
+use crate::atium::common::model::{CollisionPolicy, detect_protocol, InputProtocol, parse_collision_policy, ThumbnailRequest};
+use crate::atium::measure::model::NormalizePreset;
+use crate::converter::model::{AacEncoder, BitDepth, BlurFill, FilterGraph, Disposition, DownmixMode, ConversionInput, ConversionOutput, ConversionRequest, HwAccel, Intro, InputSourceType, OutputCodec, OutputMetadata, OutputResolution, Pad, parse_output_codec, parse_resolution, Pip, ProgressBar, resolve_auto_codec, resolve_encoder, SilenceTrim, StreamSpec, StreamType, SubtitleBurn, validate_conversion_output};
+
+/// Fluent builder for a [`ConversionRequest`].
+/// Only the input and the output paths are required, the other options start with the
+/// same defaults the CLI uses: HD resolution, H264 codec and the `Rename` collision policy.
+/// When they are not set, the resolution, the codec and the collision policy are taken from
+/// the `ATIUM_RESOLUTION`, `ATIUM_CODEC` and `ATIUM_ON_COLLISION` variables before these defaults.
+///
+/// ```
+/// let request = ConversionRequestBuilder::new("/path/to/input.mp4", "/path/to/output.mp4")
+///     .resolution(OutputResolution::FullHd)
+///     .fps("25")
+///     .web_optimized(true)
+///     .build()?;
+/// ```
+pub struct ConversionRequestBuilder {
+    input: ConversionInput,
+    output: ConversionOutput,
+    // kept apart from the output to tell an explicit value from a default one
+    resolution: Option<OutputResolution>,
+    codec: Option<OutputCodec>,
+    collision_policy: Option<CollisionPolicy>,
+    /// Reads the `ATIUM_*` variables, replaced in tests so they don't see the caller's shell
+    env_source: fn(&str) -> Option<String>
+}
+
+impl ConversionRequestBuilder {
+    /// Constructs a new builder converting `input_file` into `output_file`.
+    /// The source type is [`InputSourceType::Url`] when `input_file` is a URL
+    pub fn new(input_file: &str, output_file: &str) -> Self {
+        let source_type = match detect_protocol(input_file) {
+            InputProtocol::Local => InputSourceType::Local,
+            InputProtocol::Remote(_) => InputSourceType::Url
+        };
+        ConversionRequestBuilder {
+            input: ConversionInput {
+                source_type,
+                file_name: input_file.to_string(),
+                hwaccel_decode: None,
+                force_decoder: None
+            },
+            output: ConversionOutput {
+                file: output_file.to_string(),
+                resolution: OutputResolution::Hd,
+                codec: OutputCodec::H264,
+                thumbnail_request: None,
+                maxrate: None,
+                bufsize: None,
+                collision_policy: CollisionPolicy::Rename,
+                metadata: None,
+                strip_metadata: false,
+                fps: None,
+                force_cfr: false,
+                burn_subtitles: None,
+                skip_existing: false,
+                subtitle_charset: None,
+                target_size: None,
+                keep_streams: None,
+                dispositions: None,
+                stream_metadata: None,
+                web_optimized: false,
+                fragmented: false,
+                muxing_queue_size: None,
+                video_timebase: None,
+                aspect_ratio: None,
+                set_sar: None,
+                anamorphic_fix: false,
+                normalize_preset: None,
+                aac_encoder: None,
+                b_frames: None,
+                ref_frames: None,
+                x265_params: None,
+                frame_limit: None,
+                force_keyframes: None,
+                sample_duration: None,
+                bit_depth: None,
+                reverse: false,
+                pad: None,
+                blur_fill: None,
+                progress_bar: None,
+                max_dimensions: None,
+                audio_offset_ms: None,
+                cover: None,
+                preserve_mtime: false,
+                audio_downmix: None,
+                trim_silence: None,
+                pip: None,
+                intro: None,
+                filter_complex: None,
+                hardware_encode: false,
+                fallback_codec: None,
+                write_report: false,
+                strict: false
+            },
+            resolution: None,
+            codec: None,
+            collision_policy: None,
+            env_source: env_default
+        }
+    }
+    /// Decodes the input with the given hardware accelerator
+    pub fn hwaccel_decode(mut self, hwaccel: HwAccel) -> Self {
+        self.input.hwaccel_decode = Some(hwaccel);
+        self
+    }
+    /// Decodes the input video with the given decoder instead of the autodetected one
+    pub fn force_decoder(mut self, decoder: &str) -> Self {
+        self.input.force_decoder = Some(decoder.to_string());
+        self
+    }
+    pub fn resolution(mut self, resolution: OutputResolution) -> Self {
+        self.resolution = Some(resolution);
+        self
+    }
+    pub fn codec(mut self, codec: OutputCodec) -> Self {
+        self.codec = Some(codec);
+        self
+    }
+    pub fn thumbnail(mut self, thumbnail_request: ThumbnailRequest) -> Self {
+        self.output.thumbnail_request = Some(thumbnail_request);
+        self
+    }
+    /// Sets VBV rate control, both values are required by `ffmpeg`
+    pub fn rate_control(mut self, maxrate: &str, bufsize: &str) -> Self {
+        self.output.maxrate = Some(maxrate.to_string());
+        self.output.bufsize = Some(bufsize.to_string());
+        self
+    }
+    pub fn collision_policy(mut self, collision_policy: CollisionPolicy) -> Self {
+        self.collision_policy = Some(collision_policy);
+        self
+    }
+    /// Sets the container metadata, empty metadata is ignored
+    pub fn metadata(mut self, metadata: OutputMetadata) -> Self {
+        self.output.metadata = Some(metadata).filter(|m| !m.is_empty());
+        self
+    }
+    pub fn strip_metadata(mut self, strip_metadata: bool) -> Self {
+        self.output.strip_metadata = strip_metadata;
+        self
+    }
+    pub fn fps(mut self, fps: &str) -> Self {
+        self.output.fps = Some(fps.to_string());
+        self
+    }
+    pub fn force_cfr(mut self, force_cfr: bool) -> Self {
+        self.output.force_cfr = force_cfr;
+        self
+    }
+    pub fn burn_subtitles(mut self, burn_subtitles: SubtitleBurn) -> Self {
+        self.output.burn_subtitles = Some(burn_subtitles);
+        self
+    }
+    /// Fits the output under the given size, e.g. `8MB`, with a two-pass encode
+    pub fn target_size(mut self, target_size: &str) -> Self {
+        self.output.target_size = Some(target_size.to_string());
+        self
+    }
+    /// Skips the conversion when a valid output already exists
+    pub fn skip_existing(mut self, skip_existing: bool) -> Self {
+        self.output.skip_existing = skip_existing;
+        self
+    }
+    /// Sets the character encoding of a burned subtitle file
+    pub fn subtitle_charset(mut self, charset: &str) -> Self {
+        self.output.subtitle_charset = Some(charset.to_string());
+        self
+    }
+    pub fn keep_streams(mut self, stream_types: Vec<StreamType>) -> Self {
+        self.output.keep_streams = Some(stream_types);
+        self
+    }
+    /// Sets the disposition flags of output streams, empty ones are ignored
+    pub fn dispositions(mut self, dispositions: Vec<(StreamSpec, Disposition)>) -> Self {
+        if !dispositions.is_empty() {
+            self.output.dispositions = Some(dispositions);
+        }
+        self
+    }
+    /// Sets the metadata of output streams, empty ones are ignored
+    pub fn stream_metadata(mut self, stream_metadata: Vec<(StreamSpec, String, String)>) -> Self {
+        if !stream_metadata.is_empty() {
+            self.output.stream_metadata = Some(stream_metadata);
+        }
+        self
+    }
+    pub fn web_optimized(mut self, web_optimized: bool) -> Self {
+        self.output.web_optimized = web_optimized;
+        self
+    }
+    pub fn fragmented(mut self, fragmented: bool) -> Self {
+        self.output.fragmented = fragmented;
+        self
+    }
+    /// Raises the muxing queue, see [`ConversionOutput::muxing_queue_size`]
+    pub fn muxing_queue_size(mut self, muxing_queue_size: u32) -> Self {
+        self.output.muxing_queue_size = Some(muxing_queue_size);
+        self
+    }
+    /// Sets the mp4/mov video track timescale, see [`ConversionOutput::video_timebase`]
+    pub fn video_timebase(mut self, video_timebase: &str) -> Self {
+        self.output.video_timebase = Some(video_timebase.to_string());
+        self
+    }
+    pub fn aspect_ratio(mut self, aspect_ratio: &str) -> Self {
+        self.output.aspect_ratio = Some(aspect_ratio.to_string());
+        self
+    }
+    pub fn set_sar(mut self, sar: &str) -> Self {
+        self.output.set_sar = Some(sar.to_string());
+        self
+    }
+    pub fn anamorphic_fix(mut self, anamorphic_fix: bool) -> Self {
+        self.output.anamorphic_fix = anamorphic_fix;
+        self
+    }
+    pub fn normalize_preset(mut self, preset: NormalizePreset) -> Self {
+        self.output.normalize_preset = Some(preset);
+        self
+    }
+    pub fn aac_encoder(mut self, aac_encoder: AacEncoder) -> Self {
+        self.output.aac_encoder = Some(aac_encoder);
+        self
+    }
+    pub fn b_frames(mut self, b_frames: u32) -> Self {
+        self.output.b_frames = Some(b_frames);
+        self
+    }
+    pub fn ref_frames(mut self, ref_frames: u32) -> Self {
+        self.output.ref_frames = Some(ref_frames);
+        self
+    }
+    /// Sets the libx265 tunables, empty ones are ignored
+    pub fn x265_params(mut self, x265_params: HashMap<String, String>) -> Self {
+        if !x265_params.is_empty() {
+            self.output.x265_params = Some(x265_params);
+        }
+        self
+    }
+    pub fn frame_limit(mut self, frame_limit: u64) -> Self {
+        self.output.frame_limit = Some(frame_limit);
+        self
+    }
+    /// Forces keyframes at `force_keyframes`, an `expr:` expression or a list of times
+    pub fn force_keyframes(mut self, force_keyframes: &str) -> Self {
+        self.output.force_keyframes = Some(force_keyframes.to_string());
+        self
+    }
+    /// Test mode: only the first `sample_duration` of the input is converted
+    pub fn sample_duration(mut self, sample_duration: &str) -> Self {
+        self.output.sample_duration = Some(sample_duration.to_string());
+        self
+    }
+    pub fn bit_depth(mut self, bit_depth: BitDepth) -> Self {
+        self.output.bit_depth = Some(bit_depth);
+        self
+    }
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.output.reverse = reverse;
+        self
+    }
+    pub fn pad(mut self, pad: Pad) -> Self {
+        self.output.pad = Some(pad);
+        self
+    }
+    pub fn blur_fill(mut self, blur_fill: BlurFill) -> Self {
+        self.output.blur_fill = Some(blur_fill);
+        self
+    }
+    pub fn progress_bar(mut self, progress_bar: ProgressBar) -> Self {
+        self.output.progress_bar = Some(progress_bar);
+        self
+    }
+    pub fn max_dimensions(mut self, width: u32, height: u32) -> Self {
+        self.output.max_dimensions = Some((width, height));
+        self
+    }
+    pub fn audio_offset_ms(mut self, audio_offset_ms: i64) -> Self {
+        self.output.audio_offset_ms = Some(audio_offset_ms);
+        self
+    }
+    /// Embeds `cover_file`, a jpg or png image, as the cover of the output
+    pub fn cover(mut self, cover_file: &str) -> Self {
+        self.output.cover = Some(cover_file.to_string());
+        self
+    }
+    pub fn preserve_mtime(mut self, preserve_mtime: bool) -> Self {
+        self.output.preserve_mtime = preserve_mtime;
+        self
+    }
+    pub fn audio_downmix(mut self, downmix: DownmixMode) -> Self {
+        self.output.audio_downmix = Some(downmix);
+        self
+    }
+    pub fn trim_silence(mut self, trim: SilenceTrim) -> Self {
+        self.output.trim_silence = Some(trim);
+        self
+    }
+    /// Draws the video of [`Pip::overlay_input`] over a corner of the output
+    pub fn pip(mut self, pip: Pip) -> Self {
+        self.output.pip = Some(pip);
+        self
+    }
+    /// Plays `intro` before the converted video
+    pub fn intro(mut self, intro: Intro) -> Self {
+        self.output.intro = Some(intro);
+        self
+    }
+    /// Runs `filter_graph` in place of the filters assembled from the other options
+    pub fn filter_complex(mut self, filter_graph: FilterGraph) -> Self {
+        self.output.filter_complex = Some(filter_graph);
+        self
+    }
+    /// Encodes with the hardware encoder matching [`ConversionRequestBuilder::hwaccel_decode`]
+    pub fn hardware_encode(mut self, hardware_encode: bool) -> Self {
+        self.output.hardware_encode = hardware_encode;
+        self
+    }
+    /// Retries the conversion with the `codec` software encoder when the hardware one fails
+    pub fn fallback_codec(mut self, codec: OutputCodec) -> Self {
+        self.output.fallback_codec = Some(codec);
+        self
+    }
+    pub fn write_report(mut self, write_report: bool) -> Self {
+        self.output.write_report = write_report;
+        self
+    }
+    /// Fails on the input streams the output container would drop, instead of warning
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.output.strict = strict;
+        self
+    }
+    /// Reads the `ATIUM_*` variables from `env_source` instead of the process environment
+    #[cfg(test)]
+    pub fn env_source(mut self, env_source: fn(&str) -> Option<String>) -> Self {
+        self.env_source = env_source;
+        self
+    }
+    /// Merges the options left unset with their `ATIUM_*` variables, validates all the
+    /// options together and returns the [`ConversionRequest`]
+    pub fn build(mut self) -> Result<ConversionRequest, AtiumError> {
+        let output_file = self.output.file.clone();
+        self.output.resolution = resolve_setting(self.resolution, "RESOLUTION", (self.env_source)("RESOLUTION"),
+            |value| match value.to_lowercase().as_str() {
+                "sd" | "hd" | "fhd" | "2k" | "uhd" | "8k" => Ok(parse_resolution(value)),
+                _ => Err(AtiumError::InvalidArgument("valid resolutions are: sd, hd, fhd, 2k, uhd, 8k".to_string()))
+            },
+            OutputResolution::Hd)?;
+        self.output.codec = resolve_setting(self.codec, "CODEC", (self.env_source)("CODEC"),
+            |value| match value.to_lowercase().as_str() {
+                "auto" => Ok(resolve_auto_codec(&output_file)),
+                codec => parse_output_codec(codec)
+            },
+            OutputCodec::H264)?;
+        self.output.collision_policy = resolve_setting(self.collision_policy, "ON_COLLISION", (self.env_source)("ON_COLLISION"),
+            |value| match value.to_lowercase().as_str() {
+                "error" | "overwrite" | "rename" => Ok(parse_collision_policy(Some(value.to_string())).unwrap_or(CollisionPolicy::Rename)),
+                _ => Err(AtiumError::InvalidArgument("valid policies are: error, overwrite, rename".to_string()))
+            },
+            CollisionPolicy::Rename)?;
+
+        validate_conversion_output(&self.output)?;
+        if self.output.hardware_encode {
+            let hwaccel = self.input.hwaccel_decode.as_ref()
+                .ok_or(AtiumError::InvalidArgument("hardware_encode requires hwaccel_decode".to_string()))?;
+            resolve_encoder(&self.output.codec, Some(hwaccel))?;
+        }
+
+        Ok(ConversionRequest {
+            input: self.input,
+            output: self.output
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::converter::model::SilenceEdge;
+    use super::*;
+
+    fn builder(input_file: &str, output_file: &str) -> ConversionRequestBuilder {
+        ConversionRequestBuilder::new(input_file, output_file).env_source(|_| None)
+    }
+
+    #[test]
+    fn test_build_defaults() {
+        let request = builder("/tmp/input.mov", "/tmp/output.mp4")
+            .build()
+            .ok()
+            .unwrap();
+
+        assert_eq!(request.input.file_name, "/tmp/input.mov");
+        assert_eq!(request.output.file, "/tmp/output.mp4");
+        assert!(matches!(request.output.resolution, OutputResolution::Hd));
+        assert!(matches!(request.input.source_type, InputSourceType::Local));
+        assert!(matches!(request.output.collision_policy, CollisionPolicy::Rename));
+        assert!(request.output.metadata.is_none());
+    }
+
+    #[test]
+    fn test_build_url_input() {
+        let request = builder("https://example.com/input.mp4", "/tmp/output.mp4")
+            .build()
+            .ok()
+            .unwrap();
+
+        assert!(matches!(request.input.source_type, InputSourceType::Url));
+    }
+
+    #[test]
+    fn test_build_validation() {
+        let builder = || builder("/tmp/input.mov", "/tmp/output.mp4");
+
+        assert!(builder().rate_control("2M", "4M").fps("30000/1001").build().is_ok());
+        assert!(builder().fps("fast").build().is_err());
+        assert!(builder().target_size("8XB").build().is_err());
+        assert!(builder().keep_streams(vec![StreamType::Audio]).build().is_err());
+        assert!(builder().keep_streams(vec![StreamType::Video])
+            .normalize_preset(NormalizePreset::Podcast)
+            .build()
+            .is_err());
+        assert!(builder().keep_streams(vec![StreamType::Video]).audio_offset_ms(-250).build().is_err());
+        assert!(builder().keep_streams(vec![StreamType::Video]).audio_downmix(DownmixMode::StereoDialogBoost).build().is_err());
+        assert!(builder().subtitle_charset("CP1252").build().is_err());
+        assert!(builder().frame_limit(0).build().is_err());
+        assert!(builder().muxing_queue_size(0).build().is_err());
+        assert!(builder().video_timebase("90000").build().is_ok());
+        assert!(builder().video_timebase("0").build().is_err());
+        assert!(builder().video_timebase("1/90000").build().is_err());
+        assert!(builder().x265_params(HashMap::from([(String::from("aq-mode"), String::from("3"))])).build().is_err());
+        assert!(builder().codec(OutputCodec::H265).x265_params(HashMap::from([(String::from("aq-mode"), String::from("3"))])).build().is_ok());
+        assert!(builder().keep_streams(vec![StreamType::Video])
+            .dispositions(vec![(StreamSpec { stream_type: StreamType::Audio, index: 0 }, Disposition::Default)])
+            .build()
+            .is_err());
+        let filter_graph = FilterGraph { graph: String::from("[0:v]hflip[vout]"), maps: vec![String::from("[vout]")] };
+        assert!(builder().filter_complex(filter_graph.clone()).build().is_ok());
+        assert!(builder().filter_complex(filter_graph).fps("25").build().is_err());
+        assert!(builder().frame_limit(300).build().is_ok());
+        assert!(builder().max_dimensions(1920, 1080)
+            .pad(Pad { width: 1920, height: 1080, color: String::from("black") })
+            .build()
+            .is_err());
+        assert!(builder().strip_metadata(true)
+            .metadata(OutputMetadata { title: Some(String::from("title")), ..Default::default() })
+            .build()
+            .is_err());
+        assert!(builder().web_optimized(true).fragmented(true).build().is_err());
+        assert!(builder().cover("/tmp/missing-cover.jpg").build().is_err());
+        assert!(builder().trim_silence(SilenceTrim { edge: SilenceEdge::Both, threshold_db: 3.0, min_duration_secs: 0.5 }).build().is_err());
+        assert!(builder().hardware_encode(true).build().is_err());
+        assert!(builder().hwaccel_decode(HwAccel::Cuda).hardware_encode(true).build().is_ok());
+        assert!(builder().hwaccel_decode(HwAccel::Cuda).hardware_encode(true).target_size("8MB").build().is_err());
+        assert!(builder().codec(OutputCodec::Vp9).hwaccel_decode(HwAccel::Cuda).hardware_encode(true).build().is_err());
+        assert!(builder().fallback_codec(OutputCodec::H264).build().is_err());
+        assert!(builder().hwaccel_decode(HwAccel::Cuda).hardware_encode(true).fallback_codec(OutputCodec::H265).build().is_ok());
+        assert!(ConversionRequestBuilder::new("/tmp/input.mov", "/tmp/output.webm").env_source(|_| None).build().is_err());
+        assert!(ConversionRequestBuilder::new("/tmp/input.mov", "/tmp/output.webm").env_source(|_| None).codec(OutputCodec::Vp9).build().is_ok());
+    }
+
+    #[test]
+    fn test_build_env_defaults() {
+        let env = |name: &str| match name {
+            "RESOLUTION" => Some(String::from("fhd")),
+            "CODEC" => Some(String::from("auto")),
+            "ON_COLLISION" => Some(String::from("overwrite")),
+            _ => None
+        };
+        let request = builder("/tmp/input.mov", "/tmp/output.webm").env_source(env).build().ok().unwrap();
+
+        assert!(matches!(request.output.resolution, OutputResolution::FullHd));
+        assert!(matches!(request.output.codec, OutputCodec::Vp9));
+        assert!(matches!(request.output.collision_policy, CollisionPolicy::Overwrite));
+        let explicit = builder("/tmp/input.mov", "/tmp/output.mp4").env_source(env).resolution(OutputResolution::Sd).build().ok().unwrap();
+        assert!(matches!(explicit.output.resolution, OutputResolution::Sd));
+        assert!(builder("/tmp/input.mov", "/tmp/output.mp4").env_source(|_| Some(String::from("mpeg2"))).build().is_err());
+    }
+}
\ No newline at end of file
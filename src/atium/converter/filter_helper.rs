@@ -0,0 +1,587 @@
+use log::debug;
+use crate::atium::common::analysis_helper::AnalysisOutput;
+use crate::atium::common::error::AtiumError;
+use crate::converter::model::{BlurFill, ConversionOutput, DownmixMode, HwAccel, Intro, Pad, Pip, PipPosition, ProgressBar, SilenceEdge, SilenceTrim, SubtitleTrack};
+
+fn escape_chars(value: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Escapes a value, typically a file path, so it can be used as a filter option inside a
+/// `-vf`/`-filter_complex` graph. `ffmpeg` unescapes it twice, once for the option list
+/// and once for the graph description
+pub fn escape_filter_value(value: &str) -> String {
+    let option_level = escape_chars(value, &['\\', '\'', ':']);
+
+    escape_chars(&option_level, &['\\', '\'', '[', ']', ',', ';'])
+}
+
+/// Builds the filters fitting the video inside the [`Pad`] frame: it is shrunk or enlarged
+/// keeping its aspect ratio, to an even size, then centered over the background color
+pub fn build_pad_filter(pad: &Pad) -> String {
+    format!(
+        "scale={w}:{h}:force_original_aspect_ratio=decrease:force_divisible_by=2,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2:{color}",
+        w = pad.width,
+        h = pad.height,
+        color = pad.color)
+}
+
+/// Builds the graph filling the [`BlurFill`] frame: a copy of the video is enlarged to cover
+/// the frame, cropped and blurred, then the video fitted inside the frame is centered over it.
+/// The graph has a single input and output, so it can be chained like a plain filter
+pub fn build_blur_fill_filter(blur_fill: &BlurFill) -> String {
+    format!(
+        "split[background][foreground];\
+        [background]scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h},boxblur={strength}:2[blurred];\
+        [foreground]scale={w}:{h}:force_original_aspect_ratio=decrease:force_divisible_by=2[fitted];\
+        [blurred][fitted]overlay=(W-w)/2:(H-h)/2",
+        w = blur_fill.width,
+        h = blur_fill.height,
+        strength = blur_fill.strength)
+}
+
+fn get_device_scale_filter(hwaccel: &HwAccel) -> Option<&'static str> {
+    match hwaccel {
+        HwAccel::Cuda => Some("scale_cuda"),
+        HwAccel::Vaapi => Some("scale_vaapi"),
+        HwAccel::Qsv => Some("scale_qsv"),
+        _ => None
+    }
+}
+
+/// Returns the accelerator among `hwaccel` that can scale `output` on its device, instead of
+/// copying the frames to system memory first. Only the plain `resolution` scaling of 8 bit
+/// outputs without the anamorphic fix is done there, since the other sizing modes and filter
+/// graphs are software filters
+pub fn select_device_scaling<'a>(output: &ConversionOutput, hwaccel: Option<&'a HwAccel>) -> Option<&'a HwAccel> {
+    hwaccel.filter(|hwaccel| get_device_scale_filter(hwaccel).is_some()
+        && output.blur_fill.is_none()
+        && output.pad.is_none()
+        && output.max_dimensions.is_none()
+        && output.filter_complex.is_none()
+        && !output.anamorphic_fix
+        && output.intro.is_none()
+        && output.bit_depth.is_none())
+}
+
+/// Builds the filter scaling to `width`x`height` on the device of `hwaccel`: `scale_cuda`,
+/// which unlike `scale_npp` doesn't need a non-free build, `scale_vaapi` or `scale_qsv`.
+/// The frames are converted to `nv12` too, since they otherwise keep the decoded format,
+/// e.g. `p010` for 10 bit sources, which [`download_device_frames`] can't download as `nv12`
+pub fn build_device_scale_filter(hwaccel: &HwAccel, width: i32, height: i32) -> Option<String> {
+    get_device_scale_filter(hwaccel).map(|filter| format!("{}=w={}:h={}:format=nv12", filter, width, height))
+}
+
+/// Downloads the frames of `filters`, which start with a device scaling, to system memory
+/// right after it, as the software filters and encoders need. They are left on the device
+/// when a hardware encoder reads them and the other filters, `fps` and `setsar`, handle
+/// device frames too
+pub fn download_device_frames(filters: &mut Vec<String>, hardware_encode: bool) {
+    let device_only = filters.iter().skip(1).all(|filter| filter.starts_with("fps=") || filter.starts_with("setsar="));
+    if hardware_encode && device_only {
+        return
+    }
+
+    filters.insert(1, String::from("hwdownload"));
+    filters.insert(2, String::from("format=nv12"));
+}
+
+/// Builds the `scale` filter fitting the video within `width`x`height` without upscaling:
+/// each side is capped to the source one, so smaller videos keep their size. Commas inside
+/// `min` are escaped so they do not split the filter chain
+pub fn build_max_dimensions_filter(width: u32, height: u32) -> String {
+    format!("scale=min(iw\\,{}):min(ih\\,{}):force_original_aspect_ratio=decrease:force_divisible_by=2", width, height)
+}
+
+/// Builds the `drawbox` filter of a [`ProgressBar`]. The width grows with the frame time
+/// `t` over `duration_secs`, which `drawbox` evaluates again for each frame
+pub fn build_progress_bar_filter(progress_bar: &ProgressBar, duration_secs: f64) -> Result<String, AtiumError> {
+    if duration_secs.is_nan() || duration_secs <= 0.0 {
+        return Err(AtiumError::InvalidArgument("A progress bar requires an input with a known duration".to_string()))
+    }
+
+    // the expression holds no commas, so it does not need escaping inside the graph
+    Ok(format!("drawbox=x=0:y=ih-{h}:w=iw*t/{duration}:h={h}:color={color}:t=fill",
+        h = progress_bar.height,
+        duration = duration_secs,
+        color = progress_bar.color))
+}
+
+/// Checks that a color is in one of the formats accepted by `ffmpeg`: a color name like
+/// `white`, or `#RRGGBB`/`0xRRGGBB` with an optional alpha byte, optionally followed by
+/// an `@opacity` modifier
+pub fn validate_color(color: &str) -> Result<(), AtiumError> {
+    let base = color.split_once('@').map(|(base, _)| base).unwrap_or(color);
+    let hex = base.strip_prefix('#').or_else(|| base.strip_prefix("0x"));
+
+    let valid = match hex {
+        Some(digits) => (digits.len() == 6 || digits.len() == 8) && digits.chars().all(|c| c.is_ascii_hexdigit()),
+        None => !base.is_empty() && base.chars().all(|c| c.is_ascii_alphabetic())
+    };
+
+    if !valid {
+        return Err(AtiumError::InvalidArgument(format!("Invalid color [{}]", color)))
+    }
+
+    Ok(())
+}
+
+/// Returns the `si` index, relative to the subtitle streams of the input, that matches the
+/// requested [`SubtitleTrack`]. A language matches against any of the language fields of
+/// the track (`en`, `eng`, `English`), ignoring case
+pub fn resolve_subtitle_index(analysis_output: &AnalysisOutput, track: &SubtitleTrack) -> Result<usize, AtiumError> {
+    let subtitle_tracks = analysis_output.get_tracks_by_type("Text");
+    if subtitle_tracks.is_empty() {
+        return Err(AtiumError::InvalidArgument("Input file has no subtitle streams".to_string()))
+    }
+
+    match track {
+        SubtitleTrack::Index(index) => {
+            if *index >= subtitle_tracks.len() {
+                return Err(AtiumError::InvalidArgument(format!(
+                    "Subtitle stream [{}] not found, input has {} subtitle streams", index, subtitle_tracks.len())))
+            }
+
+            Ok(*index)
+        }
+        SubtitleTrack::Language(language) => {
+            let language = language.to_lowercase();
+            subtitle_tracks.iter()
+                .position(|t| ["Language", "Language_String", "Language_String1", "Language_String3"].iter()
+                    .filter_map(|field| analysis_output.get_track_field(t, field))
+                    .any(|value| value.to_lowercase() == language))
+                .inspect(|index| debug!("Subtitle language [{}] resolved to stream [{}]", language, index))
+                .ok_or(AtiumError::InvalidArgument(format!("No subtitle stream found for language [{}]", language)))
+        }
+    }
+}
+
+/// Character encodings accepted for subtitle files
+const SUBTITLE_CHARSETS: [&str; 24] = [
+    "UTF-8", "UTF-16", "UTF-16LE", "UTF-16BE",
+    "CP1250", "CP1251", "CP1252", "CP1253", "CP1254", "CP1255", "CP1256", "CP1257",
+    "ISO-8859-1", "ISO-8859-2", "ISO-8859-5", "ISO-8859-7", "ISO-8859-9", "ISO-8859-15",
+    "KOI8-R", "SHIFT_JIS", "EUC-JP", "EUC-KR", "GB18030", "BIG5"
+];
+
+/// Validates a subtitle character encoding against a list of common ones, ignoring case.
+/// `WINDOWS-125x` aliases of `CP125x` are accepted too
+pub fn validate_subtitle_charset(charset: &str) -> Result<(), AtiumError> {
+    let normalized = charset.to_uppercase().replace("WINDOWS-", "CP");
+    if !SUBTITLE_CHARSETS.contains(&normalized.as_str()) {
+        return Err(AtiumError::InvalidArgument(format!(
+            "Unsupported subtitle charset [{}], valid charsets are: {}", charset, SUBTITLE_CHARSETS.join(", "))))
+    }
+
+    Ok(())
+}
+
+/// Builds the `subtitles` filter burning an external subtitle file, decoded with
+/// `charset` when given. `ffmpeg` reads subtitle files as UTF-8 by default
+pub fn build_subtitles_file_filter(subtitle_file: &str, charset: Option<&str>) -> String {
+    match charset {
+        Some(charset) => format!("subtitles=filename={}:charenc={}", escape_filter_value(subtitle_file), escape_filter_value(charset)),
+        None => format!("subtitles=filename={}", escape_filter_value(subtitle_file))
+    }
+}
+
+/// Builds the `subtitles` filter burning the `si`-th subtitle stream embedded in the input
+pub fn build_subtitles_embedded_filter(input_file: &str, stream_index: usize) -> String {
+    format!("subtitles=filename={}:si={}", escape_filter_value(input_file), stream_index)
+}
+
+/// Builds the `lavfi` source exposing the closed captions carried in the video stream of
+/// `input_file` as a subtitle stream, through the `subcc` output of the `movie` source
+pub fn build_closed_captions_source(input_file: &str) -> String {
+    format!("movie={}[out+subcc]", escape_filter_value(input_file))
+}
+
+/// Returns `true` when mediainfo reports CEA-608/708 captions, which it names `EIA-608`
+/// and `EIA-708`. Some containers hide them from mediainfo, so `false` is not conclusive
+pub fn has_closed_captions(analysis_output: &AnalysisOutput) -> bool {
+    analysis_output.get_tracks_by_type("Text").iter()
+        .filter_map(|track| analysis_output.get_track_field(track, "Format"))
+        .any(|format| ["EIA-608", "EIA-708", "CEA-608", "CEA-708"].contains(&format.as_str()))
+}
+
+/// Builds the `pan` filter mixing a `channels` stream down to stereo. Channels are referenced
+/// by index, in the `ffmpeg` order `FL FR FC LFE` followed by the surrounds, so both the
+/// back and the side 5.1 layouts work. The LFE channel is dropped as usual. Fails when the
+/// stream is not 5.1 or 7.1
+pub fn build_downmix_filter(mode: &DownmixMode, channels: u32) -> Result<String, AtiumError> {
+    let surrounds: &[(usize, usize)] = match channels {
+        6 => &[(4, 5)],
+        8 => &[(4, 5), (6, 7)],
+        _ => return Err(AtiumError::InvalidArgument(format!(
+            "Downmix requires a 5.1 or 7.1 audio stream, the input has [{}] channels", channels)))
+    };
+    // `<` renormalizes the gains so they sum up to 1, `=` keeps them as they are
+    let (operator, front, center, surround) = match mode {
+        DownmixMode::StereoDefault => ("<", "1.0", "0.707", "0.707"),
+        DownmixMode::StereoDialogBoost => ("=", "0.3", "1.0", "0.3")
+    };
+
+    let side = |front_index: usize, pick: fn(&(usize, usize)) -> usize| {
+        let mut terms = vec![format!("{}*c{}", front, front_index), format!("{}*c2", center)];
+        terms.extend(surrounds.iter().map(|pair| format!("{}*c{}", surround, pick(pair))));
+        terms.join("+")
+    };
+
+    Ok(format!("pan=stereo|FL{}{}|FR{}{}", operator, side(0, |pair| pair.0), operator, side(1, |pair| pair.1)))
+}
+
+/// Builds the `silenceremove` filters trimming the silence of `trim`. The trailing silence is
+/// removed as leading silence of the reversed audio, since `stop_periods` also cuts the pauses
+/// in the middle and changed meaning across `ffmpeg` versions. Reversing buffers the audio
+pub fn build_silence_trim_filter(trim: &SilenceTrim) -> String {
+    let leading = format!("silenceremove=start_periods=1:start_duration={}:start_threshold={}dB",
+        trim.min_duration_secs, trim.threshold_db);
+
+    match trim.edge {
+        SilenceEdge::Leading => leading,
+        SilenceEdge::Trailing => format!("areverse,{},areverse", leading),
+        SilenceEdge::Both => format!("{},areverse,{},areverse", leading, leading)
+    }
+}
+
+/// Builds the `-filter_complex` graph drawing the video of input `pip_input` over the main
+/// video once `main_filters` are applied to it. The overlay is scaled against the filtered
+/// main video, keeping its aspect ratio, and disappears when it ends before the main one.
+/// The result is labeled `[vout]`
+pub fn build_pip_filter(pip: &Pip, main_filters: &str, pip_input: usize) -> String {
+    let margin = pip.margin;
+    let (x, y) = match pip.position {
+        PipPosition::TopLeft => (format!("{}", margin), format!("{}", margin)),
+        PipPosition::TopRight => (format!("W-w-{}", margin), format!("{}", margin)),
+        PipPosition::BottomLeft => (format!("{}", margin), format!("H-h-{}", margin)),
+        PipPosition::BottomRight => (format!("W-w-{}", margin), format!("H-h-{}", margin))
+    };
+
+    format!("[0:v]{}[main];[{}:v][main]scale2ref=w=trunc(main_w*{}/2)*2:h=-2[pip][base];[base][pip]overlay=x={}:y={}:eof_action=pass[vout]",
+        main_filters, pip_input, pip.scale, x, y)
+}
+
+/// Builds the `-filter_complex` graph playing the intro of input `intro_input` before the
+/// main video once `main_filters` are applied to it. The intro is scaled to the filtered
+/// main video and both get square pixels, as `concat` needs matching frames. With `audio`,
+/// the `(audio_input, silence_input, audio_filter)` of the main and intro inputs are joined
+/// too. The results are labeled `[vout]` and `[aout]`
+pub fn build_intro_filter(intro: &Intro, main_filters: &str, intro_input: usize, audio: Option<(usize, usize, Option<&str>)>) -> String {
+    let text_filter = intro.text.as_ref()
+        .map(|text| format!("drawtext=text={}:expansion=none:fontcolor=white:fontsize=h/12:x=(w-text_w)/2:y=(h-text_h)/2,", escape_filter_value(text)))
+        .unwrap_or_default();
+    let video = format!("[0:v]{},setsar=1[main];[{}:v][main]scale2ref[intro_scaled][base];[intro_scaled]{}setsar=1[intro]",
+        main_filters, intro_input, text_filter);
+
+    match audio {
+        Some((audio_input, silence_input, audio_filter)) => format!(
+            "{};[{}:a]{}[amain];[intro][{}:a][base][amain]concat=n=2:v=1:a=1[vout][aout]",
+            video, audio_input, audio_filter.unwrap_or("anull"), silence_input),
+        None => format!("{};[intro][base]concat=n=2:v=1:a=0[vout]", video)
+    }
+}
+
+/// Builds the graph mixing the audio of input `pip_input` into the one of `audio_input`.
+/// `audio_filter`, if any, applies to the main audio only, so a downmix still sees the
+/// input layout. The mix lasts as long as the main audio and is labeled `[aout]`
+pub fn build_pip_audio_filter(audio_input: usize, pip_input: usize, audio_filter: Option<&str>) -> String {
+    let main_audio = match audio_filter {
+        Some(audio_filter) => format!("[{}:a]{}[amain];[amain]", audio_input, audio_filter),
+        None => format!("[{}:a]", audio_input)
+    };
+
+    format!("{}[{}:a]amix=inputs=2:duration=first[aout]", main_audio, pip_input)
+}
+
+/// Computes the start of each crossfade when joining clips of `durations` seconds with
+/// fades of `fade` seconds. Each fade overlaps the end of a clip with the start of the next
+/// one, so the transition `i` starts once the clips up to `i` have played, minus the `i + 1`
+/// fades already overlapped. Fails when a clip is too short for the fades it takes part in
+pub fn compute_crossfade_offsets(durations: &[f64], fade: f64) -> Result<Vec<f64>, AtiumError> {
+    if !fade.is_finite() || fade <= 0.0 {
+        return Err(AtiumError::InvalidArgument(format!("Crossfade [{}] must be greater than 0", fade)))
+    }
+    let last = durations.len().saturating_sub(1);
+    for (index, duration) in durations.iter().enumerate() {
+        // inner clips fade in and out, the first and the last one only once
+        let fades = if index == 0 || index == last { 1.0 } else { 2.0 };
+        if *duration <= fade * fades {
+            return Err(AtiumError::InvalidArgument(format!(
+                "Clip [{}] lasts [{}s], too short for a [{}s] crossfade", index, duration, fade)))
+        }
+    }
+
+    let mut elapsed = 0.0;
+    Ok(durations[..last].iter()
+        .map(|duration| {
+            elapsed += duration - fade;
+            elapsed
+        })
+        .collect())
+}
+
+/// Builds the graph joining `inputs` clips one after the other into `[vout]`, and `[aout]`
+/// when `with_audio` is set. Without `crossfade` the clips are cut with `concat`, otherwise
+/// they are chained with `xfade` and `acrossfade` at the `offsets` of
+/// [`compute_crossfade_offsets`]. `xfade` needs the same timebase on both sides, so each
+/// clip is rebased first
+pub fn build_concat_filter(inputs: usize, with_audio: bool, crossfade: Option<(f64, &[f64])>) -> String {
+    let (fade, offsets) = match crossfade {
+        Some(crossfade) => crossfade,
+        None => {
+            let streams: String = (0..inputs)
+                .map(|index| if with_audio { format!("[{}:v][{}:a]", index, index) } else { format!("[{}:v]", index) })
+                .collect();
+            let outputs = if with_audio { "[vout][aout]" } else { "[vout]" };
+            return format!("{}concat=n={}:v=1:a={}{}", streams, inputs, u8::from(with_audio), outputs)
+        }
+    };
+
+    let mut graph: Vec<String> = (0..inputs)
+        .map(|index| format!("[{}:v]settb=AVTB,setpts=PTS-STARTPTS[v{}]", index, index))
+        .collect();
+    // each transition takes the result of the previous one and the next clip
+    let label = |kind: &str, index: usize| if index == inputs - 1 { format!("[{}out]", kind) } else { format!("[{}x{}]", kind, index) };
+    let mut previous = String::from("[v0]");
+    for (index, offset) in (1..inputs).zip(offsets) {
+        graph.push(format!("{}[v{}]xfade=transition=fade:duration={}:offset={:.3}{}", previous, index, fade, offset, label("v", index)));
+        previous = label("v", index);
+    }
+    if with_audio {
+        let mut previous = String::from("[0:a]");
+        for index in 1..inputs {
+            graph.push(format!("{}[{}:a]acrossfade=d={}{}", previous, index, fade, label("a", index)));
+            previous = label("a", index);
+        }
+    }
+
+    graph.join(";")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::converter::builder::ConversionRequestBuilder;
+    use crate::converter::model::IntroBackground;
+    use super::*;
+    use crate::atium::common::analysis_helper::MediaInfoJsonLoader;
+
+    #[test]
+    fn test_closed_captions() {
+        assert_eq!(build_closed_captions_source("/tmp/news: late.ts"), "movie=/tmp/news\\\\: late.ts[out+subcc]");
+
+        let json = r#"{"media": {"track": [
+            {"@type": "General"},
+            {"@type": "Video", "Format": "MPEG Video"},
+            {"@type": "Text", "Format": "EIA-608", "MuxingMode": "A/53 / DTVCC Transport"}
+        ]}}"#;
+        assert!(has_closed_captions(&MediaInfoJsonLoader{}.load_json_from_string(json).unwrap()));
+
+        let json = r#"{"media": {"track": [{"@type": "General"}, {"@type": "Text", "Format": "UTF-8"}]}}"#;
+        assert!(!has_closed_captions(&MediaInfoJsonLoader{}.load_json_from_string(json).unwrap()));
+    }
+
+    fn load_subtitled_output() -> AnalysisOutput {
+        let json = r#"{"media": {"track": [
+            {"@type": "General"},
+            {"@type": "Video", "Width": "1920"},
+            {"@type": "Text", "Language": "en", "Language_String": "English", "Language_String3": "eng"},
+            {"@type": "Text", "Language": "it", "Language_String": "Italian", "Language_String3": "ita"}
+        ]}}"#;
+
+        MediaInfoJsonLoader{}.load_json_from_string(json).ok().unwrap()
+    }
+
+    #[test]
+    fn test_escape_filter_value() {
+        assert_eq!(escape_filter_value("/tmp/subs.srt"), "/tmp/subs.srt");
+        assert_eq!(escape_filter_value("/tmp/a:b.srt"), "/tmp/a\\\\:b.srt");
+        assert_eq!(escape_filter_value("/tmp/it's,here.srt"), "/tmp/it\\\\\\'s\\,here.srt");
+    }
+
+    #[test]
+    fn test_build_pad_filter() {
+        let pad = Pad { width: 1920, height: 1080, color: String::from("black") };
+
+        assert_eq!(build_pad_filter(&pad),
+            "scale=1920:1080:force_original_aspect_ratio=decrease:force_divisible_by=2,pad=1920:1080:(ow-iw)/2:(oh-ih)/2:black");
+    }
+
+    #[test]
+    fn test_build_blur_fill_filter() {
+        let blur_fill = BlurFill { width: 1080, height: 1920, strength: 20 };
+
+        assert_eq!(build_blur_fill_filter(&blur_fill),
+            "split[background][foreground];\
+            [background]scale=1080:1920:force_original_aspect_ratio=increase,crop=1080:1920,boxblur=20:2[blurred];\
+            [foreground]scale=1080:1920:force_original_aspect_ratio=decrease:force_divisible_by=2[fitted];\
+            [blurred][fitted]overlay=(W-w)/2:(H-h)/2");
+    }
+
+    #[test]
+    fn test_build_downmix_filter() {
+        assert_eq!(build_downmix_filter(&DownmixMode::StereoDefault, 6).ok().unwrap(),
+            "pan=stereo|FL<1.0*c0+0.707*c2+0.707*c4|FR<1.0*c1+0.707*c2+0.707*c5");
+        assert_eq!(build_downmix_filter(&DownmixMode::StereoDialogBoost, 8).ok().unwrap(),
+            "pan=stereo|FL=0.3*c0+1.0*c2+0.3*c4+0.3*c6|FR=0.3*c1+1.0*c2+0.3*c5+0.3*c7");
+        assert!(build_downmix_filter(&DownmixMode::StereoDefault, 2).is_err());
+    }
+
+    #[test]
+    fn test_build_silence_trim_filter() {
+        let trim = |edge| SilenceTrim { edge, threshold_db: -50.0, min_duration_secs: 0.5 };
+
+        assert_eq!(build_silence_trim_filter(&trim(SilenceEdge::Leading)),
+            "silenceremove=start_periods=1:start_duration=0.5:start_threshold=-50dB");
+        assert_eq!(build_silence_trim_filter(&trim(SilenceEdge::Trailing)),
+            "areverse,silenceremove=start_periods=1:start_duration=0.5:start_threshold=-50dB,areverse");
+        assert_eq!(build_silence_trim_filter(&trim(SilenceEdge::Both)),
+            "silenceremove=start_periods=1:start_duration=0.5:start_threshold=-50dB,areverse,silenceremove=start_periods=1:start_duration=0.5:start_threshold=-50dB,areverse");
+    }
+
+    #[test]
+    fn test_build_pip_filter() {
+        let pip = Pip {
+            overlay_input: String::from("/tmp/webcam.mp4"),
+            position: PipPosition::BottomRight,
+            scale: 0.25,
+            margin: 16,
+            mix_audio: true
+        };
+
+        assert_eq!(build_pip_filter(&pip, "scale=1280:720", 1),
+            "[0:v]scale=1280:720[main];[1:v][main]scale2ref=w=trunc(main_w*0.25/2)*2:h=-2[pip][base];[base][pip]overlay=x=W-w-16:y=H-h-16:eof_action=pass[vout]");
+        assert!(build_pip_filter(&Pip { position: PipPosition::TopLeft, ..pip }, "scale=1280:720", 2)
+            .contains("[2:v][main]scale2ref=w=trunc(main_w*0.25/2)*2:h=-2[pip][base];[base][pip]overlay=x=16:y=16:"));
+        assert_eq!(build_pip_audio_filter(0, 1, None), "[0:a][1:a]amix=inputs=2:duration=first[aout]");
+        assert_eq!(build_pip_audio_filter(1, 2, Some("pan=stereo|FL<c0|FR<c1")),
+            "[1:a]pan=stereo|FL<c0|FR<c1[amain];[amain][2:a]amix=inputs=2:duration=first[aout]");
+    }
+
+    #[test]
+    fn test_compute_crossfade_offsets() {
+        assert_eq!(compute_crossfade_offsets(&[10.0, 8.0], 1.0).ok().unwrap(), vec![9.0]);
+        assert_eq!(compute_crossfade_offsets(&[10.0, 8.0, 6.0, 5.0], 1.0).ok().unwrap(), vec![9.0, 16.0, 21.0]);
+        assert_eq!(compute_crossfade_offsets(&[10.0], 1.0).ok().unwrap(), Vec::<f64>::new());
+        assert!(compute_crossfade_offsets(&[10.0, 1.5, 10.0], 1.0).is_err());
+        assert!(compute_crossfade_offsets(&[10.0, 1.5], 1.0).is_ok());
+        assert!(compute_crossfade_offsets(&[10.0, 8.0], 0.0).is_err());
+    }
+
+    #[test]
+    fn test_build_concat_filter() {
+        assert_eq!(build_concat_filter(3, true, None), "[0:v][0:a][1:v][1:a][2:v][2:a]concat=n=3:v=1:a=1[vout][aout]");
+        assert_eq!(build_concat_filter(2, false, None), "[0:v][1:v]concat=n=2:v=1:a=0[vout]");
+        assert_eq!(build_concat_filter(3, true, Some((1.0, &[9.0, 16.0]))), [
+            "[0:v]settb=AVTB,setpts=PTS-STARTPTS[v0]",
+            "[1:v]settb=AVTB,setpts=PTS-STARTPTS[v1]",
+            "[2:v]settb=AVTB,setpts=PTS-STARTPTS[v2]",
+            "[v0][v1]xfade=transition=fade:duration=1:offset=9.000[vx1]",
+            "[vx1][v2]xfade=transition=fade:duration=1:offset=16.000[vout]",
+            "[0:a][1:a]acrossfade=d=1[ax1]",
+            "[ax1][2:a]acrossfade=d=1[aout]"
+        ].join(";"));
+    }
+
+    #[test]
+    fn test_build_intro_filter() {
+        let intro = Intro { duration_secs: 2.0, background: IntroBackground::Color(String::from("black")), text: Some(String::from("Day 1: Rome")) };
+
+        assert_eq!(build_intro_filter(&Intro { text: None, ..intro.clone() }, "scale=1280:720", 1, None),
+            "[0:v]scale=1280:720,setsar=1[main];[1:v][main]scale2ref[intro_scaled][base];[intro_scaled]setsar=1[intro];[intro][base]concat=n=2:v=1:a=0[vout]");
+        assert_eq!(build_intro_filter(&intro, "scale=1280:720", 1, Some((0, 2, None))),
+            "[0:v]scale=1280:720,setsar=1[main];[1:v][main]scale2ref[intro_scaled][base];\
+[intro_scaled]drawtext=text=Day 1\\\\: Rome:expansion=none:fontcolor=white:fontsize=h/12:x=(w-text_w)/2:y=(h-text_h)/2,setsar=1[intro];\
+[0:a]anull[amain];[intro][2:a][base][amain]concat=n=2:v=1:a=1[vout][aout]");
+    }
+
+    #[test]
+    fn test_device_scaling() {
+        let output = ConversionRequestBuilder::new("/tmp/input.mov", "/tmp/output.mp4").env_source(|_| None).build().ok().unwrap().output;
+        let padded = ConversionRequestBuilder::new("/tmp/input.mov", "/tmp/output.mp4")
+            .env_source(|_| None)
+            .pad(Pad { width: 1920, height: 1080, color: String::from("black") })
+            .build()
+            .ok()
+            .unwrap()
+            .output;
+
+        assert_eq!(select_device_scaling(&output, Some(&HwAccel::Cuda)), Some(&HwAccel::Cuda));
+        assert_eq!(select_device_scaling(&output, Some(&HwAccel::VideoToolbox)), None);
+        assert_eq!(select_device_scaling(&padded, Some(&HwAccel::Vaapi)), None);
+        assert_eq!(build_device_scale_filter(&HwAccel::Vaapi, 1280, 720).unwrap(), "scale_vaapi=w=1280:h=720:format=nv12");
+        assert_eq!(build_device_scale_filter(&HwAccel::Qsv, 1280, 720).unwrap(), "scale_qsv=w=1280:h=720:format=nv12");
+        assert_eq!(build_device_scale_filter(&HwAccel::VideoToolbox, 1280, 720), None);
+
+        // a 10 bit source decodes to p010, so the scaler itself has to output the nv12 downloaded next
+        let mut filters = vec![build_device_scale_filter(&HwAccel::Cuda, 1280, 720).unwrap(), String::from("fps=25")];
+        download_device_frames(&mut filters, true);
+        assert_eq!(filters, vec!["scale_cuda=w=1280:h=720:format=nv12", "fps=25"]);
+        download_device_frames(&mut filters, false);
+        assert_eq!(filters, vec!["scale_cuda=w=1280:h=720:format=nv12", "hwdownload", "format=nv12", "fps=25"]);
+    }
+
+    #[test]
+    fn test_build_max_dimensions_filter() {
+        assert_eq!(build_max_dimensions_filter(1920, 1080),
+            "scale=min(iw\\,1920):min(ih\\,1080):force_original_aspect_ratio=decrease:force_divisible_by=2");
+    }
+
+    #[test]
+    fn test_build_progress_bar_filter() {
+        let progress_bar = ProgressBar { height: 8, color: String::from("#FF0000@0.8") };
+
+        assert_eq!(build_progress_bar_filter(&progress_bar, 12.5).ok().unwrap(),
+            "drawbox=x=0:y=ih-8:w=iw*t/12.5:h=8:color=#FF0000@0.8:t=fill");
+        assert!(build_progress_bar_filter(&progress_bar, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_validate_color() {
+        assert!(validate_color("white").is_ok());
+        assert!(validate_color("#00FF00").is_ok());
+        assert!(validate_color("0x00ff00aa").is_ok());
+        assert!(validate_color("black@0.5").is_ok());
+
+        assert!(validate_color("").is_err());
+        assert!(validate_color("#00FF0").is_err());
+        assert!(validate_color("red;drop").is_err());
+    }
+
+    #[test]
+    fn test_subtitle_charset() {
+        assert!(validate_subtitle_charset("utf-8").is_ok());
+        assert!(validate_subtitle_charset("Windows-1252").is_ok());
+        assert!(validate_subtitle_charset("CP1251").is_ok());
+        assert!(validate_subtitle_charset("latin-42").is_err());
+
+        assert_eq!(build_subtitles_file_filter("/tmp/subs.srt", None), "subtitles=filename=/tmp/subs.srt");
+        assert_eq!(build_subtitles_file_filter("/tmp/subs.srt", Some("CP1252")), "subtitles=filename=/tmp/subs.srt:charenc=CP1252");
+    }
+
+    #[test]
+    fn test_resolve_subtitle_index() {
+        let output = load_subtitled_output();
+
+        assert_eq!(resolve_subtitle_index(&output, &SubtitleTrack::Index(1)).ok().unwrap(), 1);
+        assert!(resolve_subtitle_index(&output, &SubtitleTrack::Index(2)).is_err());
+        assert_eq!(resolve_subtitle_index(&output, &SubtitleTrack::Language(String::from("ita"))).ok().unwrap(), 1);
+        assert_eq!(resolve_subtitle_index(&output, &SubtitleTrack::Language(String::from("English"))).ok().unwrap(), 0);
+        assert!(resolve_subtitle_index(&output, &SubtitleTrack::Language(String::from("de"))).is_err());
+    }
+
+    #[test]
+    fn test_resolve_subtitle_index_without_subtitles() {
+        let json = r#"{"media": {"track": [{"@type": "General"}, {"@type": "Video"}]}}"#;
+        let output = MediaInfoJsonLoader{}.load_json_from_string(json).ok().unwrap();
+
+        assert!(resolve_subtitle_index(&output, &SubtitleTrack::Index(0)).is_err());
+    }
+}
\ No newline at end of file
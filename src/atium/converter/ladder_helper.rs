@@ -0,0 +1,144 @@
+use std::path::Path;
+use crate::atium::common::error::AtiumError;
+use crate::converter::model::{get_encoder_name, OutputCodec};
+
+/// How the quality of a [`Rendition`] is controlled
+#[derive(Clone, Debug, PartialEq)]
+pub enum RenditionQuality {
+    /// Constant rate factor, from 0 (lossless) to 51, lower is better
+    Crf(u8),
+    /// Average bitrate, e.g. `2500k` or `5M`
+    Bitrate(String)
+}
+
+/// A rung of a rendition ladder, scaled to `height` keeping the aspect ratio
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rendition {
+    pub height: u32,
+    pub quality: RenditionQuality
+}
+
+/// Parses a rendition like `1080p:crf=20` or `720:2500k`
+pub fn parse_rendition(input: &str) -> Result<Rendition, AtiumError> {
+    let invalid = || AtiumError::InvalidArgument(format!("Invalid rendition [{}], expected HEIGHT:crf=N or HEIGHT:BITRATE", input));
+
+    let (height, quality) = input.split_once(':').ok_or_else(invalid)?;
+    let height = height.trim_end_matches('p').parse::<u32>().map_err(|_| invalid())?;
+    let quality = match quality.strip_prefix("crf=") {
+        Some(crf) => RenditionQuality::Crf(crf.parse::<u8>().map_err(|_| invalid())?),
+        None => RenditionQuality::Bitrate(quality.to_string())
+    };
+    let rendition = Rendition { height, quality };
+    validate_rendition(&rendition)?;
+
+    Ok(rendition)
+}
+
+/// Validates the height, even for 4:2:0 chroma, and the quality of a [`Rendition`]
+pub fn validate_rendition(rendition: &Rendition) -> Result<(), AtiumError> {
+    if rendition.height == 0 || !rendition.height.is_multiple_of(2) {
+        return Err(AtiumError::InvalidArgument(format!("Rendition height [{}] must be an even, non zero value", rendition.height)))
+    }
+
+    validate_quality(&rendition.quality, 51)
+}
+
+/// Validates a [`RenditionQuality`], where a crf can't go past `max_crf`
+pub fn validate_quality(quality: &RenditionQuality, max_crf: u8) -> Result<(), AtiumError> {
+    match quality {
+        RenditionQuality::Crf(crf) if *crf > max_crf =>
+            Err(AtiumError::InvalidArgument(format!("Crf [{}] must be between 0 and {}", crf, max_crf))),
+        RenditionQuality::Crf(_) => Ok(()),
+        RenditionQuality::Bitrate(bitrate) => {
+            let digits = bitrate.trim_end_matches(['k', 'K', 'm', 'M']);
+            let valid = !digits.is_empty()
+                && digits.len() + 1 >= bitrate.len()
+                && digits.chars().all(|c| c.is_ascii_digit());
+            if valid {
+                Ok(())
+            } else {
+                Err(AtiumError::InvalidArgument(format!("Invalid bitrate [{}], expected e.g. 2500k", bitrate)))
+            }
+        }
+    }
+}
+
+/// Builds the `filter_complex` graph splitting the input video once per rendition and
+/// scaling each copy, labelled `[v0]`, `[v1]` and so on in the order of `renditions`
+pub fn build_ladder_filter(renditions: &[Rendition]) -> String {
+    let split_labels: String = (0..renditions.len()).map(|index| format!("[s{}]", index)).collect();
+    let scales = renditions.iter()
+        .enumerate()
+        .map(|(index, rendition)| format!("[s{index}]scale=-2:{}[v{index}]", rendition.height));
+
+    std::iter::once(format!("[0:v]split={}{}", renditions.len(), split_labels))
+        .chain(scales)
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Builds the encoder options of a rendition. `specifier` selects the output stream the
+/// options apply to, e.g. `:v:1` when several renditions share one output, or is empty
+pub fn build_rendition_quality_args(rendition: &Rendition, specifier: &str) -> Vec<String> {
+    let mut args = vec![
+        format!("-c{}", specifier),
+        get_encoder_name(OutputCodec::H264)
+    ];
+    match &rendition.quality {
+        RenditionQuality::Crf(crf) => {
+            args.push(format!("-crf{}", specifier));
+            args.push(crf.to_string());
+        }
+        RenditionQuality::Bitrate(bitrate) => {
+            args.push(format!("-b{}", specifier));
+            args.push(bitrate.clone());
+        }
+    }
+
+    args
+}
+
+/// Returns the output path of a rendition, e.g. `/out/movie_720p.mp4` for `/out/movie.mp4`
+pub fn build_rendition_output(output_file: &str, rendition: &Rendition) -> Result<String, AtiumError> {
+    let path = Path::new(output_file);
+    let stem = path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or(AtiumError::IOError("Could not parse filename".to_string()))?;
+    let extension = path.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("mp4");
+
+    path.with_file_name(format!("{}_{}p.{}", stem, rendition.height, extension))
+        .to_str()
+        .map(String::from)
+        .ok_or(AtiumError::IOError("Could not build output path".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rendition() {
+        assert_eq!(parse_rendition("1080p:crf=20").ok().unwrap(), Rendition { height: 1080, quality: RenditionQuality::Crf(20) });
+        assert_eq!(parse_rendition("720:2500k").ok().unwrap(), Rendition { height: 720, quality: RenditionQuality::Bitrate(String::from("2500k")) });
+        assert!(parse_rendition("720").is_err());
+        assert!(parse_rendition("721p:crf=20").is_err());
+        assert!(parse_rendition("720p:crf=60").is_err());
+        assert!(parse_rendition("720p:fast").is_err());
+        assert!(parse_rendition("720p:25kk").is_err());
+    }
+
+    #[test]
+    fn test_build_ladder_args() {
+        let renditions = vec![
+            parse_rendition("1080p:crf=20").ok().unwrap(),
+            parse_rendition("480p:900k").ok().unwrap()
+        ];
+
+        assert_eq!(build_ladder_filter(&renditions), "[0:v]split=2[s0][s1];[s0]scale=-2:1080[v0];[s1]scale=-2:480[v1]");
+        assert_eq!(build_rendition_quality_args(&renditions[0], ":v"), vec!["-c:v", "libx264", "-crf:v", "20"]);
+        assert_eq!(build_rendition_quality_args(&renditions[1], ":v:1"), vec!["-c:v:1", "libx264", "-b:v:1", "900k"]);
+        assert_eq!(build_rendition_output("/out/movie.mp4", &renditions[1]).ok().unwrap(), "/out/movie_480p.mp4");
+    }
+}
\ No newline at end of file
@@ -1,2 +1,9 @@
 pub mod service;
-pub mod model;
\ No newline at end of file
+pub mod model;
+pub mod builder;
+pub mod filter_helper;
+pub mod progress_helper;
+pub mod ladder_helper;
+pub mod subtitle_helper;
+pub mod multi_output_helper;
+pub mod batch_helper;
\ No newline at end of file
@@ -1,14 +1,112 @@
-use crate::atium::common::model::{ThumbnailRequest, ThumbnailResponse};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use serde::Serialize;
+
+use crate::atium::common::error::AtiumError;
+use crate::converter::subtitle_helper::CueSelector;
+use crate::atium::common::model::{CollisionPolicy, ResourceUsage, ThumbnailRequest, ThumbnailResponse};
+use crate::atium::common::time_helper::parse_timestamp;
+use crate::atium::common::track_helper::TrackList;
+use crate::atium::measure::model::NormalizePreset;
+use crate::converter::filter_helper::{validate_color, validate_subtitle_charset};
+use crate::converter::ladder_helper::Rendition;
+use crate::converter::multi_output_helper::OutputSpec;
 
 /// The input file source type
+#[derive(Clone, Serialize)]
 pub enum InputSourceType {
-    Local
+    Local,
+    /// A remote URL read directly by `ffmpeg`, without a local copy
+    Url
+}
+
+/// Hardware accelerators available for decoding
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum HwAccel {
+    Cuda, Vaapi, Qsv, VideoToolbox, D3d11va, Dxva2
+}
+
+impl HwAccel {
+    /// Returns the name used by `ffmpeg -hwaccel`
+    pub fn name(&self) -> &str {
+        match self {
+            HwAccel::Cuda => "cuda",
+            HwAccel::Vaapi => "vaapi",
+            HwAccel::Qsv => "qsv",
+            HwAccel::VideoToolbox => "videotoolbox",
+            HwAccel::D3d11va => "d3d11va",
+            HwAccel::Dxva2 => "dxva2"
+        }
+    }
+}
+
+/// Returns a value of [`HwAccel`] based on its `ffmpeg` name, e.g. `cuda`
+pub fn parse_hwaccel(input: &str) -> Result<HwAccel, AtiumError> {
+    match input.to_lowercase().as_str() {
+        "cuda" => Ok(HwAccel::Cuda),
+        "vaapi" => Ok(HwAccel::Vaapi),
+        "qsv" => Ok(HwAccel::Qsv),
+        "videotoolbox" => Ok(HwAccel::VideoToolbox),
+        "d3d11va" => Ok(HwAccel::D3d11va),
+        "dxva2" => Ok(HwAccel::Dxva2),
+        _ => Err(AtiumError::InvalidArgument(format!("Unknown hardware accelerator [{}]", input)))
+    }
+}
+
+/// AAC encoders available for the output audio
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum AacEncoder {
+    /// The `aac` encoder built into `ffmpeg`
+    Native,
+    /// The Fraunhofer `libfdk_aac`, better at low bitrates but non-free, so often missing
+    Fdk
+}
+
+impl AacEncoder {
+    /// Returns the name used by `ffmpeg -c:a`
+    pub fn name(&self) -> &'static str {
+        match self {
+            AacEncoder::Native => "aac",
+            AacEncoder::Fdk => "libfdk_aac"
+        }
+    }
+}
+
+/// Returns a value of [`AacEncoder`] based on its name `native/fdk`
+pub fn parse_aac_encoder(input: &str) -> Result<AacEncoder, AtiumError> {
+    match input.to_lowercase().as_str() {
+        "native" | "aac" => Ok(AacEncoder::Native),
+        "fdk" | "libfdk_aac" => Ok(AacEncoder::Fdk),
+        _ => Err(AtiumError::InvalidArgument(format!("Unknown AAC encoder [{}], use native or fdk", input)))
+    }
+}
+
+/// Picks the encoder used for the `requested` one: a missing `libfdk_aac` falls back to
+/// the native encoder, unless the output is `strict`
+pub fn pick_aac_encoder(requested: &AacEncoder, fdk_available: bool, strict: bool) -> Result<AacEncoder, AtiumError> {
+    match requested {
+        AacEncoder::Fdk if !fdk_available && strict =>
+            Err(AtiumError::DependencyMissing("encoder [libfdk_aac] is not available in ffmpeg".to_string())),
+        AacEncoder::Fdk if !fdk_available => Ok(AacEncoder::Native),
+        _ => Ok(requested.clone())
+    }
 }
 
 /// The proper input file name and its source type
+#[derive(Clone, Serialize)]
 pub struct ConversionInput {
     pub source_type: InputSourceType,
-    pub file_name: String
+    pub file_name: String,
+    /// Decodes the input on the GPU with `-hwaccel`. Decoded frames are copied back to
+    /// system memory, so the usual filters keep working, except when the plain resolution
+    /// scaling can run on the device too, see
+    /// [`select_device_scaling`](crate::converter::filter_helper::select_device_scaling)
+    pub hwaccel_decode: Option<HwAccel>,
+    /// Decodes the input video with the given `ffmpeg` decoder, e.g. `h264_cuvid` or `libdav1d`,
+    /// instead of the autodetected one. It is passed as `-c:v` before the input
+    pub force_decoder: Option<String>
 }
 
 /// Output resolution options:
@@ -18,6 +116,7 @@ pub struct ConversionInput {
 /// * FullHd2k    -> 1080p - 2048x1080
 /// * UltraHd     -> 4k    - 3840x2160
 /// * FullUltraHd -> 8k    - 7680x4320
+#[derive(Clone, Serialize)]
 pub enum OutputResolution {
     Sd, Hd, FullHd, FullHd2k, UltraHd, FullUltraHd
 }
@@ -30,7 +129,7 @@ pub enum OutputResolution {
 /// * 2k   -> FULL-HD-2K
 /// * uhd  -> ULTRA-HD
 /// * 8k   -> 8K
-pub fn parse_resolution(resolution_string: &String) -> OutputResolution {
+pub fn parse_resolution(resolution_string: &str) -> OutputResolution {
     match resolution_string.to_lowercase().as_str() {
         "sd" => OutputResolution::Sd,
         "hd" => OutputResolution::Hd,
@@ -61,26 +160,2654 @@ pub fn get_width_height(resolution: OutputResolution) -> (i32,i32) {
 }
 
 /// Output codec options
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub enum OutputCodec {
-    H264
+    H264, H265, Vp9, Av1
+}
+
+/// Returns a value of [`OutputCodec`] based on its friendly name: `h264`, `h265`, `vp9`
+/// or `av1`. `avc` and `hevc` are accepted as well
+pub fn parse_output_codec(input: &str) -> Result<OutputCodec, AtiumError> {
+    match input.to_lowercase().as_str() {
+        "h264" | "avc" => Ok(OutputCodec::H264),
+        "h265" | "hevc" => Ok(OutputCodec::H265),
+        "vp9" => Ok(OutputCodec::Vp9),
+        "av1" => Ok(OutputCodec::Av1),
+        _ => Err(AtiumError::InvalidArgument(format!("Unknown codec [{}], valid codecs are: auto, h264, h265, vp9, av1", input)))
+    }
+}
+
+/// Picks the codec of an `auto` conversion from the output container: VP9 for webm, which
+/// can't store h264, and h264, the most widely played, for everything else
+pub fn resolve_auto_codec(output_file: &str) -> OutputCodec {
+    match detect_container(output_file) {
+        OutputContainer::Webm => OutputCodec::Vp9,
+        _ => OutputCodec::H264
+    }
+}
+
+/// Returns the `ffmpeg` software encoder name for the given [`OutputCodec`]
+pub fn get_encoder_name(codec: OutputCodec) -> String {
+    match codec {
+        OutputCodec::H264 => String::from("libx264"),
+        OutputCodec::H265 => String::from("libx265"),
+        OutputCodec::Vp9 => String::from("libvpx-vp9"),
+        OutputCodec::Av1 => String::from("libaom-av1")
+    }
+}
+
+/// Returns the encoder of `codec`: the hardware one of `hwaccel` when given, e.g.
+/// `hevc_nvenc` for h265 on `cuda`, the software one otherwise. Fails when the accelerator
+/// has no encoder for the codec, or needs frames uploaded to the GPU like `vaapi` does
+pub fn resolve_encoder(codec: &OutputCodec, hwaccel: Option<&HwAccel>) -> Result<String, AtiumError> {
+    let hwaccel = match hwaccel {
+        Some(hwaccel) => hwaccel,
+        None => return Ok(get_encoder_name(codec.clone()))
+    };
+    let encoder = match (codec, hwaccel) {
+        (OutputCodec::H264, HwAccel::Cuda) => "h264_nvenc",
+        (OutputCodec::H265, HwAccel::Cuda) => "hevc_nvenc",
+        (OutputCodec::Av1, HwAccel::Cuda) => "av1_nvenc",
+        (OutputCodec::H264, HwAccel::Qsv) => "h264_qsv",
+        (OutputCodec::H265, HwAccel::Qsv) => "hevc_qsv",
+        (OutputCodec::Vp9, HwAccel::Qsv) => "vp9_qsv",
+        (OutputCodec::Av1, HwAccel::Qsv) => "av1_qsv",
+        (OutputCodec::H264, HwAccel::VideoToolbox) => "h264_videotoolbox",
+        (OutputCodec::H265, HwAccel::VideoToolbox) => "hevc_videotoolbox",
+        _ => return Err(AtiumError::InvalidArgument(format!(
+            "No hardware encoder available for {:?} with [{}]", codec, hwaccel.name())))
+    };
+
+    Ok(encoder.to_string())
+}
+
+/// Lowercase `ffmpeg` messages telling that the hardware device or encoder failed,
+/// rather than the request itself
+const HWACCEL_FAILURE_PATTERNS: [&str; 11] = [
+    "openencodesessionex failed",
+    "no capable devices found",
+    "cannot load libnvidia-encode",
+    "driver does not support the required nvenc api version",
+    "failed to initialise vaapi connection",
+    "no va display found",
+    "error creating a mfx session",
+    "error initializing an internal mfx session",
+    "cannot create compression session",
+    "device creation failed",
+    "hardware device setup failed"
+];
+
+/// Tells whether the stderr of a failed encode matches a known hardware failure, in which
+/// case the same conversion can still succeed with a software encoder
+pub fn is_hwaccel_failure(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    HWACCEL_FAILURE_PATTERNS.iter().any(|pattern| stderr.contains(pattern))
+}
+
+/// Bits per color component of the output frames
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum BitDepth {
+    Eight, Ten, Twelve
+}
+
+impl BitDepth {
+    /// Returns the 4:2:0 pixel format storing frames at this depth
+    pub fn pixel_format(&self) -> &'static str {
+        match self {
+            BitDepth::Eight => "yuv420p",
+            BitDepth::Ten => "yuv420p10le",
+            BitDepth::Twelve => "yuv420p12le"
+        }
+    }
+}
+
+/// Returns a value of [`BitDepth`] based on input: `8`, `10` or `12`
+pub fn parse_bit_depth(input: &str) -> Result<BitDepth, AtiumError> {
+    match input {
+        "8" => Ok(BitDepth::Eight),
+        "10" => Ok(BitDepth::Ten),
+        "12" => Ok(BitDepth::Twelve),
+        _ => Err(AtiumError::InvalidArgument(format!("Unknown bit depth [{}], valid depths are: 8, 10, 12", input)))
+    }
+}
+
+/// How a surround audio stream is mixed down to stereo
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum DownmixMode {
+    /// Standard coefficients, center and surrounds at -3 dB, normalized so it never clips
+    StereoDefault,
+    /// Keeps the center, where dialog lives, at full level and lowers the rest, for
+    /// downmixes where voices end up too quiet
+    StereoDialogBoost
+}
+
+/// Returns a value of [`DownmixMode`] based on input: `stereo` or `dialog`
+pub fn parse_downmix_mode(input: &str) -> Result<DownmixMode, AtiumError> {
+    match input.to_lowercase().as_str() {
+        "stereo" => Ok(DownmixMode::StereoDefault),
+        "dialog" => Ok(DownmixMode::StereoDialogBoost),
+        _ => Err(AtiumError::InvalidArgument(format!("Unknown downmix mode [{}], valid modes are: stereo, dialog", input)))
+    }
+}
+
+/// Which end of the audio [`SilenceTrim`] removes silence from
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum SilenceEdge {
+    Leading, Trailing, Both
+}
+
+/// Returns a value of [`SilenceEdge`] based on input: `leading`, `trailing` or `both`
+pub fn parse_silence_edge(input: &str) -> Result<SilenceEdge, AtiumError> {
+    match input.to_lowercase().as_str() {
+        "leading" => Ok(SilenceEdge::Leading),
+        "trailing" => Ok(SilenceEdge::Trailing),
+        "both" => Ok(SilenceEdge::Both),
+        _ => Err(AtiumError::InvalidArgument(format!("Unknown silence edge [{}], valid edges are: leading, trailing, both", input)))
+    }
+}
+
+/// Removal of the silence at the start and/or the end of the audio, e.g. for podcast cleanup
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SilenceTrim {
+    pub edge: SilenceEdge,
+    /// Level under which audio counts as silence, in dBFS, e.g. `-50.0`
+    pub threshold_db: f64,
+    /// Shortest silence that is removed, in seconds. Shorter pauses are kept
+    pub min_duration_secs: f64
+}
+
+/// Checks that the threshold of `trim` is below 0 dBFS and that its duration is positive
+pub fn validate_silence_trim(trim: &SilenceTrim) -> Result<(), AtiumError> {
+    if trim.threshold_db.is_nan() || trim.threshold_db >= 0.0 {
+        return Err(AtiumError::InvalidArgument(format!("Silence threshold [{}] must be below 0 dB", trim.threshold_db)))
+    }
+    if trim.min_duration_secs.is_nan() || trim.min_duration_secs <= 0.0 {
+        return Err(AtiumError::InvalidArgument(format!("Silence duration [{}] must be greater than 0", trim.min_duration_secs)))
+    }
+
+    Ok(())
+}
+
+/// Corner of the main video where [`Pip`] places the overlay
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum PipPosition {
+    TopLeft, TopRight, BottomLeft, BottomRight
+}
+
+/// Returns a value of [`PipPosition`] based on input: `top-left`, `top-right`, `bottom-left`
+/// or `bottom-right`
+pub fn parse_pip_position(input: &str) -> Result<PipPosition, AtiumError> {
+    match input.to_lowercase().as_str() {
+        "top-left" => Ok(PipPosition::TopLeft),
+        "top-right" => Ok(PipPosition::TopRight),
+        "bottom-left" => Ok(PipPosition::BottomLeft),
+        "bottom-right" => Ok(PipPosition::BottomRight),
+        _ => Err(AtiumError::InvalidArgument(format!(
+            "Unknown pip position [{}], valid positions are: top-left, top-right, bottom-left, bottom-right", input)))
+    }
+}
+
+/// A second video drawn over a corner of the converted one, e.g. a webcam over a screen recording
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Pip {
+    pub overlay_input: String,
+    pub position: PipPosition,
+    /// Width of the overlay as a fraction of the main video width, e.g. `0.25`
+    pub scale: f32,
+    /// Distance of the overlay from the edges, in pixels
+    pub margin: u32,
+    /// Mixes the audio of the overlay into the main one instead of dropping it
+    pub mix_audio: bool
+}
+
+/// What an [`Intro`] shows under its text
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum IntroBackground {
+    /// A solid color, e.g. `black` or `#1E1E1E`
+    Color(String),
+    /// A still jpg/png image, stretched to the output size
+    Image(String)
+}
+
+/// Returns an [`IntroBackground`] from a jpg/png image path, or a color otherwise
+pub fn parse_intro_background(input: &str) -> IntroBackground {
+    let extension = Path::new(input).extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "jpg" | "jpeg" | "png" => IntroBackground::Image(input.to_string()),
+        _ => IntroBackground::Color(input.to_string())
+    }
+}
+
+/// A generated clip played before the converted video, e.g. a title card for a slideshow.
+/// It takes the size of the converted video and is silent
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Intro {
+    pub duration_secs: f32,
+    pub background: IntroBackground,
+    /// Text drawn at the center of the intro
+    pub text: Option<String>
+}
+
+/// Checks that the duration of the intro is positive, that its color is valid or its image
+/// exists, and that its text is not empty
+pub fn validate_intro(intro: &Intro) -> Result<(), AtiumError> {
+    if !intro.duration_secs.is_finite() || intro.duration_secs <= 0.0 {
+        return Err(AtiumError::InvalidArgument(format!("Intro duration [{}] must be greater than 0", intro.duration_secs)))
+    }
+    match &intro.background {
+        IntroBackground::Color(color) => validate_color(color)?,
+        IntroBackground::Image(image) => if !Path::new(image).exists() {
+            return Err(AtiumError::IOError(format!("Intro image [{}] not found", image)))
+        }
+    }
+    if intro.text.as_ref().is_some_and(|text| text.trim().is_empty()) {
+        return Err(AtiumError::InvalidArgument("Intro text can't be empty".to_string()))
+    }
+
+    Ok(())
+}
+
+/// Builds the inputs generating the intro: a `color` source or a looped image lasting the
+/// intro, then, when `with_audio` is set, the silence played under it
+pub fn build_intro_input_args(intro: &Intro, with_audio: bool) -> Vec<String> {
+    let duration = format!("{:.3}", intro.duration_secs);
+    let mut args = match &intro.background {
+        IntroBackground::Color(color) => vec![
+            String::from("-f"),
+            String::from("lavfi"),
+            String::from("-i"),
+            format!("color=c={}:s=1280x720:r=25:d={}", color, duration)
+        ],
+        IntroBackground::Image(image) => vec![
+            String::from("-loop"),
+            String::from("1"),
+            String::from("-framerate"),
+            String::from("25"),
+            String::from("-t"),
+            duration.clone(),
+            String::from("-i"),
+            image.clone()
+        ]
+    };
+    if with_audio {
+        args.extend(vec![
+            String::from("-f"),
+            String::from("lavfi"),
+            String::from("-t"),
+            duration,
+            String::from("-i"),
+            String::from("anullsrc=r=48000:cl=stereo")
+        ]);
+    }
+
+    args
+}
+
+/// A filter graph written by hand, run with `-filter_complex` in place of the automatic filters
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FilterGraph {
+    pub graph: String,
+    /// `-map` values selecting the streams of the output, e.g. `[vout]` or `0:a?`. Unlike the
+    /// automatic filters, nothing is mapped implicitly
+    pub maps: Vec<String>
+}
+
+/// Checks that `filter_graph` maps at least one stream and that each mapped label is in the graph
+pub fn validate_filter_graph(filter_graph: &FilterGraph) -> Result<(), AtiumError> {
+    if filter_graph.graph.trim().is_empty() {
+        return Err(AtiumError::InvalidArgument("The filter graph can't be empty".to_string()))
+    }
+    if filter_graph.maps.is_empty() {
+        return Err(AtiumError::InvalidArgument("A filter graph requires its outputs to be mapped explicitly".to_string()))
+    }
+    if let Some(label) = filter_graph.maps.iter().find(|map| map.starts_with('[') && !filter_graph.graph.contains(map.as_str())) {
+        return Err(AtiumError::InvalidArgument(format!("Mapped label {} is not in the filter graph", label)))
+    }
+
+    Ok(())
+}
+
+/// Checks that the overlay of `pip` exists and that its scale is between 0 and 1
+pub fn validate_pip(pip: &Pip) -> Result<(), AtiumError> {
+    if pip.scale.is_nan() || pip.scale <= 0.0 || pip.scale >= 1.0 {
+        return Err(AtiumError::InvalidArgument(format!("Pip scale [{}] must be between 0 and 1", pip.scale)))
+    }
+    if !Path::new(&pip.overlay_input).exists() {
+        return Err(AtiumError::IOError(format!("Pip overlay file [{}] not found", pip.overlay_input)))
+    }
+
+    Ok(())
+}
+
+/// Checks that `video_timebase` is a positive integer number of ticks per second
+pub fn validate_video_timebase(video_timebase: &str) -> Result<(), AtiumError> {
+    match video_timebase.parse::<u32>() {
+        Ok(timescale) if timescale > 0 => Ok(()),
+        _ => Err(AtiumError::InvalidArgument(format!("Video timebase [{}] must be a positive integer, e.g. 90000", video_timebase)))
+    }
+}
+
+/// Checks that the test mode `sample_duration` is a timestamp above 0, see [`parse_timestamp`]
+pub fn validate_sample_duration(sample_duration: &str) -> Result<(), AtiumError> {
+    match parse_timestamp(sample_duration) {
+        Ok(duration) if !duration.is_zero() => Ok(()),
+        _ => Err(AtiumError::InvalidArgument(format!(
+            "Invalid sample duration [{}], use a time greater than 0 like 10 or 00:00:10", sample_duration)))
+    }
+}
+
+/// Validates a `-force_key_frames` value: an `expr:` expression with balanced parentheses,
+/// or a comma separated list of times in seconds or `HH:MM:SS(.mmm)`
+pub fn validate_force_keyframes(force_keyframes: &str) -> Result<(), AtiumError> {
+    let invalid = || AtiumError::InvalidArgument(format!(
+        "Invalid forced keyframes [{}], use an expr: expression or a comma separated list of times", force_keyframes));
+
+    if let Some(expression) = force_keyframes.strip_prefix("expr:") {
+        let mut depth = 0i32;
+        for c in expression.chars() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            if depth < 0 {
+                return Err(invalid())
+            }
+        }
+        return if expression.trim().is_empty() || depth != 0 { Err(invalid()) } else { Ok(()) }
+    }
+
+    if force_keyframes.split(',').all(|time| parse_timestamp(time).is_ok()) {
+        Ok(())
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Returns the encoder profile required to store `bit_depth` with `codec`, if any. Fails
+/// when the encoder cannot produce that depth
+pub fn get_bit_depth_profile(codec: &OutputCodec, bit_depth: &BitDepth) -> Result<Option<&'static str>, AtiumError> {
+    match (codec, bit_depth) {
+        (OutputCodec::H264, BitDepth::Eight) => Ok(None),
+        (OutputCodec::H264, BitDepth::Ten) => Ok(Some("high10")),
+        (OutputCodec::H264, BitDepth::Twelve) =>
+            Err(AtiumError::InvalidArgument("h264 output supports 8 or 10 bit depth only".to_string())),
+        (OutputCodec::H265, BitDepth::Eight) => Ok(None),
+        (OutputCodec::H265, BitDepth::Ten) => Ok(Some("main10")),
+        (OutputCodec::H265, BitDepth::Twelve) => Ok(Some("main12")),
+        (OutputCodec::Vp9, BitDepth::Eight) => Ok(None),
+        // profile 2 is the 4:2:0 high bit depth one
+        (OutputCodec::Vp9, _) => Ok(Some("2")),
+        (OutputCodec::Av1, BitDepth::Twelve) => Ok(Some("professional")),
+        (OutputCodec::Av1, _) => Ok(None)
+    }
+}
+
+/// Checks that rate control options are used together, since `-maxrate` without
+/// `-bufsize` is rejected by most encoders
+pub fn validate_rate_control(maxrate: &Option<String>, bufsize: &Option<String>) -> Result<(), AtiumError> {
+    match (maxrate, bufsize) {
+        (Some(_), None) => Err(AtiumError::InvalidArgument("bufsize is required when maxrate is set".to_string())),
+        (None, Some(_)) => Err(AtiumError::InvalidArgument("maxrate is required when bufsize is set".to_string())),
+        _ => Ok(())
+    }
+}
+
+/// Checks that a frame rate is either a positive number (`25`, `29.97`) or a positive
+/// rational (`30000/1001`)
+pub fn validate_frame_rate(frame_rate: &str) -> Result<(), AtiumError> {
+    let valid = match frame_rate.split_once('/') {
+        Some((num, den)) => matches!(
+            (num.parse::<u32>(), den.parse::<u32>()),
+            (Ok(num), Ok(den)) if num > 0 && den > 0
+        ),
+        None => frame_rate.parse::<f64>().map(|fps| fps > 0.0).unwrap_or(false)
+    };
+
+    if !valid {
+        return Err(AtiumError::InvalidArgument(format!("Invalid frame rate [{}]", frame_rate)))
+    }
+
+    Ok(())
+}
+
+/// Selects one of the subtitle streams embedded in the input
+#[derive(Clone, Serialize)]
+pub enum SubtitleTrack {
+    /// Index among the subtitle streams, starting from 0
+    Index(usize),
+    /// Language of the subtitle stream, e.g. `en`, `eng` or `English`
+    Language(String)
+}
+
+/// Parses a subtitle track selector, numbers are treated as indexes and anything else
+/// as a language
+pub fn parse_subtitle_track(input: &str) -> SubtitleTrack {
+    match input.parse::<usize>() {
+        Ok(index) => SubtitleTrack::Index(index),
+        Err(_) => SubtitleTrack::Language(input.to_string())
+    }
+}
+
+/// Subtitles burned into the video frames
+#[derive(Clone, Serialize)]
+pub enum SubtitleBurn {
+    /// An external subtitle file, e.g. `.srt` or `.ass`
+    File(String),
+    /// A subtitle stream embedded in the input
+    Embedded(SubtitleTrack)
+}
+
+/// Kinds of streams that can be selected from the input
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum StreamType {
+    Video, Audio, Subtitle, Data
+}
+
+impl StreamType {
+    /// Returns the letter selecting this kind of stream in a `ffmpeg` stream specifier
+    pub fn specifier(&self) -> &'static str {
+        match self {
+            StreamType::Video => "v",
+            StreamType::Audio => "a",
+            StreamType::Subtitle => "s",
+            StreamType::Data => "d"
+        }
+    }
+}
+
+/// Parses a list of stream kinds like `video`, `audio`, `subtitle` and `data`
+pub fn parse_stream_types(input: &[String]) -> Result<Vec<StreamType>, AtiumError> {
+    input.iter()
+        .map(|stream_type| match stream_type.to_lowercase().as_str() {
+            "video" | "v" => Ok(StreamType::Video),
+            "audio" | "a" => Ok(StreamType::Audio),
+            "subtitle" | "s" => Ok(StreamType::Subtitle),
+            "data" | "d" => Ok(StreamType::Data),
+            _ => Err(AtiumError::InvalidArgument(format!("Unknown stream type [{}]", stream_type)))
+        })
+        .collect()
+}
+
+/// Builds the `-map` selectors keeping only the given kinds of streams of the first input,
+/// except video and audio which are taken from the `video_source` and `audio_source`
+/// selectors, e.g. `1:a?` or a `[aout]` filter output. Input selectors are optional,
+/// so a kind missing from the input is simply skipped
+pub fn build_keep_streams_args(stream_types: &[StreamType], video_source: &str, audio_source: &str) -> Result<Vec<String>, AtiumError> {
+    if stream_types.is_empty() {
+        return Err(AtiumError::InvalidArgument("At least one stream type must be kept".to_string()))
+    }
+
+    let mut args = Vec::new();
+    for stream_type in [StreamType::Video, StreamType::Audio, StreamType::Subtitle, StreamType::Data] {
+        if !stream_types.contains(&stream_type) {
+            continue
+        }
+        let source = match stream_type {
+            StreamType::Video => video_source,
+            StreamType::Audio => audio_source,
+            StreamType::Subtitle => "0:s?",
+            StreamType::Data => "0:d?"
+        };
+        args.push(String::from("-map"));
+        args.push(source.to_string());
+    }
+
+    Ok(args)
+}
+
+/// An output stream specifier like `a:1`, the second audio stream of the output
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StreamSpec {
+    pub stream_type: StreamType,
+    /// Position of the stream among the output streams of its kind, starting at 0
+    pub index: u32
+}
+
+impl StreamSpec {
+    /// Returns the `ffmpeg` stream specifier, e.g. `a:1`
+    pub fn specifier(&self) -> String {
+        format!("{}:{}", self.stream_type.specifier(), self.index)
+    }
+}
+
+/// Parses a stream specifier like `v:0`, `a:1` or `s:0`
+pub fn parse_stream_spec(input: &str) -> Result<StreamSpec, AtiumError> {
+    let invalid = || AtiumError::InvalidArgument(format!("Invalid stream specifier [{}], expected e.g. a:1 or s:0", input));
+
+    let (stream_type, index) = input.split_once(':').ok_or_else(invalid)?;
+    let stream_type = match stream_type {
+        "v" => StreamType::Video,
+        "a" => StreamType::Audio,
+        "s" => StreamType::Subtitle,
+        "d" => StreamType::Data,
+        _ => return Err(invalid())
+    };
+    if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid())
+    }
+
+    Ok(StreamSpec { stream_type, index: index.parse::<u32>().map_err(|_| invalid())? })
+}
+
+/// Disposition flags which tell players how to treat an output stream
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum Disposition {
+    /// The stream played when the user didn't pick one
+    Default,
+    /// Subtitles shown even when subtitles are off, e.g. for foreign dialogue
+    Forced,
+    Dub,
+    Original,
+    Comment,
+    HearingImpaired,
+    VisualImpaired,
+    Captions,
+    Descriptions
+}
+
+impl Disposition {
+    /// Returns the `ffmpeg` flag name
+    pub fn flag(&self) -> &'static str {
+        match self {
+            Disposition::Default => "default",
+            Disposition::Forced => "forced",
+            Disposition::Dub => "dub",
+            Disposition::Original => "original",
+            Disposition::Comment => "comment",
+            Disposition::HearingImpaired => "hearing_impaired",
+            Disposition::VisualImpaired => "visual_impaired",
+            Disposition::Captions => "captions",
+            Disposition::Descriptions => "descriptions"
+        }
+    }
+}
+
+/// Returns a value of [`Disposition`] based on its `ffmpeg` flag name
+pub fn parse_disposition(input: &str) -> Result<Disposition, AtiumError> {
+    match input.to_lowercase().as_str() {
+        "default" => Ok(Disposition::Default),
+        "forced" => Ok(Disposition::Forced),
+        "dub" => Ok(Disposition::Dub),
+        "original" => Ok(Disposition::Original),
+        "comment" => Ok(Disposition::Comment),
+        "hearing_impaired" => Ok(Disposition::HearingImpaired),
+        "visual_impaired" => Ok(Disposition::VisualImpaired),
+        "captions" => Ok(Disposition::Captions),
+        "descriptions" => Ok(Disposition::Descriptions),
+        _ => Err(AtiumError::InvalidArgument(format!(
+            "Unknown disposition [{}], valid ones are: default, forced, dub, original, comment, hearing_impaired, visual_impaired, captions, descriptions", input)))
+    }
+}
+
+/// Parses disposition entries like `a:1=default` or `s:0=forced`
+pub fn parse_disposition_entries(entries: &[String]) -> Result<Vec<(StreamSpec, Disposition)>, AtiumError> {
+    entries.iter()
+        .map(|entry| {
+            let (spec, flag) = entry.split_once('=')
+                .ok_or(AtiumError::InvalidArgument(format!("Disposition entry [{}] must be in `spec=flag` format", entry)))?;
+
+            Ok((parse_stream_spec(spec)?, parse_disposition(flag)?))
+        })
+        .collect()
+}
+
+/// Checks that the kind of stream targeted by `spec` is not dropped by `keep_streams`
+fn validate_stream_kept(spec: &StreamSpec, keep_streams: Option<&[StreamType]>) -> Result<(), AtiumError> {
+    if keep_streams.is_some_and(|keep_streams| !keep_streams.contains(&spec.stream_type)) {
+        return Err(AtiumError::InvalidArgument(format!("Stream [{}] targets a kind of stream which is not kept", spec.specifier())))
+    }
+
+    Ok(())
+}
+
+/// Validates the dispositions against the kept streams: a stream can't be flagged when its
+/// kind is dropped, and only one stream of each kind can be the default one
+pub fn validate_dispositions(dispositions: &[(StreamSpec, Disposition)], keep_streams: Option<&[StreamType]>) -> Result<(), AtiumError> {
+    let mut defaults: Vec<&StreamSpec> = Vec::new();
+    for (spec, disposition) in dispositions {
+        validate_stream_kept(spec, keep_streams)?;
+        if *disposition != Disposition::Default {
+            continue
+        }
+        if let Some(other) = defaults.iter().find(|other| other.stream_type == spec.stream_type && **other != spec) {
+            return Err(AtiumError::InvalidArgument(format!(
+                "Both [{}] and [{}] are marked default, only one stream of a kind can be", other.specifier(), spec.specifier())))
+        }
+        defaults.push(spec);
+    }
+
+    Ok(())
+}
+
+/// Builds the `-disposition:<spec> <flags>` arguments, joining the flags of a stream with
+/// `+`. `ffmpeg` keeps the dispositions of the input streams, so when a stream is marked
+/// default the other streams of its kind are cleared first with `-disposition:<kind> 0`,
+/// otherwise the previous default track would still play. The per stream values come
+/// after, as the last matching option wins
+pub fn build_disposition_args(dispositions: &[(StreamSpec, Disposition)]) -> Vec<String> {
+    let mut cleared: Vec<&StreamType> = Vec::new();
+    let mut flags: Vec<(&StreamSpec, Vec<&'static str>)> = Vec::new();
+    for (spec, disposition) in dispositions {
+        if *disposition == Disposition::Default && !cleared.contains(&&spec.stream_type) {
+            cleared.push(&spec.stream_type);
+        }
+        match flags.iter_mut().find(|(other, _)| *other == spec) {
+            Some((_, spec_flags)) if spec_flags.contains(&disposition.flag()) => {}
+            Some((_, spec_flags)) => spec_flags.push(disposition.flag()),
+            None => flags.push((spec, vec![disposition.flag()]))
+        }
+    }
+
+    let mut args = Vec::new();
+    for stream_type in cleared {
+        args.push(format!("-disposition:{}", stream_type.specifier()));
+        args.push(String::from("0"));
+    }
+    for (spec, spec_flags) in flags {
+        args.push(format!("-disposition:{}", spec.specifier()));
+        args.push(spec_flags.join("+"));
+    }
+
+    args
+}
+
+/// Formats an audio offset in milliseconds as the seconds taken by `-itsoffset`
+pub fn format_offset_secs(offset_ms: i64) -> String {
+    let sign = if offset_ms < 0 { "-" } else { "" };
+    let offset_ms = offset_ms.unsigned_abs();
+
+    format!("{}{}.{:03}", sign, offset_ms / 1000, offset_ms % 1000)
+}
+
+/// Output containers detected from the output file extension
+#[derive(Clone, PartialEq, Debug)]
+pub enum OutputContainer {
+    Mp4, Mov, Mkv, Webm,
+    /// Any other container, holding the lowercase extension
+    Other(String)
+}
+
+impl OutputContainer {
+    /// Returns `true` for the containers written by the `ffmpeg` mov/mp4 muxer, which
+    /// are the ones supporting `-movflags`
+    pub fn is_mov_family(&self) -> bool {
+        matches!(self, OutputContainer::Mp4 | OutputContainer::Mov)
+    }
+}
+
+/// Detects the [`OutputContainer`] from the extension of the output file
+pub fn detect_container(output_file: &str) -> OutputContainer {
+    let extension = Path::new(output_file)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    match extension.as_str() {
+        "mp4" | "m4v" | "m4a" => OutputContainer::Mp4,
+        "mov" => OutputContainer::Mov,
+        "mkv" => OutputContainer::Mkv,
+        "webm" => OutputContainer::Webm,
+        _ => OutputContainer::Other(extension)
+    }
+}
+
+/// Checks that an aspect ratio is either `n:m` with positive integers or a positive float
+pub fn validate_aspect_ratio(ratio: &str) -> Result<(), AtiumError> {
+    let valid = match ratio.split_once(':') {
+        Some((num, den)) => matches!(
+            (num.parse::<u32>(), den.parse::<u32>()),
+            (Ok(num), Ok(den)) if num > 0 && den > 0
+        ),
+        None => ratio.parse::<f64>().map(|r| r > 0.0).unwrap_or(false)
+    };
+
+    if !valid {
+        return Err(AtiumError::InvalidArgument(format!("Invalid aspect ratio [{}], expected `n:m` or a number", ratio)))
+    }
+
+    Ok(())
+}
+
+/// Letterboxing of the video into a fixed frame size: the video is scaled to fit inside
+/// the frame keeping its aspect ratio, and the borders are filled with `color`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Pad {
+    pub width: u32,
+    pub height: u32,
+    /// Background color, e.g. `black` or `#1E1E1E`
+    pub color: String
+}
+
+/// Parses a `WIDTHxHEIGHT` frame size, e.g. `1920x1080`
+pub fn parse_frame_size(input: &str) -> Result<(u32, u32), AtiumError> {
+    input.to_lowercase()
+        .split_once('x')
+        .and_then(|(width, height)| Some((width.trim().parse::<u32>().ok()?, height.trim().parse::<u32>().ok()?)))
+        .filter(|(width, height)| *width > 0 && *height > 0)
+        .ok_or(AtiumError::InvalidArgument(format!("Invalid frame size [{}], expected WIDTHxHEIGHT", input)))
+}
+
+/// Validates the [`Pad`] frame size and color. Sizes must be even since 4:2:0 chroma is
+/// subsampled by two in both directions
+pub fn validate_pad(pad: &Pad) -> Result<(), AtiumError> {
+    if pad.width == 0 || pad.height == 0 || !pad.width.is_multiple_of(2) || !pad.height.is_multiple_of(2) {
+        return Err(AtiumError::InvalidArgument(format!("Pad size [{}x{}] must be made of even, non zero values", pad.width, pad.height)))
+    }
+
+    validate_color(&pad.color)
+}
+
+/// Fill of a fixed frame size with a blurred copy of the video behind it, e.g. to turn a
+/// landscape video into a vertical one without black bars
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BlurFill {
+    pub width: u32,
+    pub height: u32,
+    /// Radius of the background blur in pixels
+    pub strength: u32
+}
+
+/// Checks that the frame of `blur_fill` is made of even values and that its blur radius is
+/// between 1 and 100
+pub fn validate_blur_fill(blur_fill: &BlurFill) -> Result<(), AtiumError> {
+    if blur_fill.width == 0 || blur_fill.height == 0 || !blur_fill.width.is_multiple_of(2) || !blur_fill.height.is_multiple_of(2) {
+        return Err(AtiumError::InvalidArgument(format!("Blur fill size [{}x{}] must be made of even, non zero values", blur_fill.width, blur_fill.height)))
+    }
+    if blur_fill.strength == 0 || blur_fill.strength > 100 {
+        return Err(AtiumError::InvalidArgument(format!("Blur strength [{}] must be between 1 and 100", blur_fill.strength)))
+    }
+
+    Ok(())
+}
+
+/// A bar along the bottom edge that fills up as the video plays
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ProgressBar {
+    /// Height of the bar in pixels
+    pub height: u32,
+    /// Fill color, e.g. `white` or `#FF0000@0.8`
+    pub color: String
+}
+
+/// Validates the [`ProgressBar`] height and color
+pub fn validate_progress_bar(progress_bar: &ProgressBar) -> Result<(), AtiumError> {
+    if progress_bar.height == 0 {
+        return Err(AtiumError::InvalidArgument("Progress bar height must be greater than 0".to_string()))
+    }
+
+    validate_color(&progress_bar.color)
+}
+
+/// Builds the filters stretching anamorphic frames to square pixels, given the
+/// `PixelAspectRatio` reported by `MediaInfo`. The width is scaled by the source `sar`
+/// and kept even, the height is untouched. Returns None for square or unknown pixels
+pub fn build_anamorphic_fix_filter(pixel_aspect_ratio: &str) -> Option<String> {
+    let pixel_aspect_ratio = pixel_aspect_ratio.trim().parse::<f64>().ok()
+        .filter(|pixel_aspect_ratio| pixel_aspect_ratio.is_finite() && *pixel_aspect_ratio > 0.0)?;
+    if (pixel_aspect_ratio - 1.0).abs() < 0.001 {
+        return None
+    }
+
+    Some(String::from("scale=trunc(iw*sar/2)*2:ih,setsar=1"))
+}
+
+/// Builds the `setsar` filter. The `n:m` form is rewritten as `n/m` since a colon would
+/// split it into two filter options
+pub fn build_setsar_filter(ratio: &str) -> String {
+    format!("setsar={}", ratio.replace(':', "/"))
+}
+
+/// Container level metadata written to the output file
+#[derive(Clone, Default, Serialize)]
+pub struct OutputMetadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub comment: Option<String>,
+    pub date: Option<String>,
+    /// Any other `key=value` pair supported by the output container
+    pub custom: HashMap<String, String>
+}
+
+impl OutputMetadata {
+    /// Returns `true` when no metadata value has been set
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.artist.is_none()
+            && self.comment.is_none()
+            && self.date.is_none()
+            && self.custom.is_empty()
+    }
+    /// Returns all the metadata entries, typed fields first and then the custom ones
+    /// sorted by key
+    pub fn entries(&self) -> Vec<(String, String)> {
+        let typed = [
+            ("title", &self.title),
+            ("artist", &self.artist),
+            ("comment", &self.comment),
+            ("date", &self.date)
+        ];
+        let mut entries: Vec<(String, String)> = typed.iter()
+            .filter_map(|(key, value)| value.as_ref().map(|v| (key.to_string(), v.clone())))
+            .collect();
+
+        let mut custom: Vec<(String, String)> = self.custom.iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        custom.sort();
+        entries.extend(custom);
+
+        entries
+    }
+}
+
+fn validate_metadata_key(key: &str) -> Result<(), AtiumError> {
+    if key.is_empty() || key.contains('=') || key.chars().any(char::is_whitespace) {
+        return Err(AtiumError::InvalidArgument(format!("Invalid metadata key [{}]", key)))
+    }
+
+    Ok(())
+}
+
+/// Parses `key=value` entries into a metadata map
+pub fn parse_metadata_entries(entries: &[String]) -> Result<HashMap<String, String>, AtiumError> {
+    entries.iter()
+        .map(|entry| {
+            let (key, value) = entry.split_once('=')
+                .ok_or(AtiumError::InvalidArgument(format!("Metadata entry [{}] must be in `key=value` format", entry)))?;
+            validate_metadata_key(key)?;
+
+            Ok((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Builds the `-metadata key=value` arguments. Each pair is passed to `ffmpeg` as a single
+/// argument, so values containing spaces don't need any shell quoting
+pub fn build_metadata_args(metadata: &OutputMetadata) -> Result<Vec<String>, AtiumError> {
+    let mut args = Vec::new();
+    for (key, value) in metadata.entries() {
+        validate_metadata_key(&key)?;
+        args.push(String::from("-metadata"));
+        args.push(format!("{}={}", key, value));
+    }
+
+    Ok(args)
+}
+
+/// Parses per stream metadata entries like `a:1:title=Director's Commentary` or
+/// `s:0:language=eng`, the stream specifier followed by a `key=value` pair
+pub fn parse_stream_metadata_entries(entries: &[String]) -> Result<Vec<(StreamSpec, String, String)>, AtiumError> {
+    entries.iter()
+        .map(|entry| {
+            let invalid = || AtiumError::InvalidArgument(format!("Stream metadata entry [{}] must be in `spec:key=value` format, e.g. a:1:title=Commentary", entry));
+            let mut parts = entry.splitn(3, ':');
+            let spec = match (parts.next(), parts.next()) {
+                (Some(stream_type), Some(index)) => parse_stream_spec(&format!("{}:{}", stream_type, index))?,
+                _ => return Err(invalid())
+            };
+            let (key, value) = parts.next().and_then(|pair| pair.split_once('=')).ok_or_else(invalid)?;
+            validate_metadata_key(key)?;
+
+            Ok((spec, key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Validates the per stream metadata: keys must be valid and the targeted kinds of stream kept
+pub fn validate_stream_metadata(stream_metadata: &[(StreamSpec, String, String)], keep_streams: Option<&[StreamType]>) -> Result<(), AtiumError> {
+    for (spec, key, _) in stream_metadata {
+        validate_metadata_key(key)?;
+        validate_stream_kept(spec, keep_streams)?;
+    }
+
+    Ok(())
+}
+
+/// Builds the `-metadata:s:<spec> key=value` arguments, in the given order. As for
+/// [`build_metadata_args`], each pair is a single argument and `ffmpeg` splits it at the
+/// first `=`, so values can hold spaces, quotes or `=` without any escaping
+pub fn build_stream_metadata_args(stream_metadata: &[(StreamSpec, String, String)]) -> Result<Vec<String>, AtiumError> {
+    let mut args = Vec::new();
+    for (spec, key, value) in stream_metadata {
+        validate_metadata_key(key)?;
+        args.push(format!("-metadata:s:{}", spec.specifier()));
+        args.push(format!("{}={}", key, value));
+    }
+
+    Ok(args)
 }
 
 /// Conversion output options
+#[derive(Clone, Serialize)]
 pub struct ConversionOutput {
+    /// Output path. It can be an existing FIFO, which is written directly regardless of the
+    /// collision policy, as long as the container can be streamed, e.g. mkv or fragmented mp4
     pub file: String,
     pub resolution: OutputResolution,
     pub codec: OutputCodec,
-    pub thumbnail_request: Option<ThumbnailRequest>
+    pub thumbnail_request: Option<ThumbnailRequest>,
+    /// Maximum bitrate for VBV rate control, e.g. `2M`. Requires `bufsize`
+    pub maxrate: Option<String>,
+    /// VBV buffer size, e.g. `4M`. Requires `maxrate`
+    pub bufsize: Option<String>,
+    /// What to do when `file` already exists
+    pub collision_policy: CollisionPolicy,
+    /// Container metadata to write, cannot be used along with `strip_metadata`
+    pub metadata: Option<OutputMetadata>,
+    /// Removes all the container metadata coming from the input
+    pub strip_metadata: bool,
+    /// Output frame rate applied through the `fps` filter, which drops or duplicates
+    /// frames inside the filter graph
+    pub fps: Option<String>,
+    /// Forces a constant frame rate output with `-vsync cfr -r <fps>`, where the rate
+    /// is `fps` when set or the probed average frame rate of the source.
+    /// Unlike the `fps` filter this acts at the output timestamps level, which is what
+    /// fixes variable frame rate sources for editors
+    pub force_cfr: bool,
+    /// Subtitles to burn into the video
+    pub burn_subtitles: Option<SubtitleBurn>,
+    /// Maximum output file size, e.g. `8MB`. The video bitrate is computed from the
+    /// input duration and a two-pass encode is run at that bitrate, so it can't be used with
+    /// `hardware_encode`
+    pub target_size: Option<String>,
+    /// Skips the conversion when `file` already exists and holds a readable video stream,
+    /// which makes interrupted jobs restartable. Takes precedence over `collision_policy`
+    pub skip_existing: bool,
+    /// Character encoding of a burned subtitle file, e.g. `CP1252`. If None, UTF-8 is assumed
+    pub subtitle_charset: Option<String>,
+    /// Keeps only the given kinds of streams and drops the rest. If None, `ffmpeg`
+    /// default stream selection is used
+    pub keep_streams: Option<Vec<StreamType>>,
+    /// Disposition flags set on output streams with `-disposition`, e.g. `a:1` default or
+    /// `s:0` forced, see [`build_disposition_args`]. If None, the input ones are kept
+    pub dispositions: Option<Vec<(StreamSpec, Disposition)>>,
+    /// Metadata set on output streams with `-metadata:s:<spec>`, e.g. the `title` or the
+    /// `language` of an audio track, as `(spec, key, value)`. Unlike `metadata` it is not
+    /// container level and it can be used along with `strip_metadata`
+    pub stream_metadata: Option<Vec<(StreamSpec, String, String)>>,
+    /// Moves the mp4/mov index to the front of the file with `-movflags +faststart`, so
+    /// playback can start while it is still downloading. Ignored for other containers
+    pub web_optimized: bool,
+    /// Writes a fragmented mp4/mov with `-movflags frag_keyframe+empty_moov+default_base_moof`,
+    /// so it can be played while it is written, e.g. by live pipelines. Unlike `web_optimized`
+    /// there is no index to move, so the two can't be combined. Ignored for other containers
+    pub fragmented: bool,
+    /// Packets buffered per output stream while waiting for the other streams, set with
+    /// `-max_muxing_queue_size`. Only needed when `ffmpeg` fails with "Too many packets
+    /// buffered for output stream", usually with sparse subtitle or data streams
+    pub muxing_queue_size: Option<u32>,
+    /// Ticks per second of the mp4/mov video track, set with `-video_track_timescale`, e.g.
+    /// `90000` or `24000` for editing software rejecting unusual timebases. Ignored for other
+    /// containers: mkv has no per-track timescale, its `-timestamp_scale` muxer option sets
+    /// the nanoseconds per tick of the whole file instead, `1000000` by default
+    pub video_timebase: Option<String>,
+    /// Display aspect ratio stored in the output with `-aspect`, e.g. `16:9` or `1.7778`.
+    /// Frames are untouched, only the playback shape changes
+    pub aspect_ratio: Option<String>,
+    /// Sample aspect ratio applied to the frames through the `setsar` filter, e.g. `1:1`
+    /// to fix sources with wrong pixel shapes
+    pub set_sar: Option<String>,
+    /// Stretches anamorphic sources, e.g. DVD rips, to square pixels before the other
+    /// filters, so they don't play squished in players ignoring the sample aspect ratio.
+    /// The source pixel aspect ratio is probed first and square sources are left as they are
+    pub anamorphic_fix: bool,
+    /// Normalizes the audio loudness to the targets of the preset with a two-pass `loudnorm`
+    pub normalize_preset: Option<NormalizePreset>,
+    /// Encodes the audio with the given AAC encoder, set with `-c:a`. A missing `libfdk_aac`
+    /// falls back to the native encoder with a warning, or fails when the output is strict.
+    /// If None, the container default is used
+    pub aac_encoder: Option<AacEncoder>,
+    /// Maximum number of consecutive B-frames, set with `-bf`. `0` disables them
+    pub b_frames: Option<u32>,
+    /// Number of reference frames, set with `-refs`
+    pub ref_frames: Option<u32>,
+    /// libx265 tunables passed as `-x265-params key=value:key=value`, e.g. `aq-mode=3`,
+    /// see [`build_x265_params`]. Only for the software h265 encoder
+    pub x265_params: Option<HashMap<String, String>>,
+    /// Exact number of video frames written, set with `-frames:v`, e.g. to build a dataset.
+    /// The audio is cut along with the video
+    pub frame_limit: Option<u64>,
+    /// Keyframe positions set with `-force_key_frames`, either an `expr:` expression, e.g.
+    /// `expr:gte(t,n_forced*2)` for one every 2 seconds, or a comma separated list of times.
+    /// It makes segment boundaries land on keyframes regardless of the scene cuts
+    pub force_keyframes: Option<String>,
+    /// Test mode: stops reading the inputs after this `[[H:]M:]S` time, set with `-t` after
+    /// them, so the effect of the other settings can be previewed without converting the
+    /// whole file. Unlike the frame limit it is meant for trying settings out, not for
+    /// producing the final output
+    pub sample_duration: Option<String>,
+    /// Bit depth of the output frames, selecting the pixel format and the encoder profile.
+    /// If None, the encoder default is used, usually the source depth
+    pub bit_depth: Option<BitDepth>,
+    /// Plays the video and audio backwards with the `reverse` and `areverse` filters.
+    /// Both buffer the whole stream in memory, so it is meant for short clips
+    pub reverse: bool,
+    /// Fits the video into a fixed frame with colored borders instead of stretching it.
+    /// When set, it replaces the `resolution` scaling
+    pub pad: Option<Pad>,
+    /// Fits the video into a fixed frame over a blurred, enlarged copy of itself, the
+    /// popular social media alternative to `pad`. When set, it replaces the `resolution` scaling
+    pub blur_fill: Option<BlurFill>,
+    /// Draws a bar that fills up with the playback, as seen in social media clips
+    pub progress_bar: Option<ProgressBar>,
+    /// Shrinks the video to fit within `(width, height)` keeping its aspect ratio, while
+    /// smaller sources are left untouched. When set, it replaces the `resolution` scaling
+    pub max_dimensions: Option<(u32, u32)>,
+    /// Shifts the audio against the video to fix A/V sync, in milliseconds. Positive
+    /// values delay the audio, so it plays later, negative values advance it
+    pub audio_offset_ms: Option<i64>,
+    /// A jpg or png image embedded as the cover of the output, e.g. for podcasts or music.
+    /// mp4/mov store it as an extra video stream flagged as attached picture, while mkv
+    /// stores it as an attachment
+    pub cover: Option<String>,
+    /// Gives the output the modification time of the input once converted, e.g. to keep
+    /// a library sorted by date. Ignored for URL inputs
+    pub preserve_mtime: bool,
+    /// Mixes a 5.1 or 7.1 audio stream down to stereo with a `pan` filter
+    pub audio_downmix: Option<DownmixMode>,
+    /// Removes the leading and/or trailing silence of the audio. The video is untouched, so
+    /// it is meant for audio-first content like podcasts
+    pub trim_silence: Option<SilenceTrim>,
+    /// Draws a second video over a corner of the output, see [`Pip`]. The audio comes from
+    /// the main input unless the overlay one is mixed in
+    pub pip: Option<Pip>,
+    /// Plays a generated clip before the converted video, see [`Intro`]
+    pub intro: Option<Intro>,
+    /// Runs a hand written filter graph instead of assembling the video filters, see
+    /// [`FilterGraph`]. The `resolution` is then ignored, and the options adding filters or
+    /// inputs can't be set along with it
+    pub filter_complex: Option<FilterGraph>,
+    /// Encodes with the hardware encoder of the input `hwaccel_decode` instead of the
+    /// software one, see [`resolve_encoder`]
+    pub hardware_encode: bool,
+    /// Software codec the whole conversion is retried with when the hardware encode fails
+    /// because of the device, e.g. when the nvenc sessions are exhausted, see [`is_hwaccel_failure`].
+    /// Requires `hardware_encode`
+    pub fallback_codec: Option<OutputCodec>,
+    /// Writes a `<output>.atium.json` [`ConversionReport`] next to the output once converted
+    pub write_report: bool,
+    /// Fails before converting when an input stream would be dropped because the output
+    /// container can't carry it, instead of only warning about it
+    pub strict: bool
 }
 
-/// Conversion request containing options for input and output
-pub struct ConversionRequest {
-    pub input: ConversionInput,
-    pub output: ConversionOutput
-}
+/// Bitrate assumed for the audio track when fitting a target size, it matches the
+/// `ffmpeg` default for AAC
+pub const TARGET_SIZE_AUDIO_BITRATE: u64 = 128_000;
 
-/// Conversion response containing the output filepath
+/// Duration above which reversing an input logs a memory warning
+pub const REVERSE_WARNING_DURATION_SECS: f64 = 60.0;
+/// Share of the target size kept free for container overhead
+const TARGET_SIZE_OVERHEAD_MARGIN: f64 = 0.03;
+
+/// Parses a file size like `8MB`, `500K` or `1.5G` into bytes. Units are decimal,
+/// so `8MB` is 8,000,000 bytes, and plain numbers are bytes
+pub fn parse_size(size: &str) -> Result<u64, AtiumError> {
+    let upper = size.trim().to_uppercase();
+    let number_end = upper.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(upper.len());
+    let (number, unit) = upper.split_at(number_end);
+    let multiplier = match unit.trim() {
+        "" | "B" => 1.0,
+        "K" | "KB" => 1e3,
+        "M" | "MB" => 1e6,
+        "G" | "GB" => 1e9,
+        _ => return Err(AtiumError::InvalidArgument(format!("Unknown size unit in [{}]", size)))
+    };
+
+    match number.parse::<f64>() {
+        Ok(value) if value > 0.0 => Ok((value * multiplier) as u64),
+        _ => Err(AtiumError::InvalidArgument(format!("Invalid size [{}]", size)))
+    }
+}
+
+/// Returns the suffixes of the stats files a two-pass encode with `codec` writes after the
+/// `-passlogfile` prefix: `-0.log` for the first output stream, plus the lookahead stats
+/// of x264 (`.mbtree`) and x265 (`.cutree`) and the `.temp` files they rename at the end
+pub fn get_passlog_suffixes(codec: &OutputCodec) -> &'static [&'static str] {
+    match codec {
+        OutputCodec::H264 => &["-0.log", "-0.log.temp", "-0.log.mbtree", "-0.log.mbtree.temp"],
+        OutputCodec::H265 => &["-0.log", "-0.log.temp", "-0.log.cutree", "-0.log.cutree.temp"],
+        OutputCodec::Vp9 | OutputCodec::Av1 => &["-0.log"]
+    }
+}
+
+/// Computes the video bitrate in bits per second that fits `target_bytes` given the
+/// duration and the audio bitrate, keeping a small margin for container overhead
+pub fn compute_target_video_bitrate(target_bytes: u64, duration_secs: f64, audio_bitrate: u64) -> Result<u64, AtiumError> {
+    if duration_secs <= 0.0 {
+        return Err(AtiumError::InvalidArgument("Cannot fit a target size without knowing the input duration".to_string()))
+    }
+
+    let available_bits = target_bytes as f64 * 8.0 * (1.0 - TARGET_SIZE_OVERHEAD_MARGIN);
+    let video_bits = available_bits - audio_bitrate as f64 * duration_secs;
+    if video_bits <= 0.0 {
+        return Err(AtiumError::InvalidArgument(format!(
+            "Target size of [{}] bytes is too small for [{:.1}s] of media", target_bytes, duration_secs)))
+    }
+
+    Ok((video_bits / duration_secs) as u64)
+}
+
+/// Validates that `codec` can be stored in the given container
+pub fn validate_codec_container(codec: &OutputCodec, container: &OutputContainer) -> Result<(), AtiumError> {
+    match (codec, container) {
+        (OutputCodec::H264, OutputContainer::Webm) =>
+            Err(AtiumError::InvalidArgument("h264 video cannot be stored in a webm container".to_string())),
+        (OutputCodec::H265, OutputContainer::Webm) =>
+            Err(AtiumError::InvalidArgument("h265 video cannot be stored in a webm container".to_string())),
+        _ => Ok(())
+    }
+}
+
+/// Returns the highest number of consecutive B-frames and of reference frames supported
+/// by the encoder of `codec`. None when the encoder doesn't take `-bf` and `-refs`, as
+/// the VP9 and AV1 ones pick their reference structure on their own
+pub fn get_frame_reference_limits(codec: &OutputCodec) -> Option<(u32, u32)> {
+    match codec {
+        OutputCodec::H264 | OutputCodec::H265 => Some((16, 16)),
+        OutputCodec::Vp9 | OutputCodec::Av1 => None
+    }
+}
+
+/// Validates the B-frames and reference frames counts against the limits of `codec`
+pub fn validate_frame_references(codec: &OutputCodec, b_frames: Option<u32>, ref_frames: Option<u32>) -> Result<(), AtiumError> {
+    if b_frames.is_none() && ref_frames.is_none() {
+        return Ok(())
+    }
+    let (max_b_frames, max_ref_frames) = get_frame_reference_limits(codec)
+        .ok_or(AtiumError::InvalidArgument(format!("B-frames and reference frames cannot be set for {}", get_encoder_name(codec.clone()))))?;
+    if let Some(b_frames) = b_frames {
+        if b_frames > max_b_frames {
+            return Err(AtiumError::InvalidArgument(format!("B-frames [{}] exceed the maximum of [{}] for {}", b_frames, max_b_frames, get_encoder_name(codec.clone()))))
+        }
+    }
+    if let Some(ref_frames) = ref_frames {
+        if ref_frames == 0 || ref_frames > max_ref_frames {
+            return Err(AtiumError::InvalidArgument(format!("Reference frames [{}] must be between 1 and [{}] for {}", ref_frames, max_ref_frames, get_encoder_name(codec.clone()))))
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the libx265 tunables can be written in the `key=value:key=value` format,
+/// so keys can't be empty nor hold `:` or `=`, and values can't hold `:`. They are only
+/// understood by the software h265 encoder
+pub fn validate_x265_params(codec: &OutputCodec, hardware_encode: bool, x265_params: &HashMap<String, String>) -> Result<(), AtiumError> {
+    if *codec != OutputCodec::H265 || hardware_encode {
+        return Err(AtiumError::InvalidArgument("x265 params require the libx265 encoder, use the h265 codec without hardware encoding".to_string()))
+    }
+    for (key, value) in x265_params {
+        if key.is_empty() || key.contains(':') || key.contains('=') {
+            return Err(AtiumError::InvalidArgument(format!("x265 param key [{}] must be non empty and without `:` or `=`", key)))
+        }
+        if value.contains(':') {
+            return Err(AtiumError::InvalidArgument(format!("x265 param [{}] value [{}] cannot contain `:`", key, value)))
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses `key=value` entries into libx265 tunables
+pub fn parse_x265_params(entries: &[String]) -> Result<HashMap<String, String>, AtiumError> {
+    entries.iter()
+        .map(|entry| entry.split_once('=')
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .ok_or(AtiumError::InvalidArgument(format!("x265 param [{}] must be in `key=value` format", entry))))
+        .collect()
+}
+
+/// Builds the `-x265-params` value, with keys sorted so the same tunables always give the
+/// same command line
+pub fn build_x265_params(x265_params: &HashMap<String, String>) -> String {
+    let mut entries: Vec<String> = x265_params.iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    entries.sort();
+
+    entries.join(":")
+}
+
+/// Returns `false` when a stream with the given `mediainfo` format, e.g. `AVC` or `Opus`,
+/// cannot be copied into `container` as it is. Unknown containers are left to `ffmpeg`
+pub fn is_copy_compatible(format: &str, container: &OutputContainer) -> bool {
+    let format = format.to_lowercase();
+    match container {
+        OutputContainer::Webm => matches!(format.as_str(), "vp8" | "vp9" | "av1" | "opus" | "vorbis"),
+        OutputContainer::Mp4 | OutputContainer::Mov => !matches!(format.as_str(), "vp8" | "opus" | "vorbis"),
+        OutputContainer::Mkv | OutputContainer::Other(_) => true
+    }
+}
+
+/// What happens to an input stream that the output container can't carry as it is
+#[derive(Debug, PartialEq)]
+pub enum StreamFate {
+    /// The stream is converted to the given format
+    Transcoded(&'static str),
+    /// The stream is left out of the output
+    Dropped
+}
+
+/// An input stream that doesn't make it into the output as it is, see [`check_stream_compatibility`]
+#[derive(Debug, PartialEq)]
+pub struct StreamIssue {
+    /// The stream specifier, e.g. `0:s:1`
+    pub stream: String,
+    /// The mediainfo format of the stream, e.g. `PGS`
+    pub format: String,
+    pub fate: StreamFate
+}
+
+impl fmt::Display for StreamIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.fate {
+            StreamFate::Transcoded(format) =>
+                write!(f, "Stream [{}] ({}) can't be stored as it is and is converted to {}", self.stream, self.format, format),
+            StreamFate::Dropped =>
+                write!(f, "Stream [{}] ({}) can't be stored in the output container and is dropped", self.stream, self.format)
+        }
+    }
+}
+
+/// Cross-checks the input streams against what `container` can carry. Audio and video are
+/// always encoded for the container, so only the subtitles are checked: mp4/mov store text
+/// subtitles as `mov_text` and webm as `webvtt`, while neither of them stores bitmap ones
+/// like PGS, which `ffmpeg` leaves out silently. Unknown formats and containers are not reported
+pub fn check_stream_compatibility(tracks: &TrackList, container: &OutputContainer) -> Vec<StreamIssue> {
+    tracks.subtitles.iter()
+        .filter_map(|track| {
+            let format = track.codec.to_lowercase();
+            let is_bitmap = matches!(format.as_str(), "pgs" | "vobsub" | "dvb subtitle");
+            let is_text = matches!(format.as_str(), "utf-8" | "ass" | "ssa" | "webvtt" | "timed text");
+            let fate = match container {
+                OutputContainer::Mp4 | OutputContainer::Mov | OutputContainer::Webm if is_bitmap => StreamFate::Dropped,
+                OutputContainer::Mp4 | OutputContainer::Mov if is_text && format != "timed text" => StreamFate::Transcoded("mov_text"),
+                OutputContainer::Webm if is_text && format != "webvtt" => StreamFate::Transcoded("webvtt"),
+                _ => return None
+            };
+
+            Some(StreamIssue {
+                stream: format!("0:s:{}", track.index),
+                format: track.codec.clone(),
+                fate
+            })
+        })
+        .collect()
+}
+
+/// Validates that a video and an audio stream can be muxed into `output_file` by stream
+/// copy, suggesting mkv, which stores any of them, otherwise
+pub fn validate_stream_copy(video_format: &str, audio_format: &str, output_file: &str) -> Result<(), AtiumError> {
+    let container = detect_container(output_file);
+    for format in [video_format, audio_format] {
+        if !is_copy_compatible(format, &container) {
+            return Err(AtiumError::InvalidArgument(format!(
+                "{} streams cannot be copied into [{}], use a .mkv output or re-encode them", format, output_file)))
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the MIME type of a cover image from its extension, only jpg and png are supported
+pub fn get_cover_mimetype(cover_file: &str) -> Result<&'static str, AtiumError> {
+    let extension = Path::new(cover_file)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    match extension.as_str() {
+        "jpg" | "jpeg" => Ok("image/jpeg"),
+        "png" => Ok("image/png"),
+        _ => Err(AtiumError::InvalidArgument(format!("Cover [{}] must be a jpg or png image", cover_file)))
+    }
+}
+
+/// Validates that `cover_file` is an existing jpg/png image and that `output_file` can store it
+pub fn validate_cover(cover_file: &str, output_file: &str) -> Result<(), AtiumError> {
+    get_cover_mimetype(cover_file)?;
+    let container = detect_container(output_file);
+    if !container.is_mov_family() && container != OutputContainer::Mkv {
+        return Err(AtiumError::InvalidArgument(format!("A cover can only be embedded into mp4, mov or mkv outputs, not [{}]", output_file)))
+    }
+    if !Path::new(cover_file).exists() {
+        return Err(AtiumError::IOError(format!("Cover file [{}] not found", cover_file)))
+    }
+
+    Ok(())
+}
+
+/// Builds the args embedding `cover_file` into `container`, after the converted video
+/// stream. mp4/mov copy it from input `cover_input` as a second video stream flagged
+/// `attached_pic`, mkv attaches the file itself with its MIME type, as players expect a
+/// `cover.*` attachment
+pub fn build_cover_args(cover_file: &str, container: &OutputContainer, cover_input: usize) -> Result<Vec<String>, AtiumError> {
+    let mimetype = get_cover_mimetype(cover_file)?;
+    match container {
+        OutputContainer::Mp4 | OutputContainer::Mov => Ok(vec![
+            String::from("-map"),
+            format!("{}:v:0", cover_input),
+            String::from("-c:v:1"),
+            String::from("copy"),
+            String::from("-disposition:v:1"),
+            String::from("attached_pic")
+        ]),
+        OutputContainer::Mkv => {
+            let extension = if mimetype == "image/png" { "png" } else { "jpg" };
+            Ok(vec![
+                String::from("-attach"),
+                cover_file.to_string(),
+                String::from("-metadata:s:t:0"),
+                format!("mimetype={}", mimetype),
+                String::from("-metadata:s:t:0"),
+                format!("filename=cover.{}", extension)
+            ])
+        }
+        _ => Err(AtiumError::InvalidArgument("A cover can only be embedded into mp4, mov or mkv outputs".to_string()))
+    }
+}
+
+/// Runs all the checks on a [`ConversionOutput`], including the ones across fields
+pub fn validate_conversion_output(output: &ConversionOutput) -> Result<(), AtiumError> {
+    validate_codec_container(&output.codec, &detect_container(&output.file))?;
+    if let Some(fallback_codec) = &output.fallback_codec {
+        if !output.hardware_encode {
+            return Err(AtiumError::InvalidArgument("fallback_codec requires hardware_encode".to_string()))
+        }
+        validate_codec_container(fallback_codec, &detect_container(&output.file))?;
+    }
+    validate_rate_control(&output.maxrate, &output.bufsize)?;
+    validate_frame_references(&output.codec, output.b_frames, output.ref_frames)?;
+    if let Some(x265_params) = &output.x265_params {
+        validate_x265_params(&output.codec, output.hardware_encode, x265_params)?;
+    }
+    if let Some(dispositions) = &output.dispositions {
+        validate_dispositions(dispositions, output.keep_streams.as_deref())?;
+    }
+    if let Some(stream_metadata) = &output.stream_metadata {
+        validate_stream_metadata(stream_metadata, output.keep_streams.as_deref())?;
+    }
+    if let Some(bit_depth) = &output.bit_depth {
+        get_bit_depth_profile(&output.codec, bit_depth)?;
+    }
+    if let Some(pad) = &output.pad {
+        validate_pad(pad)?;
+    }
+    if let Some(blur_fill) = &output.blur_fill {
+        if output.pad.is_some() {
+            return Err(AtiumError::InvalidArgument("blur_fill cannot be used along with pad".to_string()))
+        }
+        validate_blur_fill(blur_fill)?;
+    }
+    if let Some((width, height)) = output.max_dimensions {
+        if output.pad.is_some() || output.blur_fill.is_some() {
+            return Err(AtiumError::InvalidArgument("max_dimensions cannot be used along with pad or blur_fill".to_string()))
+        }
+        if width == 0 || height == 0 {
+            return Err(AtiumError::InvalidArgument(format!("Max dimensions [{}x{}] must be greater than 0", width, height)))
+        }
+    }
+    if let Some(progress_bar) = &output.progress_bar {
+        validate_progress_bar(progress_bar)?;
+    }
+    if let Some(video_timebase) = &output.video_timebase {
+        validate_video_timebase(video_timebase)?;
+    }
+    if output.muxing_queue_size == Some(0) {
+        return Err(AtiumError::InvalidArgument("Muxing queue size must be greater than 0".to_string()))
+    }
+    if let Some(force_keyframes) = &output.force_keyframes {
+        validate_force_keyframes(force_keyframes)?;
+    }
+    if let Some(sample_duration) = &output.sample_duration {
+        validate_sample_duration(sample_duration)?;
+    }
+    if output.frame_limit == Some(0) {
+        return Err(AtiumError::InvalidArgument("Frame limit must be at least 1".to_string()))
+    }
+    if let Some(trim) = &output.trim_silence {
+        validate_silence_trim(trim)?;
+    }
+    if let Some(pip) = &output.pip {
+        validate_pip(pip)?;
+    }
+    if let Some(intro) = &output.intro {
+        validate_intro(intro)?;
+        let conflicting = [
+            ("pip", output.pip.is_some()),
+            ("keep_streams", output.keep_streams.is_some()),
+            ("audio_offset_ms", output.audio_offset_ms.is_some()),
+            ("cover", output.cover.is_some())
+        ];
+        if let Some((option, _)) = conflicting.iter().find(|(_, is_set)| *is_set) {
+            return Err(AtiumError::InvalidArgument(format!("An intro can't be used along with {}", option)))
+        }
+    }
+    if let Some(filter_graph) = &output.filter_complex {
+        validate_filter_graph(filter_graph)?;
+        let conflicting = [
+            ("pad", output.pad.is_some()),
+            ("blur_fill", output.blur_fill.is_some()),
+            ("max_dimensions", output.max_dimensions.is_some()),
+            ("fps", output.fps.is_some()),
+            ("set_sar", output.set_sar.is_some()),
+            ("anamorphic_fix", output.anamorphic_fix),
+            ("burn_subtitles", output.burn_subtitles.is_some()),
+            ("reverse", output.reverse),
+            ("progress_bar", output.progress_bar.is_some()),
+            ("pip", output.pip.is_some()),
+            ("intro", output.intro.is_some()),
+            ("keep_streams", output.keep_streams.is_some()),
+            ("audio_offset_ms", output.audio_offset_ms.is_some()),
+            ("cover", output.cover.is_some())
+        ];
+        if let Some((option, _)) = conflicting.iter().find(|(_, is_set)| *is_set) {
+            return Err(AtiumError::InvalidArgument(format!("A filter graph can't be used along with {}", option)))
+        }
+    }
+    if let Some(target_size) = &output.target_size {
+        parse_size(target_size)?;
+        if output.hardware_encode {
+            return Err(AtiumError::InvalidArgument("A target size needs a two-pass encode, which hardware encoders don't support".to_string()))
+        }
+    }
+    if let Some(fps) = &output.fps {
+        validate_frame_rate(fps)?;
+    }
+    if let Some(aspect_ratio) = &output.aspect_ratio {
+        validate_aspect_ratio(aspect_ratio)?;
+    }
+    if output.aac_encoder.is_some() && detect_container(&output.file) == OutputContainer::Webm {
+        return Err(AtiumError::InvalidArgument("A webm output can't store AAC audio".to_string()))
+    }
+    if let Some(sar) = &output.set_sar {
+        validate_aspect_ratio(sar)?;
+        if output.anamorphic_fix {
+            return Err(AtiumError::InvalidArgument("The anamorphic fix already sets square pixels, it can't be used along with a sample aspect ratio".to_string()))
+        }
+    }
+    if let Some(stream_types) = &output.keep_streams {
+        if stream_types.is_empty() {
+            return Err(AtiumError::InvalidArgument("At least one stream type must be kept".to_string()))
+        }
+        if !stream_types.contains(&StreamType::Video) {
+            return Err(AtiumError::InvalidArgument("Video streams must be kept since the conversion scales the video".to_string()))
+        }
+        if output.audio_offset_ms.is_some() && !stream_types.contains(&StreamType::Audio) {
+            return Err(AtiumError::InvalidArgument("Audio streams must be kept to shift them".to_string()))
+        }
+        if output.pip.as_ref().is_some_and(|pip| pip.mix_audio) && !stream_types.contains(&StreamType::Audio) {
+            return Err(AtiumError::InvalidArgument("Audio streams must be kept to mix the pip audio".to_string()))
+        }
+        if output.trim_silence.is_some() && !stream_types.contains(&StreamType::Audio) {
+            return Err(AtiumError::InvalidArgument("Audio streams must be kept to trim their silence".to_string()))
+        }
+        if output.audio_downmix.is_some() && !stream_types.contains(&StreamType::Audio) {
+            return Err(AtiumError::InvalidArgument("Audio streams must be kept to downmix them".to_string()))
+        }
+        if output.normalize_preset.is_some() && !stream_types.contains(&StreamType::Audio) {
+            return Err(AtiumError::InvalidArgument("Audio streams must be kept to normalize their loudness".to_string()))
+        }
+    }
+    if let Some(SubtitleBurn::File(subtitle_file)) = &output.burn_subtitles {
+        if !Path::new(subtitle_file).exists() {
+            return Err(AtiumError::IOError(format!("Subtitle file [{}] not found", subtitle_file)))
+        }
+    }
+    if let Some(charset) = &output.subtitle_charset {
+        validate_subtitle_charset(charset)?;
+        if !matches!(output.burn_subtitles, Some(SubtitleBurn::File(_))) {
+            return Err(AtiumError::InvalidArgument("A subtitle charset can only be set when burning a subtitle file".to_string()))
+        }
+    }
+    if let Some(cover) = &output.cover {
+        validate_cover(cover, &output.file)?;
+    }
+    if output.web_optimized && output.fragmented {
+        return Err(AtiumError::InvalidArgument("A fragmented output can't be web optimized, use only one of them".to_string()))
+    }
+    if output.strip_metadata && output.metadata.is_some() {
+        return Err(AtiumError::InvalidArgument("metadata cannot be set while stripping metadata".to_string()))
+    }
+
+    Ok(())
+}
+
+/// Conversion request containing options for input and output
+#[derive(Clone, Serialize)]
+pub struct ConversionRequest {
+    pub input: ConversionInput,
+    pub output: ConversionOutput
+}
+
+/// A progress tick of a running conversion
+#[derive(Clone, Serialize)]
+pub struct ConversionProgress {
+    /// Completion percentage, from 0 to 100. None when the input duration is unknown,
+    /// e.g. for piped or live sources
+    pub percent: Option<f32>,
+    /// Seconds of output produced so far
+    pub out_time_secs: f64,
+    /// Encoding speed relative to playback, e.g. `2.0` means twice as fast as real time
+    pub speed: Option<f32>,
+    /// Estimated seconds left, None when the speed or the input duration is unknown
+    pub eta_secs: Option<f64>
+}
+
+/// Request replacing the audio of a video with an external audio track
+pub struct AudioReplacementRequest {
+    /// The video whose first video stream is kept, it is copied without re-encoding
+    pub video_input: String,
+    /// The file providing the new audio, its first audio stream is used
+    pub audio_input: String,
+    /// Output path of the combined file
+    pub output_file: String,
+    /// `ffmpeg` audio encoder, e.g. `aac`. If None, the audio stream is copied as is
+    pub audio_codec: Option<String>,
+    /// Stops at the end of the shortest stream with `-shortest`, useful when the audio
+    /// is longer than the video
+    pub shortest: bool,
+    /// What to do when `output_file` already exists
+    pub collision_policy: CollisionPolicy
+}
+
+/// Builds the `ffmpeg` arguments replacing the audio: the first video stream of the video
+/// input is copied and the first audio stream of the audio input is copied or encoded
+pub fn build_replace_audio_args(request: &AudioReplacementRequest, output_file: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    if let CollisionPolicy::Overwrite = request.collision_policy {
+        args.push(String::from("-y"));
+    }
+    args.extend(vec![
+        String::from("-i"),
+        request.video_input.clone(),
+        String::from("-i"),
+        request.audio_input.clone(),
+        String::from("-map"),
+        String::from("0:v:0"),
+        String::from("-map"),
+        String::from("1:a:0"),
+        String::from("-c:v"),
+        String::from("copy"),
+        String::from("-c:a"),
+        request.audio_codec.clone().unwrap_or_else(|| String::from("copy"))
+    ]);
+    if request.shortest {
+        args.push(String::from("-shortest"));
+    }
+    args.push(output_file.to_string());
+    args
+}
+
+/// Request changing only the sample format, and so the bit depth, of the audio of a
+/// lossless file, e.g. a 24-bit FLAC to a 16-bit one. The other streams, like cover art,
+/// are copied
+pub struct SampleFormatRequest {
+    pub input_file: String,
+    pub output_file: String,
+    /// `ffmpeg` sample format, e.g. `s16`, supported by the encoder of the input codec,
+    /// see [`resolve_sample_format_encoder`]
+    pub sample_format: String,
+    /// Noise shaping applied when the bit depth is reduced, see [`AudioDither`]. If None,
+    /// the `ffmpeg` default is kept
+    pub dither: Option<AudioDither>,
+    /// What to do when `output_file` already exists
+    pub collision_policy: CollisionPolicy
+}
+
+/// Dithering of the samples when the bit depth is reduced, which turns the quantization
+/// distortion into a less audible noise
+#[derive(Clone, Debug, PartialEq)]
+pub enum AudioDither {
+    /// High-pass triangular dither, a safe choice for any material
+    TriangularHp,
+    /// Shibata noise shaping, pushing the noise towards the less audible frequencies
+    Shibata,
+    /// No dithering at all, samples are simply rounded
+    None
+}
+
+impl AudioDither {
+    /// Returns the value of the `-dither_method` resampler option
+    pub fn method(&self) -> &'static str {
+        match self {
+            AudioDither::TriangularHp => "triangular_hp",
+            AudioDither::Shibata => "shibata",
+            // the resampler has no named constant for it
+            AudioDither::None => "0"
+        }
+    }
+}
+
+/// Returns a value of [`AudioDither`] based on its name `triangular_hp/shibata/none`
+pub fn parse_audio_dither(input: &str) -> Result<AudioDither, AtiumError> {
+    match input.to_lowercase().as_str() {
+        "triangular_hp" => Ok(AudioDither::TriangularHp),
+        "shibata" => Ok(AudioDither::Shibata),
+        "none" => Ok(AudioDither::None),
+        _ => Err(AtiumError::InvalidArgument(format!("Unknown dither [{}], use triangular_hp, shibata or none", input)))
+    }
+}
+
+/// Returns the bits per sample of an `ffmpeg` sample format, planar or not, e.g. 16 for `s16p`
+fn sample_format_bit_depth(sample_format: &str) -> Option<u32> {
+    match sample_format.trim_end_matches('p') {
+        "u8" => Some(8),
+        "s16" => Some(16),
+        // flac stores at most 24 bits, which is what s32 samples hold when read from a 24-bit file
+        "s32" | "flt" => Some(32),
+        "s64" | "dbl" => Some(64),
+        _ => None
+    }
+}
+
+/// Tells whether converting `source_bit_depth` samples, as reported by MediaInfo, to
+/// `sample_format` loses bits, the only case where dithering does something
+pub fn is_bit_depth_reduction(source_bit_depth: Option<u32>, sample_format: &str) -> bool {
+    match (source_bit_depth, sample_format_bit_depth(sample_format)) {
+        (Some(source), Some(target)) => target < source,
+        _ => false
+    }
+}
+
+/// Lossless codecs by their MediaInfo format, with their encoder and the sample formats it accepts
+const SAMPLE_FORMAT_ENCODERS: [(&str, &str, &[&str]); 3] = [
+    ("flac", "flac", &["s16", "s32"]),
+    ("alac", "alac", &["s16p", "s32p"]),
+    ("wavpack", "wavpack", &["u8p", "s16p", "s32p", "fltp"])
+];
+
+/// PCM encoders storing each sample format, PCM having an encoder per format
+const PCM_SAMPLE_FORMAT_ENCODERS: [(&str, &str); 5] = [
+    ("u8", "pcm_u8"),
+    ("s16", "pcm_s16le"),
+    ("s32", "pcm_s32le"),
+    ("flt", "pcm_f32le"),
+    ("dbl", "pcm_f64le")
+];
+
+/// Returns the encoder re-encoding audio of the MediaInfo `audio_format`, e.g. `FLAC`, with
+/// `sample_format`. Only lossless codecs are supported, since the bit depth of a lossy one
+/// doesn't tell its quality
+pub fn resolve_sample_format_encoder(audio_format: &str, sample_format: &str) -> Result<String, AtiumError> {
+    let audio_format = audio_format.to_lowercase();
+    let (encoder, sample_formats): (Option<&str>, Vec<&str>) = if audio_format == "pcm" {
+        (PCM_SAMPLE_FORMAT_ENCODERS.iter().find(|(format, _)| *format == sample_format).map(|(_, encoder)| *encoder),
+         PCM_SAMPLE_FORMAT_ENCODERS.iter().map(|(format, _)| *format).collect())
+    } else {
+        let (_, encoder, sample_formats) = SAMPLE_FORMAT_ENCODERS.iter()
+            .find(|(format, _, _)| *format == audio_format)
+            .ok_or(AtiumError::InvalidArgument(format!(
+                "Sample format conversion supports flac, alac, wavpack and pcm audio, not [{}]", audio_format)))?;
+        (Some(*encoder).filter(|_| sample_formats.contains(&sample_format)), sample_formats.to_vec())
+    };
+
+    encoder.map(String::from)
+        .ok_or(AtiumError::InvalidArgument(format!(
+            "Sample format [{}] is not supported by {} audio, use one of {}", sample_format, audio_format, sample_formats.join("/"))))
+}
+
+/// Request muxing a subtitle file into a copy of the input as a selectable track, without
+/// re-encoding anything
+pub struct SubtitleMuxRequest {
+    pub input_file: String,
+    pub subtitle_file: String,
+    pub output_file: String,
+    /// What to do when `output_file` already exists
+    pub collision_policy: CollisionPolicy
+}
+
+/// Builds the `ffmpeg` arguments muxing the subtitle file after the input subtitles, all
+/// converted with `subtitle_codec`. Input subtitles the container can't carry, see
+/// [`check_stream_compatibility`], are not mapped, since a single `-c:s` can't turn bitmap
+/// subtitles like PGS into text ones
+pub fn build_subtitle_mux_args(request: &SubtitleMuxRequest, tracks: &TrackList, subtitle_codec: &str, output_file: &str) -> Vec<String> {
+    let dropped: Vec<String> = check_stream_compatibility(tracks, &detect_container(output_file)).into_iter()
+        .filter(|issue| issue.fate == StreamFate::Dropped)
+        .map(|issue| issue.stream)
+        .collect();
+
+    let mut args = Vec::new();
+    if let CollisionPolicy::Overwrite = request.collision_policy {
+        args.push(String::from("-y"));
+    }
+    args.extend(vec![
+        String::from("-i"),
+        request.input_file.clone(),
+        String::from("-i"),
+        request.subtitle_file.clone(),
+        String::from("-map"),
+        String::from("0:v?"),
+        String::from("-map"),
+        String::from("0:a?")
+    ]);
+    for track in &tracks.subtitles {
+        let stream = format!("0:s:{}", track.index);
+        if !dropped.contains(&stream) {
+            args.push(String::from("-map"));
+            args.push(stream);
+        }
+    }
+    args.extend(vec![
+        String::from("-map"),
+        String::from("1:s:0"),
+        String::from("-c"),
+        String::from("copy"),
+        String::from("-c:s"),
+        subtitle_codec.to_string(),
+        output_file.to_string()
+    ]);
+    args
+}
+
+/// The two copies written by [`crate::converter::service::FFMPEGConversionService::convert_with_soft_subtitles`]
+pub struct SubtitleCopiesResponse {
+    /// The conversion with the subtitles burned into the video
+    pub burned: ConversionResponse,
+    /// The input with the subtitles as a track the player can turn off
+    pub soft: ConversionResponse
+}
+
+/// Returns the subtitle codec a container stores text subtitles with: `mov_text` for the
+/// mov family, `webvtt` for webm, while mkv keeps the subtitle file as it is
+pub fn get_subtitle_codec(container: &OutputContainer) -> Result<&'static str, AtiumError> {
+    match container {
+        OutputContainer::Mp4 | OutputContainer::Mov => Ok("mov_text"),
+        OutputContainer::Webm => Ok("webvtt"),
+        OutputContainer::Mkv => Ok("copy"),
+        OutputContainer::Other(extension) => Err(AtiumError::InvalidArgument(format!(
+            "Cannot store subtitle tracks in a [{}] container, use mp4, mov, mkv or webm", extension)))
+    }
+}
+
+/// Request making a GIF of the moment of a subtitle cue, with the subtitles burned in
+pub struct CueGifRequest {
+    pub input_file: String,
+    /// SRT or WebVTT file the cue is looked up in, also the one burned into the GIF
+    pub subtitle_file: String,
+    pub cue: CueSelector,
+    pub output_file: String,
+    /// GIF width in pixels, the height keeps the aspect ratio
+    pub width: u32,
+    pub fps: u32,
+    /// What to do when `output_file` already exists
+    pub collision_policy: CollisionPolicy
+}
+
+/// Errors reported by `ffmpeg` while demuxing every packet of a media
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntegrityReport {
+    pub errors: Vec<String>
+}
+
+impl IntegrityReport {
+    /// Returns `true` when no errors were reported
+    pub fn is_clean(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Builds an [`IntegrityReport`] from the stderr of an `ffmpeg -v error` run, where each
+/// non-empty line is an error
+pub fn parse_integrity_report(stderr: &str) -> IntegrityReport {
+    IntegrityReport {
+        errors: stderr.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect()
+    }
+}
+
+/// Request encoding a rendition ladder, a rendition per output, in a single `ffmpeg` run
+pub struct LadderRequest {
+    pub input_file: String,
+    /// Base output path, each rendition is written next to it with its height appended,
+    /// e.g. `movie_720p.mp4` for `movie.mp4`
+    pub output_file: String,
+    pub renditions: Vec<Rendition>,
+    /// What to do when a rendition output already exists
+    pub collision_policy: CollisionPolicy
+}
+
+/// Outcome of a rendition ladder encode
+pub struct LadderResponse {
+    /// Output paths, in the order of the requested renditions
+    pub output_files: Vec<String>,
+    /// Resources used by the `ffmpeg` process
+    pub resource_usage: ResourceUsage
+}
+
+/// Request encoding the input to several outputs, each with its own codec, quality and
+/// container, in a single `ffmpeg` run so the input is decoded only once
+pub struct MultiOutputRequest {
+    pub input_file: String,
+    pub outputs: Vec<OutputSpec>,
+    /// What to do when an output already exists
+    pub collision_policy: CollisionPolicy
+}
+
+/// Outcome of a multi output encode
+pub struct MultiOutputResponse {
+    /// Output paths, in the order of the requested outputs
+    pub output_files: Vec<String>,
+    /// Resources used by the `ffmpeg` process
+    pub resource_usage: ResourceUsage
+}
+
+/// Request packaging a rendition ladder for MPEG-DASH, in a single `ffmpeg` run
+pub struct DashRequest {
+    pub input_file: String,
+    /// Directory receiving the `manifest.mpd` and the segments, it is created when missing
+    pub output_dir: String,
+    /// Video renditions, each becoming a representation of the video adaptation set
+    pub renditions: Vec<Rendition>,
+    /// Target segment duration in seconds. If None, the `dash` muxer default is used
+    pub segment_duration: Option<u32>
+}
+
+/// Outcome of a DASH packaging
+pub struct DashResponse {
+    pub manifest_file: String,
+    /// Initialization and media segments written next to the manifest
+    pub segment_files: Vec<String>
+}
+
+/// Lists the files written next to `manifest_file` by the `dash` muxer, sorted by name
+pub fn list_dash_segments(output_dir: &Path, manifest_file: &Path) -> Result<Vec<String>, AtiumError> {
+    let entries = fs::read_dir(output_dir)
+        .map_err(|err| AtiumError::IOError(format!("Could not read output directory: {}", err)))?;
+
+    let mut segment_files: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path != manifest_file && path.extension().map(|extension| extension == "m4s").unwrap_or(false))
+        .filter_map(|path| path.to_str().map(String::from))
+        .collect();
+    segment_files.sort();
+
+    Ok(segment_files)
+}
+
+/// Sample formats of the raw PCM extraction, named as the `ffmpeg` muxers
+const PCM_SAMPLE_FORMATS: [&str; 12] = ["u8", "s8", "s16le", "s16be", "s24le", "s24be", "s32le", "s32be", "f32le", "f32be", "f64le", "f64be"];
+
+/// How the extracted audio tracks are written
+pub enum AudioExtractionTarget {
+    /// The tracks are copied as they are into `.mka` files
+    Copy,
+    /// The tracks are decoded to raw PCM, e.g. `s16le` or `f32le`, for analysis tools reading
+    /// samples. Unlike WAV there is no header, so the reader must be told the format
+    Pcm { sample_format: String, sample_rate: u32, channels: u32 }
+}
+
+/// Checks the sample format, rate and channels of a raw PCM extraction
+pub fn validate_pcm_target(sample_format: &str, sample_rate: u32, channels: u32) -> Result<(), AtiumError> {
+    if !PCM_SAMPLE_FORMATS.contains(&sample_format) {
+        return Err(AtiumError::InvalidArgument(format!(
+            "Unknown PCM sample format [{}], use one of {}", sample_format, PCM_SAMPLE_FORMATS.join("/"))))
+    }
+    if sample_rate == 0 {
+        return Err(AtiumError::InvalidArgument("PCM sample rate must be greater than 0".to_string()))
+    }
+    if channels == 0 {
+        return Err(AtiumError::InvalidArgument("PCM channels must be at least 1".to_string()))
+    }
+
+    Ok(())
+}
+
+/// Builds the output options decoding an audio stream to headerless PCM: the `pcm_*` codec
+/// and the muxer share the sample format name
+pub fn build_pcm_args(sample_format: &str, sample_rate: u32, channels: u32) -> Vec<String> {
+    vec![
+        String::from("-c:a"),
+        format!("pcm_{}", sample_format),
+        String::from("-ar"),
+        sample_rate.to_string(),
+        String::from("-ac"),
+        channels.to_string(),
+        String::from("-f"),
+        sample_format.to_string()
+    ]
+}
+
+/// Request exporting every audio stream of a media to its own file, e.g. all the dubs of a movie
+pub struct AudioTracksExtractionRequest {
+    pub input_file: String,
+    /// Directory receiving the tracks, it is created when missing. Files are named after the
+    /// input, the stream index and the language, e.g. `movie_a1_italian.mka`. With a PCM
+    /// target, `-` writes the first track to stdout instead
+    pub output_dir: String,
+    /// How the tracks are written
+    pub target: AudioExtractionTarget,
+    /// What to do when a track file already exists
+    pub collision_policy: CollisionPolicy
+}
+
+/// Builds the `ffmpeg` arguments writing every audio track in a single run, one output per
+/// `(stream index, output file)` pair, each encoded with `codec_args`
+pub fn build_audio_tracks_args(request: &AudioTracksExtractionRequest, codec_args: &[String], outputs: &[(usize, String)]) -> Vec<String> {
+    let mut args = Vec::new();
+    if let CollisionPolicy::Overwrite = request.collision_policy {
+        args.push(String::from("-y"));
+    }
+    args.push(String::from("-i"));
+    args.push(request.input_file.clone());
+
+    for (index, output_file) in outputs {
+        args.push(String::from("-map"));
+        args.push(format!("0:a:{}", index));
+        args.extend(codec_args.iter().cloned());
+        args.push(output_file.clone());
+    }
+    args
+}
+
+/// Request cutting a part of a media by stream copy, without re-encoding it
+pub struct CutRequest {
+    pub input_file: String,
+    pub output_file: String,
+    /// Start of the cut, in seconds
+    pub start_secs: f64,
+    /// End of the cut, in seconds. If None, the cut lasts until the end of the input
+    pub end_secs: Option<f64>,
+    /// Moves the start back to the closest preceding keyframe, where a copied video can
+    /// actually start, instead of leaving a gap until the next one
+    pub snap_to_keyframe: bool,
+    /// What to do when the output file already exists
+    pub collision_policy: CollisionPolicy
+}
+
+pub struct CutResponse {
+    pub output_file: String,
+    /// Actual start of the cut, in seconds, which differs from the requested one when
+    /// snapping to a keyframe
+    pub start_secs: f64
+}
+
+/// Validates the cut bounds: the start must not be negative and the end must come after it
+pub fn validate_cut_bounds(start_secs: f64, end_secs: Option<f64>) -> Result<(), AtiumError> {
+    if start_secs.is_nan() || start_secs < 0.0 {
+        return Err(AtiumError::InvalidArgument(format!("Cut start [{}] can't be negative", start_secs)))
+    }
+    if let Some(end_secs) = end_secs.filter(|end_secs| end_secs.is_nan() || *end_secs <= start_secs) {
+        return Err(AtiumError::InvalidArgument(format!("Cut end [{}] must come after its start [{}]", end_secs, start_secs)))
+    }
+
+    Ok(())
+}
+
+/// Returns the last of the `keyframes` at or before `time`, all in seconds. When there is
+/// none, e.g. with an empty list, `time` is returned as it is
+pub fn snap_to_preceding_keyframe(keyframes: &[f64], time: f64) -> f64 {
+    keyframes.iter()
+        .copied()
+        .filter(|keyframe| *keyframe <= time)
+        .reduce(f64::max)
+        .unwrap_or(time)
+}
+
+/// Request joining clips one after the other into a single video. The clips must share
+/// their resolution and frame rate
+pub struct ConcatRequest {
+    /// The clips to join, in playback order
+    pub input_files: Vec<String>,
+    pub output_file: String,
+    /// Seconds over which each clip fades into the next one. If None, the clips are cut
+    pub crossfade: Option<f32>,
+    /// What to do when the output file already exists
+    pub collision_policy: CollisionPolicy
+}
+
+/// Request extracting the CEA-608 closed captions carried in a video stream to SRT
+pub struct CaptionExtractionRequest {
+    pub input_file: String,
+    /// Output path of the `.srt` file
+    pub output_file: String,
+    /// What to do when `output_file` already exists
+    pub collision_policy: CollisionPolicy
+}
+
+/// Request rewriting a damaged media into a new container by stream copy
+pub struct RepairRequest {
+    pub input_file: String,
+    pub output_file: String,
+    /// Regenerates missing presentation timestamps with `-fflags +genpts`
+    pub generate_pts: bool,
+    /// What to do when `output_file` already exists
+    pub collision_policy: CollisionPolicy
+}
+
+/// Outcome of a repair, with the integrity of the media before and after it
+pub struct RepairResponse {
+    pub output_file: String,
+    pub before: IntegrityReport,
+    pub after: IntegrityReport
+}
+
+impl RepairResponse {
+    /// Returns `true` when the remux changed the errors reported by the integrity probe
+    pub fn changed(&self) -> bool {
+        self.before != self.after
+    }
+}
+
+/// Conversion response containing the output filepath
 pub struct ConversionResponse {
     pub output_file: String,
-    pub thumbnail_response: Option<ThumbnailResponse>
+    pub thumbnail_response: Option<ThumbnailResponse>,
+    /// Resources used by the `ffmpeg` conversion process
+    pub resource_usage: ResourceUsage,
+    /// `true` when the conversion did not run because a valid output already existed
+    pub skipped: bool,
+    /// Path of the written [`ConversionReport`], see [`ConversionOutput::write_report`]
+    pub report_file: Option<String>
+}
+
+/// Provenance of a conversion, written as JSON next to the output for reproducibility audits
+#[derive(Serialize)]
+pub struct ConversionReport {
+    /// Version of `atium` that ran the conversion
+    pub atium_version: String,
+    /// The [`ConversionRequest`] as it was received
+    pub request: serde_json::Value,
+    /// Arguments of the final `ffmpeg` command, the second pass one for two-pass encodes
+    pub command: Vec<String>,
+    /// Wall-clock time of the conversion, including the analysis and any first pass
+    pub elapsed_secs: f64,
+    /// One-line summaries of the input and the output, e.g. `1920x1080 h264 24fps 5m32s 8.2Mbps`.
+    /// The output one is None when it can't be analyzed
+    pub input_summary: String,
+    pub output_summary: Option<String>
+}
+
+/// Returns the path of the report of `output_file`, e.g. `movie.mp4.atium.json`
+pub fn build_report_file(output_file: &str) -> String {
+    format!("{}.atium.json", output_file)
+}
+
+/// Writes `report` as pretty-printed JSON to `report_file`
+pub fn write_conversion_report(report: &ConversionReport, report_file: &str) -> Result<(), AtiumError> {
+    let content = serde_json::to_string_pretty(report)
+        .map_err(|err| AtiumError::IOError(format!("Could not serialize report: {}", err)))?;
+
+    fs::write(report_file, content)
+        .map_err(|err| AtiumError::IOError(format!("Could not write report [{}]: {}", report_file, err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::atium::common::track_helper::TrackInfo;
+    use super::*;
+
+    #[test]
+    fn test_x265_params() {
+        let params = parse_x265_params(&[String::from("aq-mode=3"), String::from("no-sao=1")]).ok().unwrap();
+
+        assert_eq!(build_x265_params(&params), "aq-mode=3:no-sao=1");
+        assert!(validate_x265_params(&OutputCodec::H265, false, &params).is_ok());
+        assert!(validate_x265_params(&OutputCodec::H264, false, &params).is_err());
+        assert!(validate_x265_params(&OutputCodec::H265, true, &params).is_err());
+        assert!(validate_x265_params(&OutputCodec::H265, false, &HashMap::from([(String::from("a:b"), String::from("1"))])).is_err());
+        assert!(validate_x265_params(&OutputCodec::H265, false, &HashMap::from([(String::from("zones"), String::from("0,100:b=2"))])).is_err());
+        assert!(parse_x265_params(&[String::from("aq-mode")]).is_err());
+    }
+
+    #[test]
+    fn test_parse_disposition_entries() {
+        let dispositions = parse_disposition_entries(&[String::from("a:1=default"), String::from("s:0=FORCED")]).ok().unwrap();
+
+        assert_eq!(dispositions, vec![
+            (StreamSpec { stream_type: StreamType::Audio, index: 1 }, Disposition::Default),
+            (StreamSpec { stream_type: StreamType::Subtitle, index: 0 }, Disposition::Forced)
+        ]);
+        assert!(parse_disposition_entries(&[String::from("a:1")]).is_err());
+        assert!(parse_disposition_entries(&[String::from("x:1=default")]).is_err());
+        assert!(parse_disposition_entries(&[String::from("a=default")]).is_err());
+        assert!(parse_disposition_entries(&[String::from("a:-1=default")]).is_err());
+        assert!(parse_disposition_entries(&[String::from("a:1=loud")]).is_err());
+    }
+
+    #[test]
+    fn test_disposition_args() {
+        let dispositions = parse_disposition_entries(&[
+            String::from("a:1=default"),
+            String::from("s:0=forced"),
+            String::from("s:0=hearing_impaired"),
+            String::from("a:1=default")
+        ]).ok().unwrap();
+
+        assert!(validate_dispositions(&dispositions, None).is_ok());
+        assert!(validate_dispositions(&dispositions, Some(&[StreamType::Video, StreamType::Audio])).is_err());
+        assert!(validate_dispositions(&parse_disposition_entries(&[String::from("a:0=default"), String::from("a:1=default")]).ok().unwrap(), None).is_err());
+        assert_eq!(build_disposition_args(&dispositions), vec![
+            "-disposition:a", "0", "-disposition:a:1", "default", "-disposition:s:0", "forced+hearing_impaired"
+        ]);
+    }
+
+    #[test]
+    fn test_get_subtitle_codec() {
+        assert_eq!(get_subtitle_codec(&detect_container("/tmp/out.mp4")).ok().unwrap(), "mov_text");
+        assert_eq!(get_subtitle_codec(&detect_container("/tmp/out.webm")).ok().unwrap(), "webvtt");
+        assert_eq!(get_subtitle_codec(&detect_container("/tmp/out.mkv")).ok().unwrap(), "copy");
+        assert!(get_subtitle_codec(&detect_container("/tmp/out.avi")).is_err());
+    }
+
+    #[test]
+    fn test_is_bit_depth_reduction() {
+        assert!(is_bit_depth_reduction(Some(24), "s16"));
+        assert!(is_bit_depth_reduction(Some(16), "u8"));
+        assert!(!is_bit_depth_reduction(Some(16), "s32p"));
+        assert!(!is_bit_depth_reduction(Some(16), "s16"));
+        assert!(!is_bit_depth_reduction(None, "s16"));
+        assert_eq!(parse_audio_dither("Shibata").ok().unwrap().method(), "shibata");
+    }
+
+    #[test]
+    fn test_resolve_sample_format_encoder() {
+        assert_eq!(resolve_sample_format_encoder("FLAC", "s16").ok().unwrap(), "flac");
+        assert_eq!(resolve_sample_format_encoder("PCM", "s32").ok().unwrap(), "pcm_s32le");
+        assert_eq!(resolve_sample_format_encoder("ALAC", "s16p").ok().unwrap(), "alac");
+        assert!(resolve_sample_format_encoder("FLAC", "fltp").is_err());
+        assert!(resolve_sample_format_encoder("AAC", "fltp").is_err());
+    }
+
+    #[test]
+    fn test_metadata_args() {
+        let mut custom = HashMap::new();
+        custom.insert(String::from("genre"), String::from("Documentary"));
+        custom.insert(String::from("album"), String::from("Holidays"));
+        let metadata = OutputMetadata {
+            title: Some(String::from("My Summer Trip")),
+            comment: Some(String::from("first cut")),
+            custom,
+            ..Default::default()
+        };
+
+        let args = build_metadata_args(&metadata).ok().unwrap();
+
+        assert_eq!(args, vec![
+            "-metadata", "title=My Summer Trip",
+            "-metadata", "comment=first cut",
+            "-metadata", "album=Holidays",
+            "-metadata", "genre=Documentary"
+        ]);
+    }
+
+    #[test]
+    fn test_stream_metadata_args() {
+        let stream_metadata = parse_stream_metadata_entries(&[
+            String::from("a:1:title=Director's Commentary"),
+            String::from("a:1:language=eng"),
+            String::from("s:0:title=Signs = Songs")
+        ]).ok().unwrap();
+
+        assert_eq!(stream_metadata[0], (StreamSpec { stream_type: StreamType::Audio, index: 1 }, String::from("title"), String::from("Director's Commentary")));
+        assert_eq!(build_stream_metadata_args(&stream_metadata).ok().unwrap(), vec![
+            "-metadata:s:a:1", "title=Director's Commentary",
+            "-metadata:s:a:1", "language=eng",
+            "-metadata:s:s:0", "title=Signs = Songs"
+        ]);
+        assert!(validate_stream_metadata(&stream_metadata, Some(&[StreamType::Video, StreamType::Audio])).is_err());
+        assert!(parse_stream_metadata_entries(&[String::from("a:1:title")]).is_err());
+        assert!(parse_stream_metadata_entries(&[String::from("a:title=Commentary")]).is_err());
+        assert!(parse_stream_metadata_entries(&[String::from("x:0:title=Commentary")]).is_err());
+        assert!(parse_stream_metadata_entries(&[String::from("a:0:=Commentary")]).is_err());
+        assert!(parse_stream_metadata_entries(&[String::from("a:0:track title=Commentary")]).is_err());
+    }
+
+    #[test]
+    fn test_keep_streams_args() {
+        let stream_types = parse_stream_types(&[String::from("audio"), String::from("video"), String::from("a")]).ok().unwrap();
+
+        assert_eq!(build_keep_streams_args(&stream_types, "0:v?", "0:a?").ok().unwrap(), vec!["-map", "0:v?", "-map", "0:a?"]);
+        assert_eq!(build_keep_streams_args(&stream_types, "0:v?", "1:a?").ok().unwrap(), vec!["-map", "0:v?", "-map", "1:a?"]);
+        assert_eq!(build_keep_streams_args(&stream_types, "[vout]", "[aout]").ok().unwrap(), vec!["-map", "[vout]", "-map", "[aout]"]);
+        assert!(build_keep_streams_args(&[], "0:v?", "0:a?").is_err());
+        assert!(parse_stream_types(&[String::from("attachment")]).is_err());
+    }
+
+    #[test]
+    fn test_format_offset_secs() {
+        assert_eq!(format_offset_secs(250), "0.250");
+        assert_eq!(format_offset_secs(-1500), "-1.500");
+        assert_eq!(format_offset_secs(0), "0.000");
+    }
+
+    #[test]
+    fn test_validate_rate_control() {
+        let rate = |value: &str| Some(value.to_string());
+
+        assert!(validate_rate_control(&rate("2M"), &rate("4M")).is_ok());
+        assert!(validate_rate_control(&None, &None).is_ok());
+        assert_eq!(validate_rate_control(&rate("2M"), &None).err().unwrap().to_string(),
+                   "Invalid Argument: bufsize is required when maxrate is set");
+        assert_eq!(validate_rate_control(&None, &rate("4M")).err().unwrap().to_string(),
+                   "Invalid Argument: maxrate is required when bufsize is set");
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("8MB").ok().unwrap(), 8_000_000);
+        assert_eq!(parse_size("500k").ok().unwrap(), 500_000);
+        assert_eq!(parse_size("1.5 GB").ok().unwrap(), 1_500_000_000);
+        assert_eq!(parse_size("1024").ok().unwrap(), 1024);
+
+        assert!(parse_size("8TB").is_err());
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("0MB").is_err());
+    }
+
+    #[test]
+    fn test_compute_target_video_bitrate() {
+        // 8MB over 60s with 128k audio: 64Mbit * 0.97 = 62.08Mbit, minus 7.68Mbit of audio
+        let bitrate = compute_target_video_bitrate(8_000_000, 60.0, TARGET_SIZE_AUDIO_BITRATE).ok().unwrap();
+        assert_eq!(bitrate, 906_666);
+
+        assert_eq!(compute_target_video_bitrate(1_000_000, 10.0, 0).ok().unwrap(), 776_000);
+        assert!(compute_target_video_bitrate(100_000, 60.0, TARGET_SIZE_AUDIO_BITRATE).is_err());
+        assert!(compute_target_video_bitrate(8_000_000, 0.0, 0).is_err());
+    }
+
+    #[test]
+    fn test_get_passlog_suffixes() {
+        assert!(get_passlog_suffixes(&OutputCodec::H264).contains(&"-0.log.mbtree"));
+        assert!(get_passlog_suffixes(&OutputCodec::H265).contains(&"-0.log.cutree"));
+        assert_eq!(get_passlog_suffixes(&OutputCodec::Vp9), ["-0.log"]);
+        assert_eq!(get_passlog_suffixes(&OutputCodec::Av1), ["-0.log"]);
+    }
+
+    #[test]
+    fn test_parse_hwaccel() {
+        assert_eq!(parse_hwaccel("CUDA").ok().unwrap(), HwAccel::Cuda);
+        assert_eq!(parse_hwaccel("videotoolbox").ok().unwrap().name(), "videotoolbox");
+        assert!(parse_hwaccel("nvenc").is_err());
+    }
+
+    #[test]
+    fn test_pick_aac_encoder() {
+        assert_eq!(parse_aac_encoder("FDK").ok().unwrap(), AacEncoder::Fdk);
+        assert!(parse_aac_encoder("faac").is_err());
+        assert_eq!(pick_aac_encoder(&AacEncoder::Fdk, true, true).ok().unwrap().name(), "libfdk_aac");
+        assert_eq!(pick_aac_encoder(&AacEncoder::Fdk, false, false).ok().unwrap(), AacEncoder::Native);
+        assert!(pick_aac_encoder(&AacEncoder::Fdk, false, true).is_err());
+        assert_eq!(pick_aac_encoder(&AacEncoder::Native, false, true).ok().unwrap(), AacEncoder::Native);
+    }
+
+    #[test]
+    fn test_codec_container() {
+        assert!(validate_codec_container(&OutputCodec::H264, &OutputContainer::Mp4).is_ok());
+        assert!(validate_codec_container(&OutputCodec::H264, &OutputContainer::Mkv).is_ok());
+        assert!(validate_codec_container(&OutputCodec::H264, &OutputContainer::Webm).is_err());
+        assert!(validate_codec_container(&OutputCodec::Vp9, &OutputContainer::Webm).is_ok());
+    }
+
+    #[test]
+    fn test_resolve_codec() {
+        assert_eq!(parse_output_codec("HEVC").ok().unwrap(), OutputCodec::H265);
+        assert!(parse_output_codec("mpeg2").is_err());
+        assert_eq!(resolve_auto_codec("/tmp/output.webm"), OutputCodec::Vp9);
+        assert_eq!(resolve_auto_codec("/tmp/output.mkv"), OutputCodec::H264);
+
+        assert_eq!(resolve_encoder(&OutputCodec::Av1, None).ok().unwrap(), "libaom-av1");
+        assert_eq!(resolve_encoder(&OutputCodec::H265, Some(&HwAccel::Cuda)).ok().unwrap(), "hevc_nvenc");
+        assert_eq!(resolve_encoder(&OutputCodec::H264, Some(&HwAccel::VideoToolbox)).ok().unwrap(), "h264_videotoolbox");
+        assert!(resolve_encoder(&OutputCodec::Vp9, Some(&HwAccel::Cuda)).is_err());
+        assert!(resolve_encoder(&OutputCodec::H264, Some(&HwAccel::Vaapi)).is_err());
+    }
+
+    #[test]
+    fn test_bit_depth() {
+        let ten = parse_bit_depth("10").ok().unwrap();
+        assert_eq!(ten.pixel_format(), "yuv420p10le");
+        assert_eq!(get_bit_depth_profile(&OutputCodec::H264, &ten).ok().unwrap(), Some("high10"));
+        assert_eq!(get_bit_depth_profile(&OutputCodec::H264, &BitDepth::Eight).ok().unwrap(), None);
+        assert!(get_bit_depth_profile(&OutputCodec::H264, &BitDepth::Twelve).is_err());
+        assert!(parse_bit_depth("16").is_err());
+    }
+
+    #[test]
+    fn test_validate_stream_copy() {
+        assert!(validate_stream_copy("AVC", "AAC", "/tmp/merged.mp4").is_ok());
+        assert!(validate_stream_copy("VP9", "Opus", "/tmp/merged.webm").is_ok());
+        assert!(validate_stream_copy("AVC", "Opus", "/tmp/merged.mkv").is_ok());
+        assert!(validate_stream_copy("AVC", "Opus", "/tmp/merged.mp4").is_err());
+        assert!(validate_stream_copy("AVC", "AAC", "/tmp/merged.webm").is_err());
+    }
+
+    #[test]
+    fn test_pad() {
+        assert_eq!(parse_frame_size("1920x1080").ok().unwrap(), (1920, 1080));
+        assert!(parse_frame_size("1920").is_err());
+        assert!(parse_frame_size("0x1080").is_err());
+
+        let pad = Pad { width: 1920, height: 1080, color: String::from("#1E1E1E") };
+        assert!(validate_pad(&pad).is_ok());
+        assert!(validate_pad(&Pad { width: 1921, ..pad.clone() }).is_err());
+        assert!(validate_pad(&Pad { color: String::from("dark grey"), ..pad }).is_err());
+
+        let blur_fill = BlurFill { width: 1080, height: 1920, strength: 20 };
+        assert!(validate_blur_fill(&blur_fill).is_ok());
+        assert!(validate_blur_fill(&BlurFill { height: 1921, ..blur_fill.clone() }).is_err());
+        assert!(validate_blur_fill(&BlurFill { strength: 0, ..blur_fill }).is_err());
+    }
+
+    #[test]
+    fn test_parse_integrity_report() {
+        let stderr = "[mov,mp4,m4a,3gp,3g2,mj2 @ 0x1] moov atom not found
+/tmp/broken.mp4: Invalid data found when processing input
+
+";
+        let report = parse_integrity_report(stderr);
+
+        assert_eq!(report.errors.len(), 2);
+        assert!(!report.is_clean());
+        assert!(parse_integrity_report("\n").is_clean());
+    }
+
+    #[test]
+    fn test_cut() {
+        let keyframes = [0.0, 2.002, 4.004, 6.006];
+
+        assert_eq!(snap_to_preceding_keyframe(&keyframes, 5.5), 4.004);
+        assert_eq!(snap_to_preceding_keyframe(&keyframes, 2.002), 2.002);
+        assert_eq!(snap_to_preceding_keyframe(&keyframes[1..], 1.0), 1.0);
+        assert_eq!(snap_to_preceding_keyframe(&[], 3.0), 3.0);
+        assert!(validate_cut_bounds(5.5, Some(10.0)).is_ok());
+        assert!(validate_cut_bounds(5.5, Some(5.5)).is_err());
+        assert!(validate_cut_bounds(-1.0, None).is_err());
+    }
+
+    #[test]
+    fn test_list_dash_segments() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["manifest.mpd", "init-stream0.m4s", "chunk-stream0-00001.m4s", "notes.txt"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let segments = list_dash_segments(&dir, &dir.join("manifest.mpd")).ok().unwrap();
+
+        assert_eq!(segments, vec![
+            dir.join("chunk-stream0-00001.m4s").to_str().unwrap().to_string(),
+            dir.join("init-stream0.m4s").to_str().unwrap().to_string()
+        ]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_frame_references() {
+        assert!(validate_frame_references(&OutputCodec::H264, Some(0), Some(16)).is_ok());
+        assert!(validate_frame_references(&OutputCodec::H264, Some(3), None).is_ok());
+        assert!(validate_frame_references(&OutputCodec::H264, Some(17), None).is_err());
+        assert!(validate_frame_references(&OutputCodec::H264, None, Some(17)).is_err());
+        assert!(validate_frame_references(&OutputCodec::H264, None, Some(0)).is_err());
+        assert!(validate_frame_references(&OutputCodec::Vp9, Some(2), None).is_err());
+        assert!(validate_frame_references(&OutputCodec::Av1, None, None).is_ok());
+    }
+
+    #[test]
+    fn test_write_conversion_report() {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let report_file = build_report_file(dir.join("movie.mp4").to_str().unwrap());
+        let report = ConversionReport {
+            atium_version: String::from("0.1.2"),
+            request: serde_json::json!({"output": {"codec": "H264"}}),
+            command: vec![String::from("-i"), String::from("input.mov"), String::from("movie.mp4")],
+            elapsed_secs: 12.5,
+            input_summary: String::from("1920x1080 h264 24fps 5m32s 8.2Mbps"),
+            output_summary: None
+        };
+
+        write_conversion_report(&report, &report_file).ok().unwrap();
+
+        assert!(report_file.ends_with("movie.mp4.atium.json"));
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&report_file).unwrap()).unwrap();
+        assert_eq!(written["request"]["output"]["codec"], "H264");
+        assert_eq!(written["elapsed_secs"], 12.5);
+        assert!(written["output_summary"].is_null());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_validate_force_keyframes() {
+        assert!(validate_force_keyframes("expr:gte(t,n_forced*2)").is_ok());
+        assert!(validate_force_keyframes("0,2.5,00:01:05.250").is_ok());
+        assert!(validate_force_keyframes("expr:gte(t,n_forced*2").is_err());
+        assert!(validate_force_keyframes("expr:").is_err());
+        assert!(validate_force_keyframes("2,,4").is_err());
+        assert!(validate_force_keyframes("every 2s").is_err());
+        assert!(validate_force_keyframes("-2").is_err());
+    }
+
+    #[test]
+    fn test_validate_sample_duration() {
+        assert!(validate_sample_duration("00:00:10").is_ok());
+        assert!(validate_sample_duration("0").is_err());
+        assert!(validate_sample_duration("1:2:3:4").is_err());
+        assert!(validate_sample_duration("ten").is_err());
+        assert!(validate_sample_duration("00:00:-5").is_err());
+    }
+
+    #[test]
+    fn test_validate_filter_graph() {
+        let filter_graph = FilterGraph {
+            graph: String::from("[0:v]hflip[vout]"),
+            maps: vec![String::from("[vout]"), String::from("0:a?")]
+        };
+
+        assert!(validate_filter_graph(&filter_graph).is_ok());
+        assert!(validate_filter_graph(&FilterGraph { maps: vec![String::from("[out]")], ..filter_graph.clone() }).is_err());
+        assert!(validate_filter_graph(&FilterGraph { maps: Vec::new(), ..filter_graph.clone() }).is_err());
+        assert!(validate_filter_graph(&FilterGraph { graph: String::from(" "), ..filter_graph }).is_err());
+    }
+
+    #[test]
+    fn test_validate_pip() {
+        let pip = |scale| Pip {
+            overlay_input: String::from("/tmp/missing-webcam.mp4"),
+            position: parse_pip_position("Bottom-Right").ok().unwrap(),
+            scale,
+            margin: 16,
+            mix_audio: false
+        };
+
+        assert_eq!(pip(0.25).position, PipPosition::BottomRight);
+        assert!(parse_pip_position("center").is_err());
+        assert!(validate_pip(&pip(1.5)).is_err());
+        assert!(validate_pip(&pip(f32::NAN)).is_err());
+        assert!(matches!(validate_pip(&pip(0.25)), Err(AtiumError::IOError(_))));
+    }
+
+    #[test]
+    fn test_check_stream_compatibility() {
+        let track = |index, codec: &str| TrackInfo {
+            index,
+            codec: codec.to_string(),
+            language: None,
+            title: None,
+            channels: None,
+            default: false
+        };
+        let tracks = TrackList {
+            audio: vec![track(0, "AAC")],
+            subtitles: vec![track(0, "UTF-8"), track(1, "PGS"), track(2, "Timed Text")]
+        };
+
+        assert_eq!(check_stream_compatibility(&tracks, &OutputContainer::Mp4), vec![
+            StreamIssue { stream: String::from("0:s:0"), format: String::from("UTF-8"), fate: StreamFate::Transcoded("mov_text") },
+            StreamIssue { stream: String::from("0:s:1"), format: String::from("PGS"), fate: StreamFate::Dropped }
+        ]);
+        assert_eq!(check_stream_compatibility(&tracks, &OutputContainer::Webm).len(), 3);
+        assert!(check_stream_compatibility(&tracks, &OutputContainer::Mkv).is_empty());
+        assert_eq!(check_stream_compatibility(&tracks, &OutputContainer::Mp4)[1].to_string(),
+            "Stream [0:s:1] (PGS) can't be stored in the output container and is dropped");
+    }
+
+    #[test]
+    fn test_build_subtitle_mux_args() {
+        let track = |index, codec: &str| TrackInfo {
+            index,
+            codec: codec.to_string(),
+            language: None,
+            title: None,
+            channels: None,
+            default: false
+        };
+        let tracks = TrackList {
+            audio: vec![track(0, "AAC")],
+            subtitles: vec![track(0, "UTF-8"), track(1, "PGS")]
+        };
+        let request = SubtitleMuxRequest {
+            input_file: String::from("movie.mkv"),
+            subtitle_file: String::from("movie.it.srt"),
+            output_file: String::from("out.mp4"),
+            collision_policy: CollisionPolicy::Error
+        };
+
+        assert_eq!(build_subtitle_mux_args(&request, &tracks, "mov_text", "out.mp4"), vec![
+            "-i", "movie.mkv", "-i", "movie.it.srt", "-map", "0:v?", "-map", "0:a?", "-map", "0:s:0",
+            "-map", "1:s:0", "-c", "copy", "-c:s", "mov_text", "out.mp4"
+        ]);
+        assert_eq!(build_subtitle_mux_args(&request, &tracks, "copy", "out.mkv"), vec![
+            "-i", "movie.mkv", "-i", "movie.it.srt", "-map", "0:v?", "-map", "0:a?", "-map", "0:s:0", "-map", "0:s:1",
+            "-map", "1:s:0", "-c", "copy", "-c:s", "copy", "out.mkv"
+        ]);
+    }
+
+    #[test]
+    fn test_build_cover_args() {
+        assert_eq!(build_cover_args("/tmp/cover.jpg", &OutputContainer::Mp4, 1).ok().unwrap(), vec![
+            "-map", "1:v:0", "-c:v:1", "copy", "-disposition:v:1", "attached_pic"
+        ]);
+        assert_eq!(build_cover_args("/tmp/cover.PNG", &OutputContainer::Mkv, 1).ok().unwrap(), vec![
+            "-attach", "/tmp/cover.PNG", "-metadata:s:t:0", "mimetype=image/png", "-metadata:s:t:0", "filename=cover.png"
+        ]);
+        assert!(build_cover_args("/tmp/cover.gif", &OutputContainer::Mp4, 1).is_err());
+        assert!(build_cover_args("/tmp/cover.jpg", &OutputContainer::Webm, 1).is_err());
+        assert!(validate_cover("/tmp/cover.jpg", "/tmp/output.webm").is_err());
+    }
+
+    #[test]
+    fn test_intro() {
+        let intro = Intro { duration_secs: 3.0, background: parse_intro_background("navy"), text: None };
+
+        assert!(validate_intro(&intro).is_ok());
+        assert!(validate_intro(&Intro { duration_secs: 0.0, ..intro.clone() }).is_err());
+        assert!(validate_intro(&Intro { text: Some(String::from(" ")), ..intro.clone() }).is_err());
+        assert!(validate_intro(&Intro { background: parse_intro_background("/tmp/missing_title.png"), ..intro.clone() }).is_err());
+        assert_eq!(build_intro_input_args(&intro, false), vec!["-f", "lavfi", "-i", "color=c=navy:s=1280x720:r=25:d=3.000"]);
+        assert_eq!(build_intro_input_args(&intro, true)[4..], ["-f", "lavfi", "-t", "3.000", "-i", "anullsrc=r=48000:cl=stereo"]);
+    }
+
+    #[test]
+    fn test_build_replace_audio_args() {
+        let mut request = AudioReplacementRequest {
+            video_input: String::from("video.mp4"),
+            audio_input: String::from("dub.wav"),
+            output_file: String::from("out.mp4"),
+            audio_codec: None,
+            shortest: false,
+            collision_policy: CollisionPolicy::Rename
+        };
+        assert_eq!(build_replace_audio_args(&request, "out.mp4"),
+                   vec!["-i", "video.mp4", "-i", "dub.wav", "-map", "0:v:0", "-map", "1:a:0", "-c:v", "copy", "-c:a", "copy", "out.mp4"]);
+
+        request.audio_codec = Some(String::from("aac"));
+        request.shortest = true;
+        request.collision_policy = CollisionPolicy::Overwrite;
+        assert_eq!(build_replace_audio_args(&request, "out.mp4"),
+                   vec!["-y", "-i", "video.mp4", "-i", "dub.wav", "-map", "0:v:0", "-map", "1:a:0", "-c:v", "copy", "-c:a", "aac", "-shortest", "out.mp4"]);
+    }
+
+    #[test]
+    fn test_build_audio_tracks_args() {
+        let request = AudioTracksExtractionRequest {
+            input_file: String::from("movie.mkv"),
+            output_dir: String::from("tracks"),
+            target: AudioExtractionTarget::Copy,
+            collision_policy: CollisionPolicy::Overwrite
+        };
+        let codec_args = vec![String::from("-c"), String::from("copy")];
+        let outputs = vec![(0, String::from("tracks/movie_a0_english.mka")), (1, String::from("tracks/movie_a1.mka"))];
+
+        assert_eq!(build_audio_tracks_args(&request, &codec_args, &outputs), vec![
+            "-y", "-i", "movie.mkv",
+            "-map", "0:a:0", "-c", "copy", "tracks/movie_a0_english.mka",
+            "-map", "0:a:1", "-c", "copy", "tracks/movie_a1.mka"
+        ]);
+    }
+
+    #[test]
+    fn test_pcm_target() {
+        assert!(validate_pcm_target("s16le", 48000, 2).is_ok());
+        assert!(validate_pcm_target("s16", 48000, 2).is_err());
+        assert!(validate_pcm_target("f32le", 0, 2).is_err());
+        assert!(validate_pcm_target("f32le", 44100, 0).is_err());
+        assert_eq!(build_pcm_args("f32le", 16000, 1), vec!["-c:a", "pcm_f32le", "-ar", "16000", "-ac", "1", "-f", "f32le"]);
+    }
+
+    #[test]
+    fn test_is_hwaccel_failure() {
+        assert!(is_hwaccel_failure("[h264_nvenc @ 0x55] OpenEncodeSessionEx failed: out of memory (10): (no details)"));
+        assert!(is_hwaccel_failure("[AVHWDeviceContext @ 0x1] Failed to initialise VAAPI connection: -1 (unknown libva error)."));
+        assert!(!is_hwaccel_failure("input.mp4: No such file or directory"));
+        assert!(!is_hwaccel_failure(""));
+    }
+
+    #[test]
+    fn test_detect_container() {
+        assert_eq!(detect_container("/tmp/video.MP4"), OutputContainer::Mp4);
+        assert_eq!(detect_container("/tmp/video.mov"), OutputContainer::Mov);
+        assert_eq!(detect_container("/tmp/video.mkv"), OutputContainer::Mkv);
+        assert_eq!(detect_container("/tmp/video.avi"), OutputContainer::Other(String::from("avi")));
+        assert_eq!(detect_container("/tmp/video"), OutputContainer::Other(String::new()));
+    }
+
+    #[test]
+    fn test_aspect_ratio() {
+        assert!(validate_aspect_ratio("16:9").is_ok());
+        assert!(validate_aspect_ratio("1.7778").is_ok());
+        assert!(validate_aspect_ratio("16:0").is_err());
+        assert!(validate_aspect_ratio("16x9").is_err());
+        assert!(validate_aspect_ratio("-1.5").is_err());
+
+        assert_eq!(build_setsar_filter("1:1"), "setsar=1/1");
+        assert_eq!(build_setsar_filter("1.5"), "setsar=1.5");
+        assert_eq!(build_anamorphic_fix_filter("1.185").unwrap(), "scale=trunc(iw*sar/2)*2:ih,setsar=1");
+        assert!(build_anamorphic_fix_filter("1.000").is_none());
+        assert!(build_anamorphic_fix_filter("").is_none());
+    }
+
+    #[test]
+    fn test_validate_frame_rate() {
+        assert!(validate_frame_rate("25").is_ok());
+        assert!(validate_frame_rate("29.97").is_ok());
+        assert!(validate_frame_rate("30000/1001").is_ok());
+
+        assert!(validate_frame_rate("0").is_err());
+        assert!(validate_frame_rate("-24").is_err());
+        assert!(validate_frame_rate("30000/0").is_err());
+        assert!(validate_frame_rate("fast").is_err());
+    }
+
+    #[test]
+    fn test_parse_metadata_entries() {
+        let entries = parse_metadata_entries(&[String::from("encoder=atium v1"), String::from("empty=")]).ok().unwrap();
+        assert_eq!(entries.get("encoder").unwrap(), "atium v1");
+        assert_eq!(entries.get("empty").unwrap(), "");
+
+        assert!(parse_metadata_entries(&[String::from("missing-separator")]).is_err());
+        assert!(parse_metadata_entries(&[String::from("bad key=value")]).is_err());
+        assert!(parse_metadata_entries(&[String::from("=value")]).is_err());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+use crate::atium::common::error::AtiumError;
+use crate::converter::ladder_helper::{RenditionQuality, validate_quality};
+use crate::converter::model::{detect_container, get_encoder_name, OutputCodec, OutputContainer, parse_output_codec, validate_codec_container};
+
+/// An output of a multi output encode, with its own codec and quality. The container
+/// comes from the extension of `output_file`
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutputSpec {
+    pub output_file: String,
+    pub codec: OutputCodec,
+    pub quality: RenditionQuality
+}
+
+/// Parses an output spec like `movie.webm:vp9:crf=32` or `movie.mp4:h264:2500k`.
+/// The path is everything before the last two `:`, so it may hold one itself
+pub fn parse_output_spec(input: &str) -> Result<OutputSpec, AtiumError> {
+    let invalid = || AtiumError::InvalidArgument(format!("Invalid output [{}], expected PATH:CODEC:crf=N or PATH:CODEC:BITRATE", input));
+
+    let mut parts = input.rsplitn(3, ':');
+    let quality = parts.next().ok_or_else(invalid)?;
+    let codec = parts.next().ok_or_else(invalid)?;
+    let output_file = parts.next().filter(|output_file| !output_file.is_empty()).ok_or_else(invalid)?;
+    let quality = match quality.strip_prefix("crf=") {
+        Some(crf) => RenditionQuality::Crf(crf.parse::<u8>().map_err(|_| invalid())?),
+        None => RenditionQuality::Bitrate(quality.to_string())
+    };
+    let spec = OutputSpec {
+        output_file: output_file.to_string(),
+        codec: parse_output_codec(codec)?,
+        quality
+    };
+    validate_output_spec(&spec)?;
+
+    Ok(spec)
+}
+
+/// Returns the highest crf taken by the encoder of `codec`
+pub fn get_max_crf(codec: &OutputCodec) -> u8 {
+    match codec {
+        OutputCodec::H264 | OutputCodec::H265 => 51,
+        OutputCodec::Vp9 | OutputCodec::Av1 => 63
+    }
+}
+
+/// Validates the quality of an [`OutputSpec`] and that its codec fits the container
+pub fn validate_output_spec(spec: &OutputSpec) -> Result<(), AtiumError> {
+    validate_codec_container(&spec.codec, &detect_container(&spec.output_file))?;
+    validate_quality(&spec.quality, get_max_crf(&spec.codec))
+}
+
+/// Validates a whole set of outputs: at least one, each valid, and no path used twice
+/// since `ffmpeg` would write both encodes to the same file
+pub fn validate_output_specs(specs: &[OutputSpec]) -> Result<(), AtiumError> {
+    if specs.is_empty() {
+        return Err(AtiumError::InvalidArgument("At least one output is required".to_string()))
+    }
+
+    let mut output_files = HashSet::new();
+    for spec in specs {
+        validate_output_spec(spec)?;
+        if !output_files.insert(spec.output_file.as_str()) {
+            return Err(AtiumError::InvalidArgument(format!("Output [{}] is requested more than once", spec.output_file)))
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the audio encoder of an output: opus for webm, which can't hold aac, aac otherwise
+pub fn get_output_audio_encoder(output_file: &str) -> &'static str {
+    match detect_container(output_file) {
+        OutputContainer::Webm => "libopus",
+        _ => "aac"
+    }
+}
+
+/// Builds the options group of an output of a multi output encode, ending with the output
+/// path. Options only apply to the output they come before, so each group maps the
+/// streams again and carries its own encoders. `output_file` is the final path, which may
+/// differ from the spec one once the collision policy is applied
+pub fn build_output_spec_args(spec: &OutputSpec, output_file: &str) -> Vec<String> {
+    let mut args = vec![
+        String::from("-map"),
+        String::from("0:v:0"),
+        String::from("-map"),
+        String::from("0:a:0?"),
+        String::from("-c:v"),
+        get_encoder_name(spec.codec.clone())
+    ];
+    match &spec.quality {
+        RenditionQuality::Crf(crf) => {
+            args.push(String::from("-crf"));
+            args.push(crf.to_string());
+            // libvpx-vp9 and libaom-av1 only run in constant quality mode with a zero bitrate
+            if matches!(spec.codec, OutputCodec::Vp9 | OutputCodec::Av1) {
+                args.push(String::from("-b:v"));
+                args.push(String::from("0"));
+            }
+        }
+        RenditionQuality::Bitrate(bitrate) => {
+            args.push(String::from("-b:v"));
+            args.push(bitrate.clone());
+        }
+    }
+    args.extend(vec![
+        String::from("-c:a"),
+        get_output_audio_encoder(&spec.output_file).to_string(),
+        output_file.to_string()
+    ]);
+
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_output_spec() {
+        assert_eq!(parse_output_spec("movie.webm:vp9:crf=32").ok().unwrap(), OutputSpec {
+            output_file: String::from("movie.webm"),
+            codec: OutputCodec::Vp9,
+            quality: RenditionQuality::Crf(32)
+        });
+        assert_eq!(parse_output_spec("C:/out/movie.mp4:h264:2500k").ok().unwrap().output_file, "C:/out/movie.mp4");
+        assert!(parse_output_spec("movie.mp4:h264:crf=52").is_err());
+        assert!(parse_output_spec("movie.webm:h264:crf=20").is_err());
+        assert!(parse_output_spec("movie.mp4:mpeg2:crf=20").is_err());
+        assert!(parse_output_spec("h264:crf=20").is_err());
+        assert!(parse_output_spec(":h264:crf=20").is_err());
+    }
+
+    #[test]
+    fn test_validate_output_specs() {
+        let mp4 = parse_output_spec("movie.mp4:h264:crf=20").ok().unwrap();
+        let webm = parse_output_spec("movie.webm:vp9:crf=63").ok().unwrap();
+
+        assert!(validate_output_specs(&[mp4.clone(), webm]).is_ok());
+        assert!(validate_output_specs(&[mp4.clone(), mp4]).is_err());
+        assert!(validate_output_specs(&[]).is_err());
+    }
+
+    #[test]
+    fn test_build_output_spec_args() {
+        let webm = parse_output_spec("movie.webm:vp9:crf=32").ok().unwrap();
+        let mp4 = parse_output_spec("movie.mp4:h265:2500k").ok().unwrap();
+
+        assert_eq!(build_output_spec_args(&webm, "movie(1).webm"), vec![
+            "-map", "0:v:0", "-map", "0:a:0?", "-c:v", "libvpx-vp9", "-crf", "32", "-b:v", "0", "-c:a", "libopus", "movie(1).webm"
+        ]);
+        assert_eq!(build_output_spec_args(&mp4, "movie.mp4"), vec![
+            "-map", "0:v:0", "-map", "0:a:0?", "-c:v", "libx265", "-b:v", "2500k", "-c:a", "aac", "movie.mp4"
+        ]);
+    }
+}
\ No newline at end of file
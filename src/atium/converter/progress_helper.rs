@@ -0,0 +1,122 @@
+use crate::converter::model::ConversionProgress;
+
+/// Parses the `key=value` blocks written by `ffmpeg -progress pipe:1`.
+/// Each block ends with a `progress=continue` or `progress=end` line, which is when a
+/// [`ConversionProgress`] is emitted
+pub struct FFMPEGProgressParser {
+    total_duration_secs: Option<f64>,
+    out_time_secs: f64,
+    speed: Option<f32>
+}
+
+impl FFMPEGProgressParser {
+    /// Creates a parser for an input lasting `total_duration_secs`. A zero or invalid
+    /// duration means it is unknown, so only the processed time and the speed are reported
+    pub fn new(total_duration_secs: f64) -> Self {
+        FFMPEGProgressParser {
+            total_duration_secs: Some(total_duration_secs).filter(|duration| duration.is_finite() && *duration > 0.0),
+            out_time_secs: 0.0,
+            speed: None
+        }
+    }
+    fn build_progress(&self) -> ConversionProgress {
+        let percent = self.total_duration_secs
+            .map(|total_duration| (self.out_time_secs / total_duration * 100.0).clamp(0.0, 100.0) as f32);
+        let eta_secs = match (self.total_duration_secs, self.speed.filter(|speed| *speed > 0.0)) {
+            (Some(total_duration), Some(speed)) => Some((total_duration - self.out_time_secs).max(0.0) / speed as f64),
+            _ => None
+        };
+
+        ConversionProgress {
+            percent,
+            out_time_secs: self.out_time_secs,
+            speed: self.speed,
+            eta_secs
+        }
+    }
+    /// Feeds a single line of the progress output, returns Some when a progress tick is complete
+    pub fn feed_line(&mut self, line: &str) -> Option<ConversionProgress> {
+        let (key, value) = line.trim().split_once('=')?;
+        match key {
+            // despite the name, out_time_ms is expressed in microseconds as well
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(micros) = value.parse::<f64>() {
+                    self.out_time_secs = micros / 1_000_000.0;
+                }
+                None
+            }
+            "speed" => {
+                self.speed = value.trim_end_matches('x').trim().parse::<f32>().ok();
+                None
+            }
+            "progress" => {
+                if let (Some(total_duration), "end") = (self.total_duration_secs, value) {
+                    self.out_time_secs = self.out_time_secs.max(total_duration);
+                }
+                Some(self.build_progress())
+            }
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_ticks() {
+        let mut parser = FFMPEGProgressParser::new(20.0);
+        let lines = "frame=125
+fps=50.00
+out_time_us=5000000
+out_time_ms=5000000
+out_time=00:00:05.000000
+speed=2.00x
+progress=continue";
+
+        let ticks: Vec<ConversionProgress> = lines.lines()
+            .filter_map(|line| parser.feed_line(line))
+            .collect();
+
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks[0].percent, Some(25.0));
+        assert_eq!(ticks[0].speed, Some(2.0));
+        assert_eq!(ticks[0].eta_secs, Some(7.5));
+
+        let end = parser.feed_line("progress=end").unwrap();
+        assert_eq!(end.percent, Some(100.0));
+        assert_eq!(end.eta_secs, Some(0.0));
+    }
+
+    #[test]
+    fn test_progress_without_speed() {
+        let mut parser = FFMPEGProgressParser::new(10.0);
+        parser.feed_line("out_time_us=1000000");
+        parser.feed_line("speed=N/A");
+
+        let tick = parser.feed_line("progress=continue").unwrap();
+
+        assert_eq!(tick.percent, Some(10.0));
+        assert!(tick.speed.is_none());
+        assert!(tick.eta_secs.is_none());
+    }
+
+    #[test]
+    fn test_progress_with_unknown_duration() {
+        let mut parser = FFMPEGProgressParser::new(0.0);
+        parser.feed_line("out_time_us=3000000");
+        parser.feed_line("speed=1.50x");
+
+        let tick = parser.feed_line("progress=continue").unwrap();
+
+        assert!(tick.percent.is_none());
+        assert!(tick.eta_secs.is_none());
+        assert_eq!(tick.out_time_secs, 3.0);
+        assert_eq!(tick.speed, Some(1.5));
+
+        let end = parser.feed_line("progress=end").unwrap();
+        assert!(end.percent.is_none());
+        assert_eq!(end.out_time_secs, 3.0);
+    }
+}
\ No newline at end of file
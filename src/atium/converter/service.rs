@@ -1,41 +1,65 @@
 use std::fs;
+use std::path::Path;
+use std::time::Instant;
 
-use log::{debug, error, warn};
+use log::{debug, error, info, warn};
 use uuid::Uuid;
 
-use crate::{InfoExtractorRequest, MediaInfoExtractorService, ThumbnailRequest};
-use crate::atium::common::analysis_helper::{AnalysisOutput, compute_output_file, MediaInfoJsonLoader};
+use crate::{MediaInfoExtractorService, ThumbnailRequest};
+use crate::atium::common::analysis_helper::{AnalysisOutput, compute_output_file, copy_modified_time, is_complete_output, is_fifo};
 
-use crate::atium::common::command_manager::CommandManager;
+use crate::atium::common::capability_helper::{is_encoder_available, require_decoder, require_encoder, require_hwaccel, require_input_protocol, require_muxer};
+use crate::atium::common::command_manager::{CommandManager, CommandOutcome};
+use crate::atium::common::env_helper::temp_dir;
 use crate::atium::common::error::AtiumError;
-use crate::atium::common::model::{InfoFormat, InfoOutputType, ThumbnailResponse};
+use crate::atium::common::keyframe_service::FFProbeKeyframeService;
+use crate::atium::common::model::{CollisionPolicy, detect_protocol, InputProtocol, ResourceUsage, ThumbnailResponse};
 use crate::atium::common::thumbnail_service::FFMPEGThumbnailService;
-use crate::converter::model::{ConversionInput, ConversionRequest, ConversionResponse, get_width_height, InputSourceType, OutputResolution};
+use crate::atium::common::time_helper::{format_timestamp, parse_timestamp};
+use crate::atium::measure::model::build_loudnorm_filter;
+use crate::atium::measure::service::FFMPEGMeasurementService;
+use crate::converter::model::{AacEncoder, build_intro_input_args, AudioExtractionTarget, build_pcm_args, validate_pcm_target, pick_aac_encoder, check_stream_compatibility, StreamFate, CutRequest, CutResponse, snap_to_preceding_keyframe, validate_cut_bounds, AudioReplacementRequest, build_replace_audio_args, AudioTracksExtractionRequest, build_audio_tracks_args, CaptionExtractionRequest, ConcatRequest, DashRequest, DashResponse, build_report_file, ConversionReport, write_conversion_report, list_dash_segments, LadderRequest, MultiOutputRequest, MultiOutputResponse, build_cover_args, build_disposition_args, OutputContainer, LadderResponse, OutputCodec, build_keep_streams_args, build_metadata_args, build_stream_metadata_args, build_anamorphic_fix_filter, build_setsar_filter, ConversionInput, ConversionOutput, ConversionProgress, ConversionRequest, ConversionResponse, detect_container, compute_target_video_bitrate, format_offset_secs, get_bit_depth_profile, build_x265_params, get_encoder_name, get_passlog_suffixes, get_width_height, HwAccel, is_hwaccel_failure, resolve_encoder, InputSourceType, OutputResolution, parse_size, REVERSE_WARNING_DURATION_SECS, StreamType, SubtitleBurn, get_subtitle_codec, SubtitleCopiesResponse, SubtitleMuxRequest, build_subtitle_mux_args, CueGifRequest, TARGET_SIZE_AUDIO_BITRATE, IntegrityReport, parse_integrity_report, RepairRequest, RepairResponse, is_bit_depth_reduction, resolve_sample_format_encoder, SampleFormatRequest, validate_conversion_output, validate_frame_rate, validate_stream_copy};
+use crate::converter::ladder_helper::{build_ladder_filter, build_rendition_output, build_rendition_quality_args, validate_rendition};
+use crate::converter::multi_output_helper::{build_output_spec_args, get_output_audio_encoder, validate_output_specs};
+use crate::converter::progress_helper::FFMPEGProgressParser;
+use crate::converter::filter_helper::{build_intro_filter, build_device_scale_filter, download_device_frames, select_device_scaling, build_blur_fill_filter, build_closed_captions_source, build_concat_filter, compute_crossfade_offsets, build_downmix_filter, build_pip_audio_filter, build_pip_filter, build_silence_trim_filter, build_max_dimensions_filter, has_closed_captions, build_pad_filter, build_progress_bar_filter, build_subtitles_embedded_filter, build_subtitles_file_filter, resolve_subtitle_index};
+use crate::converter::subtitle_helper::{find_cue, parse_subtitle_cues};
 
 
+/// A pass of a two-pass encode at a fixed video bitrate
+#[derive(Clone, Copy)]
+struct EncodePass<'a> {
+    video_bitrate: u64,
+    pass: u8,
+    passlog_file: &'a str
+}
+
+/// Per-command settings of [`FFMPEGConversionService::build_args`] that are computed
+/// while converting instead of being read from the [`ConversionOutput`]
+#[derive(Clone, Copy, Default)]
+struct CommandSettings<'a> {
+    hwaccel_decode: Option<&'a HwAccel>,
+    force_decoder: Option<&'a str>,
+    encode_pass: Option<EncodePass<'a>>,
+    audio_filter: Option<&'a str>,
+    audio_encoder: Option<&'static str>
+}
+
+/// Returns the index of the input the audio is taken from: a shifted audio comes from the
+/// second, offset, opening of the input
+fn get_audio_input(output: &ConversionOutput) -> usize {
+    if output.audio_offset_ms.is_some() { 1 } else { 0 }
+}
+
 pub struct FFMPEGConversionService {
     command_manager: CommandManager
 }
 
 impl FFMPEGConversionService {
-    fn extract_info(&self, file: &String) -> Result<AnalysisOutput, AtiumError> {
+    fn extract_info(&self, file: &str) -> Result<AnalysisOutput, AtiumError> {
         let service = MediaInfoExtractorService::new()?;
-        let request = InfoExtractorRequest {
-            input: file.to_string(),
-            format: Some(InfoFormat::Json),
-            full: None,
-            output_file: None,
-            output_type: Some(InfoOutputType::Plain)
-        };
-
-        let response = service.get_info(request)?
-            .output
-            .content
-            .unwrap_or(String::from(""));
-
 
-        let media_info_loader = MediaInfoJsonLoader{};
-        match media_info_loader.load_json_from_string(&response) {
+        match service.get_analysis_output(file) {
             Ok(output) => {
                 debug!("Analysis done!");
                 Ok(output)
@@ -46,13 +70,13 @@ impl FFMPEGConversionService {
             }
         }
     }
-    fn load_source_file(&self, source: ConversionInput) -> Result<String, &'static str> {
+    fn load_source_file(&self, source: ConversionInput) -> Result<String, AtiumError> {
         match source.source_type {
             InputSourceType::Local => {
-                let uuid = Uuid::new_v4().to_string();
-                let mut new_path = String::from("/tmp/");
-                new_path.push_str(uuid.as_str());
-                new_path.push_str(".mp4");
+                let new_path = temp_dir().join(format!("{}.mp4", Uuid::new_v4()));
+                let new_path = new_path.to_str()
+                    .ok_or(AtiumError::IOError("Could not build temporary path".to_string()))?
+                    .to_string();
 
                 match fs::copy(source.file_name, new_path.clone()) {
                     Ok(_) => {
@@ -61,13 +85,26 @@ impl FFMPEGConversionService {
                     },
                     Err(err) => {
                         error!("Error when trying to copy input file: {}", err);
-                        Err("Error when trying to copy input file")
+                        Err(AtiumError::ConversionError("Error when trying to copy input file".to_string()))
                     }
                 }
             }
+            InputSourceType::Url => {
+                match detect_protocol(&source.file_name) {
+                    InputProtocol::Remote(protocol) => require_input_protocol(&protocol)?,
+                    InputProtocol::Local =>
+                        return Err(AtiumError::InvalidArgument(format!("Input [{}] is not a URL", source.file_name)))
+                }
+                debug!("Reading remote input [{}]", source.file_name);
+                Ok(source.file_name)
+            }
         }
     }
     fn cleanup_tmp_file(&self, tmp_filepath: String) {
+        if let InputProtocol::Remote(_) = detect_protocol(&tmp_filepath) {
+            return
+        }
+
         match fs::remove_file(tmp_filepath) {
             Ok(_) => debug!("Temporary file removed successfully"),
             Err(err) => warn!("Temporary file not removed: {}", err)
@@ -91,24 +128,548 @@ impl FFMPEGConversionService {
 
         Ok((width, height))
     }
-    fn build_args(&self, resolution: OutputResolution, analysis_output: AnalysisOutput, input_file_path: String, output_file: String) -> Result<Vec<String>, AtiumError> {
-        let curr_width = analysis_output.extract_field_from_track(1, &"Width".to_string())?;
-        let curr_height = analysis_output.extract_field_from_track(1, &"Height".to_string())?;
+    /// Returns the `ffmpeg` name of the AAC encoder requested by `output`, after checking
+    /// that `libfdk_aac` is compiled in, see [`pick_aac_encoder`]
+    fn resolve_aac_encoder(&self, output: &ConversionOutput) -> Result<Option<&'static str>, AtiumError> {
+        let Some(requested) = &output.aac_encoder else {
+            return Ok(None)
+        };
+        let fdk_available = *requested == AacEncoder::Fdk && is_encoder_available(AacEncoder::Fdk.name())?;
+        let aac_encoder = pick_aac_encoder(requested, fdk_available, output.strict)?;
+        if aac_encoder != *requested {
+            warn!("Encoder [{}] is not available in ffmpeg, falling back to [{}]", requested.name(), aac_encoder.name());
+        }
+
+        Ok(Some(aac_encoder.name()))
+    }
+    fn probe_frame_rate(&self, analysis_output: &AnalysisOutput) -> Result<String, AtiumError> {
+        let frame_rate = analysis_output.extract_field_from_track(1, "FrameRate")?;
+        validate_frame_rate(&frame_rate)
+            .map_err(|_| AtiumError::ConversionError("Could not detect source frame rate, please provide fps".to_string()))?;
+
+        Ok(frame_rate)
+    }
+    /// Warns about the input streams the output container can't carry as they are. A
+    /// dropped stream is an error instead when the output is strict. Nothing is checked
+    /// when the subtitles are not kept or the streams are mapped by hand
+    fn check_output_streams(&self, output: &ConversionOutput, analysis_output: &AnalysisOutput) -> Result<(), AtiumError> {
+        let keeps_subtitles = output.keep_streams.as_ref()
+            .is_none_or(|stream_types| stream_types.contains(&StreamType::Subtitle));
+        if !keeps_subtitles || output.filter_complex.is_some() {
+            return Ok(())
+        }
+
+        for issue in check_stream_compatibility(&analysis_output.list_tracks(), &detect_container(&output.file)) {
+            match issue.fate {
+                StreamFate::Dropped if output.strict => return Err(AtiumError::InvalidArgument(issue.to_string())),
+                _ => warn!("{}", issue)
+            }
+        }
+
+        Ok(())
+    }
+    fn build_map_args(&self, output: &ConversionOutput, cover_stream: bool, mixed_audio: bool) -> Result<Vec<String>, AtiumError> {
+        let video_source = if output.pip.is_some() || output.intro.is_some() { String::from("[vout]") } else { String::from("0:v?") };
+        let audio_source = if mixed_audio { String::from("[aout]") } else { format!("{}:a?", get_audio_input(output)) };
+        if let Some(filter_graph) = &output.filter_complex {
+            return Ok(filter_graph.maps.iter()
+                .flat_map(|map| [String::from("-map"), map.clone()])
+                .collect())
+        }
+        match &output.keep_streams {
+            Some(stream_types) => build_keep_streams_args(stream_types, &video_source, &audio_source),
+            // an extra input disables the automatic stream selection
+            None if output.audio_offset_ms.is_some() || cover_stream || output.pip.is_some() || output.intro.is_some() =>
+                build_keep_streams_args(&[StreamType::Video, StreamType::Audio], &video_source, &audio_source),
+            None => Ok(Vec::new())
+        }
+    }
+    /// Builds the chain of video filters applied by `-vf`: the anamorphic fix, the scaling,
+    /// then the frame rate, the sample aspect ratio, the subtitles, the reversing and the
+    /// progress bar
+    fn build_video_filters(&self, output: &ConversionOutput, analysis_output: &AnalysisOutput, input_file_path: &str, device_scaling: Option<&HwAccel>) -> Result<Vec<String>, AtiumError> {
+        let scale_filter = match (&output.blur_fill, &output.pad, output.max_dimensions) {
+            (Some(blur_fill), _, _) => {
+                debug!("Filling [{}x{}] with a blurred background", blur_fill.width, blur_fill.height);
+                build_blur_fill_filter(blur_fill)
+            }
+            (None, Some(pad), _) => {
+                debug!("Padding to [{}x{}] with [{}]", pad.width, pad.height, pad.color);
+                build_pad_filter(pad)
+            }
+            (None, None, Some((max_width, max_height))) => {
+                debug!("Fitting within [{}x{}]", max_width, max_height);
+                build_max_dimensions_filter(max_width, max_height)
+            }
+            (None, None, None) => {
+                let curr_width = analysis_output.extract_field_from_track(1, "Width")?;
+                let curr_height = analysis_output.extract_field_from_track(1, "Height")?;
+
+                let (width, height) = self.compute_resolution(output.resolution.clone(), (curr_width, curr_height))?;
+
+                debug!("Requested resolution is [{}x{}]", width, height);
+                match device_scaling.and_then(|hwaccel| build_device_scale_filter(hwaccel, width, height)) {
+                    Some(device_scale_filter) => device_scale_filter,
+                    None => format!("scale={}:{}", width, height)
+                }
+            }
+        };
+
+        let mut filters = Vec::new();
+        if output.anamorphic_fix {
+            let pixel_aspect_ratio = analysis_output.extract_field_from_track(1, "PixelAspectRatio").unwrap_or_default();
+            match build_anamorphic_fix_filter(&pixel_aspect_ratio) {
+                Some(anamorphic_fix_filter) => {
+                    debug!("Stretching pixels with aspect ratio [{}] to square ones", pixel_aspect_ratio);
+                    filters.push(anamorphic_fix_filter);
+                }
+                None => debug!("Source pixels are already square, no anamorphic fix needed")
+            }
+        }
+        filters.push(scale_filter);
+        if let Some(fps) = &output.fps {
+            filters.push(format!("fps={}", fps));
+        }
+        if let Some(sar) = &output.set_sar {
+            filters.push(build_setsar_filter(sar));
+        }
+        match &output.burn_subtitles {
+            Some(SubtitleBurn::File(subtitle_file)) =>
+                filters.push(build_subtitles_file_filter(subtitle_file, output.subtitle_charset.as_deref())),
+            Some(SubtitleBurn::Embedded(track)) => {
+                let stream_index = resolve_subtitle_index(analysis_output, track)?;
+                filters.push(build_subtitles_embedded_filter(input_file_path, stream_index));
+            }
+            None => {}
+        }
+        // Reversing last keeps burned subtitles on the source timeline and buffers scaled frames
+        if output.reverse {
+            filters.push(String::from("reverse"));
+        }
+        // Drawn after reversing so the bar always fills forward
+        if let Some(progress_bar) = &output.progress_bar {
+            let duration = analysis_output.duration_secs();
+            filters.push(build_progress_bar_filter(progress_bar, duration)?);
+        }
+        if device_scaling.is_some() {
+            download_device_frames(&mut filters, output.hardware_encode);
+        }
 
-        let (width, height) = self.compute_resolution(resolution, (curr_width, curr_height))?;
+        Ok(filters)
+    }
+    fn build_args(&self, output: &ConversionOutput, analysis_output: AnalysisOutput, settings: CommandSettings, input_file_path: String, output_file: String) -> Result<Vec<String>, AtiumError> {
+        // a pipe can't be seeked back to write the mp4/mov index at the end
+        let output_is_fifo = is_fifo(&output_file);
+        if output_is_fifo && detect_container(&output_file).is_mov_family() && !output.fragmented {
+            return Err(AtiumError::InvalidArgument(format!("FIFO output [{}] must be fragmented to be written as mp4/mov", output_file)))
+        }
 
-        debug!("Requested resolution is [{}x{}]", width, height);
+        let mut args = Vec::new();
+        // ffmpeg asks before writing to any existing path, FIFOs included
+        if matches!(output.collision_policy, CollisionPolicy::Overwrite) || output_is_fifo {
+            args.push(String::from("-y"));
+        }
 
-        Ok(vec![
+        let device_scaling = select_device_scaling(output, settings.hwaccel_decode);
+        if let Some(hwaccel) = settings.hwaccel_decode {
+            args.push(String::from("-hwaccel"));
+            args.push(hwaccel.name().to_string());
+        }
+        // decoded frames stay on the device for the scaling filter
+        if let Some(hwaccel) = device_scaling {
+            args.push(String::from("-hwaccel_output_format"));
+            args.push(hwaccel.name().to_string());
+        }
+        // -c:v before -i selects the decoder of that input
+        if let Some(decoder) = settings.force_decoder {
+            args.push(String::from("-c:v"));
+            args.push(decoder.to_string());
+        }
+        args.extend(vec![
             String::from("-i"),
+            input_file_path.clone()
+        ]);
+        if let Some(audio_offset_ms) = output.audio_offset_ms {
+            // -itsoffset applies to the next input only, so the input is opened again
+            // and its audio is mapped from there, see build_map_args
+            args.extend(vec![
+                String::from("-itsoffset"),
+                format_offset_secs(audio_offset_ms),
+                String::from("-i"),
+                input_file_path.clone()
+            ]);
+        }
+
+        // mp4/mov store the cover as a second input, the first pass output is never one of them
+        let container = detect_container(&output_file);
+        let cover_stream = output.cover.is_some() && container.is_mov_family();
+        if let (Some(cover), true) = (&output.cover, cover_stream) {
+            args.push(String::from("-i"));
+            args.push(cover.clone());
+        }
+        let pip_input = 1 + usize::from(output.audio_offset_ms.is_some()) + usize::from(cover_stream);
+        if let Some(pip) = &output.pip {
+            args.push(String::from("-i"));
+            args.push(pip.overlay_input.clone());
+        }
+        // the first pass drops the audio, so there is nothing to mix
+        let first_pass = settings.encode_pass.is_some_and(|encode_pass| encode_pass.pass == 1);
+        let intro_audio = output.intro.is_some() && !first_pass && !analysis_output.get_tracks_by_type("Audio").is_empty();
+        if let Some(intro) = &output.intro {
+            args.extend(build_intro_input_args(intro, intro_audio));
+        }
+        let mixed_audio = (output.pip.as_ref().is_some_and(|pip| pip.mix_audio) || intro_audio) && !first_pass;
+
+        // as an output option -t stops reading every input once reached
+        if let Some(sample_duration) = &output.sample_duration {
+            info!("Test mode: converting only the first [{}] of the input", sample_duration);
+            args.push(String::from("-t"));
+            args.push(format_timestamp(parse_timestamp(sample_duration)?));
+        }
+
+        args.extend(self.build_map_args(output, cover_stream, mixed_audio)?);
+
+        match (&output.filter_complex, &output.pip, &output.intro) {
+            (Some(filter_graph), _, _) => {
+                debug!("Running the custom filter graph [{}]", filter_graph.graph);
+                args.push(String::from("-filter_complex"));
+                args.push(filter_graph.graph.clone());
+            }
+            (None, None, Some(intro)) => {
+                debug!("Playing a [{}s] intro first", intro.duration_secs);
+                let filters = self.build_video_filters(output, &analysis_output, &input_file_path, device_scaling)?;
+                // the intro inputs come right after the main one, the silence after the intro video
+                let audio = Some((get_audio_input(output), 2, settings.audio_filter)).filter(|_| intro_audio);
+                args.push(String::from("-filter_complex"));
+                args.push(build_intro_filter(intro, &filters.join(","), 1, audio));
+            }
+            (None, Some(pip), _) => {
+                let filters = self.build_video_filters(output, &analysis_output, &input_file_path, device_scaling)?;
+                let mut graph = vec![build_pip_filter(pip, &filters.join(","), pip_input)];
+                if mixed_audio {
+                    graph.push(build_pip_audio_filter(get_audio_input(output), pip_input, settings.audio_filter));
+                }
+                args.push(String::from("-filter_complex"));
+                args.push(graph.join(";"));
+            }
+            (None, None, None) => {
+                let filters = self.build_video_filters(output, &analysis_output, &input_file_path, device_scaling)?;
+                // the copied cover stream can't be filtered, so only the first video stream is
+                args.push(String::from(if cover_stream { "-filter:v:0" } else { "-vf" }));
+                args.push(filters.join(","));
+            }
+        }
+        // before the cover ones, so clearing the video dispositions keeps its attached_pic
+        if let Some(dispositions) = &output.dispositions {
+            args.extend(build_disposition_args(dispositions));
+        }
+        args.push(String::from("-c:v"));
+        args.push(resolve_encoder(&output.codec, settings.hwaccel_decode.filter(|_| output.hardware_encode))?);
+        if let Some(cover) = &output.cover {
+            if cover_stream || container == OutputContainer::Mkv {
+                let cover_input = if output.audio_offset_ms.is_some() { 2 } else { 1 };
+                args.extend(build_cover_args(cover, &container, cover_input)?);
+            }
+        }
+
+        if let Some(encode_pass) = settings.encode_pass {
+            args.extend(vec![
+                String::from("-b:v"),
+                encode_pass.video_bitrate.to_string(),
+                String::from("-pass"),
+                encode_pass.pass.to_string(),
+                String::from("-passlogfile"),
+                encode_pass.passlog_file.to_string()
+            ]);
+        }
+
+        if let Some(b_frames) = output.b_frames {
+            args.push(String::from("-bf"));
+            args.push(b_frames.to_string());
+        }
+        if let Some(ref_frames) = output.ref_frames {
+            args.push(String::from("-refs"));
+            args.push(ref_frames.to_string());
+        }
+        if let Some(x265_params) = &output.x265_params {
+            args.push(String::from("-x265-params"));
+            args.push(build_x265_params(x265_params));
+        }
+        if let Some(force_keyframes) = &output.force_keyframes {
+            args.push(String::from("-force_key_frames"));
+            args.push(force_keyframes.clone());
+        }
+        if let Some(frame_limit) = output.frame_limit {
+            // -frames:v only ends the video stream, the audio would keep going without -shortest
+            args.extend(vec![
+                String::from("-frames:v"),
+                frame_limit.to_string(),
+                String::from("-shortest")
+            ]);
+        }
+
+        if let Some(bit_depth) = &output.bit_depth {
+            args.push(String::from("-pix_fmt"));
+            args.push(bit_depth.pixel_format().to_string());
+            if let Some(profile) = get_bit_depth_profile(&output.codec, bit_depth)? {
+                args.push(String::from("-profile:v"));
+                args.push(profile.to_string());
+            }
+        }
+
+        if let Some(aspect_ratio) = &output.aspect_ratio {
+            args.push(String::from("-aspect"));
+            args.push(aspect_ratio.clone());
+        }
+
+        if output.force_cfr {
+            let frame_rate = match &output.fps {
+                Some(fps) => fps.clone(),
+                None => self.probe_frame_rate(&analysis_output)?
+            };
+            debug!("Forcing constant frame rate at [{}] fps", frame_rate);
+
+            args.push(String::from("-vsync"));
+            args.push(String::from("cfr"));
+            args.push(String::from("-r"));
+            args.push(frame_rate);
+        }
+
+        if let (Some(maxrate), Some(bufsize)) = (&output.maxrate, &output.bufsize) {
+            args.push(String::from("-maxrate"));
+            args.push(maxrate.clone());
+            args.push(String::from("-bufsize"));
+            args.push(bufsize.clone());
+        }
+
+        // an output option, so it applies to every stream of the output
+        if let Some(muxing_queue_size) = output.muxing_queue_size {
+            args.push(String::from("-max_muxing_queue_size"));
+            args.push(muxing_queue_size.to_string());
+        }
+
+        if let Some(audio_encoder) = settings.audio_encoder {
+            args.push(String::from("-c:a"));
+            args.push(audio_encoder.to_string());
+        }
+
+        // a mixed audio is already filtered in the filter graph
+        if let (Some(audio_filter), false) = (settings.audio_filter, mixed_audio) {
+            args.push(String::from("-af"));
+            args.push(audio_filter.to_string());
+        }
+
+        if output.strip_metadata {
+            args.push(String::from("-map_metadata"));
+            args.push(String::from("-1"));
+        }
+        if let Some(metadata) = &output.metadata {
+            args.extend(build_metadata_args(metadata)?);
+        }
+        if let Some(stream_metadata) = &output.stream_metadata {
+            args.extend(build_stream_metadata_args(stream_metadata)?);
+        }
+
+        if output.web_optimized {
+            if detect_container(&output_file).is_mov_family() {
+                args.push(String::from("-movflags"));
+                args.push(String::from("+faststart"));
+            } else {
+                warn!("Web optimization is only available for mp4/mov outputs, ignoring it");
+            }
+        }
+        if output.fragmented {
+            if detect_container(&output_file).is_mov_family() {
+                args.push(String::from("-movflags"));
+                args.push(String::from("frag_keyframe+empty_moov+default_base_moof"));
+            } else {
+                warn!("Fragmentation is only available for mp4/mov outputs, ignoring it");
+            }
+        }
+        if let Some(video_timebase) = &output.video_timebase {
+            if detect_container(&output_file).is_mov_family() {
+                args.push(String::from("-video_track_timescale"));
+                args.push(video_timebase.clone());
+            } else {
+                warn!("The video timebase is only available for mp4/mov outputs, ignoring it");
+            }
+        }
+
+        args.push(String::from("-progress"));
+        args.push(String::from("pipe:1"));
+
+        args.push(output_file);
+
+        Ok(args)
+    }
+    fn compute_target_bitrate(&self, output: &ConversionOutput, analysis_output: &AnalysisOutput) -> Result<Option<u64>, AtiumError> {
+        let target_size = match &output.target_size {
+            Some(target_size) => parse_size(target_size)?,
+            None => return Ok(None)
+        };
+        let duration = analysis_output.duration_secs();
+        let keeps_audio = output.keep_streams.as_ref()
+            .map(|stream_types| stream_types.contains(&StreamType::Audio))
+            .unwrap_or(true);
+        let audio_bitrate = if keeps_audio && !analysis_output.get_tracks_by_type("Audio").is_empty() {
+            TARGET_SIZE_AUDIO_BITRATE
+        } else {
+            0
+        };
+
+        let video_bitrate = compute_target_video_bitrate(target_size, duration, audio_bitrate)?;
+        debug!("Target size of [{}] bytes requires a video bitrate of [{}]", target_size, video_bitrate);
+
+        Ok(Some(video_bitrate))
+    }
+    fn build_first_pass_args(&self, output: &ConversionOutput, analysis_output: AnalysisOutput, settings: CommandSettings, input_file_path: String) -> Result<Vec<String>, AtiumError> {
+        // audio is dropped from the first pass, so its filter is too
+        let settings = CommandSettings { audio_filter: None, ..settings };
+        let mut args = self.build_args(output, analysis_output, settings, input_file_path, String::from("-"))?;
+        // The first pass only writes the stats file, the output is discarded
+        args.pop();
+        args.extend(vec![
+            String::from("-an"),
+            String::from("-f"),
+            String::from("null"),
+            String::from("-")
+        ]);
+
+        Ok(args)
+    }
+    fn run_first_pass(&self, output: &ConversionOutput, analysis_output: AnalysisOutput, settings: CommandSettings, input_file_path: String) -> Result<(), AtiumError> {
+        let args = self.build_first_pass_args(output, analysis_output, settings, input_file_path)?;
+
+        debug!("Running first pass at [{}] bps", settings.encode_pass.map(|pass| pass.video_bitrate).unwrap_or(0));
+        let outcome = self.command_manager.execute_with_usage(args.iter().map(AsRef::as_ref).collect(), &mut |_| {})?;
+        if !outcome.output.status.success() {
+            return Err(self.encode_failure(output, outcome.output.stderr))
+        }
+
+        Ok(())
+    }
+    /// Turns the stderr of a failed encode into its error: a [`AtiumError::HardwareError`]
+    /// when a hardware encode failed because of the device, which can be retried in software
+    fn encode_failure(&self, output: &ConversionOutput, stderr: Vec<u8>) -> AtiumError {
+        if output.hardware_encode && is_hwaccel_failure(&String::from_utf8_lossy(&stderr)) {
+            return AtiumError::HardwareError("Hardware encoder failed".to_string())
+        }
+        if let Err(err) = self.command_manager.print_command_output(stderr) {
+            return err
+        }
+
+        AtiumError::ConversionError("Execution of command returned ERROR".to_string())
+    }
+    /// Returns the audio filters of the request, if any: the downmix `pan`, the silence trim, `areverse` and the
+    /// `loudnorm` filter of the normalization preset. When `measure` is set a first loudness
+    /// pass is run so the filter applies a linear gain
+    fn compute_audio_filter(&self, output: &ConversionOutput, analysis_output: &AnalysisOutput, input_file_path: &str, measure: bool) -> Result<Option<String>, AtiumError> {
+        let has_audio = !analysis_output.get_tracks_by_type("Audio").is_empty();
+        let mut filters = Vec::new();
+        if let Some(downmix) = &output.audio_downmix {
+            let channels = analysis_output.list_tracks().audio.first()
+                .and_then(|track| track.channels)
+                .ok_or(AtiumError::InvalidArgument(format!("Input file [{}] has no audio stream to downmix", input_file_path)))?;
+            filters.push(build_downmix_filter(downmix, channels)?);
+        }
+        if let Some(trim) = &output.trim_silence {
+            if !has_audio {
+                return Err(AtiumError::InvalidArgument(format!("Input file [{}] has no audio stream to trim", input_file_path)))
+            }
+            filters.push(build_silence_trim_filter(trim));
+        }
+        if output.reverse && has_audio {
+            filters.push(String::from("areverse"));
+        }
+
+        if let Some(preset) = &output.normalize_preset {
+            if !has_audio {
+                return Err(AtiumError::InvalidArgument(format!("Input file [{}] has no audio stream to normalize", input_file_path)))
+            }
+
+            let target = preset.target();
+            // the source is measured before the downmix, which changes its loudness
+            let measured = if measure && output.audio_downmix.is_none() {
+                debug!("Measuring loudness for the [{:?}] preset", preset);
+                Some(FFMPEGMeasurementService::new()?.measure_loudness(input_file_path, &target)?)
+            } else {
+                None
+            };
+            filters.push(build_loudnorm_filter(&target, measured.as_ref()));
+        }
+
+        if filters.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(filters.join(",")))
+        }
+    }
+    /// Warns when a reversed input lasts long enough to risk running out of memory, since
+    /// the reverse filters buffer every decoded frame before writing the first one
+    fn warn_long_reverse(&self, analysis_output: &AnalysisOutput) {
+        let duration = analysis_output.duration_secs();
+        if duration > REVERSE_WARNING_DURATION_SECS {
+            warn!("Reversing [{:.0}s] of media buffers all of it in memory, consider trimming the input first", duration);
+        }
+    }
+    /// Returns the `ffmpeg` arguments of each command a conversion of `conversion_request`
+    /// would run, in order, without running them. Two commands are returned for two-pass
+    /// encodes. The input is probed but, unlike [`FFMPEGConversionService::convert`], it is
+    /// referenced directly instead of through a temporary copy, and loudness normalization
+    /// is shown with its targets only since it is not measured
+    pub fn explain(&self, conversion_request: &ConversionRequest) -> Result<Vec<Vec<String>>, AtiumError> {
+        validate_conversion_output(&conversion_request.output)?;
+
+        let analysis_output = self.extract_info(&conversion_request.input.file_name)?;
+        self.explain_with_analysis(conversion_request, analysis_output)
+    }
+    fn explain_with_analysis(&self, conversion_request: &ConversionRequest, analysis_output: AnalysisOutput) -> Result<Vec<Vec<String>>, AtiumError> {
+        let input_file_path = conversion_request.input.file_name.clone();
+        let hwaccel_decode = conversion_request.input.hwaccel_decode.as_ref();
+        self.check_output_streams(&conversion_request.output, &analysis_output)?;
+        let output_file = compute_output_file(
+            &conversion_request.output.file,
+            "mp4",
+            conversion_request.output.collision_policy.clone())?;
+
+        let audio_filter = self.compute_audio_filter(&conversion_request.output, &analysis_output, &input_file_path, false)?;
+
+        let passlog_file = temp_dir().join(Uuid::new_v4().to_string()).to_string_lossy().to_string();
+        let second_pass = self.compute_target_bitrate(&conversion_request.output, &analysis_output)?
+            .map(|video_bitrate| EncodePass { video_bitrate, pass: 2, passlog_file: &passlog_file });
+        let settings = CommandSettings {
+            hwaccel_decode,
+            force_decoder: conversion_request.input.force_decoder.as_deref(),
+            encode_pass: second_pass,
+            audio_filter: audio_filter.as_deref(),
+            audio_encoder: self.resolve_aac_encoder(&conversion_request.output)?
+        };
+
+        let mut commands = Vec::new();
+        if let Some(second_pass) = second_pass {
+            let first_pass = EncodePass { pass: 1, ..second_pass };
+            commands.push(self.build_first_pass_args(
+                &conversion_request.output,
+                analysis_output.clone(),
+                CommandSettings { encode_pass: Some(first_pass), ..settings },
+                input_file_path.clone())?);
+        }
+        commands.push(self.build_args(
+            &conversion_request.output,
+            analysis_output,
+            settings,
             input_file_path,
-            String::from("-vf"),
-            format!("scale={}:{}", width, height),
-            output_file
-        ])
+            output_file)?);
+
+        Ok(commands)
+    }
+    fn cleanup_passlog_files(&self, passlog_file: &str, codec: &OutputCodec) {
+        for suffix in get_passlog_suffixes(codec) {
+            let _ = fs::remove_file(format!("{}{}", passlog_file, suffix));
+        }
     }
     fn extract_thumbnail(&self, thumbnail_request: Option<ThumbnailRequest>, video_file: String, analysis_output: AnalysisOutput) -> Option<ThumbnailResponse> {
-        return match thumbnail_request {
+        match thumbnail_request {
             None => {
                 debug!("Thumbnail extraction not requested");
                 None
@@ -116,9 +677,10 @@ impl FFMPEGConversionService {
             Some(req) => {
                 match FFMPEGThumbnailService::new() {
                     Ok(service) => {
-                        let duration = analysis_output
-                            .extract_field_from_track(0, &"Duration".to_string())
-                            .unwrap_or("1.0".to_string());
+                        // an unknown duration is left to the thumbnail service, which probes it
+                        let duration = Some(analysis_output.duration_secs())
+                            .filter(|duration| *duration > 0.0)
+                            .map(|duration| duration.to_string());
                         let input_file =
                         if req.input_file.is_none() {
                                 Some(video_file)
@@ -129,7 +691,8 @@ impl FFMPEGConversionService {
                             timestamp: req.timestamp,
                             input_file,
                             output_file: req.output_file,
-                            input_duration: Some(duration)
+                            input_duration: duration,
+                            ..req
                         };
 
                         match service.extract_thumbnail(request) {
@@ -147,35 +710,232 @@ impl FFMPEGConversionService {
     }
     /// Converts a media info and returns a [`ConversionResponse`]
     pub fn convert(&self, conversion_request: ConversionRequest) -> Result<ConversionResponse, AtiumError> {
-        let input_file_path = self.load_source_file(conversion_request.input)
-            .map_err(|err_msg| AtiumError::ConversionError(err_msg.to_string()))?;
+        self.convert_with_progress(conversion_request, |_| {})
+    }
+    /// Converts a media info like [`FFMPEGConversionService::convert`], calling `on_progress`
+    /// for each progress tick reported by `ffmpeg`
+    /// When the hardware encode fails and a `fallback_codec` is set, the whole conversion is
+    /// run again with the software encoder of that codec
+    pub fn convert_with_progress<F: FnMut(ConversionProgress)>(&self, conversion_request: ConversionRequest, mut on_progress: F) -> Result<ConversionResponse, AtiumError> {
+        let fallback_request = conversion_request.output.fallback_codec.clone()
+            .map(|fallback_codec| {
+                let mut fallback_request = conversion_request.clone();
+                // the device is the culprit, so it is left out of the decoding too
+                fallback_request.input.hwaccel_decode = None;
+                fallback_request.output.hardware_encode = false;
+                fallback_request.output.fallback_codec = None;
+                fallback_request.output.codec = fallback_codec;
+                fallback_request
+            });
 
-        let analysis_output = self.extract_info(&input_file_path)?;
+        match (self.run_conversion(conversion_request, &mut on_progress), fallback_request) {
+            (Err(AtiumError::HardwareError(msg)), Some(fallback_request)) => {
+                warn!("{}, converting again with [{}]", msg, get_encoder_name(fallback_request.output.codec.clone()));
+                self.run_conversion(fallback_request, &mut on_progress)
+            }
+            (result, _) => result
+        }
+    }
+    fn run_conversion<F: FnMut(ConversionProgress)>(&self, conversion_request: ConversionRequest, on_progress: &mut F) -> Result<ConversionResponse, AtiumError> {
+        validate_conversion_output(&conversion_request.output)?;
+        let hardware_encoder = conversion_request.input.hwaccel_decode.as_ref()
+            .filter(|_| conversion_request.output.hardware_encode);
+        require_encoder(&resolve_encoder(&conversion_request.output.codec, hardware_encoder)?)?;
+        if conversion_request.output.skip_existing && is_complete_output(&conversion_request.output.file, |file| self.extract_info(file)) {
+            info!("Output [{}] already exists, skipping conversion", conversion_request.output.file);
+            return Ok(ConversionResponse {
+                output_file: conversion_request.output.file,
+                thumbnail_response: None,
+                resource_usage: ResourceUsage { max_rss_kb: None, user_cpu_time: None },
+                skipped: true,
+                report_file: None
+            })
+        }
+        let started = Instant::now();
+        // serialized before the request is consumed by the conversion
+        let request_json = if conversion_request.output.write_report {
+            Some(serde_json::to_value(&conversion_request)
+                .map_err(|err| AtiumError::IOError(format!("Could not serialize request: {}", err)))?)
+        } else {
+            None
+        };
+        let hwaccel_decode = conversion_request.input.hwaccel_decode.clone();
+        if let Some(hwaccel) = &hwaccel_decode {
+            require_hwaccel(hwaccel.name())?;
+        }
+        let force_decoder = conversion_request.input.force_decoder.clone();
+        if let Some(decoder) = &force_decoder {
+            require_decoder(decoder)?;
+        }
+
+        // the input is read through a temporary copy, so its path is kept for the mtime
+        let source_file = match conversion_request.input.source_type {
+            InputSourceType::Local => Some(conversion_request.input.file_name.clone()),
+            InputSourceType::Url => None
+        };
+        let input_file_path = self.load_source_file(conversion_request.input)?;
 
-        let output_file = compute_output_file(&conversion_request.output.file, "mp4")?;
-        let built_args = self.build_args(
-            conversion_request.output.resolution,
+        let analysis_output = match self.extract_info(&input_file_path) {
+            Ok(analysis_output) => analysis_output,
+            Err(err) => {
+                self.cleanup_tmp_file(input_file_path);
+                return Err(err)
+            }
+        };
+
+        let output_file = match compute_output_file(
+            &conversion_request.output.file,
+            "mp4",
+            conversion_request.output.collision_policy.clone()) {
+            Ok(output_file) => output_file,
+            Err(err) => {
+                self.cleanup_tmp_file(input_file_path);
+                return Err(err)
+            }
+        };
+
+        if conversion_request.output.reverse {
+            self.warn_long_reverse(&analysis_output);
+        }
+        if analysis_output.is_hdr() {
+            warn!("Input [{}] is HDR and is not tonemapped, colors will look washed out on SDR displays", input_file_path);
+        }
+        if let Err(err) = self.check_output_streams(&conversion_request.output, &analysis_output) {
+            self.cleanup_tmp_file(input_file_path);
+            return Err(err)
+        }
+        let audio_filter = match self.compute_audio_filter(&conversion_request.output, &analysis_output, &input_file_path, true) {
+            Ok(audio_filter) => audio_filter,
+            Err(err) => {
+                self.cleanup_tmp_file(input_file_path);
+                return Err(err)
+            }
+        };
+
+        let passlog_file = temp_dir().join(Uuid::new_v4().to_string()).to_string_lossy().to_string();
+        let second_pass = match self.compute_target_bitrate(&conversion_request.output, &analysis_output) {
+            Ok(video_bitrate) => video_bitrate
+                .map(|video_bitrate| EncodePass { video_bitrate, pass: 2, passlog_file: &passlog_file }),
+            Err(err) => {
+                self.cleanup_tmp_file(input_file_path);
+                return Err(err)
+            }
+        };
+        let audio_encoder = match self.resolve_aac_encoder(&conversion_request.output) {
+            Ok(audio_encoder) => audio_encoder,
+            Err(err) => {
+                self.cleanup_tmp_file(input_file_path);
+                return Err(err)
+            }
+        };
+        let settings = CommandSettings {
+            hwaccel_decode: hwaccel_decode.as_ref(),
+            force_decoder: force_decoder.as_deref(),
+            encode_pass: second_pass,
+            audio_filter: audio_filter.as_deref(),
+            audio_encoder
+        };
+        if let Some(second_pass) = second_pass {
+            let first_pass = EncodePass { pass: 1, ..second_pass };
+            if let Err(err) = self.run_first_pass(
+                &conversion_request.output,
+                analysis_output.clone(),
+                CommandSettings { encode_pass: Some(first_pass), ..settings },
+                input_file_path.clone()) {
+                self.cleanup_passlog_files(&passlog_file, &conversion_request.output.codec);
+                self.cleanup_tmp_file(input_file_path);
+                return Err(err)
+            }
+        }
+
+        let built_args = match self.build_args(
+            &conversion_request.output,
             analysis_output.clone(),
+            settings,
             input_file_path.clone(),
-            output_file.clone())
-            .map_err(|err_msg| AtiumError::ConversionError(err_msg.to_string()))?;
+            output_file.clone()) {
+            Ok(built_args) => built_args,
+            Err(err) => {
+                self.cleanup_tmp_file(input_file_path);
+                return Err(AtiumError::ConversionError(err.to_string()))
+            }
+        };
 
         debug!("Converting file at path [{}]", input_file_path);
 
-        match self.command_manager.execute_with_args(built_args.iter().map(AsRef::as_ref).collect()) {
-            Ok(result) => {
-                if !result.status.success() {
-                    self.command_manager.print_command_output(result.stderr)?;
-                    return Err(AtiumError::ConversionError("Execution of command returned ERROR".to_string()))
+        let total_duration = analysis_output.duration_secs();
+        if total_duration <= 0.0 {
+            debug!("Input duration is unknown, progress is reported without percentage and ETA");
+        }
+        let mut progress_parser = FFMPEGProgressParser::new(total_duration);
+        let mut on_stdout_line = |line: &str| {
+            if let Some(progress) = progress_parser.feed_line(line) {
+                on_progress(progress);
+            }
+        };
+
+        // a partial output of a failed hardware encode is removed only if this run created it
+        let output_existed = Path::new(&output_file).exists() || is_fifo(&output_file);
+        let result = self.command_manager.execute_with_usage(built_args.iter().map(AsRef::as_ref).collect(), &mut on_stdout_line);
+        if second_pass.is_some() {
+            self.cleanup_passlog_files(&passlog_file, &conversion_request.output.codec);
+        }
+
+        match result {
+            Ok(outcome) => {
+                if !outcome.output.status.success() {
+                    let err = self.encode_failure(&conversion_request.output, outcome.output.stderr);
+                    self.cleanup_tmp_file(input_file_path);
+                    if let AtiumError::HardwareError(_) = err {
+                        if !output_existed {
+                            let _ = fs::remove_file(&output_file);
+                        }
+                    }
+                    return Err(err)
                 }
 
                 self.cleanup_tmp_file(input_file_path);
 
                 debug!("Conversion done!");
 
+                // a FIFO has no content left to probe once written, so nothing is read back from it
+                let output_is_fifo = is_fifo(&output_file);
+                if conversion_request.output.preserve_mtime {
+                    match &source_file {
+                        _ if output_is_fifo => warn!("Modification time can't be set on a FIFO output, ignoring it"),
+                        Some(source_file) => copy_modified_time(source_file, &output_file)?,
+                        None => warn!("Modification time can't be preserved for URL inputs, ignoring it")
+                    }
+                }
+
+                let report_file = match request_json {
+                    Some(request) => {
+                        let report = ConversionReport {
+                            atium_version: env!("CARGO_PKG_VERSION").to_string(),
+                            request,
+                            command: built_args,
+                            elapsed_secs: started.elapsed().as_secs_f64(),
+                            input_summary: analysis_output.summary(),
+                            output_summary: Some(&output_file)
+                                .filter(|_| !output_is_fifo)
+                                .and_then(|output_file| self.extract_info(output_file).ok())
+                                .map(|analysis| analysis.summary())
+                        };
+                        let report_file = build_report_file(&output_file);
+                        write_conversion_report(&report, &report_file)?;
+                        Some(report_file)
+                    }
+                    None => None
+                };
+
+                let thumbnail_request = conversion_request.output.thumbnail_request
+                    .filter(|request| !output_is_fifo || request.input_file.is_some());
                 Ok(ConversionResponse {
                     output_file: output_file.clone(),
-                    thumbnail_response: self.extract_thumbnail(conversion_request.output.thumbnail_request, output_file, analysis_output)
+                    thumbnail_response: self.extract_thumbnail(thumbnail_request, output_file, analysis_output),
+                    resource_usage: outcome.resource_usage,
+                    skipped: false,
+                    report_file
                 })
             }
             Err(_) => {
@@ -184,6 +944,627 @@ impl FFMPEGConversionService {
             }
         }
     }
+    fn execute_checked(&self, args: &[String]) -> Result<CommandOutcome, AtiumError> {
+        let outcome = self.command_manager.execute_with_usage(args.iter().map(AsRef::as_ref).collect(), &mut |_| {})?;
+        if !outcome.output.status.success() {
+            self.command_manager.print_command_output(outcome.output.stderr)?;
+            return Err(AtiumError::ConversionError("Execution of command returned ERROR".to_string()))
+        }
+
+        Ok(outcome)
+    }
+    fn validate_input_exists(&self, input_file: &str) -> Result<(), AtiumError> {
+        if !Path::new(input_file).exists() {
+            return Err(AtiumError::IOError(format!("Input file [{}] not found", input_file)))
+        }
+
+        Ok(())
+    }
+    /// Replaces the audio of a video with an external track and returns a [`ConversionResponse`].
+    /// The video stream is copied, while the audio is copied or encoded with `audio_codec`
+    pub fn replace_audio(&self, request: AudioReplacementRequest) -> Result<ConversionResponse, AtiumError> {
+        self.validate_input_exists(&request.video_input)?;
+        self.validate_input_exists(&request.audio_input)?;
+        if let Some(audio_codec) = &request.audio_codec {
+            require_encoder(audio_codec)?;
+        }
+
+        let output_file = compute_output_file(&request.output_file, "mp4", request.collision_policy.clone())?;
+
+        let args = build_replace_audio_args(&request, &output_file);
+        let outcome = self.execute_checked(&args)?;
+        debug!("Audio replaced in [{}]", output_file);
+
+        Ok(ConversionResponse {
+            output_file,
+            thumbnail_response: None,
+            resource_usage: outcome.resource_usage,
+            skipped: false,
+            report_file: None
+        })
+    }
+    fn probe_stream_format(&self, input_file: &str, track_type: &str) -> Result<String, AtiumError> {
+        let analysis_output = self.extract_info(input_file)?;
+
+        analysis_output.get_tracks_by_type(track_type)
+            .first()
+            .and_then(|track| analysis_output.get_track_field(track, "Format"))
+            .ok_or(AtiumError::InvalidArgument(format!("Input file [{}] has no {} stream", input_file, track_type.to_lowercase())))
+    }
+    /// Muxes a subtitle file into a stream copy of the input as its last track and returns a
+    /// [`ConversionResponse`]. The subtitles are converted to the format of the output container,
+    /// input subtitles it can't carry, like PGS in a mp4, are left out with a warning
+    pub fn mux_subtitles(&self, request: SubtitleMuxRequest) -> Result<ConversionResponse, AtiumError> {
+        self.validate_input_exists(&request.subtitle_file)?;
+        let subtitle_codec = get_subtitle_codec(&detect_container(&request.output_file))?;
+        let output_file = compute_output_file(&request.output_file, "mkv", request.collision_policy.clone())?;
+        let tracks = self.extract_info(&request.input_file)?.list_tracks();
+        for issue in check_stream_compatibility(&tracks, &detect_container(&output_file)) {
+            warn!("{}", issue);
+        }
+
+        let args = build_subtitle_mux_args(&request, &tracks, subtitle_codec, &output_file);
+        let outcome = self.execute_checked(&args)?;
+        debug!("Subtitles muxed in [{}]", output_file);
+
+        Ok(ConversionResponse {
+            output_file,
+            thumbnail_response: None,
+            resource_usage: outcome.resource_usage,
+            skipped: false,
+            report_file: None
+        })
+    }
+    /// Converts a request burning a subtitle file and also writes `soft_output`, a stream copy
+    /// of the input carrying the same subtitles as a selectable track. The soft copy runs first
+    /// since it is cheap, so a broken subtitle file fails before the encode
+    pub fn convert_with_soft_subtitles<F: FnMut(ConversionProgress)>(&self, conversion_request: ConversionRequest, soft_output: &str, on_progress: F) -> Result<SubtitleCopiesResponse, AtiumError> {
+        let subtitle_file = match &conversion_request.output.burn_subtitles {
+            Some(SubtitleBurn::File(subtitle_file)) => subtitle_file.clone(),
+            _ => return Err(AtiumError::InvalidArgument("A soft subtitled copy requires burning a subtitle file".to_string()))
+        };
+        validate_conversion_output(&conversion_request.output)?;
+
+        let soft = self.mux_subtitles(SubtitleMuxRequest {
+            input_file: conversion_request.input.file_name.clone(),
+            subtitle_file,
+            output_file: soft_output.to_string(),
+            collision_policy: conversion_request.output.collision_policy.clone()
+        })?;
+        let burned = self.convert_with_progress(conversion_request, on_progress)?;
+
+        Ok(SubtitleCopiesResponse { burned, soft })
+    }
+    /// Makes a GIF spanning the subtitle cue picked by the request, with the subtitles burned
+    /// in, and returns a [`ConversionResponse`]. The input is seeked to the cue start, so the
+    /// frame timestamps are shifted back while burning to stay on the subtitle timeline
+    pub fn extract_cue_gif(&self, request: CueGifRequest) -> Result<ConversionResponse, AtiumError> {
+        self.validate_input_exists(&request.input_file)?;
+        if request.width == 0 || request.fps == 0 {
+            return Err(AtiumError::InvalidArgument("GIF width and fps must be greater than 0".to_string()))
+        }
+        let content = fs::read_to_string(&request.subtitle_file)
+            .map_err(|err| AtiumError::IOError(format!("Could not read subtitles [{}]: {}", request.subtitle_file, err)))?;
+        let cues = parse_subtitle_cues(&content);
+        let cue = find_cue(&cues, &request.cue)?;
+        debug!("Cue [{}] spans [{:.3}s, {:.3}s]", cue.index, cue.start_secs, cue.end_secs);
+        let duration_secs = cue.end_secs - cue.start_secs;
+        if duration_secs <= 0.0 {
+            return Err(AtiumError::InvalidArgument(format!("Subtitle cue [{}] has no duration", cue.index)))
+        }
+
+        let output_file = compute_output_file(&request.output_file, "gif", request.collision_policy.clone())?;
+        let filter = format!(
+            "setpts=PTS+{start}/TB,{subtitles},setpts=PTS-STARTPTS,fps={fps},scale={width}:-1:flags=lanczos,split[frames][palette_input];[palette_input]palettegen[palette];[frames][palette]paletteuse",
+            start = cue.start_secs,
+            subtitles = build_subtitles_file_filter(&request.subtitle_file, None),
+            fps = request.fps,
+            width = request.width);
+
+        let mut args = Vec::new();
+        if let CollisionPolicy::Overwrite = request.collision_policy {
+            args.push(String::from("-y"));
+        }
+        args.extend(vec![
+            String::from("-ss"),
+            format!("{:.3}", cue.start_secs),
+            String::from("-t"),
+            format!("{:.3}", duration_secs),
+            String::from("-i"),
+            request.input_file,
+            String::from("-vf"),
+            filter,
+            String::from("-an"),
+            String::from("-loop"),
+            String::from("0"),
+            output_file.clone()
+        ]);
+
+        let outcome = self.execute_checked(&args)?;
+        info!("GIF of cue [{}] written to [{}]", cue.index, output_file);
+
+        Ok(ConversionResponse {
+            output_file,
+            thumbnail_response: None,
+            resource_usage: outcome.resource_usage,
+            skipped: false,
+            report_file: None
+        })
+    }
+    /// Re-encodes the audio of a lossless file with another sample format and returns a
+    /// [`ConversionResponse`]. The codec and every other stream are kept as they are
+    pub fn convert_sample_format(&self, request: SampleFormatRequest) -> Result<ConversionResponse, AtiumError> {
+        self.validate_input_exists(&request.input_file)?;
+        let analysis_output = self.extract_info(&request.input_file)?;
+        let audio_track = analysis_output.get_tracks_by_type("Audio")
+            .first()
+            .copied()
+            .ok_or(AtiumError::InvalidArgument(format!("Input file [{}] has no audio stream", request.input_file)))?;
+        let audio_format = analysis_output.get_track_field(audio_track, "Format").unwrap_or_default();
+        let source_bit_depth = analysis_output.get_track_field(audio_track, "BitDepth")
+            .and_then(|bit_depth| bit_depth.parse::<u32>().ok());
+        let encoder = resolve_sample_format_encoder(&audio_format, &request.sample_format)?;
+        require_encoder(&encoder)?;
+        debug!("Converting [{}] audio to [{}] with [{}]", audio_format, request.sample_format, encoder);
+        let dither = request.dither.filter(|dither| {
+            let reduction = is_bit_depth_reduction(source_bit_depth, &request.sample_format);
+            if !reduction {
+                warn!("Dither [{}] only applies when reducing the bit depth, ignoring it", dither.method());
+            }
+            reduction
+        });
+
+        let extension = Path::new(&request.input_file).extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("mka")
+            .to_string();
+        let output_file = compute_output_file(&request.output_file, &extension, request.collision_policy.clone())?;
+
+        let mut args = Vec::new();
+        if let CollisionPolicy::Overwrite = request.collision_policy {
+            args.push(String::from("-y"));
+        }
+        args.extend(vec![
+            String::from("-i"),
+            request.input_file,
+            String::from("-map"),
+            String::from("0"),
+            String::from("-c"),
+            String::from("copy"),
+            String::from("-c:a"),
+            encoder,
+            String::from("-sample_fmt"),
+            request.sample_format
+        ]);
+        if let Some(dither) = dither {
+            args.push(String::from("-dither_method"));
+            args.push(dither.method().to_string());
+        }
+        args.push(output_file.clone());
+
+        let outcome = self.execute_checked(&args)?;
+        debug!("Sample format converted in [{}]", output_file);
+
+        Ok(ConversionResponse {
+            output_file,
+            thumbnail_response: None,
+            resource_usage: outcome.resource_usage,
+            skipped: false,
+            report_file: None
+        })
+    }
+    /// Muxes the first video stream of `video_input` and the first audio stream of
+    /// `audio_input` into `output_file` by stream copy, e.g. to merge the separate files
+    /// downloaded by `yt-dlp`. Fails before running `ffmpeg` when a stream cannot be
+    /// stored in the output container without re-encoding
+    pub fn mux(&self, video_input: &str, audio_input: &str, output_file: &str, collision_policy: CollisionPolicy) -> Result<ConversionResponse, AtiumError> {
+        self.validate_input_exists(video_input)?;
+        self.validate_input_exists(audio_input)?;
+
+        let video_format = self.probe_stream_format(video_input, "Video")?;
+        let audio_format = self.probe_stream_format(audio_input, "Audio")?;
+        debug!("Muxing [{}] video with [{}] audio", video_format, audio_format);
+        validate_stream_copy(&video_format, &audio_format, output_file)?;
+
+        self.replace_audio(AudioReplacementRequest {
+            video_input: video_input.to_string(),
+            audio_input: audio_input.to_string(),
+            output_file: output_file.to_string(),
+            audio_codec: None,
+            shortest: false,
+            collision_policy
+        })
+    }
+    /// Encodes every rendition of the ladder from a single decode of the input: the video
+    /// is split and scaled once per rendition in a `filter_complex` graph, and each scaled
+    /// copy goes to its own output along with the first input audio
+    pub fn convert_ladder(&self, request: LadderRequest) -> Result<LadderResponse, AtiumError> {
+        self.validate_input_exists(&request.input_file)?;
+        if request.renditions.is_empty() {
+            return Err(AtiumError::InvalidArgument("At least one rendition is required".to_string()))
+        }
+        for rendition in &request.renditions {
+            validate_rendition(rendition)?;
+        }
+        require_encoder(&get_encoder_name(OutputCodec::H264))?;
+
+        let output_files = request.renditions.iter()
+            .map(|rendition| build_rendition_output(&request.output_file, rendition)
+                .and_then(|output_file| compute_output_file(&output_file, "mp4", request.collision_policy.clone())))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut args = Vec::new();
+        if let CollisionPolicy::Overwrite = request.collision_policy {
+            args.push(String::from("-y"));
+        }
+        args.extend(vec![
+            String::from("-i"),
+            request.input_file,
+            String::from("-filter_complex"),
+            build_ladder_filter(&request.renditions)
+        ]);
+        for (index, (rendition, output_file)) in request.renditions.iter().zip(&output_files).enumerate() {
+            args.extend(vec![
+                String::from("-map"),
+                format!("[v{}]", index),
+                String::from("-map"),
+                String::from("0:a:0?")
+            ]);
+            args.extend(build_rendition_quality_args(rendition, ":v"));
+            args.extend(vec![
+                String::from("-c:a"),
+                String::from("aac"),
+                output_file.clone()
+            ]);
+        }
+
+        let outcome = self.execute_checked(&args)?;
+        debug!("Rendition ladder written to {:?}", output_files);
+
+        Ok(LadderResponse { output_files, resource_usage: outcome.resource_usage })
+    }
+    /// Encodes the input to every output of the request in a single `ffmpeg` run: the
+    /// input is decoded once and its frames are handed to the encoder of each output.
+    /// Returns the output paths in the order of the request
+    pub fn convert_multi_output(&self, request: MultiOutputRequest) -> Result<MultiOutputResponse, AtiumError> {
+        self.validate_input_exists(&request.input_file)?;
+        validate_output_specs(&request.outputs)?;
+        for spec in &request.outputs {
+            require_encoder(&get_encoder_name(spec.codec.clone()))?;
+            require_encoder(get_output_audio_encoder(&spec.output_file))?;
+        }
+
+        let output_files = request.outputs.iter()
+            .map(|spec| compute_output_file(&spec.output_file, "mp4", request.collision_policy.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut args = Vec::new();
+        if let CollisionPolicy::Overwrite = request.collision_policy {
+            args.push(String::from("-y"));
+        }
+        args.push(String::from("-i"));
+        args.push(request.input_file);
+        for (spec, output_file) in request.outputs.iter().zip(&output_files) {
+            args.extend(build_output_spec_args(spec, output_file));
+        }
+
+        let outcome = self.execute_checked(&args)?;
+        debug!("Multi output encode written to {:?}", output_files);
+
+        Ok(MultiOutputResponse { output_files, resource_usage: outcome.resource_usage })
+    }
+    /// Packages the input for MPEG-DASH: each rendition of the ladder becomes a
+    /// representation of the video adaptation set, and the first audio track, if any, gets
+    /// its own adaptation set. Returns the manifest along with the segments written
+    pub fn convert_dash(&self, request: DashRequest) -> Result<DashResponse, AtiumError> {
+        self.validate_input_exists(&request.input_file)?;
+        if request.renditions.is_empty() {
+            return Err(AtiumError::InvalidArgument("At least one rendition is required".to_string()))
+        }
+        for rendition in &request.renditions {
+            validate_rendition(rendition)?;
+        }
+        require_muxer("dash")?;
+        require_encoder(&get_encoder_name(OutputCodec::H264))?;
+
+        let has_audio = !self.extract_info(&request.input_file)?.get_tracks_by_type("Audio").is_empty();
+        let output_dir = Path::new(&request.output_dir);
+        fs::create_dir_all(output_dir)
+            .map_err(|err| AtiumError::IOError(format!("Could not create output directory: {}", err)))?;
+        let manifest_file = output_dir.join("manifest.mpd");
+
+        let mut args = vec![
+            String::from("-y"),
+            String::from("-i"),
+            request.input_file,
+            String::from("-filter_complex"),
+            build_ladder_filter(&request.renditions)
+        ];
+        for index in 0..request.renditions.len() {
+            args.push(String::from("-map"));
+            args.push(format!("[v{}]", index));
+        }
+        if has_audio {
+            args.push(String::from("-map"));
+            args.push(String::from("0:a:0"));
+            args.push(String::from("-c:a"));
+            args.push(String::from("aac"));
+        }
+        for (index, rendition) in request.renditions.iter().enumerate() {
+            args.extend(build_rendition_quality_args(rendition, &format!(":v:{}", index)));
+        }
+        args.push(String::from("-adaptation_sets"));
+        args.push(String::from(if has_audio { "id=0,streams=v id=1,streams=a" } else { "id=0,streams=v" }));
+        if let Some(segment_duration) = request.segment_duration {
+            args.push(String::from("-seg_duration"));
+            args.push(segment_duration.to_string());
+        }
+        args.extend(vec![
+            String::from("-f"),
+            String::from("dash"),
+            manifest_file.to_str()
+                .ok_or(AtiumError::IOError("Could not build output path".to_string()))?
+                .to_string()
+        ]);
+
+        self.execute_checked(&args)?;
+
+        let segment_files = list_dash_segments(output_dir, &manifest_file)?;
+        debug!("DASH manifest written along with [{}] segments", segment_files.len());
+
+        Ok(DashResponse {
+            manifest_file: manifest_file.to_str().unwrap_or_default().to_string(),
+            segment_files
+        })
+    }
+    /// Cuts a part of the input by stream copy and returns a [`CutResponse`]. A copied video
+    /// can only start on a keyframe, so when snapping the start is moved back to the closest
+    /// preceding one, keeping the requested end, and the actual start is reported
+    pub fn cut(&self, request: CutRequest) -> Result<CutResponse, AtiumError> {
+        self.validate_input_exists(&request.input_file)?;
+        validate_cut_bounds(request.start_secs, request.end_secs)?;
+
+        let start_secs = if request.snap_to_keyframe {
+            let keyframes = FFProbeKeyframeService::new()?.keyframes(&request.input_file)?;
+            if keyframes.is_empty() {
+                warn!("No keyframe found in [{}], cutting at the requested start", request.input_file);
+            }
+            snap_to_preceding_keyframe(&keyframes, request.start_secs)
+        } else {
+            request.start_secs
+        };
+        if start_secs != request.start_secs {
+            info!("Cut start moved from [{}s] to the keyframe at [{}s]", request.start_secs, start_secs);
+        }
+
+        let output_file = compute_output_file(&request.output_file, "mp4", request.collision_policy.clone())?;
+        let mut args = Vec::new();
+        if let CollisionPolicy::Overwrite = request.collision_policy {
+            args.push(String::from("-y"));
+        }
+        // seeking the input with -ss lands on the cut start without decoding
+        args.extend(vec![
+            String::from("-ss"),
+            start_secs.to_string(),
+            String::from("-i"),
+            request.input_file
+        ]);
+        if let Some(end_secs) = request.end_secs {
+            args.push(String::from("-t"));
+            args.push((end_secs - start_secs).to_string());
+        }
+        args.extend(vec![
+            String::from("-map"),
+            String::from("0"),
+            String::from("-c"),
+            String::from("copy"),
+            output_file.clone()
+        ]);
+
+        self.execute_checked(&args)?;
+
+        Ok(CutResponse { output_file, start_secs })
+    }
+    /// Joins the clips of the request in a single `ffmpeg` run and returns the output file.
+    /// The audio is joined too when every clip has some, otherwise it is dropped. With a
+    /// crossfade, the transition offsets are computed from the clip durations
+    pub fn concat(&self, request: ConcatRequest) -> Result<String, AtiumError> {
+        if request.input_files.len() < 2 {
+            return Err(AtiumError::InvalidArgument("At least two clips are required to concatenate".to_string()))
+        }
+        let mut durations = Vec::new();
+        let mut with_audio = true;
+        for input_file in &request.input_files {
+            self.validate_input_exists(input_file)?;
+            let analysis_output = self.extract_info(input_file)?;
+            durations.push(analysis_output.duration_secs());
+            with_audio &= !analysis_output.get_tracks_by_type("Audio").is_empty();
+        }
+        if !with_audio {
+            warn!("Not every clip has audio, the output will be silent");
+        }
+        let offsets = request.crossfade
+            .map(|crossfade| compute_crossfade_offsets(&durations, f64::from(crossfade)))
+            .transpose()?;
+        let encoder = get_encoder_name(OutputCodec::H264);
+        require_encoder(&encoder)?;
+
+        let output_file = compute_output_file(&request.output_file, "mp4", request.collision_policy.clone())?;
+        let mut args = Vec::new();
+        if let CollisionPolicy::Overwrite = request.collision_policy {
+            args.push(String::from("-y"));
+        }
+        for input_file in &request.input_files {
+            args.push(String::from("-i"));
+            args.push(input_file.clone());
+        }
+        let crossfade = request.crossfade.map(f64::from).zip(offsets.as_deref());
+        args.extend(vec![
+            String::from("-filter_complex"),
+            build_concat_filter(request.input_files.len(), with_audio, crossfade),
+            String::from("-map"),
+            String::from("[vout]"),
+            String::from("-c:v"),
+            encoder
+        ]);
+        if with_audio {
+            args.extend(vec![
+                String::from("-map"),
+                String::from("[aout]"),
+                String::from("-c:a"),
+                String::from("aac")
+            ]);
+        }
+        args.push(output_file.clone());
+
+        self.execute_checked(&args)?;
+
+        Ok(output_file)
+    }
+    /// Exports every audio stream of the input to its own Matroska audio file in a single
+    /// `ffmpeg` run, with one `-map 0:a:N` output per stream. Streams are copied, which `.mka`
+    /// allows for any codec. Returns the produced files in stream order
+    pub fn extract_audio_tracks(&self, request: AudioTracksExtractionRequest) -> Result<Vec<String>, AtiumError> {
+        self.validate_input_exists(&request.input_file)?;
+        let tracks = self.extract_info(&request.input_file)?.list_tracks().audio;
+        if tracks.is_empty() {
+            return Err(AtiumError::InvalidArgument(format!("Input file [{}] has no audio stream to extract", request.input_file)))
+        }
+        let (codec_args, extension) = match &request.target {
+            AudioExtractionTarget::Copy => (vec![String::from("-c"), String::from("copy")], "mka"),
+            AudioExtractionTarget::Pcm { sample_format, sample_rate, channels } => {
+                validate_pcm_target(sample_format, *sample_rate, *channels)?;
+                (build_pcm_args(sample_format, *sample_rate, *channels), "pcm")
+            }
+        };
+        if request.output_dir == "-" {
+            if let AudioExtractionTarget::Copy = request.target {
+                return Err(AtiumError::InvalidArgument("Only raw PCM tracks can be written to stdout".to_string()))
+            }
+            if tracks.len() > 1 {
+                warn!("Only the first of the [{}] audio tracks is written to stdout", tracks.len());
+            }
+
+            let mut args = vec![
+                String::from("-i"),
+                request.input_file.clone(),
+                String::from("-map"),
+                format!("0:a:{}", tracks[0].index)
+            ];
+            args.extend(codec_args);
+            args.push(String::from("-"));
+            let result = self.command_manager.execute_with_inherited_stdout(args.iter().map(AsRef::as_ref).collect())?;
+            if !result.status.success() {
+                // stdout carries the PCM data for the reader of the pipe
+                self.command_manager.log_command_output(result.stderr);
+                return Err(AtiumError::ConversionError("Execution of command returned ERROR".to_string()))
+            }
+
+            return Ok(vec![request.output_dir])
+        }
+        let output_dir = Path::new(&request.output_dir);
+        fs::create_dir_all(output_dir)
+            .map_err(|err| AtiumError::IOError(format!("Could not create output directory: {}", err)))?;
+
+        let mut outputs = Vec::new();
+        for track in &tracks {
+            let track_file = output_dir.join(track.export_file_name(&request.input_file, "a", extension));
+            let track_file = track_file.to_str()
+                .ok_or(AtiumError::IOError("Could not build output path".to_string()))?;
+            let output_file = compute_output_file(track_file, extension, request.collision_policy.clone())?;
+            debug!("Extracting audio stream [{}] to [{}]", track.label(), output_file);
+            outputs.push((track.index, output_file));
+        }
+
+        let args = build_audio_tracks_args(&request, &codec_args, &outputs);
+        self.execute_checked(&args)?;
+
+        Ok(outputs.into_iter().map(|(_, output_file)| output_file).collect())
+    }
+    /// Extracts the CEA-608 closed captions embedded in the video stream of a broadcast
+    /// file to SRT. Captions are not subtitle streams, so they are read through the `subcc`
+    /// output of the `lavfi` `movie` source. Fails when no caption was found
+    pub fn extract_captions(&self, request: CaptionExtractionRequest) -> Result<String, AtiumError> {
+        self.validate_input_exists(&request.input_file)?;
+        if !has_closed_captions(&self.extract_info(&request.input_file)?) {
+            warn!("No closed captions reported for [{}], trying anyway", request.input_file);
+        }
+
+        let output_file = compute_output_file(&request.output_file, "srt", request.collision_policy.clone())?;
+        let mut args = Vec::new();
+        if let CollisionPolicy::Overwrite = request.collision_policy {
+            args.push(String::from("-y"));
+        }
+        args.extend(vec![
+            String::from("-f"),
+            String::from("lavfi"),
+            String::from("-i"),
+            build_closed_captions_source(&request.input_file),
+            String::from("-map"),
+            String::from("0:s"),
+            String::from("-c:s"),
+            String::from("srt"),
+            output_file.clone()
+        ]);
+        self.execute_checked(&args)?;
+
+        let captions = fs::read_to_string(&output_file).unwrap_or_default();
+        if !captions.contains("-->") {
+            let _ = fs::remove_file(&output_file);
+            return Err(AtiumError::InvalidArgument(format!("No closed captions found in [{}]", request.input_file)))
+        }
+        debug!("Closed captions extracted to [{}]", output_file);
+
+        Ok(output_file)
+    }
+    /// Demuxes every packet of `input_file` without decoding it and collects the errors
+    /// reported by `ffmpeg`. A file that cannot be opened at all reports that as an error
+    pub fn probe_integrity(&self, input_file: &str) -> Result<IntegrityReport, AtiumError> {
+        let result = self.command_manager.execute_with_args(vec![
+            "-v", "error", "-i", input_file, "-map", "0", "-c", "copy", "-f", "null", "-"
+        ])?;
+
+        Ok(parse_integrity_report(&String::from_utf8_lossy(&result.stderr)))
+    }
+    /// Remuxes a media with broken indexes, e.g. a missing or damaged `moov` atom, into
+    /// a new file by stream copy, which rebuilds the index. The integrity is probed before
+    /// and after, so the caller can tell whether the repair made a difference
+    pub fn repair(&self, request: RepairRequest) -> Result<RepairResponse, AtiumError> {
+        self.validate_input_exists(&request.input_file)?;
+
+        let before = self.probe_integrity(&request.input_file)?;
+        debug!("[{}] integrity errors before the repair", before.errors.len());
+
+        let output_file = compute_output_file(&request.output_file, "mp4", request.collision_policy.clone())?;
+        let mut args = Vec::new();
+        if let CollisionPolicy::Overwrite = request.collision_policy {
+            args.push(String::from("-y"));
+        }
+        if request.generate_pts {
+            args.push(String::from("-fflags"));
+            args.push(String::from("+genpts"));
+        }
+        args.extend(vec![
+            String::from("-i"),
+            request.input_file,
+            String::from("-map"),
+            String::from("0"),
+            String::from("-c"),
+            String::from("copy"),
+            output_file.clone()
+        ]);
+        self.execute_checked(&args)?;
+
+        let after = self.probe_integrity(&output_file)?;
+        debug!("[{}] integrity errors after the repair", after.errors.len());
+
+        Ok(RepairResponse { output_file, before, after })
+    }
+    /// Logs `ffmpeg` warnings and errors while conversions run, see [`CommandManager::with_stderr_tee`]
+    pub fn with_stderr_tee(mut self, tee_stderr: bool) -> Self {
+        self.command_manager = self.command_manager.with_stderr_tee(tee_stderr);
+        self
+    }
     /// Constructs a new instance of [`FFMPEGConversionService`]
     pub fn new() -> Result<Self, AtiumError> {
         let command_manager =
@@ -191,4 +1572,39 @@ impl FFMPEGConversionService {
 
         Ok(Self { command_manager })
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+    use crate::atium::common::analysis_helper::MediaInfoJsonLoader;
+    use crate::converter::builder::ConversionRequestBuilder;
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failed_explain_keeps_input() {
+        let service = FFMPEGConversionService { command_manager: CommandManager::new(String::from("true"), vec![]).unwrap() };
+        let dir = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input.mp4");
+        fs::write(&input, b"media").unwrap();
+        let request = ConversionRequestBuilder::new(input.to_str().unwrap(), dir.join("output.mp4").to_str().unwrap())
+            .env_source(|_| None)
+            .target_size("10M")
+            .build()
+            .ok()
+            .unwrap();
+        // no duration, so the target size can't be turned into a bitrate
+        let analysis_output = MediaInfoJsonLoader{}.load_json_from_string(r#"{"media": {"track": [
+            {"@type": "General", "Format": "MPEG-4"},
+            {"@type": "Video", "Format": "AVC", "Width": "1920", "Height": "1080"}
+        ]}}"#).unwrap();
+
+        assert_eq!(service.explain_with_analysis(&request, analysis_output).err().unwrap().to_string(),
+                   "Invalid Argument: Cannot fit a target size without knowing the input duration");
+        assert!(input.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
\ No newline at end of file
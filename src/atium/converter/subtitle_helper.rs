@@ -0,0 +1,107 @@
+use crate::atium::common::error::AtiumError;
+use crate::atium::common::time_helper::parse_timestamp;
+
+/// A cue of a subtitle file, with its timing in seconds
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubtitleCue {
+    /// The SRT cue number, or the position of the cue in the file starting at 1 when the
+    /// cue has no number, as WebVTT ones often do
+    pub index: u32,
+    pub start_secs: f64,
+    pub end_secs: f64,
+    /// The cue lines, joined with `\n`
+    pub text: String
+}
+
+/// How the cue of a subtitle file is picked
+pub enum CueSelector {
+    /// The cue with this index, see [`SubtitleCue::index`]
+    Index(u32),
+    /// The first cue whose text contains this one, ignoring case
+    Text(String)
+}
+
+/// Parses a `[hh:]mm:ss.mmm` timestamp, with either `.` or the SRT `,` before the milliseconds
+fn parse_cue_timestamp(timestamp: &str) -> Option<f64> {
+    parse_timestamp(&timestamp.replace(',', "."))
+        .ok()
+        .map(|timestamp| timestamp.as_secs_f64())
+}
+
+/// Parses the cues of a SRT or WebVTT file. Blocks without a `start --> end` timing line,
+/// like the `WEBVTT` header or `NOTE` comments, are skipped, and so are WebVTT cue settings
+/// following the end timestamp
+pub fn parse_subtitle_cues(content: &str) -> Vec<SubtitleCue> {
+    let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let mut cues = Vec::new();
+    for block in content.split("\n\n") {
+        let lines: Vec<&str> = block.lines().filter(|line| !line.trim().is_empty()).collect();
+        let timing_position = match lines.iter().position(|line| line.contains("-->")) {
+            Some(position) => position,
+            None => continue
+        };
+        let timing = lines[timing_position].split_once("-->")
+            .and_then(|(start, end)| Some((
+                parse_cue_timestamp(start)?,
+                parse_cue_timestamp(end.split_whitespace().next()?)?)));
+        if let Some((start_secs, end_secs)) = timing {
+            let number = timing_position.checked_sub(1)
+                .and_then(|position| lines[position].trim().parse::<u32>().ok());
+            cues.push(SubtitleCue {
+                index: number.unwrap_or(cues.len() as u32 + 1),
+                start_secs,
+                end_secs,
+                text: lines[timing_position + 1..].join("\n")
+            });
+        }
+    }
+
+    cues
+}
+
+/// Returns the cue picked by `selector`, failing when no cue matches it
+pub fn find_cue<'a>(cues: &'a [SubtitleCue], selector: &CueSelector) -> Result<&'a SubtitleCue, AtiumError> {
+    match selector {
+        CueSelector::Index(index) => cues.iter()
+            .find(|cue| cue.index == *index)
+            .ok_or(AtiumError::InvalidArgument(format!("No subtitle cue with index [{}]", index))),
+        CueSelector::Text(text) => {
+            let text = text.to_lowercase();
+            cues.iter()
+                .find(|cue| cue.text.to_lowercase().contains(&text))
+                .ok_or(AtiumError::InvalidArgument(format!("No subtitle cue contains [{}]", text)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_subtitle_cues() {
+        let srt = "1\r\n00:00:01,500 --> 00:00:04,000\r\nHello there\r\n\r\n2\r\n00:01:02,250 --> 00:01:05,000\r\nGeneral\r\nKenobi\r\n";
+
+        assert_eq!(parse_subtitle_cues(srt), vec![
+            SubtitleCue { index: 1, start_secs: 1.5, end_secs: 4.0, text: String::from("Hello there") },
+            SubtitleCue { index: 2, start_secs: 62.25, end_secs: 65.0, text: String::from("General\nKenobi") }
+        ]);
+
+        let vtt = "WEBVTT\n\nNOTE made by hand\n\n00:05.000 --> 00:07.500 align:start\nFirst\n\nintro\n01:00:00.000 --> 01:00:02.000\nSecond\n";
+        let cues = parse_subtitle_cues(vtt);
+
+        assert_eq!(cues.len(), 2);
+        assert_eq!((cues[0].index, cues[0].start_secs, cues[0].end_secs), (1, 5.0, 7.5));
+        assert_eq!((cues[1].index, cues[1].start_secs), (2, 3600.0));
+    }
+
+    #[test]
+    fn test_find_cue() {
+        let cues = parse_subtitle_cues("1\n00:00:01,000 --> 00:00:02,000\nHello there\n\n2\n00:00:03,000 --> 00:00:04,000\nGeneral Kenobi\n");
+
+        assert_eq!(find_cue(&cues, &CueSelector::Index(2)).ok().unwrap().start_secs, 3.0);
+        assert_eq!(find_cue(&cues, &CueSelector::Text(String::from("kenobi"))).ok().unwrap().index, 2);
+        assert!(find_cue(&cues, &CueSelector::Index(3)).is_err());
+        assert!(find_cue(&cues, &CueSelector::Text(String::from("grievous"))).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use serde::Serialize;
+
+use crate::atium::common::error::AtiumError;
+
+/// EBU R128 loudness of an audio stream as measured by the `loudnorm` filter
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LoudnessMeasurement {
+    /// Integrated loudness in LUFS
+    pub integrated_lufs: f64,
+    /// True peak in dBTP
+    pub true_peak_dbtp: f64,
+    /// Loudness range in LU
+    pub lra_lu: f64,
+    /// Gating threshold in LUFS
+    pub threshold_lufs: f64,
+    /// Gain offset towards the targets used while measuring, in LU
+    pub target_offset: f64
+}
+
+/// Loudness targets of the `loudnorm` filter
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoudnessTarget {
+    /// Integrated loudness in LUFS
+    pub integrated_lufs: f64,
+    /// Maximum true peak in dBTP
+    pub true_peak_dbtp: f64,
+    /// Loudness range in LU
+    pub lra_lu: f64
+}
+
+/// Named loudness normalization presets:
+/// * Podcast   -> -16 LUFS, -1.5 dBTP
+/// * Music     -> -14 LUFS, -1 dBTP
+/// * Broadcast -> -23 LUFS, -1 dBTP (EBU R128)
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum NormalizePreset {
+    Podcast, Music, Broadcast
+}
+
+impl NormalizePreset {
+    /// Returns the [`LoudnessTarget`] of the preset
+    pub fn target(&self) -> LoudnessTarget {
+        let (integrated_lufs, true_peak_dbtp, lra_lu) = match self {
+            NormalizePreset::Podcast => (-16.0, -1.5, 11.0),
+            NormalizePreset::Music => (-14.0, -1.0, 11.0),
+            NormalizePreset::Broadcast => (-23.0, -1.0, 7.0)
+        };
+
+        LoudnessTarget { integrated_lufs, true_peak_dbtp, lra_lu }
+    }
+}
+
+/// Returns a value of [`NormalizePreset`] based on input: `podcast`, `music` or `broadcast`
+pub fn parse_normalize_preset(input: &str) -> Result<NormalizePreset, AtiumError> {
+    match input.to_lowercase().as_str() {
+        "podcast" => Ok(NormalizePreset::Podcast),
+        "music" => Ok(NormalizePreset::Music),
+        "broadcast" => Ok(NormalizePreset::Broadcast),
+        _ => Err(AtiumError::InvalidArgument(format!("Unknown normalize preset [{}], valid presets are: podcast, music, broadcast", input)))
+    }
+}
+
+/// Builds the `loudnorm` filter reaching `target`. When the `measured` values of a first
+/// pass are given, the filter runs in linear mode which applies a constant gain instead of
+/// dynamic compression. Silent inputs, measured at `-inf`, fall back to the single pass filter
+pub fn build_loudnorm_filter(target: &LoudnessTarget, measured: Option<&LoudnessMeasurement>) -> String {
+    let filter = format!("loudnorm=I={}:TP={}:LRA={}", target.integrated_lufs, target.true_peak_dbtp, target.lra_lu);
+
+    match measured {
+        Some(measured) if measured.integrated_lufs.is_finite() && measured.true_peak_dbtp.is_finite() => format!(
+            "{}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}:linear=true",
+            filter,
+            measured.integrated_lufs,
+            measured.true_peak_dbtp,
+            measured.lra_lu,
+            measured.threshold_lufs,
+            measured.target_offset),
+        _ => filter
+    }
+}
+
+/// Read-only measurements of a media, nothing is written while producing it
+#[derive(Clone, Serialize)]
+pub struct AnalysisReport {
+    pub loudness: LoudnessMeasurement
+}
+
+/// Objective quality of a distorted video against its reference
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct QualityMetrics {
+    /// Average SSIM over all the planes, from 0 to 1 where 1 means identical
+    pub ssim_all: f64,
+    /// Average SSIM of the luma plane
+    pub ssim_y: f64,
+    /// Average PSNR over all the planes in dB, infinite for identical videos
+    pub psnr_average: f64,
+    /// Average PSNR of the luma plane in dB
+    pub psnr_y: f64
+}
+
+/// Returns the value following `key` in a `key:value` token of the given line
+fn find_metric(line: &str, key: &str) -> Option<f64> {
+    line.split_whitespace()
+        .find_map(|token| token.strip_prefix(key))
+        .and_then(|value| value.parse::<f64>().ok())
+}
+
+/// Parses the summary lines printed on stderr by the `ssim` and `psnr` filters, e.g.
+/// `SSIM Y:0.99 (20.1) U:0.99 (21.0) V:0.99 (21.1) All:0.99 (20.4)` and
+/// `PSNR y:40.12 u:45.30 v:45.10 average:41.56 min:35.20 max:48.90`
+pub fn parse_quality_output(output: &str) -> Result<QualityMetrics, AtiumError> {
+    let ssim_line = output.lines()
+        .rfind(|line| line.contains("Parsed_ssim") && line.contains("All:"))
+        .ok_or(AtiumError::ConversionError("Could not find SSIM summary".to_string()))?;
+    let psnr_line = output.lines()
+        .rfind(|line| line.contains("Parsed_psnr") && line.contains("average:"))
+        .ok_or(AtiumError::ConversionError("Could not find PSNR summary".to_string()))?;
+
+    let metric = |line: &str, key: &str| find_metric(line, key)
+        .ok_or(AtiumError::ConversionError(format!("Could not parse [{}] metric", key)));
+
+    Ok(QualityMetrics {
+        ssim_all: metric(ssim_line, "All:")?,
+        ssim_y: metric(ssim_line, "Y:")?,
+        psnr_average: metric(psnr_line, "average:")?,
+        psnr_y: metric(psnr_line, "y:")?
+    })
+}
+
+/// A silent stretch of the audio found by `silencedetect`, in seconds
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SilenceInterval {
+    pub start_secs: f64,
+    /// None when the silence lasts until the end of the input
+    pub end_secs: Option<f64>
+}
+
+/// Parses the `silence_start: 1.5` and `silence_end: 3.2 | silence_duration: 1.7` lines printed
+/// on stderr by `silencedetect` into intervals. A start slightly before 0, which the filter
+/// reports for leading silence, is clamped
+pub fn parse_silencedetect_output(output: &str) -> Vec<SilenceInterval> {
+    let value_after = |line: &str, key: &str| line.split_once(key)
+        .and_then(|(_, rest)| rest.split('|').next())
+        .and_then(|value| value.trim().parse::<f64>().ok());
+
+    let mut intervals: Vec<SilenceInterval> = Vec::new();
+    for line in output.lines() {
+        if let Some(start_secs) = value_after(line, "silence_start:") {
+            intervals.push(SilenceInterval { start_secs: start_secs.max(0.0), end_secs: None });
+        } else if let Some(end_secs) = value_after(line, "silence_end:") {
+            match intervals.last_mut() {
+                Some(interval) if interval.end_secs.is_none() => interval.end_secs = Some(end_secs),
+                _ => {}
+            }
+        }
+    }
+
+    intervals
+}
+
+/// Extracts the timestamps of the frames printed by `metadata=print`, in seconds. Each
+/// frame is a `frame:0 pts:120 pts_time:5.005` line followed by its metadata entries,
+/// e.g. `lavfi.scene_score=0.45`, which are skipped
+pub fn parse_metadata_timestamps(output: &str) -> Vec<f64> {
+    output.lines()
+        .filter(|line| line.contains("Parsed_metadata"))
+        .filter_map(|line| line.split_whitespace()
+            .find_map(|token| token.strip_prefix("pts_time:"))
+            .and_then(|value| value.parse::<f64>().ok()))
+        .collect()
+}
+
+fn parse_measurement_field(fields: &HashMap<String, String>, name: &str) -> Result<f64, AtiumError> {
+    fields.get(name)
+        .ok_or(AtiumError::ConversionError(format!("Missing [{}] in loudnorm output", name)))?
+        .parse::<f64>()
+        .map_err(|_| AtiumError::ConversionError(format!("Invalid [{}] in loudnorm output", name)))
+}
+
+/// Parses the JSON block printed on stderr by `loudnorm=print_format=json`, which
+/// follows the `Parsed_loudnorm` log line
+pub fn parse_loudnorm_output(output: &str) -> Result<LoudnessMeasurement, AtiumError> {
+    let block_start = output.rfind("Parsed_loudnorm")
+        .and_then(|index| output[index..].find('{').map(|start| index + start))
+        .ok_or(AtiumError::ConversionError("Could not find loudnorm measurements".to_string()))?;
+    let block_end = output[block_start..].find('}')
+        .map(|end| block_start + end + 1)
+        .ok_or(AtiumError::ConversionError("Could not find loudnorm measurements".to_string()))?;
+
+    let fields: HashMap<String, String> = serde_json::from_str(&output[block_start..block_end])
+        .map_err(|err| AtiumError::ConversionError(format!("Could not parse loudnorm measurements: {}", err)))?;
+
+    Ok(LoudnessMeasurement {
+        integrated_lufs: parse_measurement_field(&fields, "input_i")?,
+        true_peak_dbtp: parse_measurement_field(&fields, "input_tp")?,
+        lra_lu: parse_measurement_field(&fields, "input_lra")?,
+        threshold_lufs: parse_measurement_field(&fields, "input_thresh")?,
+        target_offset: parse_measurement_field(&fields, "target_offset")?
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_loudnorm_output() {
+        let stderr = "Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'input.mp4':
+  Duration: 00:00:20.00, start: 0.000000, bitrate: 3261 kb/s
+[Parsed_loudnorm_0 @ 0x600002a7c000]
+{
+\t\"input_i\" : \"-27.61\",
+\t\"input_tp\" : \"-4.47\",
+\t\"input_lra\" : \"18.06\",
+\t\"input_thresh\" : \"-39.20\",
+\t\"output_i\" : \"-16.58\",
+\t\"output_tp\" : \"-1.50\",
+\t\"output_lra\" : \"14.78\",
+\t\"output_thresh\" : \"-27.71\",
+\t\"normalization_type\" : \"dynamic\",
+\t\"target_offset\" : \"0.58\"
+}
+size=N/A time=00:00:20.00 bitrate=N/A speed= 173x";
+
+        assert_eq!(parse_loudnorm_output(stderr).ok().unwrap(), LoudnessMeasurement {
+            integrated_lufs: -27.61,
+            true_peak_dbtp: -4.47,
+            lra_lu: 18.06,
+            threshold_lufs: -39.2,
+            target_offset: 0.58
+        });
+    }
+
+    #[test]
+    fn test_build_loudnorm_filter() {
+        let target = parse_normalize_preset("Podcast").ok().unwrap().target();
+        assert_eq!(build_loudnorm_filter(&target, None), "loudnorm=I=-16:TP=-1.5:LRA=11");
+
+        let measured = LoudnessMeasurement {
+            integrated_lufs: -27.61,
+            true_peak_dbtp: -4.47,
+            lra_lu: 18.06,
+            threshold_lufs: -39.2,
+            target_offset: 0.58
+        };
+        assert_eq!(build_loudnorm_filter(&NormalizePreset::Broadcast.target(), Some(&measured)),
+            "loudnorm=I=-23:TP=-1:LRA=7:measured_I=-27.61:measured_TP=-4.47:measured_LRA=18.06:measured_thresh=-39.2:offset=0.58:linear=true");
+
+        let silence = LoudnessMeasurement { integrated_lufs: f64::NEG_INFINITY, ..measured };
+        assert_eq!(build_loudnorm_filter(&NormalizePreset::Music.target(), Some(&silence)), "loudnorm=I=-14:TP=-1:LRA=11");
+        assert!(parse_normalize_preset("radio").is_err());
+    }
+
+    #[test]
+    fn test_parse_quality_output() {
+        let stderr = "[Parsed_ssim_0 @ 0x600001b08000] SSIM Y:0.987654 (19.083) U:0.991 (20.457) V:0.990 (20.000) All:0.989 (19.586)
+[Parsed_psnr_1 @ 0x600001b08140] PSNR y:38.52 u:43.90 v:44.12 average:39.85 min:33.07 max:47.61";
+
+        assert_eq!(parse_quality_output(stderr).ok().unwrap(), QualityMetrics {
+            ssim_all: 0.989,
+            ssim_y: 0.987654,
+            psnr_average: 39.85,
+            psnr_y: 38.52
+        });
+
+        let identical = "[Parsed_ssim_0 @ 0x1] SSIM Y:1.000000 (inf) U:1.000000 (inf) V:1.000000 (inf) All:1.000000 (inf)
+[Parsed_psnr_1 @ 0x2] PSNR y:inf u:inf v:inf average:inf min:inf max:inf";
+        assert!(parse_quality_output(identical).ok().unwrap().psnr_average.is_infinite());
+
+        assert!(parse_quality_output("[Parsed_ssim_0 @ 0x1] SSIM Y:0.9 All:0.9 (10.0)").is_err());
+    }
+
+    #[test]
+    fn test_parse_silencedetect_output() {
+        let stderr = "[silencedetect @ 0x1] silence_start: -0.00133333
+[silencedetect @ 0x1] silence_end: 1.50702 | silence_duration: 1.50835
+size=N/A time=00:00:05.00 bitrate=N/A speed= 200x
+[silencedetect @ 0x1] silence_start: 3.2
+[silencedetect @ 0x1] silence_end: 4 | silence_duration: 0.8
+[silencedetect @ 0x1] silence_start: 9.75";
+
+        assert_eq!(parse_silencedetect_output(stderr), vec![
+            SilenceInterval { start_secs: 0.0, end_secs: Some(1.50702) },
+            SilenceInterval { start_secs: 3.2, end_secs: Some(4.0) },
+            SilenceInterval { start_secs: 9.75, end_secs: None }
+        ]);
+        assert!(parse_silencedetect_output("frame=1 fps=0.0").is_empty());
+    }
+
+    #[test]
+    fn test_parse_metadata_timestamps() {
+        let stderr = "[Parsed_metadata_1 @ 0x1] frame:0    pts:120     pts_time:5.005
+[Parsed_metadata_1 @ 0x1] lavfi.scene_score=0.453112
+[Parsed_metadata_1 @ 0x1] frame:1    pts:311     pts_time:12.971
+[Parsed_metadata_1 @ 0x1] lavfi.scene_score=0.61
+frame=    2 fps=0.0 q=-0.0 Lsize=N/A time=00:00:12.97 bitrate=N/A speed= 90x";
+
+        assert_eq!(parse_metadata_timestamps(stderr), vec![5.005, 12.971]);
+        assert!(parse_metadata_timestamps("size=N/A time=00:00:05.00").is_empty());
+    }
+
+    #[test]
+    fn test_parse_loudnorm_silence() {
+        let stderr = "[Parsed_loudnorm_0 @ 0x1]
+{
+\t\"input_i\" : \"-inf\",
+\t\"input_tp\" : \"-inf\",
+\t\"input_lra\" : \"0.00\",
+\t\"input_thresh\" : \"-70.00\",
+\t\"target_offset\" : \"0.00\"
+}";
+
+        assert_eq!(parse_loudnorm_output(stderr).ok().unwrap().integrated_lufs, f64::NEG_INFINITY);
+        assert!(parse_loudnorm_output("frame=1 fps=0.0").is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,130 @@
+use log::debug;
+use crate::atium::common::analysis_service::MediaInfoExtractorService;
+use crate::atium::common::command_manager::CommandManager;
+use crate::atium::common::error::AtiumError;
+use crate::atium::measure::model::{AnalysisReport, build_loudnorm_filter, LoudnessMeasurement, LoudnessTarget, parse_loudnorm_output, parse_metadata_timestamps, parse_quality_output, parse_silencedetect_output, QualityMetrics, SilenceInterval};
+
+/// FFMPEG Engine Service for measuring media without producing any output
+pub struct FFMPEGMeasurementService {
+    command_manager: CommandManager
+}
+
+impl FFMPEGMeasurementService {
+    fn run_null_output(&self, args: Vec<&str>) -> Result<String, AtiumError> {
+        let mut args = args;
+        args.extend(["-f", "null", "-"]);
+
+        let result = self.command_manager.execute_with_args(args)?;
+        if !result.status.success() {
+            self.command_manager.print_command_output(result.stderr)?;
+            return Err(AtiumError::ConversionError("Execution of command returned ERROR".to_string()))
+        }
+
+        self.command_manager.get_command_output_as_string(result.stderr)
+    }
+    fn run_loudnorm(&self, input_file: &str, filter: &str) -> Result<LoudnessMeasurement, AtiumError> {
+        let analysis_output = MediaInfoExtractorService::new()?.get_analysis_output(input_file)?;
+        if analysis_output.get_tracks_by_type("Audio").is_empty() {
+            return Err(AtiumError::InvalidArgument(format!("Input file [{}] has no audio stream", input_file)))
+        }
+
+        debug!("Measuring loudness of [{}]", input_file);
+        let filter = format!("{}:print_format=json", filter);
+        let stderr = self.run_null_output(vec![
+            "-hide_banner", "-nostats", "-i", input_file, "-vn", "-af", filter.as_str()
+        ])?;
+
+        parse_loudnorm_output(&stderr)
+    }
+    /// Measures the loudness of the input and returns an [`AnalysisReport`]
+    pub fn analyze(&self, input_file: &str) -> Result<AnalysisReport, AtiumError> {
+        // the default loudnorm targets are used, they only affect the reported target offset
+        Ok(AnalysisReport {
+            loudness: self.run_loudnorm(input_file, "loudnorm")?
+        })
+    }
+    /// Runs the first pass of a loudness normalization towards `target` and returns the
+    /// measurements needed by [`build_loudnorm_filter`]
+    pub fn measure_loudness(&self, input_file: &str, target: &LoudnessTarget) -> Result<LoudnessMeasurement, AtiumError> {
+        self.run_loudnorm(input_file, &build_loudnorm_filter(target, None))
+    }
+    /// Lists the stretches of the input audio quieter than `threshold_db` dBFS for at least
+    /// `min_duration_secs` seconds, e.g. to cut the pauses out of a recording
+    pub fn detect_silence(&self, input_file: &str, threshold_db: f64, min_duration_secs: f64) -> Result<Vec<SilenceInterval>, AtiumError> {
+        if threshold_db.is_nan() || threshold_db >= 0.0 {
+            return Err(AtiumError::InvalidArgument(format!("Silence threshold [{}] must be below 0 dB", threshold_db)))
+        }
+        if min_duration_secs.is_nan() || min_duration_secs <= 0.0 {
+            return Err(AtiumError::InvalidArgument(format!("Silence duration [{}] must be greater than 0", min_duration_secs)))
+        }
+        let analysis_output = MediaInfoExtractorService::new()?.get_analysis_output(input_file)?;
+        if analysis_output.get_tracks_by_type("Audio").is_empty() {
+            return Err(AtiumError::InvalidArgument(format!("Input file [{}] has no audio stream", input_file)))
+        }
+
+        debug!("Detecting silence in [{}]", input_file);
+        let filter = format!("silencedetect=noise={}dB:d={}", threshold_db, min_duration_secs);
+        let stderr = self.run_null_output(vec![
+            "-hide_banner", "-nostats", "-i", input_file, "-vn", "-af", filter.as_str()
+        ])?;
+
+        Ok(parse_silencedetect_output(&stderr))
+    }
+    /// Returns the timestamps, in seconds, of the scene changes of the input video whose
+    /// score is above `threshold`, which goes from 0 to 1 and finds more cuts when lower
+    pub fn detect_scenes(&self, input_file: &str, threshold: f32) -> Result<Vec<f64>, AtiumError> {
+        if !(threshold > 0.0 && threshold < 1.0) {
+            return Err(AtiumError::InvalidArgument(format!("Scene threshold [{}] must be between 0 and 1", threshold)))
+        }
+        self.probe_video_stream(input_file)?;
+
+        debug!("Detecting scene changes in [{}]", input_file);
+        let filter = format!("select='gt(scene,{})',metadata=print", threshold);
+        let stderr = self.run_null_output(vec![
+            "-hide_banner", "-nostats", "-i", input_file, "-an", "-vf", filter.as_str()
+        ])?;
+
+        Ok(parse_metadata_timestamps(&stderr))
+    }
+    fn probe_video_stream(&self, input_file: &str) -> Result<(String, String, String), AtiumError> {
+        let analysis_output = MediaInfoExtractorService::new()?.get_analysis_output(input_file)?;
+        let track = analysis_output.get_tracks_by_type("Video")
+            .first()
+            .copied()
+            .ok_or(AtiumError::InvalidArgument(format!("Input file [{}] has no video stream", input_file)))?;
+        let field = |name: &str| analysis_output.get_track_field(track, name).unwrap_or_default();
+
+        Ok((field("Width"), field("Height"), field("FrameRate")))
+    }
+    /// Compares `distorted` against `reference` with the `ssim` and `psnr` filters and
+    /// returns the average [`QualityMetrics`]. Both videos must have the same dimensions
+    /// and frame rate
+    pub fn compare_quality(&self, reference: &str, distorted: &str) -> Result<QualityMetrics, AtiumError> {
+        let (ref_width, ref_height, ref_frame_rate) = self.probe_video_stream(reference)?;
+        let (width, height, frame_rate) = self.probe_video_stream(distorted)?;
+        if (&ref_width, &ref_height) != (&width, &height) {
+            return Err(AtiumError::InvalidArgument(format!(
+                "Resolution mismatch: reference is [{}x{}] while distorted is [{}x{}], scale them to the same size first",
+                ref_width, ref_height, width, height)))
+        }
+        if ref_frame_rate != frame_rate {
+            return Err(AtiumError::InvalidArgument(format!(
+                "Frame rate mismatch: reference is [{}] while distorted is [{}]", ref_frame_rate, frame_rate)))
+        }
+
+        debug!("Comparing [{}] against [{}]", distorted, reference);
+        let stderr = self.run_null_output(vec![
+            "-hide_banner", "-nostats", "-i", reference, "-i", distorted,
+            "-lavfi", "[0:v][1:v]ssim;[0:v][1:v]psnr"
+        ])?;
+
+        parse_quality_output(&stderr)
+    }
+    /// Constructs a new instance of [`FFMPEGMeasurementService`] by loading and checking `ffmpeg` availability
+    pub fn new() -> Result<Self, AtiumError> {
+        let command_manager =
+            CommandManager::new("ffmpeg".to_string(), vec!["-version"])?;
+
+        Ok(Self { command_manager })
+    }
+}
\ No newline at end of file
@@ -1,2 +1,4 @@
 pub mod converter;
-pub mod common;
\ No newline at end of file
+pub mod common;
+pub mod audio;
+pub mod measure;
\ No newline at end of file
@@ -21,7 +21,9 @@
 //! let request = ConversionRequest{
 //!     input: ConversionInput {
 //!         source_type: InputSourceType::Local,
-//!         file_name:  String::from("/path/to/input.mp4")
+//!         file_name:  String::from("/path/to/input.mp4"),
+//!         hwaccel_decode: None,
+//!         force_decoder: None
 //!     },
 //!     output: ConversionOutput {
 //!         file: String::from("/path/to/output.mp4"),
@@ -33,10 +35,64 @@
 //!               // as a source for thumbnail extraction
 //!               String::from("/path/to/input.mp4"),
 //!               String::from("/path/to/thumb.jpg")
-//!         )
+//!         ),
+//!         maxrate: None,
+//!         bufsize: None,
+//!         collision_policy: CollisionPolicy::Rename,
+//!         metadata: None,
+//!         strip_metadata: false,
+//!         fps: None,
+//!         force_cfr: false,
+//!         burn_subtitles: None,
+//!         target_size: None,
+//!         skip_existing: false,
+//!         subtitle_charset: None,
+//!         keep_streams: None,
+//!         dispositions: None,
+//!         stream_metadata: None,
+//!         web_optimized: false,
+//!         fragmented: false,
+//!         muxing_queue_size: None,
+//!         video_timebase: None,
+//!         aspect_ratio: None,
+//!         set_sar: None,
+//!         anamorphic_fix: false,
+//!         normalize_preset: None,
+//!         aac_encoder: None,
+//!         b_frames: None,
+//!         ref_frames: None,
+//!         x265_params: None,
+//!         frame_limit: None,
+//!         force_keyframes: None,
+//!         sample_duration: None,
+//!         bit_depth: None,
+//!         reverse: false,
+//!         pad: None,
+//!         blur_fill: None,
+//!         progress_bar: None,
+//!         max_dimensions: None,
+//!         audio_offset_ms: None,
+//!         cover: None,
+//!         preserve_mtime: false,
+//!         audio_downmix: None,
+//!         trim_silence: None,
+//!         pip: None,
+//!         intro: None,
+//!         filter_complex: None,
+//!         hardware_encode: false,
+//!         fallback_codec: None,
+//!         write_report: false,
+//!         strict: false
 //!     }
 //!  };
 //! ```
+//! The same request can be built with a [ConversionRequestBuilder](crate::converter::builder::ConversionRequestBuilder),
+//! which fills the defaults and validates all the options together when calling `build`.
+//! ```
+//! let request = ConversionRequestBuilder::new("/path/to/input.mp4", "/path/to/output.mp4")
+//!     .resolution(OutputResolution::Hd)
+//!     .build()?;
+//! ```
 //! Now you can perform the actual conversion! Use the previously created `conversion_service`
 //! and call the `convert` method.
 //! ```
@@ -45,21 +101,60 @@
 //! The output is a `Result<ConversionResponse, AtiumError>`, so now is up to you.
 //! This contains a [ConversionResponse](crate::converter::model::ConversionResponse) that holds the output path of the converted video!
 //!
+//! Decoding can be moved to the GPU with `hwaccel_decode`, which is passed to `ffmpeg` as an input option.
+//! Decoded frames are copied back to system memory before scaling and encoding: the fastest pipelines pair
+//! the hardware decoder with the matching hardware encoder (e.g. `cuda` with `h264_nvenc`), keeping frames on the GPU.
+//! Setting `hardware_encode` picks that encoder for the requested codec, and `fallback_codec` retries the
+//! whole conversion in software when the device fails, e.g. when no nvenc session is left.
+//!
 //! ## Command Line Interface
 //!
 //! After installing `atium` by entering `atium --help` the following helper shows up:
 //! ```text
-//! Usage: atium <COMMAND>
+//! Usage: atium [OPTIONS] <COMMAND>
 //!
 //! Commands:
-//!   convert    Conversion tool for video media
-//!   analyze    Analyze media to extract useful infos
-//!   thumbnail  Thumbnail extraction tool
-//!   help       Print this message or the help of the given subcommand(s)
+//!   convert          Conversion tool for video media
+//!   explain          Prints the ffmpeg commands a conversion would run, without running anything
+//!   validate-preset  Checks a preset file of convert options without converting, exits with 1 when it is invalid
+//!   batch            Converts several inputs with the same settings, then reports which ones failed and why
+//!   analyze          Analyze media to extract useful infos
+//!   frames           Extracts every frame of a video, or every Nth, as numbered png images
+//!   sprite           Builds a thumbnail sprite sheet along with its WebVTT track for web players
+//!   filmstrip        Builds a single row filmstrip of frames spread over the video, printing their timestamps
+//!   measure          Measures loudness without producing any output
+//!   silence          Prints the silent intervals of the audio as JSON, e.g. for jump-cut editing
+//!   scenes           Prints the timestamps of the scene cuts of a video
+//!   compare          Compares a distorted video against its reference with PSNR and SSIM
+//!   advise           Tells which streams can be copied and which need transcoding for web delivery
+//!   tracks           Lists the audio and subtitle tracks of a media with human readable labels
+//!   keyframes        Prints the keyframe timestamps of a video, in seconds, e.g. to plan `-c copy` cuts
+//!   hdr              Reports the color and HDR metadata of a video, exits with 1 when it is not HDR
+//!   thumbnail        Thumbnail extraction tool
+//!   waveform         Waveform image rendering tool
+//!   spectrogram      Spectrogram image rendering tool
+//!   replace-audio    Replace the audio of a video with an external audio track
+//!   mux              Merges a video-only and an audio-only file by stream copy
+//!   cue-gif          Makes a GIF of the moment of a subtitle cue, picked by index or text, with the subtitles burned in
+//!   sample-format    Changes only the sample format of the audio of a lossless file, e.g. a 24-bit FLAC to 16-bit
+//!   repair           Remuxes a file with broken indexes by stream copy, reporting errors before and after
+//!   captions         Extracts the CEA-608 closed captions embedded in a broadcast video to SRT
+//!   extract-audio    Exports every audio track to its own file, named after the input, the track index and the language
+//!   cut              Cuts a part of a media without re-encoding it
+//!   concat           Joins clips one after the other, with a hard cut or a crossfade. Clips must share their resolution and frame rate
+//!   ladder           Encodes a rendition ladder for adaptive streaming in a single ffmpeg run
+//!   multi-output     Encodes a video to several outputs, each with its own codec and quality, decoding it only once
+//!   dash             Packages a rendition ladder for MPEG-DASH, writing a manifest and its segments
+//!   build-info       Prints the ffmpeg version, its enabled build features and its library versions, e.g. for bug reports
+//!   streams-equal    Checks whether the video and audio of two files decode to the same content, exits with 1 when they don't
+//!   watch            Watches a directory and writes an info report next to each new video file
+//!   codec            Check whether ffmpeg supports a codec
+//!   help             Print this message or the help of the given subcommand(s)
 //!
 //! Options:
-//!   -h, --help     Print help information
-//!   -V, --version  Print version information
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
 //! ```
 //! Here you can access all the tools offered by `atium`.
 //!
@@ -68,18 +163,174 @@
 //! ```
 //! Conversion tool for video media
 //!
-//! Usage: atium convert [OPTIONS] --input <INPUT> --output <OUTPUT> --resolution <RESOLUTION>
+//! Usage: atium convert [OPTIONS] --input <INPUT>
 //!
 //! Options:
-//!   -i, --input <INPUT>                Input file to convert
-//!   -s, --source-type <SOURCE_TYPE>    Type of source to convert
-//!   -o, --output <OUTPUT>              Output path for the converted file
-//!   -r, --resolution <RESOLUTION>      Requested output resolution
-//!       --thumb-ts <THUMB_TS>          Timestamp requested for thumbnail extraction
-//!       --thumb-source <THUMB_SOURCE>  Source from where to extract the thumbnail
-//!       --thumb-out <THUMB_OUT>        Output path for the extracted thumbnail
-//!   -h, --help                         Print help information
-//!   -V, --version                      Print version information
+//!   -i, --input <INPUT>
+//!           Input file or http(s) URL to convert
+//!   -s, --source-type <SOURCE_TYPE>
+//!           Type of source to convert
+//!   -o, --output <OUTPUT>
+//!           Output path for the converted file
+//!       --output-template <OUTPUT_TEMPLATE>
+//!           Template for the output path, e.g. `{name}_converted.{ext}`. Valid tokens are `{name}`, `{ext}` and `{input_ext}`, relative paths are placed next to the input
+//!   -r, --resolution <RESOLUTION>
+//!           Requested output resolution `sd/hd/fhd/2k/uhd/8k`, `ATIUM_RESOLUTION` or hd when missing
+//!       --codec <CODEC>
+//!           Video codec `auto/h264/h265/vp9/av1`, `auto` picks vp9 for webm outputs and h264 otherwise. `ATIUM_CODEC` or h264 when missing
+//!       --thumb-ts <THUMB_TS>
+//!           Timestamp requested for thumbnail extraction
+//!       --thumb-source <THUMB_SOURCE>
+//!           Source from where to extract the thumbnail
+//!       --thumb-out <THUMB_OUT>
+//!           Output path for the extracted thumbnail
+//!       --maxrate <MAXRATE>
+//!           Maximum bitrate for constrained streaming, e.g. `2M`. Requires `--bufsize`
+//!       --bufsize <BUFSIZE>
+//!           Rate control buffer size, e.g. `4M`. Requires `--maxrate`
+//!       --on-collision <ON_COLLISION>
+//!           What to do when the output file already exists `error/overwrite/rename`
+//!       --title <TITLE>
+//!           Title stored in the output container metadata
+//!       --artist <ARTIST>
+//!           Artist stored in the output container metadata
+//!       --comment <COMMENT>
+//!           Comment stored in the output container metadata
+//!       --date <DATE>
+//!           Date stored in the output container metadata
+//!       --metadata <METADATA>
+//!           Additional container metadata as `key=value`, can be repeated
+//!       --strip-metadata
+//!           Remove all the metadata coming from the input
+//!       --fps <FPS>
+//!           Output frame rate, e.g. `25` or `30000/1001`
+//!       --force-cfr
+//!           Force a constant frame rate output, using `--fps` or the source average frame rate
+//!       --burn-subtitles <BURN_SUBTITLES>
+//!           Subtitle file to burn into the video
+//!       --burn-subtitle-track <BURN_SUBTITLE_TRACK>
+//!           Embedded subtitle stream to burn into the video, by index or language
+//!       --target-size <TARGET_SIZE>
+//!           Maximum output size, e.g. `8MB`, reached with a two-pass encode at a computed bitrate. Not available with `--hardware-encode`
+//!       --subtitle-charset <SUBTITLE_CHARSET>
+//!           Character encoding of the burned subtitle file, e.g. `CP1252`. Defaults to UTF-8
+//!       --soft-subtitles-output <SOFT_SUBTITLES_OUTPUT>
+//!           Also writes a stream copy of the input here with the burned subtitle file as a selectable track
+//!       --progress-json
+//!           Print progress to stdout as newline-delimited JSON events
+//!       --keep-streams <KEEP_STREAMS>
+//!           Keep only these stream kinds `video,audio,subtitle,data` and drop the rest
+//!       --disposition <DISPOSITION>
+//!           Disposition flag of an output stream as `spec=flag`, e.g. `a:1=default` or `s:0=forced`. Can be repeated
+//!       --stream-metadata <STREAM_METADATA>
+//!           Metadata of an output stream as `spec:key=value`, e.g. `a:1:title=Director's Commentary` or `a:0:language=eng`. Can be repeated
+//!       --web-optimized
+//!           Optimize mp4/mov outputs for progressive playback over HTTP
+//!       --fragmented
+//!           Writes a fragmented mp4/mov for low-latency streaming, it can't be web optimized
+//!       --max-muxing-queue-size <MAX_MUXING_QUEUE_SIZE>
+//!           Packets buffered per stream while muxing, raise it when ffmpeg fails with "Too many packets buffered for output stream", e.g. `1024`
+//!       --video-timebase <VIDEO_TIMEBASE>
+//!           Ticks per second of the mp4/mov video track, e.g. `90000`, for editing software rejecting unusual timebases
+//!       --aspect-ratio <ASPECT_RATIO>
+//!           Display aspect ratio of the output, e.g. `16:9`
+//!       --log-format <LOG_FORMAT>
+//!           Format of the logs written to stderr `text/json` [default: text]
+//!       --set-sar <SET_SAR>
+//!           Sample aspect ratio applied to the frames, e.g. `1:1`
+//!       --anamorphic-fix
+//!           Stretches anamorphic sources like DVD rips to square pixels, square sources are left as they are
+//!       --skip-existing
+//!           Skips the conversion when the output already exists and holds a readable video
+//!       --log-stderr
+//!           Logs ffmpeg warnings and errors as they happen during the conversion
+//!       --hwaccel-decode <HWACCEL_DECODE>
+//!           Hardware accelerator used to decode the input `cuda/vaapi/qsv/videotoolbox/d3d11va/dxva2`
+//!       --hardware-encode
+//!           Encodes with the hardware encoder of `--hwaccel-decode`, e.g. `hevc_nvenc` for h265 on cuda
+//!       --fallback-codec <FALLBACK_CODEC>
+//!           Software codec `h264/h265/vp9/av1` the conversion is retried with when the hardware encoder fails
+//!       --force-decoder <FORCE_DECODER>
+//!           Decoder used for the input video instead of the autodetected one, e.g. `libdav1d`
+//!       --normalize-preset <NORMALIZE_PRESET>
+//!           Normalizes the audio loudness with a preset `podcast/music/broadcast`
+//!       --aac-encoder <AAC_ENCODER>
+//!           AAC encoder of the audio `native/fdk`, a missing libfdk_aac falls back to native unless strict
+//!       --b-frames <B_FRAMES>
+//!           Maximum number of consecutive B-frames, `0` disables them
+//!       --ref-frames <REF_FRAMES>
+//!           Number of reference frames, up to 16 for h264
+//!       --x265-param <X265_PARAM>
+//!           libx265 tunable as `key=value`, e.g. `aq-mode=3`, can be repeated. Only for the h265 codec
+//!       --frame-limit <FRAME_LIMIT>
+//!           Writes exactly this many video frames, cutting the audio along
+//!       --force-keyframes <FORCE_KEYFRAMES>
+//!           Forces keyframes at an `expr:` expression, e.g. `expr:gte(t,n_forced*2)`, or at comma separated times, to align segments
+//!       --sample-duration <SAMPLE_DURATION>
+//!           Test mode: converts only the first `[[H:]M:]S` of the input to quickly preview the settings
+//!       --bit-depth <BIT_DEPTH>
+//!           Bits per color component `8/10/12`, h264 supports up to 10
+//!       --reverse
+//!           Plays the output backwards. The whole input is buffered in memory, so keep it short
+//!       --pad <PAD>
+//!           Fits the video into a `WIDTHxHEIGHT` frame with colored borders, replacing `--resolution`
+//!       --pad-color <PAD_COLOR>
+//!           Background color of the padding, e.g. `black` or `#1E1E1E` [default: black]
+//!       --blur-fill <BLUR_FILL>
+//!           Fits the video into a `WIDTHxHEIGHT` frame over a blurred copy of itself, e.g. `1080x1920` for vertical video
+//!       --blur-strength <BLUR_STRENGTH>
+//!           Radius of the blurred background, in pixels [default: 20]
+//!       --progress-bar
+//!           Draws a bar along the bottom edge that fills up as the video plays
+//!       --progress-bar-height <PROGRESS_BAR_HEIGHT>
+//!           Height of the progress bar in pixels [default: 8]
+//!       --progress-bar-color <PROGRESS_BAR_COLOR>
+//!           Color of the progress bar, e.g. `white` or `#FF0000@0.8` [default: white]
+//!       --max-size <MAX_SIZE>
+//!           Fits the video within `WIDTHxHEIGHT` without upscaling, replacing `--resolution`
+//!       --audio-offset-ms <AUDIO_OFFSET_MS>
+//!           Shifts the audio to fix A/V sync, in milliseconds: positive delays it, negative advances it
+//!       --cover <COVER>
+//!           Embeds a jpg/png image as the cover of a mp4, mov or mkv output
+//!       --preserve-mtime
+//!           Gives the output the modification time of the input
+//!       --downmix <DOWNMIX>
+//!           Mixes 5.1/7.1 audio down to stereo `stereo/dialog`, `dialog` keeps voices louder
+//!       --trim-silence <TRIM_SILENCE>
+//!           Removes the audio silence at the `leading/trailing/both` ends, e.g. for podcasts
+//!       --silence-threshold-db <SILENCE_THRESHOLD_DB>
+//!           Level under which audio counts as silence, in dB [default: -50]
+//!       --silence-duration <SILENCE_DURATION>
+//!           Shortest silence removed, in seconds [default: 0.5]
+//!       --pip <PIP>
+//!           Draws the given video over a corner of the output, e.g. a webcam over a screen recording
+//!       --pip-position <PIP_POSITION>
+//!           Corner of the pip overlay `top-left/top-right/bottom-left/bottom-right` [default: bottom-right]
+//!       --pip-scale <PIP_SCALE>
+//!           Width of the pip overlay as a fraction of the output width [default: 0.25]
+//!       --pip-margin <PIP_MARGIN>
+//!           Distance of the pip overlay from the edges, in pixels [default: 16]
+//!       --pip-mix-audio
+//!           Mixes the pip overlay audio into the main one
+//!       --intro <INTRO>
+//!           Plays an intro of this many seconds before the video, e.g. a title card
+//!       --intro-background <INTRO_BACKGROUND>
+//!           Background of the intro, a color like `black` or a jpg/png image [default: black]
+//!       --intro-text <INTRO_TEXT>
+//!           Text drawn at the center of the intro
+//!       --filter-complex <FILTER_COMPLEX>
+//!           Runs this filter graph instead of the automatic filters, `--resolution` is ignored. Its outputs must be mapped with `--map`
+//!       --map <MAP>
+//!           A stream of the output, e.g. a `[vout]` graph label or `0:a?`. Can be repeated
+//!       --write-report
+//!           Writes a `<output>.atium.json` report with the request, the ffmpeg command, the elapsed time and media summaries
+//!       --strict
+//!           Fails when an input stream, e.g. a PGS subtitle in a mp4, would be dropped instead of only warning
+//!   -h, --help
+//!           Print help information
+//!   -V, --version
+//!           Print version information
+//!
 //! ```
 //!
 //! ### Analyze
@@ -90,10 +341,13 @@
 //! Usage: atium analyze [OPTIONS] --input <INPUT>
 //!
 //! Options:
-//!   -i, --input <INPUT>                  Input path of the file that will be analyzed
-//!   -f, --full <FULL>                    Whether you want the full analysis or not [possible values: true, false]
-//!       --output-format <OUTPUT_FORMAT>  Output format of the analysis tool
+//!   -i, --input <INPUT>                  Input path or http(s) URL of the file that will be analyzed. Can be repeated
+//!   -f, --full <FULL>                    Whether you want the full analysis or not, can be `true/false` [possible values: true, false]
+//!       --output-format <OUTPUT_FORMAT>  Output format of the analysis tool `json/xml/html/summary`
 //!       --output-file <OUTPUT_FILE>      Output file containing analysis result
+//!       --output-type <OUTPUT_TYPE>      Output type `std/file`
+//!       --pretty                         Pretty-prints the json output
+//!       --log-format <LOG_FORMAT>        Format of the logs written to stderr `text/json` [default: text]
 //!   -h, --help                           Print help information
 //!   -V, --version                        Print version information
 //! ```
@@ -106,192 +360,2701 @@
 //! Usage: atium thumbnail [OPTIONS]
 //!
 //! Options:
-//!   -t, --timestamp <TIMESTAMP>      The timestamp of the video for thumbnail extraction
+//!   -t, --timestamp <TIMESTAMP>      The timestamp of the video for thumbnail extraction, as `SS.mmm` or `HH:MM:SS.mmm`
 //!   -s, --source-path <SOURCE_PATH>  The source video for thumbnail extraction
 //!   -o, --output-path <OUTPUT_PATH>  Where to put the extracted thumbnail
+//!       --scenes <SCENES>            Extract a thumbnail at each scene change instead, with a detection threshold between 0 and 1
+//!       --avoid-black                Picks the brightest of a few frames spread over the input, avoiding black ones
+//!       --percentage <PERCENTAGE>    Picks the frame at this percentage of the duration, from 0 to 100, e.g. 30
+//!       --crop-to-center             Crops the largest centered square of the frame
+//!       --width <WIDTH>              Width of the thumbnail in pixels, the height keeps the aspect ratio
+//!       --quality <QUALITY>          Image quality from 0 to 100, for jpeg and png outputs
+//!       --log-format <LOG_FORMAT>    Format of the logs written to stderr `text/json` [default: text]
 //!   -h, --help                       Print help information
 //!   -V, --version                    Print version information
 //! ```
-
-
-use std::env;
-use clap::{Parser, Subcommand};
-use log::{error, info};
-
-use atium::converter;
-use crate::atium::common::analysis_service::MediaInfoExtractorService;
-use crate::atium::common::model::{InfoExtractorRequest, parse_info_format, parse_info_output_type, ThumbnailRequest};
-use crate::atium::common::thumbnail_service::FFMPEGThumbnailService;
-use crate::converter::model::{ConversionInput, ConversionOutput, ConversionRequest, InputSourceType, OutputCodec, parse_resolution};
-use crate::converter::service::FFMPEGConversionService;
-
-mod atium;
-
-#[derive(Subcommand)]
-enum Commands {
-    /// Conversion tool for video media
-    Convert {
-        /// Input file to convert
-        #[arg(short, long)]
-        input: String,
-        /// Type of source to convert
-        #[arg(short, long)]
-        source_type: Option<String>,
-        /// Output path for the converted file
-        #[arg(short, long)]
-        output: String,
-        /// Requested output resolution
-        #[arg(short, long)]
-        resolution: String,
-        /// Timestamp requested for thumbnail extraction
-        #[arg(long)]
-        thumb_ts: Option<String>,
-        /// Source from where to extract the thumbnail
-        #[arg(long)]
-        thumb_source: Option<String>,
-        /// Output path for the extracted thumbnail
-        #[arg(long)]
-        thumb_out: Option<String>
-    },
-    /// Analyze media to extract useful infos
-    Analyze {
-        /// Input path of the file that will be analyzed
-        #[arg(short, long)]
-        input: String,
-        /// Whether you want the full analysis or not, can be `true/false`
-        #[arg(short, long)]
-        full: Option<bool>,
-        /// Output format of the analysis tool `json/xml/html`
-        #[arg(long)]
-        output_format: Option<String>,
-        /// Output file containing analysis result
-        #[arg(long)]
-        output_file: Option<String>,
-        /// Output type `std/file`
-        #[arg(long)]
-        output_type: Option<String>
-    },
-    /// Thumbnail extraction tool
-    Thumbnail {
-        /// The timestamp of the video for thumbnail extraction
-        #[arg(short, long)]
-        timestamp: Option<String>,
-        /// The source video for thumbnail extraction
-        #[arg(short, long)]
-        source_path: Option<String>,
-        /// Where to put the extracted thumbnail
-        #[arg(short, long)]
-        output_path: Option<String>
-    }
-}
-
-#[derive(Parser)]
-#[command(author, version, about, long_about = None)]
-#[command(propagate_version = true)]
-struct Cli {
-     #[command(subcommand)]
-     command: Commands
-}
-
-fn setup_logger() {
-    let rust_log = "RUST_LOG";
-    if env::var(rust_log).is_err() {
-        env::set_var(rust_log, "error");
-    }
-    env_logger::init()
-}
-
-fn main() {
-
-    setup_logger();
-
-    let cli = Cli::parse();
-
-    match &cli.command {
-        Commands::Analyze {
-            input,
-            output_format,
-            full,
-            output_file,
-            output_type
-        } => {
-            let info_extractor_service = MediaInfoExtractorService::new()
-                .expect("Error building media info service");
-            let request = InfoExtractorRequest {
-                input: input.to_string(),
-                format: parse_info_format(output_format.clone()),
-                full: *full,
-                output_file: output_file.clone(),
-                output_type: parse_info_output_type(output_type.clone())
-            };
-
-            match info_extractor_service.get_info(request) {
-                Ok(response) => {
-                    if response.output.file.is_some() {
-                        info!("Output written to {}", response.output.file.unwrap())
-                    }
-                    info!("Info extracted successfully")
-                }
-                Err(err) => error!("An error occurred when extracting info {}", err)
-            }
-        },
-        Commands::Convert {
-            input,
-            source_type:_,
-            output,
-            resolution,
-            thumb_ts,
-            thumb_source,
-            thumb_out
-        } => {
-            let conversion_service = FFMPEGConversionService::new()
-                .expect("Cannot build service");
-            let request = ConversionRequest{
-                input: ConversionInput {
-                    source_type: InputSourceType::Local,
-                    file_name:  input.clone()
-                },
-                output: ConversionOutput {
-                    file: output.clone(),
-                    resolution: parse_resolution(resolution),
-                    codec: OutputCodec::H264,
-                    thumbnail_request: ThumbnailRequest::new(
-                        thumb_ts,
-                        thumb_source,
-                        thumb_out
-                    )
-                }
-            };
-
-            match conversion_service.convert(request) {
-                Ok(response) => {
-                    info!("Converted file available at [{}]", response.output_file)
-                }
-                Err(msg) => error!("An error occurred when converting {}", msg)
-            }
-        },
-        Commands::Thumbnail {
-            timestamp,
-            source_path,
-            output_path
-        } => {
-            let request = ThumbnailRequest::new(
-                timestamp,
-                source_path,
-                output_path
-            );
-            let service = FFMPEGThumbnailService::new()
-                .expect("Cannot build service");
-
-            if request.is_none() {
-                error!("You didn't specify all the required options!")
-            } else {
-                match service.extract_thumbnail(request.unwrap()) {
-                    Ok(_) => info!("Thumbnail extracted successfully"),
-                    Err(err) => error!("An error occurred when extracting thumbnail: {}", err)
-                }
-            }
-
+//!
+//! ### Codec
+//!
+//! ```
+//! Check whether ffmpeg supports a codec
+//!
+//! Usage: atium codec [OPTIONS] <NAME>
+//!
+//! Arguments:
+//!   <NAME>  The ffmpeg codec name, e.g. `libx265`
+//!
+//! Options:
+//!   -d, --decoder                  Check for a decoder instead of an encoder
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
+//! ```
+//!
+//! ### Waveform
+//!
+//! ```
+//! Waveform image rendering tool
+//!
+//! Usage: atium waveform [OPTIONS] --input <INPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>            The audio or video file to render
+//!   -o, --output <OUTPUT>          Where to put the rendered image
+//!       --width <WIDTH>            Image width in pixels [default: 1280]
+//!       --height <HEIGHT>          Image height in pixels [default: 240]
+//!       --color <COLOR>            Waveform color, can be repeated to set one color per channel
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
+//! ```
+//!
+//! ### Spectrogram
+//!
+//! ```
+//! Spectrogram image rendering tool
+//!
+//! Usage: atium spectrogram [OPTIONS] --input <INPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>            The audio or video file to render
+//!   -o, --output <OUTPUT>          Where to put the rendered image
+//!       --width <WIDTH>            Spectrum width in pixels [default: 1024]
+//!       --height <HEIGHT>          Spectrum height in pixels [default: 512]
+//!       --color <COLOR>            Color palette `channel/intensity/rainbow/magma/viridis/...`
+//!       --no-legend                Do not draw the time and frequency axes
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
+//! ```
+//!
+//! ### Replace Audio
+//!
+//! ```
+//! Replace the audio of a video with an external audio track
+//!
+//! Usage: atium replace-audio [OPTIONS] --video <VIDEO> --audio <AUDIO> --output <OUTPUT>
+//!
+//! Options:
+//!       --video <VIDEO>                The video whose video stream is kept
+//!       --audio <AUDIO>                The file providing the new audio track
+//!   -o, --output <OUTPUT>              Output path for the combined file
+//!       --audio-codec <AUDIO_CODEC>    Audio encoder to use, e.g. `aac`. The audio is copied when missing
+//!       --shortest                     Stop at the end of the shortest stream
+//!       --on-collision <ON_COLLISION>  What to do when the output file already exists `error/overwrite/rename`
+//!       --log-format <LOG_FORMAT>      Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                         Print help information
+//!   -V, --version                      Print version information
+//! ```
+//!
+//! ### Advise
+//!
+//! ```
+//! Tells which streams can be copied and which need transcoding for web delivery
+//!
+//! Usage: atium advise [OPTIONS] --input <INPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>            Input path or http(s) URL of the file to inspect
+//!       --json                     Prints the advice as JSON
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
+//! ```
+//!
+//! ### Frames
+//!
+//! ```
+//! Extracts every frame of a video, or every Nth, as numbered png images
+//!
+//! Usage: atium frames [OPTIONS] --input <INPUT> --output-dir <OUTPUT_DIR>
+//!
+//! Options:
+//!   -i, --input <INPUT>                The source video
+//!   -o, --output-dir <OUTPUT_DIR>      Directory where `frame_%06d.png` images are written, created when missing
+//!       --fps <FPS>                    Frames per second to keep, e.g. `1`. Every frame is kept when missing
+//!       --start-number <START_NUMBER>  Number of the first image, e.g. to continue a previous run in the same directory
+//!       --log-format <LOG_FORMAT>      Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                         Print help information
+//!   -V, --version                      Print version information
+//! ```
+//!
+//! ### Measure
+//!
+//! ```
+//! Measures loudness without producing any output
+//!
+//! Usage: atium measure [OPTIONS] --input <INPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>            The media to measure
+//!       --json                     Prints the report as JSON
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
+//! ```
+//!
+//! ### Compare
+//!
+//! ```
+//! Compares a distorted video against its reference with PSNR and SSIM
+//!
+//! Usage: atium compare [OPTIONS] --reference <REFERENCE> --distorted <DISTORTED>
+//!
+//! Options:
+//!       --reference <REFERENCE>    The reference video
+//!       --distorted <DISTORTED>    The video to evaluate, with the same resolution and frame rate of the reference
+//!       --json                     Prints the metrics as JSON
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
+//! ```
+//!
+//! ### Explain
+//!
+//! ```
+//! Prints the ffmpeg commands a conversion would run, without running anything
+//!
+//! Usage: atium explain [OPTIONS] --input <INPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>
+//!           Input file or http(s) URL to convert
+//!   -s, --source-type <SOURCE_TYPE>
+//!           Type of source to convert
+//!   -o, --output <OUTPUT>
+//!           Output path for the converted file
+//!       --output-template <OUTPUT_TEMPLATE>
+//!           Template for the output path, e.g. `{name}_converted.{ext}`. Valid tokens are `{name}`, `{ext}` and `{input_ext}`, relative paths are placed next to the input
+//!   -r, --resolution <RESOLUTION>
+//!           Requested output resolution `sd/hd/fhd/2k/uhd/8k`, `ATIUM_RESOLUTION` or hd when missing
+//!       --codec <CODEC>
+//!           Video codec `auto/h264/h265/vp9/av1`, `auto` picks vp9 for webm outputs and h264 otherwise. `ATIUM_CODEC` or h264 when missing
+//!       --thumb-ts <THUMB_TS>
+//!           Timestamp requested for thumbnail extraction
+//!       --thumb-source <THUMB_SOURCE>
+//!           Source from where to extract the thumbnail
+//!       --thumb-out <THUMB_OUT>
+//!           Output path for the extracted thumbnail
+//!       --maxrate <MAXRATE>
+//!           Maximum bitrate for constrained streaming, e.g. `2M`. Requires `--bufsize`
+//!       --bufsize <BUFSIZE>
+//!           Rate control buffer size, e.g. `4M`. Requires `--maxrate`
+//!       --on-collision <ON_COLLISION>
+//!           What to do when the output file already exists `error/overwrite/rename`
+//!       --title <TITLE>
+//!           Title stored in the output container metadata
+//!       --artist <ARTIST>
+//!           Artist stored in the output container metadata
+//!       --comment <COMMENT>
+//!           Comment stored in the output container metadata
+//!       --date <DATE>
+//!           Date stored in the output container metadata
+//!       --metadata <METADATA>
+//!           Additional container metadata as `key=value`, can be repeated
+//!       --strip-metadata
+//!           Remove all the metadata coming from the input
+//!       --fps <FPS>
+//!           Output frame rate, e.g. `25` or `30000/1001`
+//!       --force-cfr
+//!           Force a constant frame rate output, using `--fps` or the source average frame rate
+//!       --burn-subtitles <BURN_SUBTITLES>
+//!           Subtitle file to burn into the video
+//!       --burn-subtitle-track <BURN_SUBTITLE_TRACK>
+//!           Embedded subtitle stream to burn into the video, by index or language
+//!       --target-size <TARGET_SIZE>
+//!           Maximum output size, e.g. `8MB`, reached with a two-pass encode at a computed bitrate. Not available with `--hardware-encode`
+//!       --subtitle-charset <SUBTITLE_CHARSET>
+//!           Character encoding of the burned subtitle file, e.g. `CP1252`. Defaults to UTF-8
+//!       --soft-subtitles-output <SOFT_SUBTITLES_OUTPUT>
+//!           Also writes a stream copy of the input here with the burned subtitle file as a selectable track
+//!       --progress-json
+//!           Print progress to stdout as newline-delimited JSON events
+//!       --keep-streams <KEEP_STREAMS>
+//!           Keep only these stream kinds `video,audio,subtitle,data` and drop the rest
+//!       --disposition <DISPOSITION>
+//!           Disposition flag of an output stream as `spec=flag`, e.g. `a:1=default` or `s:0=forced`. Can be repeated
+//!       --stream-metadata <STREAM_METADATA>
+//!           Metadata of an output stream as `spec:key=value`, e.g. `a:1:title=Director's Commentary` or `a:0:language=eng`. Can be repeated
+//!       --web-optimized
+//!           Optimize mp4/mov outputs for progressive playback over HTTP
+//!       --fragmented
+//!           Writes a fragmented mp4/mov for low-latency streaming, it can't be web optimized
+//!       --max-muxing-queue-size <MAX_MUXING_QUEUE_SIZE>
+//!           Packets buffered per stream while muxing, raise it when ffmpeg fails with "Too many packets buffered for output stream", e.g. `1024`
+//!       --video-timebase <VIDEO_TIMEBASE>
+//!           Ticks per second of the mp4/mov video track, e.g. `90000`, for editing software rejecting unusual timebases
+//!       --aspect-ratio <ASPECT_RATIO>
+//!           Display aspect ratio of the output, e.g. `16:9`
+//!       --log-format <LOG_FORMAT>
+//!           Format of the logs written to stderr `text/json` [default: text]
+//!       --set-sar <SET_SAR>
+//!           Sample aspect ratio applied to the frames, e.g. `1:1`
+//!       --anamorphic-fix
+//!           Stretches anamorphic sources like DVD rips to square pixels, square sources are left as they are
+//!       --skip-existing
+//!           Skips the conversion when the output already exists and holds a readable video
+//!       --log-stderr
+//!           Logs ffmpeg warnings and errors as they happen during the conversion
+//!       --hwaccel-decode <HWACCEL_DECODE>
+//!           Hardware accelerator used to decode the input `cuda/vaapi/qsv/videotoolbox/d3d11va/dxva2`
+//!       --hardware-encode
+//!           Encodes with the hardware encoder of `--hwaccel-decode`, e.g. `hevc_nvenc` for h265 on cuda
+//!       --fallback-codec <FALLBACK_CODEC>
+//!           Software codec `h264/h265/vp9/av1` the conversion is retried with when the hardware encoder fails
+//!       --force-decoder <FORCE_DECODER>
+//!           Decoder used for the input video instead of the autodetected one, e.g. `libdav1d`
+//!       --normalize-preset <NORMALIZE_PRESET>
+//!           Normalizes the audio loudness with a preset `podcast/music/broadcast`
+//!       --aac-encoder <AAC_ENCODER>
+//!           AAC encoder of the audio `native/fdk`, a missing libfdk_aac falls back to native unless strict
+//!       --b-frames <B_FRAMES>
+//!           Maximum number of consecutive B-frames, `0` disables them
+//!       --ref-frames <REF_FRAMES>
+//!           Number of reference frames, up to 16 for h264
+//!       --x265-param <X265_PARAM>
+//!           libx265 tunable as `key=value`, e.g. `aq-mode=3`, can be repeated. Only for the h265 codec
+//!       --frame-limit <FRAME_LIMIT>
+//!           Writes exactly this many video frames, cutting the audio along
+//!       --force-keyframes <FORCE_KEYFRAMES>
+//!           Forces keyframes at an `expr:` expression, e.g. `expr:gte(t,n_forced*2)`, or at comma separated times, to align segments
+//!       --sample-duration <SAMPLE_DURATION>
+//!           Test mode: converts only the first `[[H:]M:]S` of the input to quickly preview the settings
+//!       --bit-depth <BIT_DEPTH>
+//!           Bits per color component `8/10/12`, h264 supports up to 10
+//!       --reverse
+//!           Plays the output backwards. The whole input is buffered in memory, so keep it short
+//!       --pad <PAD>
+//!           Fits the video into a `WIDTHxHEIGHT` frame with colored borders, replacing `--resolution`
+//!       --pad-color <PAD_COLOR>
+//!           Background color of the padding, e.g. `black` or `#1E1E1E` [default: black]
+//!       --blur-fill <BLUR_FILL>
+//!           Fits the video into a `WIDTHxHEIGHT` frame over a blurred copy of itself, e.g. `1080x1920` for vertical video
+//!       --blur-strength <BLUR_STRENGTH>
+//!           Radius of the blurred background, in pixels [default: 20]
+//!       --progress-bar
+//!           Draws a bar along the bottom edge that fills up as the video plays
+//!       --progress-bar-height <PROGRESS_BAR_HEIGHT>
+//!           Height of the progress bar in pixels [default: 8]
+//!       --progress-bar-color <PROGRESS_BAR_COLOR>
+//!           Color of the progress bar, e.g. `white` or `#FF0000@0.8` [default: white]
+//!       --max-size <MAX_SIZE>
+//!           Fits the video within `WIDTHxHEIGHT` without upscaling, replacing `--resolution`
+//!       --audio-offset-ms <AUDIO_OFFSET_MS>
+//!           Shifts the audio to fix A/V sync, in milliseconds: positive delays it, negative advances it
+//!       --cover <COVER>
+//!           Embeds a jpg/png image as the cover of a mp4, mov or mkv output
+//!       --preserve-mtime
+//!           Gives the output the modification time of the input
+//!       --downmix <DOWNMIX>
+//!           Mixes 5.1/7.1 audio down to stereo `stereo/dialog`, `dialog` keeps voices louder
+//!       --trim-silence <TRIM_SILENCE>
+//!           Removes the audio silence at the `leading/trailing/both` ends, e.g. for podcasts
+//!       --silence-threshold-db <SILENCE_THRESHOLD_DB>
+//!           Level under which audio counts as silence, in dB [default: -50]
+//!       --silence-duration <SILENCE_DURATION>
+//!           Shortest silence removed, in seconds [default: 0.5]
+//!       --pip <PIP>
+//!           Draws the given video over a corner of the output, e.g. a webcam over a screen recording
+//!       --pip-position <PIP_POSITION>
+//!           Corner of the pip overlay `top-left/top-right/bottom-left/bottom-right` [default: bottom-right]
+//!       --pip-scale <PIP_SCALE>
+//!           Width of the pip overlay as a fraction of the output width [default: 0.25]
+//!       --pip-margin <PIP_MARGIN>
+//!           Distance of the pip overlay from the edges, in pixels [default: 16]
+//!       --pip-mix-audio
+//!           Mixes the pip overlay audio into the main one
+//!       --intro <INTRO>
+//!           Plays an intro of this many seconds before the video, e.g. a title card
+//!       --intro-background <INTRO_BACKGROUND>
+//!           Background of the intro, a color like `black` or a jpg/png image [default: black]
+//!       --intro-text <INTRO_TEXT>
+//!           Text drawn at the center of the intro
+//!       --filter-complex <FILTER_COMPLEX>
+//!           Runs this filter graph instead of the automatic filters, `--resolution` is ignored. Its outputs must be mapped with `--map`
+//!       --map <MAP>
+//!           A stream of the output, e.g. a `[vout]` graph label or `0:a?`. Can be repeated
+//!       --write-report
+//!           Writes a `<output>.atium.json` report with the request, the ffmpeg command, the elapsed time and media summaries
+//!       --strict
+//!           Fails when an input stream, e.g. a PGS subtitle in a mp4, would be dropped instead of only warning
+//!   -h, --help
+//!           Print help information
+//!   -V, --version
+//!           Print version information
+//!
+//! ```
+//!
+//! ### Sprite
+//!
+//! ```
+//! Builds a thumbnail sprite sheet along with its WebVTT track for web players
+//!
+//! Usage: atium sprite [OPTIONS] --input <INPUT> --output <OUTPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>            The source video
+//!   -o, --output <OUTPUT>          The sprite image, the `.vtt` track is written next to it
+//!       --interval <INTERVAL>      Seconds of video covered by each tile [default: 10]
+//!       --columns <COLUMNS>        Tiles in each row of the sprite [default: 5]
+//!       --tile-width <TILE_WIDTH>  Width of each tile in pixels [default: 160]
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
+//! ```
+//!
+//! ### Mux
+//!
+//! ```
+//! Merges a video-only and an audio-only file by stream copy
+//!
+//! Usage: atium mux [OPTIONS] --video <VIDEO> --audio <AUDIO> --output <OUTPUT>
+//!
+//! Options:
+//!       --video <VIDEO>                The file providing the video stream
+//!       --audio <AUDIO>                The file providing the audio stream
+//!   -o, --output <OUTPUT>              Output path for the merged file, its container must store both streams as they are
+//!       --on-collision <ON_COLLISION>  What to do when the output file already exists `error/overwrite/rename`
+//!       --log-format <LOG_FORMAT>      Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                         Print help information
+//!   -V, --version                      Print version information
+//! ```
+//!
+//! ### Tracks
+//!
+//! ```
+//! Lists the audio and subtitle tracks of a media with human readable labels
+//!
+//! Usage: atium tracks [OPTIONS] --input <INPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>            Input path or http(s) URL of the file to inspect
+//!       --json                     Prints the tracks as JSON
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
+//! ```
+//!
+//! ### HDR
+//!
+//! ```
+//! Reports the color and HDR metadata of a video, exits with 1 when it is not HDR
+//!
+//! Usage: atium hdr [OPTIONS] --input <INPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>            Input path or http(s) URL of the file to inspect
+//!       --json                     Prints the metadata as JSON
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
+//! ```
+//!
+//! ### Repair
+//!
+//! ```
+//! Remuxes a file with broken indexes by stream copy, reporting errors before and after
+//!
+//! Usage: atium repair [OPTIONS] --input <INPUT> --output <OUTPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>                The damaged media
+//!   -o, --output <OUTPUT>              Output path for the repaired file
+//!       --genpts                       Regenerate missing timestamps with `-fflags +genpts`
+//!       --on-collision <ON_COLLISION>  What to do when the output file already exists `error/overwrite/rename`
+//!       --log-format <LOG_FORMAT>      Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                         Print help information
+//!   -V, --version                      Print version information
+//! ```
+//!
+//! ### Captions
+//!
+//! ```
+//! Extracts the CEA-608 closed captions embedded in a broadcast video to SRT
+//!
+//! Usage: atium captions [OPTIONS] --input <INPUT> --output <OUTPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>                The video carrying the captions, e.g. a `.ts` recording
+//!   -o, --output <OUTPUT>              Output path of the `.srt` file
+//!       --on-collision <ON_COLLISION>  What to do when the output file already exists `error/overwrite/rename`
+//!       --log-format <LOG_FORMAT>      Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                         Print help information
+//!   -V, --version                      Print version information
+//! ```
+//!
+//! ### Ladder
+//!
+//! ```
+//! Encodes a rendition ladder for adaptive streaming in a single ffmpeg run
+//!
+//! Usage: atium ladder [OPTIONS] --input <INPUT> --output <OUTPUT> --rendition <RENDITION>
+//!
+//! Options:
+//!   -i, --input <INPUT>                The source video
+//!   -o, --output <OUTPUT>              Base output path, each rendition gets its height appended, e.g. `movie_720p.mp4`
+//!       --rendition <RENDITION>        A rendition as `HEIGHT:crf=N` or `HEIGHT:BITRATE`, e.g. `1080p:crf=20` or `720p:2500k`. Can be repeated
+//!       --on-collision <ON_COLLISION>  What to do when an output file already exists `error/overwrite/rename`
+//!       --log-format <LOG_FORMAT>      Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                         Print help information
+//!   -V, --version                      Print version information
+//! ```
+//!
+//! ### Dash
+//!
+//! ```
+//! Packages a rendition ladder for MPEG-DASH, writing a manifest and its segments
+//!
+//! Usage: atium dash [OPTIONS] --input <INPUT> --output-dir <OUTPUT_DIR> --rendition <RENDITION>
+//!
+//! Options:
+//!   -i, --input <INPUT>
+//!           The source video
+//!   -o, --output-dir <OUTPUT_DIR>
+//!           Directory receiving `manifest.mpd` and the segments, created when missing
+//!       --rendition <RENDITION>
+//!           A rendition as `HEIGHT:crf=N` or `HEIGHT:BITRATE`, e.g. `1080p:crf=20` or `720p:2500k`. Can be repeated
+//!       --segment-duration <SEGMENT_DURATION>
+//!           Target segment duration in seconds
+//!       --log-format <LOG_FORMAT>
+//!           Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help
+//!           Print help information
+//!   -V, --version
+//!           Print version information
+//! ```
+//!
+//! ### Extract Audio
+//!
+//! ```
+//! Exports every audio track to its own file, named after the input, the track index and the language
+//!
+//! Usage: atium extract-audio [OPTIONS] --input <INPUT> --output-dir <OUTPUT_DIR>
+//!
+//! Options:
+//!   -i, --input <INPUT>                Input path of the media to extract the audio tracks from
+//!   -o, --output-dir <OUTPUT_DIR>      Directory receiving the `.mka` tracks, created when missing. With `--pcm`, `-` writes the first track to stdout
+//!       --on-collision <ON_COLLISION>  What to do when a track file already exists `error/overwrite/rename`
+//!       --pcm <PCM>                    Decodes the tracks to headerless `.pcm` files with this sample format, e.g. `s16le` or `f32le`
+//!       --sample-rate <SAMPLE_RATE>    Sample rate of the PCM tracks [default: 48000]
+//!       --channels <CHANNELS>          Channels of the PCM tracks [default: 2]
+//!       --log-format <LOG_FORMAT>      Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                         Print help information
+//!   -V, --version                      Print version information
+//! ```
+//!
+//! ### Concat
+//!
+//! ```
+//! Joins clips one after the other, with a hard cut or a crossfade. Clips must share their resolution and frame rate
+//!
+//! Usage: atium concat [OPTIONS] --input <INPUT> --output <OUTPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>                A clip to join, in playback order. Can be repeated
+//!   -o, --output <OUTPUT>              Output path of the joined video
+//!       --crossfade <CROSSFADE>        Fades each clip into the next one over this many seconds instead of cutting
+//!       --on-collision <ON_COLLISION>  What to do when the output file already exists `error/overwrite/rename`
+//!       --log-format <LOG_FORMAT>      Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                         Print help information
+//!   -V, --version                      Print version information
+//! ```
+//!
+//! ### Keyframes
+//!
+//! ```
+//! Prints the keyframe timestamps of a video, in seconds, e.g. to plan `-c copy` cuts
+//!
+//! Usage: atium keyframes [OPTIONS] --input <INPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>            Input path or http(s) URL of the file to inspect
+//!       --json                     Prints the timestamps as a JSON array
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
+//! ```
+//!
+//! ### Cut
+//!
+//! ```
+//! Cuts a part of a media without re-encoding it
+//!
+//! Usage: atium cut [OPTIONS] --input <INPUT> --output <OUTPUT> --start <START>
+//!
+//! Options:
+//!   -i, --input <INPUT>                The source media
+//!   -o, --output <OUTPUT>              Output path of the cut
+//!       --start <START>                Start of the cut, as `SS.mmm` or `HH:MM:SS.mmm`
+//!       --end <END>                    End of the cut, as `SS.mmm` or `HH:MM:SS.mmm`. The cut lasts until the end of the input when missing
+//!       --snap-to-keyframe             Moves the start back to the closest preceding keyframe, requires ffprobe
+//!       --on-collision <ON_COLLISION>  What to do when the output file already exists `error/overwrite/rename`
+//!       --log-format <LOG_FORMAT>      Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                         Print help information
+//!   -V, --version                      Print version information
+//! ```
+//!
+//! ### Build Info
+//!
+//! ```
+//! Prints the ffmpeg version, its enabled build features and its library versions, e.g. for bug reports
+//!
+//! Usage: atium build-info [OPTIONS]
+//!
+//! Options:
+//!       --json                     Prints the build info as JSON
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
+//! ```
+//!
+//! ### Streams Equal
+//!
+//! ```
+//! Checks whether the video and audio of two files decode to the same content, exits with 1 when they don't
+//!
+//! Usage: atium streams-equal [OPTIONS] <FIRST> <SECOND>
+//!
+//! Arguments:
+//!   <FIRST>   The first file
+//!   <SECOND>  The second file, possibly in another container
+//!
+//! Options:
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
+//! ```
+//!
+//! ### Watch
+//!
+//! ```
+//! Watches a directory and writes an info report next to each new video file
+//!
+//! Usage: atium watch [OPTIONS] --dir <DIR>
+//!
+//! Options:
+//!   -d, --dir <DIR>                      The directory to watch
+//!       --output-format <OUTPUT_FORMAT>  Format of the reports `json/xml/html/summary`
+//!       --settle-secs <SETTLE_SECS>      Seconds the size of a new file must stay the same before it is analyzed [default: 2]
+//!       --log-format <LOG_FORMAT>        Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                           Print help information
+//!   -V, --version                        Print version information
+//! ```
+//!
+//! ### Silence
+//!
+//! ```
+//! Prints the silent intervals of the audio as JSON, e.g. for jump-cut editing
+//!
+//! Usage: atium silence [OPTIONS] --input <INPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>                The media to inspect
+//!       --threshold <THRESHOLD>        Level under which audio counts as silence, in dBFS [default: -50]
+//!       --min-duration <MIN_DURATION>  Shortest silence reported, in seconds [default: 0.5]
+//!       --log-format <LOG_FORMAT>      Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                         Print help information
+//!   -V, --version                      Print version information
+//! ```
+//!
+//! ### Scenes
+//!
+//! ```
+//! Prints the timestamps of the scene cuts of a video
+//!
+//! Usage: atium scenes [OPTIONS] --input <INPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>            The video to inspect
+//!       --threshold <THRESHOLD>    Detection threshold between 0 and 1, lower values find more cuts [default: 0.4]
+//!       --json                     Prints the timestamps as a JSON array
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
+//! ```
+//!
+//! ### Filmstrip
+//!
+//! ```
+//! Builds a single row filmstrip of frames spread over the video, printing their timestamps
+//!
+//! Usage: atium filmstrip [OPTIONS] --input <INPUT> --output <OUTPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>            The source video
+//!   -o, --output <OUTPUT>          The filmstrip image
+//!       --count <COUNT>            Frames placed side by side [default: 10]
+//!       --height <HEIGHT>          Height of each frame in pixels [default: 90]
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
+//! ```
+//!
+//! ### Sample Format
+//!
+//! ```
+//! Changes only the sample format of the audio of a lossless file, e.g. a 24-bit FLAC to 16-bit
+//!
+//! Usage: atium sample-format [OPTIONS] --input <INPUT> --output <OUTPUT> --sample-format <SAMPLE_FORMAT>
+//!
+//! Options:
+//!   -i, --input <INPUT>                  The flac, alac, wavpack or pcm audio file
+//!   -o, --output <OUTPUT>                Output path of the converted file
+//!       --sample-format <SAMPLE_FORMAT>  Target sample format, e.g. `s16` or `s32` for flac
+//!       --dither <DITHER>                Dither applied when the bit depth is reduced `triangular_hp/shibata/none`
+//!       --on-collision <ON_COLLISION>    What to do when the output file already exists `error/overwrite/rename`
+//!       --log-format <LOG_FORMAT>        Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                           Print help information
+//!   -V, --version                        Print version information
+//! ```
+//!
+//! ### Validate Preset
+//!
+//! ```
+//! Checks a preset file of convert options without converting, exits with 1 when it is invalid
+//!
+//! Usage: atium validate-preset [OPTIONS] <PATH>
+//!
+//! Arguments:
+//!   <PATH>  The preset, one convert option and its value per line, e.g. `--codec h265`
+//!
+//! Options:
+//!       --log-format <LOG_FORMAT>  Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                     Print help information
+//!   -V, --version                  Print version information
+//! ```
+//!
+//! ### Cue GIF
+//!
+//! ```
+//! Makes a GIF of the moment of a subtitle cue, picked by index or text, with the subtitles burned in
+//!
+//! Usage: atium cue-gif [OPTIONS] --input <INPUT> --subtitles <SUBTITLES> --output <OUTPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>                The source video
+//!   -s, --subtitles <SUBTITLES>        SRT or WebVTT file to look the cue up in
+//!       --cue-index <CUE_INDEX>        Index of the cue, its SRT number or its position in the file
+//!       --cue-text <CUE_TEXT>          Picks the first cue containing this text, ignoring case
+//!   -o, --output <OUTPUT>              Output path of the GIF
+//!       --width <WIDTH>                GIF width in pixels [default: 480]
+//!       --fps <FPS>                    GIF frame rate [default: 12]
+//!       --on-collision <ON_COLLISION>  What to do when the output file already exists `error/overwrite/rename`
+//!       --log-format <LOG_FORMAT>      Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                         Print help information
+//!   -V, --version                      Print version information
+//! ```
+//!
+//! ### Multi Output
+//!
+//! ```
+//! Encodes a video to several outputs, each with its own codec and quality, decoding it only once
+//!
+//! Usage: atium multi-output [OPTIONS] --input <INPUT> --output <OUTPUT>
+//!
+//! Options:
+//!   -i, --input <INPUT>                The source video
+//!   -o, --output <OUTPUT>              An output as `PATH:CODEC:crf=N` or `PATH:CODEC:BITRATE`, e.g. `movie.webm:vp9:crf=32` or `movie.mp4:h264:2500k`. Can be repeated
+//!       --on-collision <ON_COLLISION>  What to do when an output file already exists `error/overwrite/rename`
+//!       --log-format <LOG_FORMAT>      Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help                         Print help information
+//!   -V, --version                      Print version information
+//! ```
+//!
+//! ### Batch
+//!
+//! ```
+//! Converts several inputs with the same settings, then reports which ones failed and why
+//!
+//! Usage: atium batch [OPTIONS] --input <INPUT> --output-template <OUTPUT_TEMPLATE>
+//!
+//! Options:
+//!   -i, --input <INPUT>
+//!           An input to convert. Can be repeated
+//!       --output-template <OUTPUT_TEMPLATE>
+//!           Template for the output paths, e.g. `{name}_converted.{ext}`. Valid tokens are `{name}`, `{ext}` and `{input_ext}`
+//!       --preset <PRESET>
+//!           Convert options applied to every input, one option and its value per line, e.g. `--codec h265`
+//!       --continue-on-error
+//!           Keeps converting the remaining inputs after a failure instead of stopping at the first one
+//!       --report-file <REPORT_FILE>
+//!           Also writes the JSON report to this file
+//!       --log-format <LOG_FORMAT>
+//!           Format of the logs written to stderr `text/json` [default: text]
+//!   -h, --help
+//!           Print help information
+//!   -V, --version
+//!           Print version information
+//! ```
+
+
+use std::{fs, process};
+use clap::{Args, Parser, Subcommand};
+use log::{error, info};
+
+use atium::converter;
+use crate::atium::audio::model::{parse_spectrogram_color, SpectrogramRequest, WaveformRequest};
+use crate::atium::audio::service::FFMPEGAudioImageService;
+use crate::atium::common::advice_helper::StreamAction;
+use crate::atium::common::analysis_helper::{parse_preset_args, parse_preset_options, render_output_template};
+use crate::atium::common::analysis_service::MediaInfoExtractorService;
+use crate::atium::common::builder::InfoExtractorRequestBuilder;
+use crate::atium::common::capability_helper::{ffmpeg_build_config, is_decoder_available, is_encoder_available};
+use crate::atium::common::command_manager::shell_quote;
+use crate::atium::common::hash_service::FFMPEGHashService;
+use crate::atium::common::keyframe_service::FFProbeKeyframeService;
+use crate::atium::common::logging_helper::{init_logger, parse_log_format};
+use crate::atium::common::model::{CollisionPolicy, FrameExtractionRequest, InfoExtractorRequest, InfoFormat, InfoOutputType, parse_collision_policy, parse_info_format, parse_info_output_type, FilmstripRequest, SpriteRequest, ThumbnailRequest, ThumbnailSelection};
+use crate::atium::common::thumbnail_service::FFMPEGThumbnailService;
+use crate::atium::common::time_helper::parse_timestamp;
+#[cfg(feature = "watch")]
+use crate::atium::common::watch_service::WatchService;
+use crate::atium::measure::model::parse_normalize_preset;
+use crate::atium::measure::service::FFMPEGMeasurementService;
+use crate::converter::builder::ConversionRequestBuilder;
+use crate::atium::common::error::AtiumError;
+use crate::converter::batch_helper::{BatchOutcome, run_batch};
+use crate::converter::ladder_helper::parse_rendition;
+use crate::converter::multi_output_helper::parse_output_spec;
+use crate::converter::model::{AudioExtractionTarget, AudioReplacementRequest, AudioTracksExtractionRequest, CaptionExtractionRequest, ConcatRequest, CutRequest, DashRequest, CueGifRequest, LadderRequest, MultiOutputRequest, RepairRequest, SampleFormatRequest, BlurFill, ConversionProgress, ConversionRequest, FilterGraph, Intro, OutputMetadata, Pad, Pip, ProgressBar, parse_aac_encoder, parse_audio_dither, parse_intro_background, parse_bit_depth, parse_disposition_entries, parse_pip_position, parse_downmix_mode, parse_frame_size, parse_hwaccel, parse_metadata_entries, parse_output_codec, parse_resolution, parse_silence_edge, resolve_auto_codec, SilenceTrim, parse_stream_metadata_entries, parse_stream_types, parse_subtitle_track, parse_x265_params, SubtitleBurn};
+use crate::converter::service::FFMPEGConversionService;
+use crate::converter::subtitle_helper::CueSelector;
+
+mod atium;
+
+#[derive(Args)]
+struct ConvertArgs {
+    /// Input file or http(s) URL to convert
+    #[arg(short, long)]
+    input: String,
+    /// Type of source to convert
+    #[arg(short, long)]
+    source_type: Option<String>,
+    /// Output path for the converted file
+    #[arg(short, long, required_unless_present = "output_template")]
+    output: Option<String>,
+    /// Template for the output path, e.g. `{name}_converted.{ext}`.
+    /// Valid tokens are `{name}`, `{ext}` and `{input_ext}`, relative paths are placed next to the input
+    #[arg(long, conflicts_with = "output")]
+    output_template: Option<String>,
+    /// Requested output resolution `sd/hd/fhd/2k/uhd/8k`, `ATIUM_RESOLUTION` or hd when missing
+    #[arg(short, long)]
+    resolution: Option<String>,
+    /// Video codec `auto/h264/h265/vp9/av1`, `auto` picks vp9 for webm outputs and h264 otherwise. `ATIUM_CODEC` or h264 when missing
+    #[arg(long)]
+    codec: Option<String>,
+    /// Timestamp requested for thumbnail extraction
+    #[arg(long)]
+    thumb_ts: Option<String>,
+    /// Source from where to extract the thumbnail
+    #[arg(long)]
+    thumb_source: Option<String>,
+    /// Output path for the extracted thumbnail
+    #[arg(long)]
+    thumb_out: Option<String>,
+    /// Maximum bitrate for constrained streaming, e.g. `2M`. Requires `--bufsize`
+    #[arg(long)]
+    maxrate: Option<String>,
+    /// Rate control buffer size, e.g. `4M`. Requires `--maxrate`
+    #[arg(long)]
+    bufsize: Option<String>,
+    /// What to do when the output file already exists `error/overwrite/rename`
+    #[arg(long)]
+    on_collision: Option<String>,
+    /// Title stored in the output container metadata
+    #[arg(long)]
+    title: Option<String>,
+    /// Artist stored in the output container metadata
+    #[arg(long)]
+    artist: Option<String>,
+    /// Comment stored in the output container metadata
+    #[arg(long)]
+    comment: Option<String>,
+    /// Date stored in the output container metadata
+    #[arg(long)]
+    date: Option<String>,
+    /// Additional container metadata as `key=value`, can be repeated
+    #[arg(long)]
+    metadata: Vec<String>,
+    /// Remove all the metadata coming from the input
+    #[arg(long)]
+    strip_metadata: bool,
+    /// Output frame rate, e.g. `25` or `30000/1001`
+    #[arg(long)]
+    fps: Option<String>,
+    /// Force a constant frame rate output, using `--fps` or the source average frame rate
+    #[arg(long)]
+    force_cfr: bool,
+    /// Subtitle file to burn into the video
+    #[arg(long, conflicts_with = "burn_subtitle_track")]
+    burn_subtitles: Option<String>,
+    /// Embedded subtitle stream to burn into the video, by index or language
+    #[arg(long)]
+    burn_subtitle_track: Option<String>,
+    /// Maximum output size, e.g. `8MB`, reached with a two-pass encode at a computed bitrate. Not available with `--hardware-encode`
+    #[arg(long)]
+    target_size: Option<String>,
+    /// Character encoding of the burned subtitle file, e.g. `CP1252`. Defaults to UTF-8
+    #[arg(long, requires = "burn_subtitles")]
+    subtitle_charset: Option<String>,
+    /// Also writes a stream copy of the input here with the burned subtitle file as a selectable track
+    #[arg(long, requires = "burn_subtitles")]
+    soft_subtitles_output: Option<String>,
+    /// Print progress to stdout as newline-delimited JSON events
+    #[arg(long)]
+    progress_json: bool,
+    /// Keep only these stream kinds `video,audio,subtitle,data` and drop the rest
+    #[arg(long, value_delimiter = ',')]
+    keep_streams: Option<Vec<String>>,
+    /// Disposition flag of an output stream as `spec=flag`, e.g. `a:1=default` or `s:0=forced`. Can be repeated
+    #[arg(long)]
+    disposition: Vec<String>,
+    /// Metadata of an output stream as `spec:key=value`, e.g. `a:1:title=Director's Commentary` or `a:0:language=eng`. Can be repeated
+    #[arg(long)]
+    stream_metadata: Vec<String>,
+    /// Optimize mp4/mov outputs for progressive playback over HTTP
+    #[arg(long)]
+    web_optimized: bool,
+    /// Writes a fragmented mp4/mov for low-latency streaming, it can't be web optimized
+    #[arg(long, conflicts_with = "web_optimized")]
+    fragmented: bool,
+    /// Packets buffered per stream while muxing, raise it when ffmpeg fails with "Too many packets buffered for output stream", e.g. `1024`
+    #[arg(long)]
+    max_muxing_queue_size: Option<u32>,
+    /// Ticks per second of the mp4/mov video track, e.g. `90000`, for editing software rejecting unusual timebases
+    #[arg(long)]
+    video_timebase: Option<String>,
+    /// Display aspect ratio of the output, e.g. `16:9`
+    #[arg(long)]
+    aspect_ratio: Option<String>,
+    /// Sample aspect ratio applied to the frames, e.g. `1:1`
+    #[arg(long)]
+    set_sar: Option<String>,
+    /// Stretches anamorphic sources like DVD rips to square pixels, square sources are left as they are
+    #[arg(long, conflicts_with = "set_sar")]
+    anamorphic_fix: bool,
+    /// Skips the conversion when the output already exists and holds a readable video
+    #[arg(long)]
+    skip_existing: bool,
+    /// Logs ffmpeg warnings and errors as they happen during the conversion
+    #[arg(long)]
+    log_stderr: bool,
+    /// Hardware accelerator used to decode the input `cuda/vaapi/qsv/videotoolbox/d3d11va/dxva2`
+    #[arg(long)]
+    hwaccel_decode: Option<String>,
+    /// Encodes with the hardware encoder of `--hwaccel-decode`, e.g. `hevc_nvenc` for h265 on cuda
+    #[arg(long, requires = "hwaccel_decode")]
+    hardware_encode: bool,
+    /// Software codec `h264/h265/vp9/av1` the conversion is retried with when the hardware encoder fails
+    #[arg(long, requires = "hardware_encode")]
+    fallback_codec: Option<String>,
+    /// Decoder used for the input video instead of the autodetected one, e.g. `libdav1d`
+    #[arg(long)]
+    force_decoder: Option<String>,
+    /// Normalizes the audio loudness with a preset `podcast/music/broadcast`
+    #[arg(long)]
+    normalize_preset: Option<String>,
+    /// AAC encoder of the audio `native/fdk`, a missing libfdk_aac falls back to native unless strict
+    #[arg(long)]
+    aac_encoder: Option<String>,
+    /// Maximum number of consecutive B-frames, `0` disables them
+    #[arg(long)]
+    b_frames: Option<u32>,
+    /// Number of reference frames, up to 16 for h264
+    #[arg(long)]
+    ref_frames: Option<u32>,
+    /// libx265 tunable as `key=value`, e.g. `aq-mode=3`, can be repeated. Only for the h265 codec
+    #[arg(long)]
+    x265_param: Vec<String>,
+    /// Writes exactly this many video frames, cutting the audio along
+    #[arg(long)]
+    frame_limit: Option<u64>,
+    /// Forces keyframes at an `expr:` expression, e.g. `expr:gte(t,n_forced*2)`, or at comma separated times, to align segments
+    #[arg(long)]
+    force_keyframes: Option<String>,
+    /// Test mode: converts only the first `[[H:]M:]S` of the input to quickly preview the settings
+    #[arg(long)]
+    sample_duration: Option<String>,
+    /// Bits per color component `8/10/12`, h264 supports up to 10
+    #[arg(long)]
+    bit_depth: Option<String>,
+    /// Plays the output backwards. The whole input is buffered in memory, so keep it short
+    #[arg(long)]
+    reverse: bool,
+    /// Fits the video into a `WIDTHxHEIGHT` frame with colored borders, replacing `--resolution`
+    #[arg(long)]
+    pad: Option<String>,
+    /// Background color of the padding, e.g. `black` or `#1E1E1E`
+    #[arg(long, requires = "pad", default_value = "black")]
+    pad_color: String,
+    /// Fits the video into a `WIDTHxHEIGHT` frame over a blurred copy of itself, e.g. `1080x1920` for vertical video
+    #[arg(long, conflicts_with = "pad")]
+    blur_fill: Option<String>,
+    /// Radius of the blurred background, in pixels
+    #[arg(long, requires = "blur_fill", default_value_t = 20)]
+    blur_strength: u32,
+    /// Draws a bar along the bottom edge that fills up as the video plays
+    #[arg(long)]
+    progress_bar: bool,
+    /// Height of the progress bar in pixels
+    #[arg(long, requires = "progress_bar", default_value_t = 8)]
+    progress_bar_height: u32,
+    /// Color of the progress bar, e.g. `white` or `#FF0000@0.8`
+    #[arg(long, requires = "progress_bar", default_value = "white")]
+    progress_bar_color: String,
+    /// Fits the video within `WIDTHxHEIGHT` without upscaling, replacing `--resolution`
+    #[arg(long, conflicts_with_all = ["pad", "blur_fill"])]
+    max_size: Option<String>,
+    /// Shifts the audio to fix A/V sync, in milliseconds: positive delays it, negative advances it
+    #[arg(long, allow_negative_numbers = true)]
+    audio_offset_ms: Option<i64>,
+    /// Embeds a jpg/png image as the cover of a mp4, mov or mkv output
+    #[arg(long)]
+    cover: Option<String>,
+    /// Gives the output the modification time of the input
+    #[arg(long)]
+    preserve_mtime: bool,
+    /// Mixes 5.1/7.1 audio down to stereo `stereo/dialog`, `dialog` keeps voices louder
+    #[arg(long)]
+    downmix: Option<String>,
+    /// Removes the audio silence at the `leading/trailing/both` ends, e.g. for podcasts
+    #[arg(long)]
+    trim_silence: Option<String>,
+    /// Level under which audio counts as silence, in dB
+    #[arg(long, requires = "trim_silence", default_value_t = -50.0, allow_negative_numbers = true)]
+    silence_threshold_db: f64,
+    /// Shortest silence removed, in seconds
+    #[arg(long, requires = "trim_silence", default_value_t = 0.5)]
+    silence_duration: f64,
+    /// Draws the given video over a corner of the output, e.g. a webcam over a screen recording
+    #[arg(long)]
+    pip: Option<String>,
+    /// Corner of the pip overlay `top-left/top-right/bottom-left/bottom-right`
+    #[arg(long, requires = "pip", default_value = "bottom-right")]
+    pip_position: String,
+    /// Width of the pip overlay as a fraction of the output width
+    #[arg(long, requires = "pip", default_value_t = 0.25)]
+    pip_scale: f32,
+    /// Distance of the pip overlay from the edges, in pixels
+    #[arg(long, requires = "pip", default_value_t = 16)]
+    pip_margin: u32,
+    /// Mixes the pip overlay audio into the main one
+    #[arg(long, requires = "pip")]
+    pip_mix_audio: bool,
+    /// Plays an intro of this many seconds before the video, e.g. a title card
+    #[arg(long)]
+    intro: Option<f32>,
+    /// Background of the intro, a color like `black` or a jpg/png image
+    #[arg(long, requires = "intro", default_value = "black")]
+    intro_background: String,
+    /// Text drawn at the center of the intro
+    #[arg(long, requires = "intro")]
+    intro_text: Option<String>,
+    /// Runs this filter graph instead of the automatic filters, `--resolution` is ignored. Its outputs must be mapped with `--map`
+    #[arg(long, requires = "map")]
+    filter_complex: Option<String>,
+    /// A stream of the output, e.g. a `[vout]` graph label or `0:a?`. Can be repeated
+    #[arg(long, requires = "filter_complex")]
+    map: Vec<String>,
+    /// Writes a `<output>.atium.json` report with the request, the ffmpeg command, the elapsed time and media summaries
+    #[arg(long)]
+    write_report: bool,
+    /// Fails when an input stream, e.g. a PGS subtitle in a mp4, would be dropped instead of only warning
+    #[arg(long)]
+    strict: bool
+}
+
+#[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
+enum Commands {
+    /// Conversion tool for video media
+    Convert(ConvertArgs),
+    /// Prints the ffmpeg commands a conversion would run, without running anything
+    Explain(ConvertArgs),
+    /// Checks a preset file of convert options without converting, exits with 1 when it is invalid
+    ValidatePreset {
+        /// The preset, one convert option and its value per line, e.g. `--codec h265`
+        path: String
+    },
+    /// Converts several inputs with the same settings, then reports which ones failed and why
+    Batch {
+        /// An input to convert. Can be repeated
+        #[arg(short, long, required = true)]
+        input: Vec<String>,
+        /// Template for the output paths, e.g. `{name}_converted.{ext}`. Valid tokens are `{name}`, `{ext}` and `{input_ext}`
+        #[arg(long)]
+        output_template: String,
+        /// Convert options applied to every input, one option and its value per line, e.g. `--codec h265`
+        #[arg(long)]
+        preset: Option<String>,
+        /// Keeps converting the remaining inputs after a failure instead of stopping at the first one
+        #[arg(long)]
+        continue_on_error: bool,
+        /// Also writes the JSON report to this file
+        #[arg(long)]
+        report_file: Option<String>
+    },
+    /// Analyze media to extract useful infos
+    Analyze {
+        /// Input path or http(s) URL of the file that will be analyzed. Can be repeated
+        #[arg(short, long, required = true)]
+        input: Vec<String>,
+        /// Whether you want the full analysis or not, can be `true/false`
+        #[arg(short, long)]
+        full: Option<bool>,
+        /// Output format of the analysis tool `json/xml/html/summary`
+        #[arg(long)]
+        output_format: Option<String>,
+        /// Output file containing analysis result
+        #[arg(long)]
+        output_file: Option<String>,
+        /// Output type `std/file`
+        #[arg(long)]
+        output_type: Option<String>,
+        /// Pretty-prints the json output
+        #[arg(long)]
+        pretty: bool
+    },
+    /// Extracts every frame of a video, or every Nth, as numbered png images
+    Frames {
+        /// The source video
+        #[arg(short, long)]
+        input: String,
+        /// Directory where `frame_%06d.png` images are written, created when missing
+        #[arg(short, long)]
+        output_dir: String,
+        /// Frames per second to keep, e.g. `1`. Every frame is kept when missing
+        #[arg(long)]
+        fps: Option<String>,
+        /// Number of the first image, e.g. to continue a previous run in the same directory
+        #[arg(long)]
+        start_number: Option<u64>
+    },
+    /// Builds a thumbnail sprite sheet along with its WebVTT track for web players
+    Sprite {
+        /// The source video
+        #[arg(short, long)]
+        input: String,
+        /// The sprite image, the `.vtt` track is written next to it
+        #[arg(short, long)]
+        output: String,
+        /// Seconds of video covered by each tile
+        #[arg(long, default_value_t = 10.0)]
+        interval: f64,
+        /// Tiles in each row of the sprite
+        #[arg(long, default_value_t = 5)]
+        columns: u32,
+        /// Width of each tile in pixels
+        #[arg(long, default_value_t = 160)]
+        tile_width: u32
+    },
+    /// Builds a single row filmstrip of frames spread over the video, printing their timestamps
+    Filmstrip {
+        /// The source video
+        #[arg(short, long)]
+        input: String,
+        /// The filmstrip image
+        #[arg(short, long)]
+        output: String,
+        /// Frames placed side by side
+        #[arg(long, default_value_t = 10)]
+        count: u32,
+        /// Height of each frame in pixels
+        #[arg(long, default_value_t = 90)]
+        height: u32
+    },
+    /// Measures loudness without producing any output
+    Measure {
+        /// The media to measure
+        #[arg(short, long)]
+        input: String,
+        /// Prints the report as JSON
+        #[arg(long)]
+        json: bool
+    },
+    /// Prints the silent intervals of the audio as JSON, e.g. for jump-cut editing
+    Silence {
+        /// The media to inspect
+        #[arg(short, long)]
+        input: String,
+        /// Level under which audio counts as silence, in dBFS
+        #[arg(long, default_value_t = -50.0, allow_negative_numbers = true)]
+        threshold: f64,
+        /// Shortest silence reported, in seconds
+        #[arg(long, default_value_t = 0.5)]
+        min_duration: f64
+    },
+    /// Prints the timestamps of the scene cuts of a video
+    Scenes {
+        /// The video to inspect
+        #[arg(short, long)]
+        input: String,
+        /// Detection threshold between 0 and 1, lower values find more cuts
+        #[arg(long, default_value_t = 0.4)]
+        threshold: f32,
+        /// Prints the timestamps as a JSON array
+        #[arg(long)]
+        json: bool
+    },
+    /// Compares a distorted video against its reference with PSNR and SSIM
+    Compare {
+        /// The reference video
+        #[arg(long)]
+        reference: String,
+        /// The video to evaluate, with the same resolution and frame rate of the reference
+        #[arg(long)]
+        distorted: String,
+        /// Prints the metrics as JSON
+        #[arg(long)]
+        json: bool
+    },
+    /// Tells which streams can be copied and which need transcoding for web delivery
+    Advise {
+        /// Input path or http(s) URL of the file to inspect
+        #[arg(short, long)]
+        input: String,
+        /// Prints the advice as JSON
+        #[arg(long)]
+        json: bool
+    },
+    /// Lists the audio and subtitle tracks of a media with human readable labels
+    Tracks {
+        /// Input path or http(s) URL of the file to inspect
+        #[arg(short, long)]
+        input: String,
+        /// Prints the tracks as JSON
+        #[arg(long)]
+        json: bool
+    },
+    /// Prints the keyframe timestamps of a video, in seconds, e.g. to plan `-c copy` cuts
+    Keyframes {
+        /// Input path or http(s) URL of the file to inspect
+        #[arg(short, long)]
+        input: String,
+        /// Prints the timestamps as a JSON array
+        #[arg(long)]
+        json: bool
+    },
+    /// Reports the color and HDR metadata of a video, exits with 1 when it is not HDR
+    Hdr {
+        /// Input path or http(s) URL of the file to inspect
+        #[arg(short, long)]
+        input: String,
+        /// Prints the metadata as JSON
+        #[arg(long)]
+        json: bool
+    },
+    /// Thumbnail extraction tool
+    Thumbnail {
+        /// The timestamp of the video for thumbnail extraction, as `SS.mmm` or `HH:MM:SS.mmm`
+        #[arg(short, long)]
+        timestamp: Option<String>,
+        /// The source video for thumbnail extraction
+        #[arg(short, long)]
+        source_path: Option<String>,
+        /// Where to put the extracted thumbnail
+        #[arg(short, long)]
+        output_path: Option<String>,
+        /// Extract a thumbnail at each scene change instead, with a detection threshold between 0 and 1
+        #[arg(long)]
+        scenes: Option<f32>,
+        /// Picks the brightest of a few frames spread over the input, avoiding black ones
+        #[arg(long, conflicts_with_all = ["timestamp", "scenes"])]
+        avoid_black: bool,
+        /// Picks the frame at this percentage of the duration, from 0 to 100, e.g. 30
+        #[arg(long, conflicts_with_all = ["timestamp", "scenes", "avoid_black"])]
+        percentage: Option<f32>,
+        /// Crops the largest centered square of the frame
+        #[arg(long)]
+        crop_to_center: bool,
+        /// Width of the thumbnail in pixels, the height keeps the aspect ratio
+        #[arg(long)]
+        width: Option<u32>,
+        /// Image quality from 0 to 100, for jpeg and png outputs
+        #[arg(long)]
+        quality: Option<u8>
+    },
+    /// Waveform image rendering tool
+    Waveform {
+        /// The audio or video file to render
+        #[arg(short, long)]
+        input: String,
+        /// Where to put the rendered image
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Image width in pixels
+        #[arg(long, default_value_t = 1280)]
+        width: u32,
+        /// Image height in pixels
+        #[arg(long, default_value_t = 240)]
+        height: u32,
+        /// Waveform color, can be repeated to set one color per channel
+        #[arg(long)]
+        color: Vec<String>
+    },
+    /// Spectrogram image rendering tool
+    Spectrogram {
+        /// The audio or video file to render
+        #[arg(short, long)]
+        input: String,
+        /// Where to put the rendered image
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Spectrum width in pixels
+        #[arg(long, default_value_t = 1024)]
+        width: u32,
+        /// Spectrum height in pixels
+        #[arg(long, default_value_t = 512)]
+        height: u32,
+        /// Color palette `channel/intensity/rainbow/magma/viridis/...`
+        #[arg(long)]
+        color: Option<String>,
+        /// Do not draw the time and frequency axes
+        #[arg(long)]
+        no_legend: bool
+    },
+    /// Replace the audio of a video with an external audio track
+    ReplaceAudio {
+        /// The video whose video stream is kept
+        #[arg(long)]
+        video: String,
+        /// The file providing the new audio track
+        #[arg(long)]
+        audio: String,
+        /// Output path for the combined file
+        #[arg(short, long)]
+        output: String,
+        /// Audio encoder to use, e.g. `aac`. The audio is copied when missing
+        #[arg(long)]
+        audio_codec: Option<String>,
+        /// Stop at the end of the shortest stream
+        #[arg(long)]
+        shortest: bool,
+        /// What to do when the output file already exists `error/overwrite/rename`
+        #[arg(long)]
+        on_collision: Option<String>
+    },
+    /// Merges a video-only and an audio-only file by stream copy
+    Mux {
+        /// The file providing the video stream
+        #[arg(long)]
+        video: String,
+        /// The file providing the audio stream
+        #[arg(long)]
+        audio: String,
+        /// Output path for the merged file, its container must store both streams as they are
+        #[arg(short, long)]
+        output: String,
+        /// What to do when the output file already exists `error/overwrite/rename`
+        #[arg(long)]
+        on_collision: Option<String>
+    },
+    /// Makes a GIF of the moment of a subtitle cue, picked by index or text, with the subtitles burned in
+    CueGif {
+        /// The source video
+        #[arg(short, long)]
+        input: String,
+        /// SRT or WebVTT file to look the cue up in
+        #[arg(short, long)]
+        subtitles: String,
+        /// Index of the cue, its SRT number or its position in the file
+        #[arg(long, required_unless_present = "cue_text", conflicts_with = "cue_text")]
+        cue_index: Option<u32>,
+        /// Picks the first cue containing this text, ignoring case
+        #[arg(long)]
+        cue_text: Option<String>,
+        /// Output path of the GIF
+        #[arg(short, long)]
+        output: String,
+        /// GIF width in pixels
+        #[arg(long, default_value_t = 480)]
+        width: u32,
+        /// GIF frame rate
+        #[arg(long, default_value_t = 12)]
+        fps: u32,
+        /// What to do when the output file already exists `error/overwrite/rename`
+        #[arg(long)]
+        on_collision: Option<String>
+    },
+    /// Changes only the sample format of the audio of a lossless file, e.g. a 24-bit FLAC to 16-bit
+    SampleFormat {
+        /// The flac, alac, wavpack or pcm audio file
+        #[arg(short, long)]
+        input: String,
+        /// Output path of the converted file
+        #[arg(short, long)]
+        output: String,
+        /// Target sample format, e.g. `s16` or `s32` for flac
+        #[arg(long)]
+        sample_format: String,
+        /// Dither applied when the bit depth is reduced `triangular_hp/shibata/none`
+        #[arg(long)]
+        dither: Option<String>,
+        /// What to do when the output file already exists `error/overwrite/rename`
+        #[arg(long)]
+        on_collision: Option<String>
+    },
+    /// Remuxes a file with broken indexes by stream copy, reporting errors before and after
+    Repair {
+        /// The damaged media
+        #[arg(short, long)]
+        input: String,
+        /// Output path for the repaired file
+        #[arg(short, long)]
+        output: String,
+        /// Regenerate missing timestamps with `-fflags +genpts`
+        #[arg(long)]
+        genpts: bool,
+        /// What to do when the output file already exists `error/overwrite/rename`
+        #[arg(long)]
+        on_collision: Option<String>
+    },
+    /// Extracts the CEA-608 closed captions embedded in a broadcast video to SRT
+    Captions {
+        /// The video carrying the captions, e.g. a `.ts` recording
+        #[arg(short, long)]
+        input: String,
+        /// Output path of the `.srt` file
+        #[arg(short, long)]
+        output: String,
+        /// What to do when the output file already exists `error/overwrite/rename`
+        #[arg(long)]
+        on_collision: Option<String>
+    },
+    /// Exports every audio track to its own file, named after the input, the track index and the language
+    ExtractAudio {
+        /// Input path of the media to extract the audio tracks from
+        #[arg(short, long)]
+        input: String,
+        /// Directory receiving the `.mka` tracks, created when missing. With `--pcm`, `-` writes the first track to stdout
+        #[arg(short, long)]
+        output_dir: String,
+        /// What to do when a track file already exists `error/overwrite/rename`
+        #[arg(long)]
+        on_collision: Option<String>,
+        /// Decodes the tracks to headerless `.pcm` files with this sample format, e.g. `s16le` or `f32le`
+        #[arg(long)]
+        pcm: Option<String>,
+        /// Sample rate of the PCM tracks
+        #[arg(long, default_value_t = 48000)]
+        sample_rate: u32,
+        /// Channels of the PCM tracks
+        #[arg(long, default_value_t = 2)]
+        channels: u32
+    },
+    /// Cuts a part of a media without re-encoding it
+    Cut {
+        /// The source media
+        #[arg(short, long)]
+        input: String,
+        /// Output path of the cut
+        #[arg(short, long)]
+        output: String,
+        /// Start of the cut, as `SS.mmm` or `HH:MM:SS.mmm`
+        #[arg(long)]
+        start: String,
+        /// End of the cut, as `SS.mmm` or `HH:MM:SS.mmm`. The cut lasts until the end of the input when missing
+        #[arg(long)]
+        end: Option<String>,
+        /// Moves the start back to the closest preceding keyframe, requires ffprobe
+        #[arg(long)]
+        snap_to_keyframe: bool,
+        /// What to do when the output file already exists `error/overwrite/rename`
+        #[arg(long)]
+        on_collision: Option<String>
+    },
+    /// Joins clips one after the other, with a hard cut or a crossfade. Clips must share their resolution and frame rate
+    Concat {
+        /// A clip to join, in playback order. Can be repeated
+        #[arg(short, long, required = true)]
+        input: Vec<String>,
+        /// Output path of the joined video
+        #[arg(short, long)]
+        output: String,
+        /// Fades each clip into the next one over this many seconds instead of cutting
+        #[arg(long)]
+        crossfade: Option<f32>,
+        /// What to do when the output file already exists `error/overwrite/rename`
+        #[arg(long)]
+        on_collision: Option<String>
+    },
+    /// Encodes a rendition ladder for adaptive streaming in a single ffmpeg run
+    Ladder {
+        /// The source video
+        #[arg(short, long)]
+        input: String,
+        /// Base output path, each rendition gets its height appended, e.g. `movie_720p.mp4`
+        #[arg(short, long)]
+        output: String,
+        /// A rendition as `HEIGHT:crf=N` or `HEIGHT:BITRATE`, e.g. `1080p:crf=20` or `720p:2500k`. Can be repeated
+        #[arg(long, required = true)]
+        rendition: Vec<String>,
+        /// What to do when an output file already exists `error/overwrite/rename`
+        #[arg(long)]
+        on_collision: Option<String>
+    },
+    /// Encodes a video to several outputs, each with its own codec and quality, decoding it only once
+    MultiOutput {
+        /// The source video
+        #[arg(short, long)]
+        input: String,
+        /// An output as `PATH:CODEC:crf=N` or `PATH:CODEC:BITRATE`, e.g. `movie.webm:vp9:crf=32` or `movie.mp4:h264:2500k`. Can be repeated
+        #[arg(short, long, required = true)]
+        output: Vec<String>,
+        /// What to do when an output file already exists `error/overwrite/rename`
+        #[arg(long)]
+        on_collision: Option<String>
+    },
+    /// Packages a rendition ladder for MPEG-DASH, writing a manifest and its segments
+    Dash {
+        /// The source video
+        #[arg(short, long)]
+        input: String,
+        /// Directory receiving `manifest.mpd` and the segments, created when missing
+        #[arg(short, long)]
+        output_dir: String,
+        /// A rendition as `HEIGHT:crf=N` or `HEIGHT:BITRATE`, e.g. `1080p:crf=20` or `720p:2500k`. Can be repeated
+        #[arg(long, required = true)]
+        rendition: Vec<String>,
+        /// Target segment duration in seconds
+        #[arg(long)]
+        segment_duration: Option<u32>
+    },
+    /// Prints the ffmpeg version, its enabled build features and its library versions, e.g. for bug reports
+    BuildInfo {
+        /// Prints the build info as JSON
+        #[arg(long)]
+        json: bool
+    },
+    /// Checks whether the video and audio of two files decode to the same content, exits with 1 when they don't
+    StreamsEqual {
+        /// The first file
+        first: String,
+        /// The second file, possibly in another container
+        second: String
+    },
+    /// Watches a directory and writes an info report next to each new video file
+    #[cfg(feature = "watch")]
+    Watch {
+        /// The directory to watch
+        #[arg(short, long)]
+        dir: String,
+        /// Format of the reports `json/xml/html/summary`
+        #[arg(long)]
+        output_format: Option<String>,
+        /// Seconds the size of a new file must stay the same before it is analyzed
+        #[arg(long, default_value_t = 2)]
+        settle_secs: u64
+    },
+    /// Check whether ffmpeg supports a codec
+    Codec {
+        /// The ffmpeg codec name, e.g. `libx265`
+        name: String,
+        /// Check for a decoder instead of an encoder
+        #[arg(short, long)]
+        decoder: bool
+    }
+}
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+#[command(propagate_version = true)]
+struct Cli {
+     #[command(subcommand)]
+     command: Commands,
+     /// Format of the logs written to stderr `text/json`
+     #[arg(long, global = true, default_value = "text")]
+     log_format: String
+}
+
+fn build_conversion_request(args: &ConvertArgs) -> Result<ConversionRequest, AtiumError> {
+    let keep_streams = args.keep_streams.as_ref()
+        .map(|s| parse_stream_types(s))
+        .transpose()?;
+    let output_metadata = OutputMetadata {
+        title: args.title.clone(),
+        artist: args.artist.clone(),
+        comment: args.comment.clone(),
+        date: args.date.clone(),
+        custom: parse_metadata_entries(&args.metadata)?
+    };
+    let output = match (&args.output, &args.output_template) {
+        (Some(output), _) => output.clone(),
+        (None, Some(template)) => render_output_template(template, &args.input, "mp4")?,
+        (None, None) => unreachable!("clap requires an output or an output template")
+    };
+    let mut builder = ConversionRequestBuilder::new(&args.input, &output)
+        .hardware_encode(args.hardware_encode)
+        .metadata(output_metadata)
+        .strip_metadata(args.strip_metadata)
+        .force_cfr(args.force_cfr)
+        .web_optimized(args.web_optimized)
+        .fragmented(args.fragmented)
+        .skip_existing(args.skip_existing)
+        .reverse(args.reverse)
+        .preserve_mtime(args.preserve_mtime)
+        .write_report(args.write_report)
+        .strict(args.strict);
+    // left unset, they fall back to the ATIUM_* variables in the builder
+    if let Some(resolution) = &args.resolution {
+        builder = builder.resolution(parse_resolution(resolution));
+    }
+    if let Some(codec) = &args.codec {
+        builder = builder.codec(match codec.to_lowercase().as_str() {
+            "auto" => resolve_auto_codec(&output),
+            codec => parse_output_codec(codec)?
+        });
+    }
+    if let Some(collision_policy) = parse_collision_policy(args.on_collision.clone()) {
+        builder = builder.collision_policy(collision_policy);
+    }
+    if let Some(fallback_codec) = &args.fallback_codec {
+        builder = builder.fallback_codec(parse_output_codec(fallback_codec)?);
+    }
+    if let Some(thumbnail_request) = ThumbnailRequest::new(&args.thumb_ts, &args.thumb_source, &args.thumb_out) {
+        builder = builder.thumbnail(thumbnail_request);
+    }
+    match (&args.maxrate, &args.bufsize) {
+        (Some(maxrate), Some(bufsize)) => builder = builder.rate_control(maxrate, bufsize),
+        (None, None) => {}
+        _ => return Err(AtiumError::InvalidArgument("maxrate and bufsize must be set together".to_string()))
+    }
+    if let Some(fps) = &args.fps {
+        builder = builder.fps(fps);
+    }
+    if let Some(video_timebase) = &args.video_timebase {
+        builder = builder.video_timebase(video_timebase);
+    }
+    if let Some(muxing_queue_size) = args.max_muxing_queue_size {
+        builder = builder.muxing_queue_size(muxing_queue_size);
+    }
+    if let Some(subtitle_file) = &args.burn_subtitles {
+        builder = builder.burn_subtitles(SubtitleBurn::File(subtitle_file.clone()));
+    } else if let Some(track) = &args.burn_subtitle_track {
+        builder = builder.burn_subtitles(SubtitleBurn::Embedded(parse_subtitle_track(track)));
+    }
+    if let Some(target_size) = &args.target_size {
+        builder = builder.target_size(target_size);
+    }
+    if let Some(charset) = &args.subtitle_charset {
+        builder = builder.subtitle_charset(charset);
+    }
+    if let Some(keep_streams) = keep_streams {
+        builder = builder.keep_streams(keep_streams);
+    }
+    if let Some(aspect_ratio) = &args.aspect_ratio {
+        builder = builder.aspect_ratio(aspect_ratio);
+    }
+    if let Some(sar) = &args.set_sar {
+        builder = builder.set_sar(sar);
+    }
+    builder = builder.anamorphic_fix(args.anamorphic_fix);
+    if let Some(hwaccel) = &args.hwaccel_decode {
+        builder = builder.hwaccel_decode(parse_hwaccel(hwaccel)?);
+    }
+    if let Some(decoder) = &args.force_decoder {
+        builder = builder.force_decoder(decoder);
+    }
+    if let Some(preset) = &args.normalize_preset {
+        builder = builder.normalize_preset(parse_normalize_preset(preset)?);
+    }
+    if let Some(aac_encoder) = &args.aac_encoder {
+        builder = builder.aac_encoder(parse_aac_encoder(aac_encoder)?);
+    }
+    if let Some(b_frames) = args.b_frames {
+        builder = builder.b_frames(b_frames);
+    }
+    if let Some(ref_frames) = args.ref_frames {
+        builder = builder.ref_frames(ref_frames);
+    }
+    builder = builder.x265_params(parse_x265_params(&args.x265_param)?);
+    builder = builder.dispositions(parse_disposition_entries(&args.disposition)?);
+    builder = builder.stream_metadata(parse_stream_metadata_entries(&args.stream_metadata)?);
+    if let Some(force_keyframes) = &args.force_keyframes {
+        builder = builder.force_keyframes(force_keyframes);
+    }
+    if let Some(sample_duration) = &args.sample_duration {
+        builder = builder.sample_duration(sample_duration);
+    }
+    if let Some(frame_limit) = args.frame_limit {
+        builder = builder.frame_limit(frame_limit);
+    }
+    if let Some(blur_fill) = &args.blur_fill {
+        let (width, height) = parse_frame_size(blur_fill)?;
+        builder = builder.blur_fill(BlurFill { width, height, strength: args.blur_strength });
+    }
+    if let Some(pad) = &args.pad {
+        let (width, height) = parse_frame_size(pad)?;
+        builder = builder.pad(Pad { width, height, color: args.pad_color.clone() });
+    }
+    if let Some(max_size) = &args.max_size {
+        let (width, height) = parse_frame_size(max_size)?;
+        builder = builder.max_dimensions(width, height);
+    }
+    if let Some(audio_offset_ms) = args.audio_offset_ms {
+        builder = builder.audio_offset_ms(audio_offset_ms);
+    }
+    if let Some(cover) = &args.cover {
+        builder = builder.cover(cover);
+    }
+    if args.progress_bar {
+        builder = builder.progress_bar(ProgressBar {
+            height: args.progress_bar_height,
+            color: args.progress_bar_color.clone()
+        });
+    }
+    if let Some(edge) = &args.trim_silence {
+        builder = builder.trim_silence(SilenceTrim {
+            edge: parse_silence_edge(edge)?,
+            threshold_db: args.silence_threshold_db,
+            min_duration_secs: args.silence_duration
+        });
+    }
+    if let Some(overlay_input) = &args.pip {
+        builder = builder.pip(Pip {
+            overlay_input: overlay_input.clone(),
+            position: parse_pip_position(&args.pip_position)?,
+            scale: args.pip_scale,
+            margin: args.pip_margin,
+            mix_audio: args.pip_mix_audio
+        });
+    }
+    if let Some(duration_secs) = args.intro {
+        builder = builder.intro(Intro {
+            duration_secs,
+            background: parse_intro_background(&args.intro_background),
+            text: args.intro_text.clone()
+        });
+    }
+    if let Some(graph) = &args.filter_complex {
+        builder = builder.filter_complex(FilterGraph { graph: graph.clone(), maps: args.map.clone() });
+    }
+    if let Some(downmix) = &args.downmix {
+        builder = builder.audio_downmix(parse_downmix_mode(downmix)?);
+    }
+    if let Some(bit_depth) = &args.bit_depth {
+        builder = builder.bit_depth(parse_bit_depth(bit_depth)?);
+    }
+
+    builder.build()
+}
+
+fn main() {
+
+    let cli = Cli::parse();
+
+    match parse_log_format(&cli.log_format) {
+        Ok(log_format) => init_logger(log_format),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(2)
+        }
+    }
+
+    match &cli.command {
+        Commands::Analyze {
+            input,
+            output_format,
+            full,
+            output_file,
+            output_type,
+            pretty
+        } => {
+            if input.len() > 1 && output_file.is_some() {
+                error!("An output file can only be used with a single input");
+                process::exit(2)
+            }
+            let info_extractor_service = MediaInfoExtractorService::new()
+                .expect("Error building media info service");
+            let format = parse_info_format(output_format.clone());
+            // with several inputs each summary line is prefixed by its file, like `ls` does
+            let summary_listing = input.len() > 1 && matches!(format, Some(InfoFormat::Summary));
+            for input in input {
+                let mut builder = InfoExtractorRequestBuilder::new(input)
+                    .pretty(*pretty);
+                if let Some(format) = format.clone() {
+                    builder = builder.format(format);
+                }
+                if let Some(full) = full {
+                    builder = builder.full(*full);
+                }
+                if let Some(output_file) = output_file {
+                    builder = builder.output_file(output_file);
+                }
+                if let Some(output_type) = parse_info_output_type(output_type.clone()) {
+                    builder = builder.output_type(output_type);
+                }
+                if summary_listing {
+                    builder = builder.output_type(InfoOutputType::Plain);
+                }
+                let request = match builder.build() {
+                    Ok(request) => request,
+                    Err(err) => {
+                        error!("{}", err);
+                        return
+                    }
+                };
+
+                match info_extractor_service.get_info(request) {
+                    Ok(response) => {
+                        if let Some(content) = response.output.content {
+                            println!("{}  {}", input, content.trim_end());
+                        }
+                        if let Some(file) = response.output.file {
+                            info!("Output written to {}", file)
+                        }
+                        info!("Info extracted successfully")
+                    }
+                    Err(err) => error!("An error occurred when extracting info of [{}] {}", input, err)
+                }
+            }
+        },
+        Commands::Convert(args) => {
+            let conversion_service = FFMPEGConversionService::new()
+                .expect("Cannot build service")
+                .with_stderr_tee(args.log_stderr);
+            let request = match build_conversion_request(args) {
+                Ok(request) => request,
+                Err(err) => {
+                    error!("{}", err);
+                    return
+                }
+            };
+
+            let progress_json = args.progress_json;
+            let on_progress = |progress: ConversionProgress| {
+                if progress_json {
+                    match serde_json::to_string(&progress) {
+                        Ok(line) => println!("{}", line),
+                        Err(err) => error!("Could not serialize progress: {}", err)
+                    }
+                }
+            };
+            let result = match &args.soft_subtitles_output {
+                Some(soft_output) => conversion_service.convert_with_soft_subtitles(request, soft_output, on_progress)
+                    .map(|response| {
+                        info!("Soft subtitled file available at [{}]", response.soft.output_file);
+                        response.burned
+                    }),
+                None if progress_json => conversion_service.convert_with_progress(request, on_progress),
+                None => conversion_service.convert(request)
+            };
+
+            match result {
+                Ok(response) if response.skipped => info!("Conversion skipped, [{}] already exists", response.output_file),
+                Ok(response) => {
+                    info!("Converted file available at [{}]", response.output_file);
+                    if let Some(thumbnail) = response.thumbnail_response {
+                        info!("Thumbnail available at [{}]", thumbnail.output)
+                    }
+                    if let Some(report_file) = response.report_file {
+                        info!("Conversion report available at [{}]", report_file)
+                    }
+                    if let (Some(max_rss_kb), Some(user_cpu_time)) =
+                        (response.resource_usage.max_rss_kb, response.resource_usage.user_cpu_time) {
+                        info!("Conversion used [{} KB] peak memory and [{:.2}s] user CPU time",
+                            max_rss_kb, user_cpu_time.as_secs_f64())
+                    }
+                }
+                Err(msg) => error!("An error occurred when converting {}", msg)
+            }
+        },
+        Commands::Explain(args) => {
+            let conversion_service = FFMPEGConversionService::new()
+                .expect("Cannot build service");
+            let commands = build_conversion_request(args)
+                .and_then(|request| conversion_service.explain(&request));
+
+            match commands {
+                Ok(commands) => commands.iter().for_each(|args| {
+                    let quoted: Vec<String> = args.iter().map(|arg| shell_quote(arg)).collect();
+                    println!("ffmpeg {}", quoted.join(" "));
+                }),
+                Err(err) => {
+                    error!("An error occurred when explaining the conversion {}", err);
+                    process::exit(2)
+                }
+            }
+        },
+        Commands::ValidatePreset {
+            path
+        } => {
+            let content = match fs::read_to_string(path) {
+                Ok(content) => content,
+                Err(err) => {
+                    error!("Could not read preset [{}]: {}", path, err);
+                    process::exit(2)
+                }
+            };
+            let args = [String::from("atium"), String::from("convert")].into_iter()
+                .chain(parse_preset_args(&content));
+            let request = match Cli::try_parse_from(args).map(|cli| cli.command) {
+                Ok(Commands::Convert(args)) => build_conversion_request(&args).map_err(|err| err.to_string()),
+                Ok(_) => unreachable!("the preset is parsed as a convert command"),
+                Err(err) => Err(err.to_string())
+            };
+
+            match request.and_then(|request| serde_json::to_string_pretty(&request).map_err(|err| err.to_string())) {
+                Ok(settings) => {
+                    println!("valid");
+                    println!("{}", settings);
+                }
+                Err(err) => {
+                    error!("Preset [{}] is invalid: {}", path, err.trim());
+                    process::exit(1)
+                }
+            }
+        },
+        Commands::Batch {
+            input,
+            output_template,
+            preset,
+            continue_on_error,
+            report_file
+        } => {
+            let preset_options = match preset.as_ref().map(fs::read_to_string).transpose() {
+                Ok(content) => content.map(|content| parse_preset_options(&content)).unwrap_or_default(),
+                Err(err) => {
+                    error!("Could not read preset: {}", err);
+                    process::exit(2)
+                }
+            };
+            let conversion_service = FFMPEGConversionService::new()
+                .expect("Cannot build service");
+
+            let report = run_batch(input, *continue_on_error, |input_file| {
+                let args = [String::from("atium"), String::from("convert")].into_iter()
+                    .chain(preset_options.iter().cloned())
+                    .chain([String::from("--input"), input_file.to_string(), String::from("--output-template"), output_template.clone()]);
+                let args = match Cli::try_parse_from(args).map(|cli| cli.command) {
+                    Ok(Commands::Convert(args)) => args,
+                    Ok(_) => unreachable!("the batch settings are parsed as a convert command"),
+                    Err(err) => return Err(AtiumError::InvalidArgument(err.to_string().trim().to_string()))
+                };
+                let response = conversion_service.convert(build_conversion_request(&args)?)?;
+                if response.skipped {
+                    return Ok(BatchOutcome::AlreadyDone(response.output_file))
+                }
+                info!("[{}] converted to [{}]", input_file, response.output_file);
+
+                Ok(BatchOutcome::Converted(response.output_file))
+            });
+
+            report.failed.iter().for_each(|(input_file, err)| error!("[{}] failed: {}", input_file, err));
+            info!("Batch done: [{}] succeeded, [{}] already done, [{}] failed, [{}] skipped",
+                report.succeeded.len(), report.already_done.len(), report.failed.len(), report.skipped.len());
+            match serde_json::to_string_pretty(&report) {
+                Ok(content) => {
+                    println!("{}", content);
+                    if let Some(report_file) = report_file {
+                        match fs::write(report_file, &content) {
+                            Ok(_) => info!("Batch report available at [{}]", report_file),
+                            Err(err) => error!("Could not write the batch report: {}", err)
+                        }
+                    }
+                }
+                Err(err) => error!("Could not serialize the batch report: {}", err)
+            }
+            if !report.is_success() {
+                process::exit(1)
+            }
+        },
+        Commands::Thumbnail {
+            timestamp,
+            source_path,
+            output_path,
+            scenes,
+            avoid_black,
+            percentage,
+            crop_to_center,
+            width,
+            quality
+        } => {
+            let request = ThumbnailRequest::new(
+                timestamp,
+                source_path,
+                output_path
+            ).map(|request| ThumbnailRequest {
+                selection: if *avoid_black {
+                    Some(ThumbnailSelection::AvoidBlack)
+                } else if let Some(percentage) = percentage {
+                    Some(ThumbnailSelection::Percentage(*percentage))
+                } else {
+                    scenes.map(|threshold| ThumbnailSelection::Scenes { threshold })
+                },
+                crop_to_center: *crop_to_center,
+                width: *width,
+                quality: *quality,
+                ..request
+            });
+            let service = FFMPEGThumbnailService::new()
+                .expect("Cannot build service");
+
+            match request {
+                None => error!("You didn't specify all the required options!"),
+                Some(request) => match service.extract_thumbnails(request) {
+                    Ok(responses) => responses.iter().for_each(|response| match response.timestamp_secs {
+                        Some(timestamp) => info!("Thumbnail extracted successfully at [{}] from [{:.3}s]", response.output, timestamp),
+                        None => info!("Thumbnail extracted successfully at [{}]", response.output)
+                    }),
+                    Err(err) => error!("An error occurred when extracting thumbnail: {}", err)
+                }
+            }
+
+        },
+        Commands::Waveform {
+            input,
+            output,
+            width,
+            height,
+            color
+        } => {
+            let service = FFMPEGAudioImageService::new()
+                .expect("Cannot build service");
+            let request = WaveformRequest {
+                input_file: input.clone(),
+                output_file: output.clone(),
+                width: *width,
+                height: *height,
+                colors: color.clone()
+            };
+
+            match service.extract_waveform(request) {
+                Ok(response) => info!("Waveform rendered successfully at [{}]", response.output),
+                Err(err) => error!("An error occurred when rendering waveform: {}", err)
+            }
+        },
+        Commands::Spectrogram {
+            input,
+            output,
+            width,
+            height,
+            color,
+            no_legend
+        } => {
+            let service = FFMPEGAudioImageService::new()
+                .expect("Cannot build service");
+            let request = SpectrogramRequest {
+                input_file: input.clone(),
+                output_file: output.clone(),
+                width: *width,
+                height: *height,
+                color: parse_spectrogram_color(color.clone()),
+                legend: !*no_legend
+            };
+
+            match service.extract_spectrogram(request) {
+                Ok(response) => info!("Spectrogram rendered successfully at [{}]", response.output),
+                Err(err) => error!("An error occurred when rendering spectrogram: {}", err)
+            }
+        },
+        Commands::ReplaceAudio {
+            video,
+            audio,
+            output,
+            audio_codec,
+            shortest,
+            on_collision
+        } => {
+            let conversion_service = FFMPEGConversionService::new()
+                .expect("Cannot build service");
+            let request = AudioReplacementRequest {
+                video_input: video.clone(),
+                audio_input: audio.clone(),
+                output_file: output.clone(),
+                audio_codec: audio_codec.clone(),
+                shortest: *shortest,
+                collision_policy: parse_collision_policy(on_collision.clone())
+                    .unwrap_or(CollisionPolicy::Rename)
+            };
+
+            match conversion_service.replace_audio(request) {
+                Ok(response) => info!("Audio replaced, file available at [{}]", response.output_file),
+                Err(err) => error!("An error occurred when replacing audio: {}", err)
+            }
+        },
+        Commands::Mux {
+            video,
+            audio,
+            output,
+            on_collision
+        } => {
+            let conversion_service = FFMPEGConversionService::new()
+                .expect("Cannot build service");
+            let collision_policy = parse_collision_policy(on_collision.clone())
+                .unwrap_or(CollisionPolicy::Rename);
+
+            match conversion_service.mux(video, audio, output, collision_policy) {
+                Ok(response) => info!("Streams merged, file available at [{}]", response.output_file),
+                Err(err) => error!("An error occurred when merging streams: {}", err)
+            }
+        },
+        Commands::CueGif {
+            input,
+            subtitles,
+            cue_index,
+            cue_text,
+            output,
+            width,
+            fps,
+            on_collision
+        } => {
+            let conversion_service = FFMPEGConversionService::new()
+                .expect("Cannot build service");
+            let cue = match (cue_index, cue_text) {
+                (Some(index), _) => CueSelector::Index(*index),
+                (None, Some(text)) => CueSelector::Text(text.clone()),
+                (None, None) => unreachable!("clap requires a cue index or a cue text")
+            };
+            let request = CueGifRequest {
+                input_file: input.clone(),
+                subtitle_file: subtitles.clone(),
+                cue,
+                output_file: output.clone(),
+                width: *width,
+                fps: *fps,
+                collision_policy: parse_collision_policy(on_collision.clone())
+                    .unwrap_or(CollisionPolicy::Rename)
+            };
+
+            match conversion_service.extract_cue_gif(request) {
+                Ok(response) => info!("GIF available at [{}]", response.output_file),
+                Err(err) => {
+                    error!("An error occurred when making the GIF: {}", err);
+                    process::exit(2)
+                }
+            }
+        },
+        Commands::SampleFormat {
+            input,
+            output,
+            sample_format,
+            dither,
+            on_collision
+        } => {
+            let conversion_service = FFMPEGConversionService::new()
+                .expect("Cannot build service");
+            let dither = match dither.as_deref().map(parse_audio_dither).transpose() {
+                Ok(dither) => dither,
+                Err(err) => {
+                    error!("{}", err);
+                    process::exit(2)
+                }
+            };
+            let request = SampleFormatRequest {
+                input_file: input.clone(),
+                output_file: output.clone(),
+                sample_format: sample_format.clone(),
+                dither,
+                collision_policy: parse_collision_policy(on_collision.clone())
+                    .unwrap_or(CollisionPolicy::Rename)
+            };
+
+            match conversion_service.convert_sample_format(request) {
+                Ok(response) => info!("Sample format converted, file available at [{}]", response.output_file),
+                Err(err) => {
+                    error!("An error occurred when converting the sample format: {}", err);
+                    process::exit(2)
+                }
+            }
+        },
+        Commands::Repair {
+            input,
+            output,
+            genpts,
+            on_collision
+        } => {
+            let conversion_service = FFMPEGConversionService::new()
+                .expect("Cannot build service");
+            let request = RepairRequest {
+                input_file: input.clone(),
+                output_file: output.clone(),
+                generate_pts: *genpts,
+                collision_policy: parse_collision_policy(on_collision.clone())
+                    .unwrap_or(CollisionPolicy::Rename)
+            };
+
+            match conversion_service.repair(request) {
+                Ok(response) => {
+                    println!("Errors before: {}, after: {}", response.before.errors.len(), response.after.errors.len());
+                    response.after.errors.iter().for_each(|error| println!("  {}", error));
+                    if !response.changed() {
+                        info!("The remux at [{}] did not change the reported errors", response.output_file);
+                    } else if response.after.is_clean() {
+                        info!("File repaired at [{}]", response.output_file);
+                    } else {
+                        info!("File partially repaired at [{}]", response.output_file);
+                    }
+                }
+                Err(err) => error!("An error occurred when repairing: {}", err)
+            }
+        },
+        Commands::Captions {
+            input,
+            output,
+            on_collision
+        } => {
+            let conversion_service = FFMPEGConversionService::new()
+                .expect("Cannot build service");
+            let request = CaptionExtractionRequest {
+                input_file: input.clone(),
+                output_file: output.clone(),
+                collision_policy: parse_collision_policy(on_collision.clone())
+                    .unwrap_or(CollisionPolicy::Rename)
+            };
+
+            match conversion_service.extract_captions(request) {
+                Ok(output_file) => info!("Closed captions available at [{}]", output_file),
+                Err(err) => error!("An error occurred when extracting closed captions: {}", err)
+            }
+        },
+        Commands::ExtractAudio {
+            input,
+            output_dir,
+            on_collision,
+            pcm,
+            sample_rate,
+            channels
+        } => {
+            let conversion_service = FFMPEGConversionService::new()
+                .expect("Cannot build service");
+            let request = AudioTracksExtractionRequest {
+                input_file: input.clone(),
+                output_dir: output_dir.clone(),
+                target: match pcm {
+                    Some(sample_format) => AudioExtractionTarget::Pcm {
+                        sample_format: sample_format.to_lowercase(),
+                        sample_rate: *sample_rate,
+                        channels: *channels
+                    },
+                    None => AudioExtractionTarget::Copy
+                },
+                collision_policy: parse_collision_policy(on_collision.clone())
+                    .unwrap_or(CollisionPolicy::Rename)
+            };
+
+            match conversion_service.extract_audio_tracks(request) {
+                Ok(output_files) => output_files.iter()
+                    .for_each(|output_file| info!("Audio track available at [{}]", output_file)),
+                Err(err) => error!("An error occurred when extracting audio tracks: {}", err)
+            }
+        },
+        Commands::Cut {
+            input,
+            output,
+            start,
+            end,
+            snap_to_keyframe,
+            on_collision
+        } => {
+            let (start_secs, end_secs) = match (parse_timestamp(start), end.as_deref().map(parse_timestamp).transpose()) {
+                (Ok(start), Ok(end)) => (start.as_secs_f64(), end.map(|end| end.as_secs_f64())),
+                (Err(err), _) | (_, Err(err)) => {
+                    error!("{}", err);
+                    process::exit(2)
+                }
+            };
+            let conversion_service = FFMPEGConversionService::new()
+                .expect("Cannot build service");
+            let request = CutRequest {
+                input_file: input.clone(),
+                output_file: output.clone(),
+                start_secs,
+                end_secs,
+                snap_to_keyframe: *snap_to_keyframe,
+                collision_policy: parse_collision_policy(on_collision.clone())
+                    .unwrap_or(CollisionPolicy::Rename)
+            };
+
+            match conversion_service.cut(request) {
+                Ok(response) => info!("Cut starting at [{}s] available at [{}]", response.start_secs, response.output_file),
+                Err(err) => error!("An error occurred when cutting: {}", err)
+            }
+        },
+        Commands::Concat {
+            input,
+            output,
+            crossfade,
+            on_collision
+        } => {
+            let conversion_service = FFMPEGConversionService::new()
+                .expect("Cannot build service");
+            let request = ConcatRequest {
+                input_files: input.clone(),
+                output_file: output.clone(),
+                crossfade: *crossfade,
+                collision_policy: parse_collision_policy(on_collision.clone())
+                    .unwrap_or(CollisionPolicy::Rename)
+            };
+
+            match conversion_service.concat(request) {
+                Ok(output_file) => info!("Joined video available at [{}]", output_file),
+                Err(err) => error!("An error occurred when concatenating: {}", err)
+            }
+        },
+        Commands::Ladder {
+            input,
+            output,
+            rendition,
+            on_collision
+        } => {
+            let renditions = match rendition.iter().map(|rendition| parse_rendition(rendition)).collect::<Result<Vec<_>, _>>() {
+                Ok(renditions) => renditions,
+                Err(err) => {
+                    error!("{}", err);
+                    process::exit(2)
+                }
+            };
+            let conversion_service = FFMPEGConversionService::new()
+                .expect("Cannot build service");
+            let request = LadderRequest {
+                input_file: input.clone(),
+                output_file: output.clone(),
+                renditions,
+                collision_policy: parse_collision_policy(on_collision.clone())
+                    .unwrap_or(CollisionPolicy::Rename)
+            };
+
+            match conversion_service.convert_ladder(request) {
+                Ok(response) => {
+                    response.output_files.iter().for_each(|output_file| info!("Rendition available at [{}]", output_file));
+                    if let (Some(max_rss_kb), Some(user_cpu_time)) =
+                        (response.resource_usage.max_rss_kb, response.resource_usage.user_cpu_time) {
+                        info!("Ladder used [{} KB] peak memory and [{:.2}s] user CPU time",
+                            max_rss_kb, user_cpu_time.as_secs_f64())
+                    }
+                }
+                Err(err) => error!("An error occurred when encoding the ladder: {}", err)
+            }
+        },
+        Commands::MultiOutput {
+            input,
+            output,
+            on_collision
+        } => {
+            let outputs = match output.iter().map(|output| parse_output_spec(output)).collect::<Result<Vec<_>, _>>() {
+                Ok(outputs) => outputs,
+                Err(err) => {
+                    error!("{}", err);
+                    process::exit(2)
+                }
+            };
+            let conversion_service = FFMPEGConversionService::new()
+                .expect("Cannot build service");
+            let request = MultiOutputRequest {
+                input_file: input.clone(),
+                outputs,
+                collision_policy: parse_collision_policy(on_collision.clone())
+                    .unwrap_or(CollisionPolicy::Rename)
+            };
+
+            match conversion_service.convert_multi_output(request) {
+                Ok(response) => {
+                    response.output_files.iter().for_each(|output_file| info!("Output available at [{}]", output_file));
+                    if let (Some(max_rss_kb), Some(user_cpu_time)) =
+                        (response.resource_usage.max_rss_kb, response.resource_usage.user_cpu_time) {
+                        info!("Multi output encode used [{} KB] peak memory and [{:.2}s] user CPU time",
+                            max_rss_kb, user_cpu_time.as_secs_f64())
+                    }
+                }
+                Err(err) => error!("An error occurred when encoding the outputs: {}", err)
+            }
+        },
+        Commands::Dash {
+            input,
+            output_dir,
+            rendition,
+            segment_duration
+        } => {
+            let renditions = match rendition.iter().map(|rendition| parse_rendition(rendition)).collect::<Result<Vec<_>, _>>() {
+                Ok(renditions) => renditions,
+                Err(err) => {
+                    error!("{}", err);
+                    process::exit(2)
+                }
+            };
+            let conversion_service = FFMPEGConversionService::new()
+                .expect("Cannot build service");
+            let request = DashRequest {
+                input_file: input.clone(),
+                output_dir: output_dir.clone(),
+                renditions,
+                segment_duration: *segment_duration
+            };
+
+            match conversion_service.convert_dash(request) {
+                Ok(response) => info!("DASH manifest available at [{}] with [{}] segments", response.manifest_file, response.segment_files.len()),
+                Err(err) => error!("An error occurred when packaging for DASH: {}", err)
+            }
+        },
+        Commands::Frames {
+            input,
+            output_dir,
+            fps,
+            start_number
+        } => {
+            let service = FFMPEGThumbnailService::new()
+                .expect("Cannot build service");
+            let request = FrameExtractionRequest {
+                input_file: input.clone(),
+                output_dir: output_dir.clone(),
+                fps: fps.clone(),
+                start_number: *start_number
+            };
+
+            match service.extract_frames(request) {
+                Ok(response) => info!("[{}] frames available at [{}]", response.frame_count, response.output_dir),
+                Err(err) => error!("An error occurred when extracting frames: {}", err)
+            }
+        },
+        Commands::Sprite {
+            input,
+            output,
+            interval,
+            columns,
+            tile_width
+        } => {
+            let service = FFMPEGThumbnailService::new()
+                .expect("Cannot build service");
+            let request = SpriteRequest {
+                input_file: input.clone(),
+                output_file: output.clone(),
+                interval_secs: *interval,
+                columns: *columns,
+                tile_width: *tile_width
+            };
+
+            match service.generate_sprite(request) {
+                Ok(response) => info!("[{}] tiles sprite available at [{}], track at [{}]", response.tile_count, response.output_file, response.vtt_file),
+                Err(err) => error!("An error occurred when building the sprite: {}", err)
+            }
+        },
+        Commands::Filmstrip {
+            input,
+            output,
+            count,
+            height
+        } => {
+            let service = FFMPEGThumbnailService::new()
+                .expect("Cannot build service");
+            let request = FilmstripRequest {
+                input_file: input.clone(),
+                output_file: output.clone(),
+                count: *count,
+                height: *height
+            };
+
+            match service.generate_filmstrip(request) {
+                Ok(response) => {
+                    info!("[{}] frames filmstrip available at [{}]", response.timestamps_secs.len(), response.output_file);
+                    response.timestamps_secs.iter().for_each(|timestamp| println!("{:.3}", timestamp));
+                }
+                Err(err) => {
+                    error!("An error occurred when building the filmstrip: {}", err);
+                    process::exit(2)
+                }
+            }
+        },
+        Commands::Measure {
+            input,
+            json
+        } => {
+            let service = FFMPEGMeasurementService::new()
+                .expect("Cannot build service");
+
+            match service.analyze(input) {
+                Ok(report) => {
+                    if *json {
+                        match serde_json::to_string(&report) {
+                            Ok(line) => println!("{}", line),
+                            Err(err) => error!("Could not serialize report: {}", err)
+                        }
+                    } else {
+                        let loudness = report.loudness;
+                        println!("Integrated loudness: {:.2} LUFS", loudness.integrated_lufs);
+                        println!("True peak: {:.2} dBTP", loudness.true_peak_dbtp);
+                        println!("Loudness range: {:.2} LU", loudness.lra_lu);
+                        println!("Threshold: {:.2} LUFS", loudness.threshold_lufs);
+                    }
+                }
+                Err(err) => {
+                    error!("An error occurred when measuring: {}", err);
+                    process::exit(2)
+                }
+            }
+        },
+        Commands::Silence {
+            input,
+            threshold,
+            min_duration
+        } => {
+            let service = FFMPEGMeasurementService::new()
+                .expect("Cannot build service");
+
+            match service.detect_silence(input, *threshold, *min_duration) {
+                Ok(intervals) => match serde_json::to_string(&intervals) {
+                    Ok(line) => println!("{}", line),
+                    Err(err) => error!("Could not serialize silence intervals: {}", err)
+                },
+                Err(err) => {
+                    error!("An error occurred when detecting silence: {}", err);
+                    process::exit(2)
+                }
+            }
+        },
+        Commands::Scenes {
+            input,
+            threshold,
+            json
+        } => {
+            let service = FFMPEGMeasurementService::new()
+                .expect("Cannot build service");
+            let scenes = match service.detect_scenes(input, *threshold) {
+                Ok(scenes) => scenes,
+                Err(err) => {
+                    error!("An error occurred when detecting scenes: {}", err);
+                    process::exit(2)
+                }
+            };
+
+            if *json {
+                match serde_json::to_string(&scenes) {
+                    Ok(line) => println!("{}", line),
+                    Err(err) => error!("Could not serialize scenes: {}", err)
+                }
+            } else {
+                scenes.iter().for_each(|scene| println!("{:.3}", scene));
+            }
+        },
+        Commands::Compare {
+            reference,
+            distorted,
+            json
+        } => {
+            let service = FFMPEGMeasurementService::new()
+                .expect("Cannot build service");
+
+            match service.compare_quality(reference, distorted) {
+                Ok(metrics) => {
+                    if *json {
+                        match serde_json::to_string(&metrics) {
+                            Ok(line) => println!("{}", line),
+                            Err(err) => error!("Could not serialize metrics: {}", err)
+                        }
+                    } else {
+                        println!("SSIM: {:.6} (Y {:.6})", metrics.ssim_all, metrics.ssim_y);
+                        println!("PSNR: {:.2} dB (Y {:.2} dB)", metrics.psnr_average, metrics.psnr_y);
+                    }
+                }
+                Err(err) => {
+                    error!("An error occurred when comparing: {}", err);
+                    process::exit(2)
+                }
+            }
+        },
+        Commands::Advise {
+            input,
+            json
+        } => {
+            let info_extractor_service = MediaInfoExtractorService::new()
+                .expect("Error building media info service");
+            let advice = match info_extractor_service.get_analysis_output(input) {
+                Ok(analysis_output) => analysis_output.transcode_advice(),
+                Err(err) => {
+                    error!("An error occurred when extracting info {}", err);
+                    process::exit(2)
+                }
+            };
+
+            if *json {
+                match serde_json::to_string(&advice) {
+                    Ok(line) => println!("{}", line),
+                    Err(err) => error!("Could not serialize advice: {}", err)
+                }
+            } else {
+                for stream in &advice.streams {
+                    match &stream.action {
+                        StreamAction::Copy =>
+                            println!("{}:{} {} -> copy", stream.kind, stream.kind_index, stream.format),
+                        StreamAction::Transcode { target, reason } =>
+                            println!("{}:{} {} -> transcode to {} ({})", stream.kind, stream.kind_index, stream.format, target, reason)
+                    }
+                }
+            }
+            if advice.needs_transcode() {
+                process::exit(1)
+            }
+        },
+        Commands::Tracks {
+            input,
+            json
+        } => {
+            let info_extractor_service = MediaInfoExtractorService::new()
+                .expect("Error building media info service");
+            let tracks = match info_extractor_service.list_tracks(input) {
+                Ok(tracks) => tracks,
+                Err(err) => {
+                    error!("An error occurred when extracting info {}", err);
+                    process::exit(2)
+                }
+            };
+
+            if *json {
+                match serde_json::to_string(&tracks) {
+                    Ok(line) => println!("{}", line),
+                    Err(err) => error!("Could not serialize tracks: {}", err)
+                }
+            } else {
+                tracks.audio.iter().for_each(|track| println!("0:a:{} {}", track.index, track.label()));
+                tracks.subtitles.iter().for_each(|track| println!("0:s:{} {}", track.index, track.label()));
+            }
+        },
+        Commands::Keyframes {
+            input,
+            json
+        } => {
+            let keyframe_service = FFProbeKeyframeService::new()
+                .expect("Error building keyframe service");
+            let keyframes = match keyframe_service.keyframes(input) {
+                Ok(keyframes) => keyframes,
+                Err(err) => {
+                    error!("An error occurred when listing keyframes: {}", err);
+                    process::exit(2)
+                }
+            };
+
+            if *json {
+                match serde_json::to_string(&keyframes) {
+                    Ok(line) => println!("{}", line),
+                    Err(err) => error!("Could not serialize keyframes: {}", err)
+                }
+            } else {
+                keyframes.iter().for_each(|keyframe| println!("{:.3}", keyframe));
+            }
+        },
+        Commands::Hdr {
+            input,
+            json
+        } => {
+            let info_extractor_service = MediaInfoExtractorService::new()
+                .expect("Error building media info service");
+            let hdr_info = match info_extractor_service.get_analysis_output(input) {
+                Ok(analysis_output) => match analysis_output.hdr_info() {
+                    Some(hdr_info) => hdr_info,
+                    None => {
+                        error!("Input file [{}] has no video stream", input);
+                        process::exit(2)
+                    }
+                },
+                Err(err) => {
+                    error!("An error occurred when extracting info {}", err);
+                    process::exit(2)
+                }
+            };
+
+            if *json {
+                match serde_json::to_string(&hdr_info) {
+                    Ok(line) => println!("{}", line),
+                    Err(err) => error!("Could not serialize HDR info: {}", err)
+                }
+            } else {
+                let unknown = || String::from("unknown");
+                println!("HDR: {}", if hdr_info.is_hdr() { "yes" } else { "no" });
+                println!("Color primaries: {}", hdr_info.color_primaries.clone().unwrap_or_else(unknown));
+                println!("Transfer characteristics: {}", hdr_info.transfer_characteristics.clone().unwrap_or_else(unknown));
+                println!("Matrix coefficients: {}", hdr_info.matrix_coefficients.clone().unwrap_or_else(unknown));
+                if let Some(hdr_format) = &hdr_info.hdr_format {
+                    println!("HDR format: {}", hdr_format);
+                }
+                if let Some(luminance) = &hdr_info.mastering_display_luminance {
+                    println!("Mastering display: {} from {} to {} cd/m2",
+                        hdr_info.mastering_display_primaries.clone().unwrap_or_else(unknown), luminance.min, luminance.max);
+                }
+                if let (Some(max_cll), Some(max_fall)) = (hdr_info.max_cll, hdr_info.max_fall) {
+                    println!("MaxCLL: {} cd/m2, MaxFALL: {} cd/m2", max_cll, max_fall);
+                }
+            }
+            if !hdr_info.is_hdr() {
+                process::exit(1)
+            }
+        },
+        Commands::BuildInfo {
+            json
+        } => {
+            let build_config = match ffmpeg_build_config() {
+                Ok(build_config) => build_config,
+                Err(err) => {
+                    error!("An error occurred when probing ffmpeg: {}", err);
+                    process::exit(2)
+                }
+            };
+
+            if *json {
+                match serde_json::to_string(&build_config) {
+                    Ok(line) => println!("{}", line),
+                    Err(err) => error!("Could not serialize build info: {}", err)
+                }
+            } else {
+                println!("ffmpeg {}", build_config.version);
+                build_config.libraries.iter().for_each(|(name, version)| println!("{} {}", name, version));
+                println!("enabled: {}", build_config.enabled.join(" "));
+            }
+        },
+        Commands::StreamsEqual {
+            first,
+            second
+        } => {
+            let hash_service = FFMPEGHashService::new()
+                .expect("Cannot build service");
+
+            match hash_service.streams_identical(first, second) {
+                Ok(true) => println!("The streams of [{}] and [{}] are identical", first, second),
+                Ok(false) => {
+                    println!("The streams of [{}] and [{}] differ", first, second);
+                    process::exit(1)
+                }
+                Err(err) => {
+                    error!("An error occurred when hashing streams: {}", err);
+                    process::exit(2)
+                }
+            }
+        },
+        #[cfg(feature = "watch")]
+        Commands::Watch {
+            dir,
+            output_format,
+            settle_secs
+        } => {
+            let watch_service = WatchService::new()
+                .expect("Cannot build service");
+            let format = parse_info_format(output_format.clone()).unwrap_or(InfoFormat::Json);
+
+            if let Err(err) = watch_service.watch(dir, format, std::time::Duration::from_secs(*settle_secs)) {
+                error!("An error occurred when watching [{}]: {}", dir, err);
+                process::exit(2)
+            }
+        },
+        Commands::Codec {
+            name,
+            decoder
+        } => {
+            let (kind, availability) = if *decoder {
+                ("decoder", is_decoder_available(name))
+            } else {
+                ("encoder", is_encoder_available(name))
+            };
+
+            match availability {
+                Ok(true) => println!("{} {} is available", kind, name),
+                Ok(false) => {
+                    println!("{} {} is not available", kind, name);
+                    process::exit(1)
+                }
+                Err(err) => {
+                    error!("An error occurred when probing codecs: {}", err);
+                    process::exit(2)
+                }
+            }
         }
     }
 }